@@ -0,0 +1 @@
+ALTER TABLE room ADD COLUMN closed_at TIMESTAMPTZ;
@@ -0,0 +1,31 @@
+-- Applies RFC 7396 JSON merge patch to the target document.
+CREATE OR REPLACE FUNCTION jsonb_merge_patch(target JSONB, patch JSONB) RETURNS JSONB
+    LANGUAGE plpgsql
+    IMMUTABLE
+    AS $$
+BEGIN
+    IF patch IS NULL OR jsonb_typeof(patch) <> 'object' THEN
+        RETURN patch;
+    END IF;
+
+    IF target IS NULL OR jsonb_typeof(target) <> 'object' THEN
+        target := '{}'::JSONB;
+    END IF;
+
+    RETURN COALESCE(
+        (
+            SELECT jsonb_object_agg(merged.key, merged.value)
+            FROM (
+                SELECT t.key, t.value
+                FROM jsonb_each(target) AS t
+                WHERE NOT patch ? t.key
+                UNION ALL
+                SELECT p.key, jsonb_merge_patch(target -> p.key, p.value)
+                FROM jsonb_each(patch) AS p
+                WHERE jsonb_typeof(p.value) <> 'null'
+            ) AS merged
+        ),
+        '{}'::JSONB
+    );
+END;
+$$;
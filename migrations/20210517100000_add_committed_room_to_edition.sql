@@ -0,0 +1,3 @@
+ALTER TABLE edition
+    ADD COLUMN committed_room_id UUID REFERENCES room (id) ON DELETE SET NULL,
+    ADD COLUMN committed_segments INT8RANGE[];
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS room_clock (
+    room_id uuid NOT NULL,
+    last_occurred_at bigint NOT NULL,
+
+    PRIMARY KEY (room_id),
+    FOREIGN KEY (room_id) REFERENCES room (id) ON DELETE CASCADE
+);
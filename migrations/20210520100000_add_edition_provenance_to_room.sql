@@ -0,0 +1,3 @@
+ALTER TABLE room
+    ADD COLUMN source_edition_id UUID REFERENCES edition (id) ON DELETE SET NULL,
+    ADD COLUMN committed_at TIMESTAMPTZ;
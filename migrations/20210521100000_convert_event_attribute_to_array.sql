@@ -0,0 +1,32 @@
+ALTER TABLE event ADD COLUMN attributes TEXT[] NOT NULL DEFAULT '{}';
+UPDATE event SET attributes = ARRAY[attribute] WHERE attribute IS NOT NULL;
+CREATE INDEX event_attributes_idx ON event USING GIN (attributes) WHERE deleted_at IS NULL;
+
+-- `attribute` is kept in sync with the first of `attributes` both ways during the deprecation
+-- period so that instances of the previous release keep working and the release may be rolled
+-- back. The column, its index and the trigger are to be dropped in a later release.
+CREATE OR REPLACE FUNCTION sync_event_attribute() RETURNS trigger
+    LANGUAGE plpgsql
+    AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        IF NEW.attributes = '{}' AND NEW.attribute IS NOT NULL THEN
+            NEW.attributes := ARRAY[NEW.attribute];
+        ELSE
+            NEW.attribute := NEW.attributes[1];
+        END IF;
+    ELSIF NEW.attributes IS DISTINCT FROM OLD.attributes THEN
+        NEW.attribute := NEW.attributes[1];
+    ELSIF NEW.attribute IS DISTINCT FROM OLD.attribute THEN
+        NEW.attributes := CASE
+            WHEN NEW.attribute IS NULL THEN '{}'
+            ELSE ARRAY[NEW.attribute]
+        END;
+    END IF;
+
+    RETURN NEW;
+END;
+$$;
+
+CREATE TRIGGER sync_event_attribute_trigger BEFORE INSERT OR UPDATE
+ON event FOR EACH ROW EXECUTE FUNCTION sync_event_attribute();
@@ -0,0 +1 @@
+ALTER TABLE event ADD COLUMN source_occurred_at BIGINT;
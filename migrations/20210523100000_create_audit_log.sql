@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS audit_log (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    room_id uuid NOT NULL,
+    action text NOT NULL,
+    created_by agent_id NOT NULL,
+    event_id uuid,
+    details jsonb DEFAULT '{}'::jsonb NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_room_id_created_at_idx ON audit_log (room_id, created_at);
+CREATE INDEX IF NOT EXISTS audit_log_created_by_created_at_idx ON audit_log (created_by, created_at);
@@ -0,0 +1,5 @@
+-- Building the index blocks writes to `event` for the whole build. On large tables create it
+-- beforehand out of the migration's transaction so this one becomes a no-op:
+--
+-- CREATE INDEX CONCURRENTLY IF NOT EXISTS event_data_idx ON event USING GIN (data jsonb_path_ops) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS event_data_idx ON event USING GIN (data jsonb_path_ops) WHERE deleted_at IS NULL;
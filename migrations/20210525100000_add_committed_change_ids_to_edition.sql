@@ -0,0 +1 @@
+ALTER TABLE edition ADD COLUMN committed_change_ids UUID[];
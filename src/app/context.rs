@@ -7,20 +7,45 @@ use chrono::{DateTime, Utc};
 use slog::{Logger, OwnedKV, SendSyncRefUnwindSafeKV};
 use sqlx::pool::PoolConnection;
 use sqlx::postgres::{PgPool as Db, Postgres};
+use sqlx::Transaction;
+use svc_agent::mqtt::IntoPublishableMessage;
 use svc_agent::{queue_counter::QueueCounterHandle, AgentId};
 use svc_authz::cache::ConnectionPool as RedisConnectionPool;
 use svc_authz::ClientMap as Authz;
+use tokio::sync::Semaphore;
 
 use crate::app::error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind};
-use crate::app::metrics::ProfilerKeys;
+use crate::app::metrics::{
+    FullListCounter, OversizedResponseCounter, ProfilerKeys, UnknownLabelCounter,
+};
+use crate::app::rate_limiter::{AgentRequestCounter, RoomConcurrencyLimiter, RoomRateLimiter};
 use crate::app::s3_client::S3Client;
-use crate::config::Config;
+use crate::app::task_registry::TaskRegistry;
+use crate::config::{Config, FeatureFlags, ReadPool};
 use crate::profiler::Profiler;
 
+/// Publishes messages produced outside of the request handling, e.g. by background tasks.
+pub(crate) type OutgoingMessageSender =
+    futures_channel::mpsc::UnboundedSender<Box<dyn IntoPublishableMessage + Send>>;
+
 ///////////////////////////////////////////////////////////////////////////////
 
 pub(crate) trait Context: GlobalContext + MessageContext {}
 
+/// Source of the current time for handlers.
+pub(crate) trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
 #[async_trait]
 pub(crate) trait GlobalContext: Sync {
     fn authz(&self) -> &Authz;
@@ -32,8 +57,23 @@ pub(crate) trait GlobalContext: Sync {
     fn redis_pool(&self) -> &Option<RedisConnectionPool>;
     fn profiler(&self) -> Arc<Profiler<(ProfilerKeys, Option<String>)>>;
     fn get_metrics(&self, duration: u64) -> anyhow::Result<Vec<crate::app::metrics::Metric>>;
+    fn get_audience_metrics(
+        &self,
+        duration: u64,
+        audience: &str,
+    ) -> anyhow::Result<Vec<crate::app::metrics::Metric>>;
     fn running_requests(&self) -> Option<Arc<AtomicI64>>;
     fn s3_client(&self) -> Option<S3Client>;
+    fn clock(&self) -> &dyn Clock;
+    fn room_rate_limiter(&self) -> &RoomRateLimiter;
+    fn state_read_limiter(&self) -> Arc<RoomConcurrencyLimiter>;
+    fn full_list_counter(&self) -> &FullListCounter;
+    fn agent_request_counter(&self) -> &AgentRequestCounter;
+    fn unknown_label_counter(&self) -> &UnknownLabelCounter;
+    fn oversized_response_counter(&self) -> &OversizedResponseCounter;
+    fn task_registry(&self) -> Arc<TaskRegistry>;
+    fn dump_gate(&self) -> Option<Arc<Semaphore>>;
+    fn outgoing_message_sender(&self) -> Option<OutgoingMessageSender>;
 
     async fn get_conn(&self) -> Result<PoolConnection<Postgres>, AppError> {
         self.db()
@@ -43,6 +83,15 @@ pub(crate) trait GlobalContext: Sync {
             .error(AppErrorKind::DbConnAcquisitionFailed)
     }
 
+    /// Begins a transaction on the primary pool for writes that must be applied together.
+    async fn begin_txn(&self) -> Result<Transaction<'static, Postgres>, AppError> {
+        self.db()
+            .begin()
+            .await
+            .context("Failed to begin DB transaction")
+            .error(AppErrorKind::DbConnAcquisitionFailed)
+    }
+
     async fn get_ro_conn(&self) -> Result<PoolConnection<Postgres>, AppError> {
         self.ro_db()
             .acquire()
@@ -50,6 +99,27 @@ pub(crate) trait GlobalContext: Sync {
             .context("Failed to acquire read-only DB connection")
             .error(AppErrorKind::DbConnAcquisitionFailed)
     }
+
+    /// Feature flags of the audience according to `features` config.
+    fn features(&self, audience: &str) -> FeatureFlags {
+        self.config().features(audience)
+    }
+
+    /// Picks the pool for the read operation according to `read_routing` config.
+    async fn get_read_conn(&self, operation: &str) -> Result<PoolConnection<Postgres>, AppError> {
+        match self.config().read_pool(operation) {
+            ReadPool::Primary => self.get_conn().await,
+            ReadPool::Replica => self.get_ro_conn().await,
+        }
+    }
+
+    /// Same as `get_read_conn` but doesn't wait for a free connection when the pool is exhausted.
+    fn try_get_read_conn(&self, operation: &str) -> Option<PoolConnection<Postgres>> {
+        match self.config().read_pool(operation) {
+            ReadPool::Primary => self.db().try_acquire(),
+            ReadPool::Replica => self.ro_db().try_acquire(),
+        }
+    }
 }
 
 pub(crate) trait MessageContext: Send {
@@ -75,6 +145,16 @@ pub(crate) struct AppContext {
     profiler: Arc<Profiler<(ProfilerKeys, Option<String>)>>,
     running_requests: Option<Arc<AtomicI64>>,
     s3_client: Option<S3Client>,
+    clock: Arc<dyn Clock>,
+    room_rate_limiter: Arc<RoomRateLimiter>,
+    state_read_limiter: Arc<RoomConcurrencyLimiter>,
+    full_list_counter: Arc<FullListCounter>,
+    agent_request_counter: Arc<AgentRequestCounter>,
+    unknown_label_counter: Arc<UnknownLabelCounter>,
+    oversized_response_counter: Arc<OversizedResponseCounter>,
+    task_registry: Arc<TaskRegistry>,
+    dump_gate: Option<Arc<Semaphore>>,
+    outgoing_message_sender: Option<OutgoingMessageSender>,
 }
 
 impl GlobalContext for AppContext {
@@ -114,6 +194,14 @@ impl GlobalContext for AppContext {
         crate::app::metrics::Collector::new(self, duration).get()
     }
 
+    fn get_audience_metrics(
+        &self,
+        duration: u64,
+        audience: &str,
+    ) -> anyhow::Result<Vec<crate::app::metrics::Metric>> {
+        crate::app::metrics::Collector::new(self, duration).get_for_audience(audience)
+    }
+
     fn running_requests(&self) -> Option<Arc<AtomicI64>> {
         self.running_requests.clone()
     }
@@ -121,6 +209,46 @@ impl GlobalContext for AppContext {
     fn s3_client(&self) -> Option<S3Client> {
         self.s3_client.clone()
     }
+
+    fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    fn room_rate_limiter(&self) -> &RoomRateLimiter {
+        self.room_rate_limiter.as_ref()
+    }
+
+    fn state_read_limiter(&self) -> Arc<RoomConcurrencyLimiter> {
+        self.state_read_limiter.clone()
+    }
+
+    fn full_list_counter(&self) -> &FullListCounter {
+        self.full_list_counter.as_ref()
+    }
+
+    fn agent_request_counter(&self) -> &AgentRequestCounter {
+        self.agent_request_counter.as_ref()
+    }
+
+    fn unknown_label_counter(&self) -> &UnknownLabelCounter {
+        self.unknown_label_counter.as_ref()
+    }
+
+    fn oversized_response_counter(&self) -> &OversizedResponseCounter {
+        self.oversized_response_counter.as_ref()
+    }
+
+    fn task_registry(&self) -> Arc<TaskRegistry> {
+        self.task_registry.clone()
+    }
+
+    fn dump_gate(&self) -> Option<Arc<Semaphore>> {
+        self.dump_gate.clone()
+    }
+
+    fn outgoing_message_sender(&self) -> Option<OutgoingMessageSender> {
+        self.outgoing_message_sender.clone()
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -178,6 +306,14 @@ impl<'a, C: GlobalContext> GlobalContext for AppMessageContext<'a, C> {
         self.global_context.get_metrics(duration)
     }
 
+    fn get_audience_metrics(
+        &self,
+        duration: u64,
+        audience: &str,
+    ) -> anyhow::Result<Vec<crate::app::metrics::Metric>> {
+        self.global_context.get_audience_metrics(duration, audience)
+    }
+
     fn running_requests(&self) -> Option<Arc<AtomicI64>> {
         self.global_context.running_requests()
     }
@@ -185,6 +321,46 @@ impl<'a, C: GlobalContext> GlobalContext for AppMessageContext<'a, C> {
     fn s3_client(&self) -> Option<S3Client> {
         self.global_context.s3_client()
     }
+
+    fn clock(&self) -> &dyn Clock {
+        self.global_context.clock()
+    }
+
+    fn room_rate_limiter(&self) -> &RoomRateLimiter {
+        self.global_context.room_rate_limiter()
+    }
+
+    fn state_read_limiter(&self) -> Arc<RoomConcurrencyLimiter> {
+        self.global_context.state_read_limiter()
+    }
+
+    fn full_list_counter(&self) -> &FullListCounter {
+        self.global_context.full_list_counter()
+    }
+
+    fn agent_request_counter(&self) -> &AgentRequestCounter {
+        self.global_context.agent_request_counter()
+    }
+
+    fn unknown_label_counter(&self) -> &UnknownLabelCounter {
+        self.global_context.unknown_label_counter()
+    }
+
+    fn oversized_response_counter(&self) -> &OversizedResponseCounter {
+        self.global_context.oversized_response_counter()
+    }
+
+    fn task_registry(&self) -> Arc<TaskRegistry> {
+        self.global_context.task_registry()
+    }
+
+    fn dump_gate(&self) -> Option<Arc<Semaphore>> {
+        self.global_context.dump_gate()
+    }
+
+    fn outgoing_message_sender(&self) -> Option<OutgoingMessageSender> {
+        self.global_context.outgoing_message_sender()
+    }
 }
 
 impl<'a, C: GlobalContext> MessageContext for AppMessageContext<'a, C> {
@@ -217,6 +393,7 @@ pub(crate) struct AppContextBuilder {
     queue_counter: Option<QueueCounterHandle>,
     redis_pool: Option<RedisConnectionPool>,
     running_requests: Option<Arc<AtomicI64>>,
+    outgoing_message_sender: Option<OutgoingMessageSender>,
 }
 
 impl AppContextBuilder {
@@ -232,6 +409,7 @@ impl AppContextBuilder {
             queue_counter: None,
             redis_pool: None,
             running_requests: None,
+            outgoing_message_sender: None,
         }
     }
 
@@ -263,7 +441,21 @@ impl AppContextBuilder {
         }
     }
 
+    pub(crate) fn outgoing_message_sender(self, sender: OutgoingMessageSender) -> Self {
+        Self {
+            outgoing_message_sender: Some(sender),
+            ..self
+        }
+    }
+
     pub(crate) fn build(self) -> AppContext {
+        let s3_client = S3Client::new(self.config.s3.as_ref());
+
+        let dump_gate = self
+            .config
+            .max_concurrent_dumps
+            .map(|permits| Arc::new(Semaphore::new(permits)));
+
         AppContext {
             config: Arc::new(self.config),
             authz: self.authz,
@@ -274,7 +466,97 @@ impl AppContextBuilder {
             redis_pool: self.redis_pool,
             profiler: Arc::new(Profiler::<(ProfilerKeys, Option<String>)>::start()),
             running_requests: self.running_requests,
-            s3_client: S3Client::new(),
+            s3_client,
+            clock: Arc::new(SystemClock),
+            room_rate_limiter: Arc::new(RoomRateLimiter::new()),
+            state_read_limiter: Arc::new(RoomConcurrencyLimiter::new()),
+            full_list_counter: Arc::new(FullListCounter::new()),
+            agent_request_counter: Arc::new(AgentRequestCounter::new()),
+            unknown_label_counter: Arc::new(UnknownLabelCounter::new()),
+            oversized_response_counter: Arc::new(OversizedResponseCounter::new()),
+            task_registry: Arc::new(TaskRegistry::new()),
+            dump_gate,
+            outgoing_message_sender: self.outgoing_message_sender,
         }
     }
 }
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use svc_agent::mqtt::ResponseStatus;
+
+    use crate::config::ReadPool;
+    use crate::test_helpers::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn read_routing() {
+        async_std::task::block_on(async {
+            let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+            context.set_ro_db(TestDb::new().await);
+
+            // Replica is the default so the idle connection is taken from the RO pool.
+            {
+                let _conn = context
+                    .get_read_conn("state.read")
+                    .await
+                    .expect("Failed to get connection");
+
+                assert_eq!(context.db().num_idle(), 1);
+                assert_eq!(context.ro_db().num_idle(), 0);
+            }
+
+            context
+                .config_mut()
+                .read_routing
+                .insert("state.read".to_owned(), ReadPool::Primary);
+
+            {
+                let _conn = context
+                    .get_read_conn("state.read")
+                    .await
+                    .expect("Failed to get connection");
+
+                assert_eq!(context.db().num_idle(), 0);
+            }
+        });
+    }
+
+    #[test]
+    fn db_conn_acquisition_failure_differs_from_query_failure() {
+        async_std::task::block_on(async {
+            let context = TestContext::new(TestDb::new().await, TestAuthz::new());
+
+            {
+                let mut conn = context.get_conn().await.expect("Failed to get connection");
+
+                let result = sqlx::query("SELECT * FROM missing_table")
+                    .execute(&mut *conn)
+                    .await
+                    .context("Failed to query missing table")
+                    .error(AppErrorKind::DbQueryFailed);
+
+                let err = match result {
+                    Ok(_) => panic!("Unexpected query success"),
+                    Err(err) => err,
+                };
+
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "database_query_failed");
+            }
+
+            context.db().close().await;
+
+            let err = match context.get_conn().await {
+                Ok(_) => panic!("Unexpected connection acquisition success"),
+                Err(err) => err,
+            };
+
+            assert_eq!(err.status(), ResponseStatus::SERVICE_UNAVAILABLE);
+            assert_eq!(err.kind(), "database_connection_acquisition_failed");
+        });
+    }
+}
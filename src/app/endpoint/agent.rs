@@ -4,7 +4,7 @@ use async_trait::async_trait;
 use serde_derive::{Deserialize, Serialize};
 use serde_json::json;
 use svc_agent::mqtt::{IncomingRequestProperties, ResponseStatus};
-use svc_agent::AccountId;
+use svc_agent::{AccountId, Addressable};
 use svc_authn::Authenticable;
 use uuid::Uuid;
 
@@ -62,7 +62,7 @@ impl RequestHandler for ListHandler {
 
         // Get agents list in the room.
         let agents = {
-            let mut conn = context.get_ro_conn().await?;
+            let mut conn = context.get_read_conn(reqp.method()).await?;
 
             let query = db::agent::ListWithBansQuery::new(
                 payload.room_id,
@@ -128,6 +128,7 @@ pub(crate) struct UpdateHandler;
 #[async_trait]
 impl RequestHandler for UpdateHandler {
     type Payload = UpdateRequest;
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
@@ -170,6 +171,9 @@ impl RequestHandler for UpdateHandler {
             AuthzObject::new(&object)
         };
 
+        // Record the ban change in the audit log along with the ban itself.
+        let mut txn = context.begin_txn().await?;
+
         if payload.value {
             let mut query = BanInsertQuery::new(payload.account_id.clone(), payload.room_id);
 
@@ -177,12 +181,11 @@ impl RequestHandler for UpdateHandler {
                 query.reason(reason);
             }
 
-            let mut conn = context.get_conn().await?;
             context
                 .profiler()
                 .measure(
                     (ProfilerKeys::BanInsertQuery, Some(reqp.method().to_owned())),
-                    query.execute(&mut conn),
+                    query.execute(&mut txn),
                 )
                 .await
                 .context("Failed to insert room ban")
@@ -190,18 +193,35 @@ impl RequestHandler for UpdateHandler {
         } else {
             let query = BanDeleteQuery::new(payload.account_id.clone(), payload.room_id);
 
-            let mut conn = context.get_conn().await?;
             context
                 .profiler()
                 .measure(
                     (ProfilerKeys::BanDeleteQuery, Some(reqp.method().to_owned())),
-                    query.execute(&mut conn),
+                    query.execute(&mut txn),
                 )
                 .await
                 .context("Failed to delete room ban")
                 .error(AppErrorKind::DbQueryFailed)?;
         }
 
+        let query = db::audit_log::InsertQuery::new(
+            room.id(),
+            "agent.update",
+            reqp.as_agent_id().to_owned(),
+        )
+        .details(json!({
+            "account_id": payload.account_id,
+            "value": payload.value,
+            "reason": payload.reason,
+        }));
+
+        helpers::write_audit_log(context, &mut txn, query, reqp).await?;
+
+        txn.commit()
+            .await
+            .context("Failed to commit transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
         if let Err(e) = context
             .authz()
             .ban(
@@ -244,7 +264,7 @@ impl RequestHandler for UpdateHandler {
 
         messages.push(helpers::build_notification(
             "agent.ban",
-            &format!("audiences/{}/events", room.audience()),
+            &context.config().topics.audience_events(room.audience()),
             tenant_notification,
             reqp,
             context.start_timestamp(),
@@ -259,7 +279,7 @@ impl RequestHandler for UpdateHandler {
         // Notify room subscribers.
         messages.push(helpers::build_notification(
             "agent.update",
-            &format!("rooms/{}/events", room.id()),
+            &context.config().topics.room_events(room.id()),
             room_notification,
             reqp,
             context.start_timestamp(),
@@ -485,10 +505,12 @@ mod tests {
                 kind: String::from("message"),
                 set: Some(String::from("messages")),
                 label: Some(String::from("message-1")),
-                attribute: None,
+                attributes: vec![],
                 data: json!({ "text": "banmsg" }),
                 is_claim: false,
                 is_persistent: true,
+                notify: true,
+                expected_version: None,
             };
 
             let messages = handle_request::<crate::app::endpoint::event::CreateHandler>(
@@ -576,10 +598,12 @@ mod tests {
                 kind: String::from("message"),
                 set: Some(String::from("messages")),
                 label: Some(String::from("message-1")),
-                attribute: None,
+                attributes: vec![],
                 data: json!({ "text": "hello" }),
                 is_claim: false,
                 is_persistent: true,
+                notify: true,
+                expected_version: None,
             };
 
             let err =
@@ -642,10 +666,12 @@ mod tests {
                 kind: String::from("message"),
                 set: Some(String::from("messages")),
                 label: Some(String::from("message-1")),
-                attribute: None,
+                attributes: vec![],
                 data: json!({ "text": "hello 2" }),
                 is_claim: false,
                 is_persistent: true,
+                notify: true,
+                expected_version: None,
             };
 
             let messages = handle_request::<crate::app::endpoint::event::CreateHandler>(
@@ -1,9 +1,12 @@
 use std::pin::Pin;
 use std::sync::Arc;
 
+use async_trait::async_trait;
+use chrono::Duration;
+use futures::future;
 use sqlx::postgres::PgPool as Db;
 use svc_agent::AccountId;
-use svc_authz::IntentObject;
+use svc_authz::{ClientMap as Authz, Error as AuthzError, IntentObject};
 use uuid::Uuid;
 
 use crate::db::room::Object as Room;
@@ -62,6 +65,56 @@ impl From<AuthzObject> for Box<dyn IntentObject> {
     }
 }
 
+/// A single check of a batch authorization.
+#[derive(Clone)]
+pub(crate) struct AuthzIntent {
+    audience: String,
+    object: AuthzObject,
+    action: String,
+}
+
+impl AuthzIntent {
+    pub(crate) fn new(audience: &str, object: AuthzObject, action: &str) -> Self {
+        Self {
+            audience: audience.to_owned(),
+            object,
+            action: action.to_owned(),
+        }
+    }
+}
+
+#[async_trait]
+pub(crate) trait BatchAuthorize {
+    /// Authorizes the subject for each of the intents returning per-intent results in order.
+    async fn authorize_batch(
+        &self,
+        subject: &AccountId,
+        intents: Vec<AuthzIntent>,
+    ) -> Vec<Result<Duration, AuthzError>>;
+}
+
+#[async_trait]
+impl BatchAuthorize for Authz {
+    async fn authorize_batch(
+        &self,
+        subject: &AccountId,
+        intents: Vec<AuthzIntent>,
+    ) -> Vec<Result<Duration, AuthzError>> {
+        // svc-authz has no batch request so intents are sent concurrently
+        // which makes the batch take a single round-trip instead of one per intent.
+        let futures = intents.into_iter().map(|intent| {
+            self.authorize(
+                intent.audience,
+                subject.to_owned(),
+                intent.object.into(),
+                intent.action,
+            )
+        });
+
+        future::join_all(futures).await
+    }
+}
+
 pub fn db_ban_callback(db: Db) -> svc_authz::BanCallback {
     Arc::new(
         move |account_id: AccountId, intent: Box<dyn IntentObject>| {
@@ -104,6 +157,8 @@ pub fn db_ban_callback(db: Db) -> svc_authz::BanCallback {
 
 #[cfg(test)]
 mod tests {
+    use crate::test_helpers::prelude::*;
+
     use super::*;
 
     #[test]
@@ -128,4 +183,31 @@ mod tests {
         let obj: Box<dyn IntentObject> = AuthzObject::new(&["rooms", "123", "events"]).into();
         assert_eq!(obj.to_ban_key(), None);
     }
+
+    #[test]
+    fn authorize_batch() {
+        async_std::task::block_on(async {
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let mut authz = TestAuthz::new();
+            authz.allow(agent.account_id(), vec!["rooms", "1"], "read");
+            authz.allow(agent.account_id(), vec!["rooms", "2"], "read");
+            let authz: Authz = authz.into();
+
+            let intents = ["1", "2", "3"]
+                .iter()
+                .map(|room_id| {
+                    let object = AuthzObject::new(&["rooms", room_id]);
+                    AuthzIntent::new(USR_AUDIENCE, object, "read")
+                })
+                .collect();
+
+            let results = authz.authorize_batch(agent.account_id(), intents).await;
+
+            assert_eq!(results.len(), 3);
+            assert!(results[0].is_ok());
+            assert!(results[1].is_ok());
+            assert!(results[2].is_err());
+        });
+    }
 }
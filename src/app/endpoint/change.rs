@@ -1,7 +1,7 @@
 use anyhow::Context as AnyhowContext;
 use async_std::stream;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset};
 use serde_derive::Deserialize;
 use svc_agent::mqtt::{IncomingRequestProperties, ResponseStatus};
 use svc_authn::Authenticable;
@@ -19,6 +19,7 @@ pub(crate) struct CreateHandler;
 #[async_trait]
 impl RequestHandler for CreateHandler {
     type Payload = CreateRequest;
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
@@ -144,7 +145,7 @@ pub(crate) struct ListHandler;
 #[derive(Debug, Deserialize)]
 pub(crate) struct ListRequest {
     id: Uuid,
-    last_created_at: Option<DateTime<Utc>>,
+    last_created_at: Option<DateTime<FixedOffset>>,
     limit: Option<usize>,
 }
 
@@ -201,7 +202,7 @@ impl RequestHandler for ListHandler {
         let mut query = db::change::ListQuery::new(edition.id());
 
         if let Some(last_created_at) = payload.last_created_at {
-            query = query.last_created_at(last_created_at);
+            query = query.last_created_at(helpers::to_utc(context, last_created_at)?);
         }
 
         if let Some(limit) = payload.limit {
@@ -209,7 +210,7 @@ impl RequestHandler for ListHandler {
         }
 
         let changes = {
-            let mut conn = context.get_ro_conn().await?;
+            let mut conn = context.get_read_conn(reqp.method()).await?;
 
             context
                 .profiler()
@@ -247,6 +248,7 @@ pub(crate) struct DeleteRequest {
 #[async_trait]
 impl RequestHandler for DeleteHandler {
     type Payload = DeleteRequest;
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
@@ -642,6 +644,7 @@ mod tests {
     }
 
     mod list {
+        use chrono::{Duration, Utc};
         use serde_json::json;
 
         use super::super::*;
@@ -713,6 +716,79 @@ mod tests {
             });
         }
 
+        #[test]
+        fn list_changes_with_non_utc_last_created_at() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, edition) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    let edition =
+                        shared_helpers::insert_edition(&mut conn, &room, &agent.agent_id()).await;
+
+                    factory::Change::new(edition.id(), ChangeType::Addition)
+                        .event_data(json!({"key": "value"}))
+                        .event_kind("something")
+                        .event_set("type")
+                        .event_label("label")
+                        .event_occurred_at(123)
+                        .event_created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    (room, edition)
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+                let last_created_at = Utc::now() - Duration::hours(1);
+                let moscow = FixedOffset::east(3 * 3600);
+
+                let build_payload = |last_created_at: String| {
+                    serde_json::from_value::<ListRequest>(json!({
+                        "id": edition.id(),
+                        "last_created_at": last_created_at,
+                    }))
+                    .expect("Failed to parse payload")
+                };
+
+                // Converted to UTC by default.
+                let payload = build_payload(last_created_at.with_timezone(&moscow).to_rfc3339());
+
+                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Failed to list changes");
+
+                let (items, respp, _) = find_response::<Vec<Change>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(items.len(), 1);
+
+                // Rejected in strict mode while UTC is still fine.
+                context.config_mut().strict_utc_datetimes = true;
+                let payload = build_payload(last_created_at.with_timezone(&moscow).to_rfc3339());
+
+                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success listing changes with non-UTC datetime");
+
+                assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+                assert_eq!(err.kind(), "invalid_payload");
+
+                let payload = build_payload(last_created_at.to_rfc3339());
+
+                handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Failed to list changes");
+            });
+        }
+
         #[test]
         fn list_changes_not_authorized() {
             async_std::task::block_on(async {
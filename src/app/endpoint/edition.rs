@@ -1,9 +1,10 @@
+use std::time::Instant;
+
 use anyhow::Context as AnyhowContext;
 use async_std::prelude::*;
 use async_std::stream;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use futures::future::FutureExt;
+use chrono::{DateTime, FixedOffset, Utc};
 use serde_derive::{Deserialize, Serialize};
 use serde_json::{json, Value as JsonValue};
 use svc_agent::{
@@ -19,7 +20,9 @@ use uuid::Uuid;
 
 use crate::app::context::Context;
 use crate::app::endpoint::prelude::*;
-use crate::app::operations::commit_edition;
+use crate::app::endpoint::room::RoomAdjustNotification;
+use crate::app::operations::{adjust_room, commit_edition, estimate_edition, validate_edition};
+use crate::app::task_registry::TaskKind;
 use crate::db;
 use crate::db::adjustment::Segments;
 
@@ -35,6 +38,7 @@ pub(crate) struct CreateRequest {
 #[async_trait]
 impl RequestHandler for CreateHandler {
     type Payload = CreateRequest;
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
@@ -95,7 +99,7 @@ impl RequestHandler for CreateHandler {
 
         let notification = helpers::build_notification(
             "edition.create",
-            &format!("rooms/{}/editions", payload.room_id),
+            &context.config().topics.room_editions(payload.room_id),
             edition,
             reqp,
             context.start_timestamp(),
@@ -112,8 +116,12 @@ pub(crate) struct ListHandler;
 #[derive(Debug, Deserialize)]
 pub(crate) struct ListRequest {
     room_id: Uuid,
-    last_created_at: Option<DateTime<Utc>>,
+    last_created_at: Option<DateTime<FixedOffset>>,
     limit: Option<i64>,
+    #[serde(default)]
+    with_change_count: bool,
+    #[serde(default)]
+    include_derived: bool,
 }
 
 #[async_trait]
@@ -148,15 +156,19 @@ impl RequestHandler for ListHandler {
         let mut query = db::edition::ListQuery::new(room.id());
 
         if let Some(last_created_at) = payload.last_created_at {
-            query = query.last_created_at(last_created_at);
+            query = query.last_created_at(helpers::to_utc(context, last_created_at)?);
         }
 
         if let Some(limit) = payload.limit {
             query = query.limit(limit);
         }
 
+        query = query
+            .with_change_count(payload.with_change_count)
+            .include_derived(payload.include_derived);
+
         let editions = {
-            let mut conn = context.get_ro_conn().await?;
+            let mut conn = context.get_read_conn(reqp.method()).await?;
 
             context
                 .profiler()
@@ -195,6 +207,7 @@ pub(crate) struct DeleteRequest {
 #[async_trait]
 impl RequestHandler for DeleteHandler {
     type Payload = DeleteRequest;
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
@@ -278,11 +291,14 @@ pub(crate) struct CommitHandler;
 #[derive(Debug, Deserialize)]
 pub(crate) struct CommitRequest {
     id: Uuid,
+    destination_audience: Option<String>,
+    change_ids: Option<Vec<Uuid>>,
 }
 
 #[async_trait]
 impl RequestHandler for CommitHandler {
     type Payload = CommitRequest;
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
@@ -331,22 +347,107 @@ impl RequestHandler for CommitHandler {
             )
             .await?;
 
+        // A repeated commit replays the original result so it must ask for the same changes.
+        if edition.committed().is_some()
+            && !edition.is_committed_with(payload.change_ids.as_deref())
+        {
+            return Err(anyhow!(
+                "Edition has already been committed with different change_ids"
+            ))
+            .error(AppErrorKind::Conflict);
+        }
+
+        // Validate the destination audience against the allowlist.
+        let destination_audience = payload
+            .destination_audience
+            .unwrap_or_else(|| room.audience().to_owned());
+
+        if !context
+            .config()
+            .is_commit_audience_allowed(room.audience(), &destination_audience)
+        {
+            return Err(anyhow!(
+                "Committing into '{}' audience is not allowed",
+                destination_audience
+            ))
+            .error(AppErrorKind::InvalidPayload);
+        }
+
         // Run commit task asynchronously.
         let db = context.db().to_owned();
         let profiler = context.profiler();
         let logger = context.logger().new(o!());
+        let max_cut_gaps = context.config().max_cut_gaps();
+        let cut_config = context.config().cut.clone();
+        let change_ids = payload.change_ids;
+
+        let auto_adjust = context.config().auto_adjust_on_commit.clone();
+
+        let tracking = reqp.tracking().to_owned();
+        let topics = context.config().topics.clone();
+        let path = topics.audience_events(room.audience());
+
+        // The task is detached and reports through the channel so the adjustment following
+        // the commit runs to completion even if the caller drops the message stream.
+        let (tx, rx) = futures_channel::mpsc::unbounded();
+        let task_registry = context.task_registry();
+
+        async_std::task::spawn(async move {
+            // A repeated commit of an already committed edition replays the original result.
+            // `None` stands for the commit cancelled with `edition.commit.cancel`.
+            let (result, destination) = match edition.committed() {
+                Some((committed_room_id, segments)) => {
+                    (Some(Ok((committed_room_id, segments.to_owned()))), None)
+                }
+                None => {
+                    let started_at = Instant::now();
+
+                    let operation = commit_edition(
+                        &db,
+                        &profiler,
+                        &edition,
+                        &room,
+                        max_cut_gaps,
+                        &cut_config,
+                        &destination_audience,
+                        change_ids.as_deref(),
+                    );
+
+                    let result = task_registry
+                        .track(TaskKind::EditionCommit, edition.id(), operation)
+                        .await;
 
-        let notification_future = async_std::task::spawn(async move {
-            let result = commit_edition(&db, &profiler, &edition, &room).await;
+                    // The outcome goes to the method tag since the profiler has no other.
+                    let method = match result {
+                        Some(Ok(_)) => "edition.commit.success",
+                        Some(Err(_)) => "edition.commit.error",
+                        None => "edition.commit.cancelled",
+                    };
+
+                    profiler.record(
+                        (ProfilerKeys::EditionCommitTotal, Some(method.to_owned())),
+                        started_at.elapsed(),
+                    );
+
+                    match result {
+                        Some(Ok((destination, segments))) => (
+                            Some(Ok((destination.id(), segments.clone()))),
+                            Some((destination, segments)),
+                        ),
+                        Some(Err(err)) => (Some(Err(err)), None),
+                        None => (None, None),
+                    }
+                }
+            };
 
             // Handle result.
             let result = match result {
-                Ok((destination, modified_segments)) => EditionCommitResult::Success {
+                Some(Ok((committed_room_id, modified_segments))) => EditionCommitResult::Success {
                     source_room_id: edition.source_room_id(),
-                    committed_room_id: destination.id(),
+                    committed_room_id,
                     modified_segments,
                 },
-                Err(err) => {
+                Some(Err(err)) => {
                     error!(logger, "Room adjustment job failed: {}", err);
                     let app_error = AppError::new(AppErrorKind::EditionCommitTaskFailed, err);
                     app_error.notify_sentry(&logger);
@@ -354,9 +455,10 @@ impl RequestHandler for CommitHandler {
                         error: app_error.to_svc_error(),
                     }
                 }
+                None => EditionCommitResult::Cancelled {},
             };
 
-            // Publish success/failure notification.
+            // Publish success/failure/cancellation notification.
             let notification = EditionCommitNotification {
                 status: result.status(),
                 tags: room.tags().map(|t| t.to_owned()),
@@ -364,11 +466,43 @@ impl RequestHandler for CommitHandler {
             };
 
             let timing = ShortTermTimingProperties::new(Utc::now());
-            let props = OutgoingEventProperties::new("edition.commit", timing);
-            let path = format!("audiences/{}/events", room.audience());
+            let mut props = OutgoingEventProperties::new("edition.commit", timing);
+            props.set_tracking(tracking.clone());
             let event = OutgoingEvent::broadcast(notification, props, &path);
+            let _ = tx.unbounded_send(Box::new(event) as Box<dyn IntoPublishableMessage + Send>);
+
+            // Adjust the freshly committed room when configured.
+            let (auto_adjust, (destination, segments)) = match (auto_adjust, destination) {
+                (Some(auto_adjust), Some(destination)) => (auto_adjust, destination),
+                _ => return,
+            };
+
+            let started_at = match destination.time() {
+                Ok(time) => *time.start(),
+                Err(err) => {
+                    error!(logger, "Invalid committed room time: {}", err);
+                    return;
+                }
+            };
+
+            let operation_result = adjust_room(
+                &db,
+                &profiler,
+                &destination,
+                started_at,
+                &segments,
+                auto_adjust.offset,
+                max_cut_gaps,
+            )
+            .await;
 
-            Box::new(event) as Box<dyn IntoPublishableMessage + Send>
+            let notification = RoomAdjustNotification::new(&destination, operation_result, &logger);
+            let path = topics.audience_events(destination.audience());
+            let timing = ShortTermTimingProperties::new(Utc::now());
+            let mut props = OutgoingEventProperties::new("room.adjust", timing);
+            props.set_tracking(tracking);
+            let event = OutgoingEvent::broadcast(notification, props, &path);
+            let _ = tx.unbounded_send(Box::new(event) as Box<dyn IntoPublishableMessage + Send>);
         });
 
         // Respond with 202.
@@ -381,8 +515,7 @@ impl RequestHandler for CommitHandler {
             Some(authz_time),
         ));
 
-        let notification = notification_future.into_stream();
-        Ok(Box::new(response.chain(notification)))
+        Ok(Box::new(response.chain(rx)))
     }
 }
 
@@ -407,6 +540,7 @@ enum EditionCommitResult {
     Error {
         error: SvcError,
     },
+    Cancelled {},
 }
 
 impl EditionCommitResult {
@@ -414,196 +548,1373 @@ impl EditionCommitResult {
         match self {
             Self::Success { .. } => "success",
             Self::Error { .. } => "error",
+            Self::Cancelled {} => "cancelled",
         }
     }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
-#[cfg(test)]
-mod tests {
-    mod create {
-        use super::super::*;
-        use crate::db::edition::Object as Edition;
-        use crate::test_helpers::prelude::*;
-
-        #[test]
-        fn create_edition() {
-            async_std::task::block_on(async {
-                let db = TestDb::new().await;
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-
-                let room = {
-                    let mut conn = db.get_conn().await;
-                    shared_helpers::insert_room(&mut conn).await
-                };
+pub(crate) struct CommitCancelHandler;
 
-                // Allow agent to create editions
-                let mut authz = TestAuthz::new();
-                let room_id = room.id().to_string();
-                let object = vec!["rooms", &room_id];
-                authz.allow(agent.account_id(), object, "update");
+#[derive(Debug, Deserialize)]
+pub(crate) struct CommitCancelRequest {
+    id: Uuid,
+}
 
-                // Make edition.create request
-                let mut context = TestContext::new(db, authz);
-                let payload = CreateRequest { room_id: room.id() };
+#[async_trait]
+impl RequestHandler for CommitCancelHandler {
+    type Payload = CommitCancelRequest;
+    // Not mutating on its own: cancellation rolls the commit back so it's left available
+    // in read only mode to stop writes that are still in flight.
 
-                let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
-                    .await
-                    .expect("Failed to create edition");
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let (edition, room) = {
+            let query = db::edition::FindWithRoomQuery::new(payload.id);
+            let mut conn = context.get_ro_conn().await?;
 
-                // Assert response
-                let (edition, respp, _) = find_response::<Edition>(messages.as_slice());
-                assert_eq!(respp.status(), ResponseStatus::CREATED);
-                assert_eq!(edition.source_room_id(), room.id());
-            });
-        }
+            let maybe_edition = context
+                .profiler()
+                .measure(
+                    (
+                        ProfilerKeys::EditionFindWithRoomQuery,
+                        Some(reqp.method().to_owned()),
+                    ),
+                    query.execute(&mut conn),
+                )
+                .await
+                .context("Failed to find edition with room")
+                .error(AppErrorKind::DbQueryFailed)?;
 
-        #[test]
-        fn create_edition_not_authorized() {
-            async_std::task::block_on(async {
-                let db = TestDb::new().await;
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            match maybe_edition {
+                Some(edition_with_room) => edition_with_room,
+                None => {
+                    return Err(anyhow!("Edition not found")).error(AppErrorKind::EditionNotFound);
+                }
+            }
+        };
 
-                let room = {
-                    let mut conn = db.get_conn().await;
-                    shared_helpers::insert_room(&mut conn).await
-                };
+        helpers::add_room_logger_tags(context, &room);
+        context.add_logger_tags(o!("edition_id" => edition.id().to_string()));
 
-                let mut context = TestContext::new(db, TestAuthz::new());
-                let payload = CreateRequest { room_id: room.id() };
+        let object = AuthzObject::room(&room).into();
 
-                let response = handle_request::<CreateHandler>(&mut context, &agent, payload)
-                    .await
-                    .expect_err("Unexpected success creating edition with no authorization");
+        let authz_time = context
+            .authz()
+            .authorize(
+                room.audience().into(),
+                reqp.as_account_id().to_owned(),
+                object,
+                "update".into(),
+            )
+            .await?;
 
-                assert_eq!(response.status(), ResponseStatus::FORBIDDEN);
-            });
+        // The commit task itself rolls back and notifies with `cancelled` status.
+        if !context
+            .task_registry()
+            .cancel(TaskKind::EditionCommit, edition.id())
+        {
+            return Err(anyhow!("No running commit for the edition"))
+                .error(AppErrorKind::TaskNotFound);
         }
 
-        #[test]
-        fn create_edition_missing_room() {
-            async_std::task::block_on(async {
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-                let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
 
-                let payload = CreateRequest {
-                    room_id: Uuid::new_v4(),
-                };
+////////////////////////////////////////////////////////////////////////////////
 
-                let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
-                    .await
-                    .expect_err("Unexpected success creating edition for no room");
+pub(crate) struct ReadSegmentsHandler;
 
-                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-                assert_eq!(err.kind(), "room_not_found");
-            });
-        }
-    }
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReadSegmentsRequest {
+    id: Uuid,
+}
 
-    mod list {
-        use super::super::*;
-        use crate::db::edition::Object as Edition;
-        use crate::test_helpers::prelude::*;
+#[derive(Serialize)]
+struct ReadSegmentsResponse {
+    committed_room_id: Uuid,
+    #[serde(with = "crate::db::adjustment::serde::segments")]
+    modified_segments: Segments,
+}
 
-        #[test]
-        fn list_editions() {
-            async_std::task::block_on(async {
-                let db = TestDb::new().await;
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+#[async_trait]
+impl RequestHandler for ReadSegmentsHandler {
+    type Payload = ReadSegmentsRequest;
 
-                let (room, editions) = {
-                    let mut conn = db.get_conn().await;
-                    let room = shared_helpers::insert_room(&mut conn).await;
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let (edition, room) = {
+            let query = db::edition::FindWithRoomQuery::new(payload.id);
+            let mut conn = context.get_read_conn(reqp.method()).await?;
 
-                    let edition = factory::Edition::new(room.id(), agent.agent_id())
-                        .insert(&mut conn)
-                        .await;
+            let maybe_edition = context
+                .profiler()
+                .measure(
+                    (
+                        ProfilerKeys::EditionFindWithRoomQuery,
+                        Some(reqp.method().to_owned()),
+                    ),
+                    query.execute(&mut conn),
+                )
+                .await
+                .context("Failed to find edition with room")
+                .error(AppErrorKind::DbQueryFailed)?;
 
-                    (room, vec![edition])
-                };
+            match maybe_edition {
+                Some(edition_with_room) => edition_with_room,
+                None => {
+                    return Err(anyhow!("Edition not found")).error(AppErrorKind::EditionNotFound);
+                }
+            }
+        };
 
-                let mut authz = TestAuthz::new();
-                let room_id = room.id().to_string();
-                let object = vec!["rooms", &room_id];
-                authz.allow(agent.account_id(), object, "update");
+        helpers::add_room_logger_tags(context, &room);
+        context.add_logger_tags(o!("edition_id" => edition.id().to_string()));
 
-                let mut context = TestContext::new(db, authz);
+        let object = AuthzObject::room(&room).into();
 
-                let payload = ListRequest {
-                    room_id: room.id(),
-                    last_created_at: None,
-                    limit: None,
-                };
+        let authz_time = context
+            .authz()
+            .authorize(
+                room.audience().into(),
+                reqp.as_account_id().to_owned(),
+                object,
+                "update".into(),
+            )
+            .await?;
 
-                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
-                    .await
-                    .expect("Failed to list editions");
+        let response = match edition.committed() {
+            Some((committed_room_id, segments)) => ReadSegmentsResponse {
+                committed_room_id,
+                modified_segments: segments.to_owned(),
+            },
+            None => {
+                return Err(anyhow!("Edition is not committed"))
+                    .error(AppErrorKind::EditionNotCommitted);
+            }
+        };
 
-                let (resp_editions, respp, _) = find_response::<Vec<Edition>>(messages.as_slice());
-                assert_eq!(respp.status(), ResponseStatus::OK);
-                assert_eq!(resp_editions.len(), editions.len());
-                assert_eq!(resp_editions[0].id(), editions[0].id());
-            });
-        }
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            response,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
 
-        #[test]
-        fn list_editions_not_authorized() {
-            async_std::task::block_on(async {
-                let db = TestDb::new().await;
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+////////////////////////////////////////////////////////////////////////////////
 
-                let (room, _editions) = {
-                    let mut conn = db.get_conn().await;
-                    let room = shared_helpers::insert_room(&mut conn).await;
+pub(crate) struct ValidateHandler;
 
-                    let edition = factory::Edition::new(room.id(), agent.agent_id())
-                        .insert(&mut conn)
-                        .await;
+#[derive(Debug, Deserialize)]
+pub(crate) struct ValidateRequest {
+    id: Uuid,
+}
 
-                    (room, vec![edition])
-                };
+#[async_trait]
+impl RequestHandler for ValidateHandler {
+    type Payload = ValidateRequest;
 
-                let mut context = TestContext::new(db, TestAuthz::new());
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let mut conn = context.get_read_conn(reqp.method()).await?;
+
+        let (edition, room) = {
+            let query = db::edition::FindWithRoomQuery::new(payload.id);
+
+            let maybe_edition = context
+                .profiler()
+                .measure(
+                    (
+                        ProfilerKeys::EditionFindWithRoomQuery,
+                        Some(reqp.method().to_owned()),
+                    ),
+                    query.execute(&mut conn),
+                )
+                .await
+                .context("Failed to find edition with room")
+                .error(AppErrorKind::DbQueryFailed)?;
+
+            match maybe_edition {
+                Some(edition_with_room) => edition_with_room,
+                None => {
+                    return Err(anyhow!("Edition not found")).error(AppErrorKind::EditionNotFound);
+                }
+            }
+        };
+
+        helpers::add_room_logger_tags(context, &room);
+        context.add_logger_tags(o!("edition_id" => edition.id().to_string()));
+
+        // Validation reveals the same information as the commit so it requires the same access.
+        let object = AuthzObject::room(&room).into();
+
+        let authz_time = context
+            .authz()
+            .authorize(
+                room.audience().into(),
+                reqp.as_account_id().to_owned(),
+                object,
+                "update".into(),
+            )
+            .await?;
+
+        let report = validate_edition(
+            &mut conn,
+            &context.profiler(),
+            &edition,
+            &room,
+            context.config().max_cut_gaps(),
+            &context.config().cut,
+        )
+        .await
+        .context("Failed to validate edition")
+        .error(AppErrorKind::DbQueryFailed)?;
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            report,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) struct EstimateHandler;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EstimateRequest {
+    id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+struct EstimateResponse {
+    events_count: i64,
+}
+
+#[async_trait]
+impl RequestHandler for EstimateHandler {
+    type Payload = EstimateRequest;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let mut conn = context.get_read_conn(reqp.method()).await?;
+
+        let (edition, room) = {
+            let query = db::edition::FindWithRoomQuery::new(payload.id);
+
+            let maybe_edition = context
+                .profiler()
+                .measure(
+                    (
+                        ProfilerKeys::EditionFindWithRoomQuery,
+                        Some(reqp.method().to_owned()),
+                    ),
+                    query.execute(&mut conn),
+                )
+                .await
+                .context("Failed to find edition with room")
+                .error(AppErrorKind::DbQueryFailed)?;
+
+            match maybe_edition {
+                Some(edition_with_room) => edition_with_room,
+                None => {
+                    return Err(anyhow!("Edition not found")).error(AppErrorKind::EditionNotFound);
+                }
+            }
+        };
+
+        helpers::add_room_logger_tags(context, &room);
+        context.add_logger_tags(o!("edition_id" => edition.id().to_string()));
+
+        let object = AuthzObject::room(&room).into();
+
+        let authz_time = context
+            .authz()
+            .authorize(
+                room.audience().into(),
+                reqp.as_account_id().to_owned(),
+                object,
+                "read".into(),
+            )
+            .await?;
+
+        let events_count = estimate_edition(&mut conn, &context.profiler(), &edition, &room)
+            .await
+            .context("Failed to estimate edition")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            EstimateResponse { events_count },
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    mod create {
+        use super::super::*;
+        use crate::db::edition::Object as Edition;
+        use crate::test_helpers::prelude::*;
+
+        #[test]
+        fn create_edition() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let mut conn = db.get_conn().await;
+                    shared_helpers::insert_room(&mut conn).await
+                };
+
+                // Allow agent to create editions
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                // Make edition.create request
+                let mut context = TestContext::new(db, authz);
+                let payload = CreateRequest { room_id: room.id() };
+
+                let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Failed to create edition");
+
+                // Assert response
+                let (edition, respp, _) = find_response::<Edition>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::CREATED);
+                assert_eq!(edition.source_room_id(), room.id());
+            });
+        }
+
+        #[test]
+        fn create_edition_not_authorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let mut conn = db.get_conn().await;
+                    shared_helpers::insert_room(&mut conn).await
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let payload = CreateRequest { room_id: room.id() };
+
+                let response = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success creating edition with no authorization");
+
+                assert_eq!(response.status(), ResponseStatus::FORBIDDEN);
+            });
+        }
+
+        #[test]
+        fn create_edition_missing_room() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+
+                let payload = CreateRequest {
+                    room_id: Uuid::new_v4(),
+                };
+
+                let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success creating edition for no room");
+
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "room_not_found");
+            });
+        }
+    }
+
+    mod list {
+        use std::ops::Bound;
+
+        use chrono::{Duration, SubsecRound};
+
+        use super::super::*;
+        use crate::db::change::ChangeType;
+        use crate::db::edition::Object as Edition;
+        use crate::test_helpers::prelude::*;
+
+        #[test]
+        fn list_editions() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, editions) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    let edition = factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    (room, vec![edition])
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    last_created_at: None,
+                    limit: None,
+                    with_change_count: false,
+                    include_derived: false,
+                };
+
+                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Failed to list editions");
+
+                let (resp_editions, respp, _) = find_response::<Vec<Edition>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(resp_editions.len(), editions.len());
+                assert_eq!(resp_editions[0].id(), editions[0].id());
+            });
+        }
+
+        #[test]
+        fn list_editions_with_change_count() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, editions) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+                    let mut editions = vec![];
+
+                    for change_count in 0..3 {
+                        let edition = factory::Edition::new(room.id(), agent.agent_id())
+                            .insert(&mut conn)
+                            .await;
+
+                        for _ in 0..change_count {
+                            factory::Change::new(edition.id(), ChangeType::Addition)
+                                .event_data(json!({"message": "m"}))
+                                .event_kind("message")
+                                .event_occurred_at(1_000_000_000)
+                                .event_created_by(agent.agent_id())
+                                .insert(&mut conn)
+                                .await;
+                        }
+
+                        editions.push((edition, change_count));
+                    }
+
+                    (room, editions)
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    last_created_at: None,
+                    limit: None,
+                    with_change_count: true,
+                    include_derived: false,
+                };
+
+                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Failed to list editions");
+
+                let (resp_editions, respp, _) = find_response::<Vec<Edition>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(resp_editions.len(), editions.len());
+
+                for (edition, change_count) in editions {
+                    let resp_edition = resp_editions
+                        .iter()
+                        .find(|e| e.id() == edition.id())
+                        .expect("Edition not found");
+
+                    assert_eq!(resp_edition.change_count(), Some(change_count));
+                }
+            });
+        }
+
+        #[test]
+        fn list_editions_including_derived() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, editions) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+                    let now = Utc::now().trunc_subsecs(0);
+
+                    let derived_room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .source_room_id(room.id())
+                        .insert(&mut conn)
+                        .await;
+
+                    let mut editions = vec![];
+
+                    for room_id in &[room.id(), derived_room.id()] {
+                        let edition = factory::Edition::new(*room_id, agent.agent_id())
+                            .insert(&mut conn)
+                            .await;
+
+                        editions.push(edition);
+                    }
+
+                    (room, editions)
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    last_created_at: None,
+                    limit: None,
+                    with_change_count: false,
+                    include_derived: true,
+                };
+
+                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Failed to list editions");
+
+                let (resp_editions, respp, _) = find_response::<Vec<Edition>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+
+                let mut ids = resp_editions.iter().map(|e| e.id()).collect::<Vec<_>>();
+                ids.sort();
+                let mut expected_ids = editions.iter().map(|e| e.id()).collect::<Vec<_>>();
+                expected_ids.sort();
+                assert_eq!(ids, expected_ids);
+            });
+        }
+
+        #[test]
+        fn list_editions_with_non_utc_last_created_at() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    room
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+                let last_created_at = Utc::now() - Duration::hours(1);
+                let moscow = FixedOffset::east(3 * 3600);
+
+                let build_payload = |last_created_at: String| {
+                    serde_json::from_value::<ListRequest>(json!({
+                        "room_id": room.id(),
+                        "last_created_at": last_created_at,
+                    }))
+                    .expect("Failed to parse payload")
+                };
+
+                // Converted to UTC by default.
+                let payload = build_payload(last_created_at.with_timezone(&moscow).to_rfc3339());
+
+                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Failed to list editions");
+
+                let (items, respp, _) = find_response::<Vec<Edition>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(items.len(), 1);
+
+                // Rejected in strict mode while UTC is still fine.
+                context.config_mut().strict_utc_datetimes = true;
+                let payload = build_payload(last_created_at.with_timezone(&moscow).to_rfc3339());
+
+                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success listing editions with non-UTC datetime");
+
+                assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+                assert_eq!(err.kind(), "invalid_payload");
+
+                let payload = build_payload(last_created_at.to_rfc3339());
+
+                handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Failed to list editions");
+            });
+        }
+
+        #[test]
+        fn list_editions_not_authorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, _editions) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    let edition = factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    (room, vec![edition])
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    last_created_at: None,
+                    limit: None,
+                    with_change_count: false,
+                    include_derived: false,
+                };
+
+                let resp = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success without authorization on editions list");
+
+                assert_eq!(resp.status(), ResponseStatus::FORBIDDEN);
+            });
+        }
+
+        #[test]
+        fn list_editions_missing_room() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+
+                let payload = ListRequest {
+                    room_id: Uuid::new_v4(),
+                    last_created_at: None,
+                    limit: None,
+                    with_change_count: false,
+                    include_derived: false,
+                };
+
+                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success listing editions for no room");
+
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "room_not_found");
+            });
+        }
+    }
+
+    mod commit {
+        use super::super::*;
+        use crate::db::change::ChangeType;
+        use crate::test_helpers::prelude::*;
+
+        #[test]
+        fn commit_edition() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, edition) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    let edition = factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    (room, edition)
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+
+                let payload = CommitRequest {
+                    id: edition.id(),
+                    destination_audience: None,
+                    change_ids: None,
+                };
+
+                let messages = handle_request::<CommitHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Edition commit failed");
+
+                let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::ACCEPTED);
+
+                let (payload, evp, _) = find_event::<JsonValue>(messages.as_slice());
+                assert_eq!(evp.label(), "edition.commit");
+                assert_eq!(payload["status"], "success");
+
+                // The response and the async task notification share the same tracking id.
+                assert!(respp.tracking_id().is_some());
+                assert_eq!(evp.tracking_id(), respp.tracking_id());
+
+                // The whole task duration is recorded with the outcome.
+                let report = context
+                    .profiler()
+                    .flush(60)
+                    .expect("Failed to flush profiler");
+
+                let (_, entry) = report
+                    .into_iter()
+                    .find(|((key, method), _)| {
+                        *key == ProfilerKeys::EditionCommitTotal
+                            && method.as_deref() == Some("edition.commit.success")
+                    })
+                    .expect("Missing edition commit total duration");
+
+                assert!(entry.max > 0);
+            });
+        }
+
+        #[test]
+        fn commit_edition_with_auto_adjust() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, edition) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    let edition = factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    (room, edition)
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+                context.config_mut().auto_adjust_on_commit =
+                    Some(crate::config::AutoAdjustConfig { offset: 0 });
+
+                let payload = CommitRequest {
+                    id: edition.id(),
+                    destination_audience: None,
+                    change_ids: None,
+                };
+
+                let messages = handle_request::<CommitHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Edition commit failed");
+
+                let (commit_payload, commit_evp, _) =
+                    find_event_by_predicate::<JsonValue, _>(messages.as_slice(), |evp| {
+                        evp.label() == "edition.commit"
+                    })
+                    .expect("Edition commit notification not found");
+
+                assert_eq!(commit_payload["status"], "success");
+
+                let (adjust_payload, adjust_evp, _) =
+                    find_event_by_predicate::<JsonValue, _>(messages.as_slice(), |evp| {
+                        evp.label() == "room.adjust"
+                    })
+                    .expect("Room adjust notification not found");
+
+                assert_eq!(adjust_payload["status"], "success");
+                assert_eq!(
+                    adjust_payload["room_id"],
+                    commit_payload["committed_room_id"]
+                );
+                assert_eq!(adjust_evp.tracking_id(), commit_evp.tracking_id());
+            });
+        }
+
+        #[test]
+        fn commit_edition_twice() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, edition) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    let edition = factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    (room, edition)
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+                let mut committed_room_ids = vec![];
+
+                for _ in 0..2 {
+                    let payload = CommitRequest {
+                        id: edition.id(),
+                        destination_audience: None,
+                        change_ids: None,
+                    };
+
+                    let messages = handle_request::<CommitHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect("Edition commit failed");
+
+                    let (payload, _, _) = find_event::<JsonValue>(messages.as_slice());
+                    assert_eq!(payload["status"], "success");
+                    committed_room_ids.push(payload["committed_room_id"].to_owned());
+                }
+
+                // The second commit gets short-circuited with the result of the first one.
+                assert_eq!(committed_room_ids[0], committed_room_ids[1]);
+
+                let mut conn = context.db().acquire().await.expect("Failed conn checkout");
+
+                let derived_rooms = crate::db::room::DerivedListQuery::new(room.id())
+                    .execute(&mut conn)
+                    .await
+                    .expect("Failed to list derived rooms");
+
+                assert_eq!(derived_rooms.len(), 1);
+            });
+        }
+
+        #[test]
+        fn commit_edition_twice_with_different_change_ids() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, edition, changes) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    let edition = factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    let mut changes = vec![];
+
+                    for idx in 1..4 {
+                        let event = factory::Event::new()
+                            .room_id(room.id())
+                            .kind("message")
+                            .data(&json!({ "text": format!("message {}", idx) }))
+                            .occurred_at(idx * 1000)
+                            .created_by(&agent.agent_id())
+                            .insert(&mut conn)
+                            .await;
+
+                        let change = factory::Change::new(edition.id(), ChangeType::Modification)
+                            .event_id(event.id())
+                            .event_data(json!({"text": "modified"}))
+                            .insert(&mut conn)
+                            .await;
+
+                        changes.push(change.id());
+                    }
+
+                    (room, edition, changes)
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+                let mut committed_room_ids = vec![];
+
+                // The same subset in another order is the same commit.
+                for change_ids in &[
+                    vec![changes[0], changes[1]],
+                    vec![changes[1], changes[0], changes[1]],
+                ] {
+                    let payload = CommitRequest {
+                        id: edition.id(),
+                        destination_audience: None,
+                        change_ids: Some(change_ids.to_owned()),
+                    };
+
+                    let messages = handle_request::<CommitHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect("Edition commit failed");
+
+                    let (payload, _, _) = find_event::<JsonValue>(messages.as_slice());
+                    assert_eq!(payload["status"], "success");
+                    committed_room_ids.push(payload["committed_room_id"].to_owned());
+                }
+
+                assert_eq!(committed_room_ids[0], committed_room_ids[1]);
+
+                // Another subset of changes is not.
+                for change_ids in &[None, Some(vec![changes[0]]), Some(changes.to_owned())] {
+                    let payload = CommitRequest {
+                        id: edition.id(),
+                        destination_audience: None,
+                        change_ids: change_ids.to_owned(),
+                    };
+
+                    let err = handle_request::<CommitHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect_err("Unexpected success committing with different changes");
+
+                    assert_eq!(err.status(), ResponseStatus::CONFLICT);
+                    assert_eq!(err.kind(), "conflict");
+                }
+            });
+        }
 
-                let payload = ListRequest {
-                    room_id: room.id(),
-                    last_created_at: None,
-                    limit: None,
+        #[test]
+        fn commit_edition_into_not_allowed_audience() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, edition) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    let edition = factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    (room, edition)
                 };
 
-                let resp = handle_request::<ListHandler>(&mut context, &agent, payload)
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+
+                context.config_mut().edition_commit_audiences.insert(
+                    USR_AUDIENCE.to_owned(),
+                    vec!["publishing.example.org".to_owned()],
+                );
+
+                let payload = CommitRequest {
+                    id: edition.id(),
+                    destination_audience: Some("other.example.org".to_owned()),
+                    change_ids: None,
+                };
+
+                let err = handle_request::<CommitHandler>(&mut context, &agent, payload)
                     .await
-                    .expect_err("Unexpected success without authorization on editions list");
+                    .expect_err("Unexpected success committing into not allowed audience");
 
-                assert_eq!(resp.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+                assert_eq!(err.kind(), "invalid_payload");
             });
         }
+    }
+
+    mod read_segments {
+        use super::super::*;
+        use crate::test_helpers::prelude::*;
 
         #[test]
-        fn list_editions_missing_room() {
+        fn read_segments() {
             async_std::task::block_on(async {
+                let db = TestDb::new().await;
                 let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-                let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
 
-                let payload = ListRequest {
-                    room_id: Uuid::new_v4(),
-                    last_created_at: None,
-                    limit: None,
+                let (room, edition) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    let edition = factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    (room, edition)
                 };
 
-                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+
+                // Nothing to read before the commit.
+                let payload = ReadSegmentsRequest { id: edition.id() };
+
+                let err = handle_request::<ReadSegmentsHandler>(&mut context, &agent, payload)
                     .await
-                    .expect_err("Unexpected success listing editions for no room");
+                    .expect_err("Unexpected success reading segments of not committed edition");
 
                 assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-                assert_eq!(err.kind(), "room_not_found");
+                assert_eq!(err.kind(), "edition_not_committed");
+
+                // Commit the edition.
+                let payload = CommitRequest {
+                    id: edition.id(),
+                    destination_audience: None,
+                    change_ids: None,
+                };
+
+                let messages = handle_request::<CommitHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Edition commit failed");
+
+                let (notification, _, _) = find_event::<JsonValue>(messages.as_slice());
+                assert_eq!(notification["status"], "success");
+
+                // Re-fetch the segments.
+                let payload = ReadSegmentsRequest { id: edition.id() };
+
+                let messages = handle_request::<ReadSegmentsHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Failed to read edition segments");
+
+                let (response, respp, _) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(
+                    response["committed_room_id"],
+                    notification["committed_room_id"]
+                );
+                assert_eq!(
+                    response["modified_segments"],
+                    notification["modified_segments"]
+                );
+            });
+        }
+
+        #[test]
+        fn read_segments_not_authorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let edition = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let payload = ReadSegmentsRequest { id: edition.id() };
+
+                let err = handle_request::<ReadSegmentsHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success reading edition segments");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            });
+        }
+    }
+
+    mod validate {
+        use super::super::*;
+        use crate::db::change::ChangeType;
+        use crate::test_helpers::prelude::*;
+
+        #[test]
+        fn validate_broken_edition() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, edition) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+                    let other_room = shared_helpers::insert_room(&mut conn).await;
+
+                    // A cut stop without a preceding start.
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind("stream")
+                        .data(&json!({"cut": "stop"}))
+                        .occurred_at(1_000_000_000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    let foreign_event = factory::Event::new()
+                        .room_id(other_room.id())
+                        .kind("message")
+                        .data(&json!({"text": "hello"}))
+                        .occurred_at(2_000_000_000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    let edition = factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    // A removal of an event that doesn't belong to the edition's room.
+                    factory::Change::new(edition.id(), ChangeType::Removal)
+                        .event_id(foreign_event.id())
+                        .insert(&mut conn)
+                        .await;
+
+                    (room, edition)
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+                let payload = ValidateRequest { id: edition.id() };
+
+                let messages = handle_request::<ValidateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Edition validation failed");
+
+                let (report, respp, _) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(report["ready"], false);
+
+                let error_kinds = report["errors"]
+                    .as_array()
+                    .expect("Errors are not an array")
+                    .iter()
+                    .map(|issue| issue["kind"].as_str().expect("Missing issue kind"))
+                    .collect::<Vec<_>>();
+
+                assert_eq!(error_kinds, vec!["invalid_cuts", "missing_change_event"]);
+                assert_eq!(report["warnings"], json!([]));
+            });
+        }
+
+        #[test]
+        fn validate_edition_not_authorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let edition = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let payload = ValidateRequest { id: edition.id() };
+
+                let err = handle_request::<ValidateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success validating edition");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            });
+        }
+    }
+
+    mod estimate {
+        use super::super::*;
+        use crate::db::change::ChangeType;
+        use crate::test_helpers::prelude::*;
+
+        #[test]
+        fn estimate_edition() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, edition) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+                    let mut events = vec![];
+
+                    for i in 1..4 {
+                        let event = factory::Event::new()
+                            .room_id(room.id())
+                            .kind("message")
+                            .data(&json!({ "text": format!("message {}", i) }))
+                            .occurred_at(i * 1_000_000_000)
+                            .created_by(&agent.agent_id())
+                            .insert(&mut conn)
+                            .await;
+
+                        events.push(event);
+                    }
+
+                    let edition = factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    factory::Change::new(edition.id(), ChangeType::Removal)
+                        .event_id(events[0].id())
+                        .insert(&mut conn)
+                        .await;
+
+                    for _ in 0..2 {
+                        factory::Change::new(edition.id(), ChangeType::Addition)
+                            .event_data(json!({"text": "added"}))
+                            .event_kind("message")
+                            .event_occurred_at(5_000_000_000)
+                            .event_created_by(agent.agent_id())
+                            .insert(&mut conn)
+                            .await;
+                    }
+
+                    (room, edition)
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+                let mut context = TestContext::new(db, authz);
+                let payload = EstimateRequest { id: edition.id() };
+
+                let messages = handle_request::<EstimateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Edition estimate failed");
+
+                let (estimate, respp, _) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(estimate["events_count"], 4);
+
+                // The estimate must match the actual commit result.
+                let payload = CommitRequest {
+                    id: edition.id(),
+                    destination_audience: None,
+                    change_ids: None,
+                };
+
+                let messages = handle_request::<CommitHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Edition commit failed");
+
+                let (commit_payload, _, _) = find_event::<JsonValue>(messages.as_slice());
+                assert_eq!(commit_payload["status"], "success");
+
+                let committed_room_id = commit_payload["committed_room_id"]
+                    .as_str()
+                    .and_then(|id| Uuid::parse_str(id).ok())
+                    .expect("Missing committed room id");
+
+                let mut conn = context
+                    .db()
+                    .acquire()
+                    .await
+                    .expect("Failed to get DB connection");
+
+                let committed_events = db::event::ListQuery::new()
+                    .room_id(committed_room_id)
+                    .execute(&mut conn)
+                    .await
+                    .expect("Failed to list committed events");
+
+                assert_eq!(
+                    committed_events.len() as u64,
+                    estimate["events_count"].as_u64().unwrap()
+                );
+            });
+        }
+
+        #[test]
+        fn estimate_edition_not_authorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let edition = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    factory::Edition::new(room.id(), agent.agent_id())
+                        .insert(&mut conn)
+                        .await
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let payload = EstimateRequest { id: edition.id() };
+
+                let err = handle_request::<EstimateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success estimating edition");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
             });
         }
     }
 
     mod delete {
         use super::super::*;
+        use crate::db::change::ChangeType;
         use crate::db::edition::Object as Edition;
         use crate::test_helpers::prelude::*;
 
@@ -663,6 +1974,60 @@ mod tests {
             });
         }
 
+        #[test]
+        fn delete_edition_with_changes() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, edition) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+                    let edition =
+                        shared_helpers::insert_edition(&mut conn, &room, agent.agent_id()).await;
+
+                    for _ in 0..3 {
+                        factory::Change::new(edition.id(), ChangeType::Addition)
+                            .event_data(json!({"message": "m"}))
+                            .event_kind("message")
+                            .event_occurred_at(1_000_000_000)
+                            .event_created_by(agent.agent_id())
+                            .insert(&mut conn)
+                            .await;
+                    }
+
+                    (room, edition)
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                let mut context = TestContext::new(db, authz);
+
+                let payload = DeleteRequest { id: edition.id() };
+
+                handle_request::<DeleteHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Failed to delete edition");
+
+                let mut conn = context
+                    .db()
+                    .acquire()
+                    .await
+                    .expect("Failed to get DB connection");
+
+                // The changes go away with the edition by the foreign key cascade.
+                let change_count = db::change::CountQuery::new(edition.id())
+                    .execute(&mut conn)
+                    .await
+                    .expect("Failed to count changes");
+
+                assert_eq!(change_count, 0);
+            });
+        }
+
         #[test]
         fn delete_edition_not_authorized() {
             async_std::task::block_on(async {
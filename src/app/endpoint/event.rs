@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+use std::result::Result as StdResult;
+
 use anyhow::Context as AnyhowContext;
 use async_std::stream;
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
 use serde_derive::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
+use serde_json::{json, Value as JsonValue};
+use sqlx::postgres::PgConnection;
 use svc_agent::Authenticable;
 use svc_agent::{
     mqtt::{IncomingRequestProperties, ResponseStatus},
@@ -12,9 +16,12 @@ use svc_agent::{
 use uuid::Uuid;
 
 use crate::app::context::Context;
+use crate::app::endpoint::authz::{AuthzIntent, BatchAuthorize};
 use crate::app::endpoint::prelude::*;
 use crate::db;
 use crate::db::event::Object as Event;
+use crate::db::room::Object as Room;
+use crate::db::room_time::RoomTimeBound;
 
 ///////////////////////////////////////////////////////////////////////////////
 
@@ -25,12 +32,20 @@ pub(crate) struct CreateRequest {
     pub kind: String,
     pub set: Option<String>,
     pub label: Option<String>,
-    pub attribute: Option<String>,
+    #[serde(
+        default,
+        alias = "attribute",
+        deserialize_with = "crate::serde::one_or_many::deserialize"
+    )]
+    pub attributes: Vec<String>,
     pub data: JsonValue,
     #[serde(default = "CreateRequest::default_is_claim")]
     pub is_claim: bool,
     #[serde(default = "CreateRequest::default_is_persistent")]
     pub is_persistent: bool,
+    #[serde(default = "CreateRequest::default_notify")]
+    pub notify: bool,
+    pub expected_version: Option<Uuid>,
 }
 
 impl CreateRequest {
@@ -41,10 +56,25 @@ impl CreateRequest {
     fn default_is_persistent() -> bool {
         true
     }
+
+    fn default_notify() -> bool {
+        true
+    }
 }
 
 pub(crate) struct CreateHandler;
 
+#[derive(Serialize)]
+struct CreateResponse {
+    #[serde(flatten)]
+    event: Event,
+    #[serde(
+        with = "chrono::serde::ts_seconds_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    room_closes_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Serialize)]
 pub(crate) struct TenantClaimNotification {
     #[serde(flatten)]
@@ -56,17 +86,58 @@ pub(crate) struct TenantClaimNotification {
 #[async_trait]
 impl RequestHandler for CreateHandler {
     type Payload = CreateRequest;
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
-        payload: Self::Payload,
+        mut payload: Self::Payload,
         reqp: &IncomingRequestProperties,
     ) -> Result {
+        validate_data_complexity(
+            &payload.data,
+            context.config().max_data_depth(),
+            context.config().max_data_array_length(),
+        )?;
+
+        // Fill omitted set according to the configured defaults.
+        if payload.set.is_none() {
+            payload.set = context
+                .config()
+                .default_event_set(&payload.kind)
+                .map(|set| set.to_owned());
+        }
+
+        // Drop data keys not allowed for the kind to avoid collecting what we mustn't.
+        if let Some(allowed_keys) = context.config().allowed_data_keys.get(&payload.kind) {
+            if let JsonValue::Object(ref mut data) = payload.data {
+                let dropped_keys = data
+                    .keys()
+                    .filter(|key| !allowed_keys.contains(key))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                if !dropped_keys.is_empty() {
+                    for key in dropped_keys.iter() {
+                        data.remove(key);
+                    }
+
+                    warn!(
+                        context.logger(),
+                        "Dropped data keys not allowed for kind '{}': {}",
+                        payload.kind,
+                        dropped_keys.join(", ")
+                    );
+                }
+            }
+        }
+
+        let grace_period = context.config().close_grace_period();
+
         let (room, author) = {
             let room = helpers::find_room(
                 context,
                 payload.room_id,
-                helpers::RoomTimeRequirement::Open,
+                helpers::RoomTimeRequirement::OpenWithGracePeriod(grace_period),
                 reqp.method(),
             )
             .await?;
@@ -120,41 +191,106 @@ impl RequestHandler for CreateHandler {
         let is_claim = payload.is_claim;
 
         // Authorize event creation on tenant with cache.
-        let key = if let Some(ref attribute) = payload.attribute {
-            attribute
+        // An event with several attributes requires permission for each of them.
+        let keys = if !payload.attributes.is_empty() {
+            payload.attributes.iter().map(|a| a.as_str()).collect()
         } else if payload.is_claim {
-            "claims"
+            vec!["claims"]
         } else {
-            "events"
+            vec!["events"]
         };
 
-        let object = {
-            let object = room.authz_object();
-            let mut object = object.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
-            object.extend([key, &payload.kind, "authors", &author].iter());
-            AuthzObject::new(&object).into()
+        let mut authz_time = Duration::zero();
+
+        for key in keys {
+            let object = {
+                let object = room.authz_object();
+                let mut object = object.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
+                object.extend([key, &payload.kind, "authors", &author].iter());
+                AuthzObject::new(&object).into()
+            };
+
+            authz_time = authz_time
+                + context
+                    .authz()
+                    .authorize(
+                        room.audience().into(),
+                        reqp.as_account_id().to_owned(),
+                        object,
+                        "create".into(),
+                    )
+                    .await?;
+        }
+
+        // Skipping room subscribers notification is a privilege of its own.
+        let authz_time = if payload.notify {
+            authz_time
+        } else {
+            let object = {
+                let object = room.authz_object();
+                let object = object.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
+                AuthzObject::new(&object).into()
+            };
+
+            let silent_authz_time = context
+                .authz()
+                .authorize(
+                    room.audience().into(),
+                    reqp.as_account_id().to_owned(),
+                    object,
+                    "create_silently".into(),
+                )
+                .await?;
+
+            authz_time + silent_authz_time
         };
 
-        let authz_time = context
-            .authz()
-            .authorize(
-                room.audience().into(),
-                reqp.as_account_id().to_owned(),
-                object,
-                "create".into(),
-            )
-            .await?;
+        let notify = payload.notify;
+
+        // Compare-and-set is only meaningful for versions of a persistent labeled event.
+        if payload.expected_version.is_some() && (!payload.is_persistent || payload.label.is_none())
+        {
+            return Err(anyhow!(
+                "'expected_version' requires a persistent event with 'label'"
+            ))
+            .error(AppErrorKind::InvalidPayload);
+        }
 
         // Calculate occurrence date.
-        let occurred_at = match room.time().map(|t| t.start().to_owned()) {
-            Ok(opened_at) => (Utc::now() - opened_at)
-                .num_nanoseconds()
-                .unwrap_or(std::i64::MAX),
+        let now = context.clock().now();
+
+        let occurred_at = match room.time() {
+            // Events arriving within the close grace period are stamped at the room's end.
+            Ok(time) => match time.end() {
+                RoomTimeBound::Excluded(stop)
+                    if grace_period > Duration::zero() && now >= *stop =>
+                {
+                    db::event::occurred_at_from_duration(*stop - *time.start()) - 1
+                }
+                _ => db::event::occurred_at_from_duration(now - *time.start()),
+            },
             _ => {
                 return Err(anyhow!("Invalid room time")).error(AppErrorKind::InvalidRoomTime);
             }
         };
 
+        validate_occurred_at(&room, occurred_at)?;
+
+        // Throttle event insertion into the room if configured. Only requests which passed
+        // authorization and validation spend the room's budget.
+        if let Some(limit) = context.config().event_rate_limit {
+            if !context
+                .room_rate_limiter()
+                .try_acquire(room.id(), limit, now)
+            {
+                return Err(anyhow!(
+                    "Room rate limit exceeded: {} events per second",
+                    limit
+                ))
+                .error(AppErrorKind::RoomRateLimitExceeded);
+            }
+        }
+
         let event = if payload.is_persistent {
             // Insert event into the DB.
             let CreateRequest {
@@ -162,16 +298,30 @@ impl RequestHandler for CreateHandler {
                 data,
                 set,
                 label,
-                attribute,
+                attributes,
+                expected_version,
                 ..
             } = payload;
 
+            // Versions of a label are written one at a time so that compare-and-set
+            // can't race with a plain write of the same label.
+            // The event gets inserted into the set named after the kind when it's omitted.
+            let label_lock = label.as_ref().map(|label| {
+                let set = set.to_owned().unwrap_or_else(|| kind.to_owned());
+                (set, label.to_owned())
+            });
+
             let mut query = db::event::InsertQuery::new(
                 room.id(),
                 kind,
                 data,
                 occurred_at,
                 reqp.as_agent_id().to_owned(),
+            )
+            .created_at(now)
+            .monotonic_occurred_at(
+                context.config().monotonic_occurred_at
+                    || context.features(room.audience()).monotonic_occurred_at,
             );
 
             if let Some(set) = set {
@@ -182,36 +332,90 @@ impl RequestHandler for CreateHandler {
                 query = query.label(label);
             }
 
-            if let Some(attribute) = attribute {
-                query = query.attribute(attribute);
+            if !attributes.is_empty() {
+                query = query.attributes(attributes);
             }
 
-            {
-                let mut conn = context.get_conn().await?;
-
-                let event = context
-                    .profiler()
-                    .measure(
-                        (
-                            ProfilerKeys::EventInsertQuery,
-                            Some(reqp.method().to_owned()),
-                        ),
-                        query.execute(&mut conn),
-                    )
-                    .await
-                    .context("Failed to insert event")
-                    .error(AppErrorKind::DbQueryFailed)?;
+            let event = match label_lock {
+                Some((set, label)) => {
+                    let mut txn = context.begin_txn().await?;
+                    let lock_query =
+                        db::event::LabelLockQuery::new(room.id(), set.clone(), label.clone());
 
-                context.add_logger_tags(o!("event_id" => event.id().to_string()));
-                event
-            }
+                    context
+                        .profiler()
+                        .measure(
+                            (
+                                ProfilerKeys::EventLabelLockQuery,
+                                Some(reqp.method().to_owned()),
+                            ),
+                            lock_query.execute(&mut txn),
+                        )
+                        .await
+                        .context("Failed to lock event label")
+                        .error(AppErrorKind::DbQueryFailed)?;
+
+                    // Insert the new version only if the latest one is still the expected one.
+                    if let Some(expected_version) = expected_version {
+                        let latest_query =
+                            db::event::LatestVersionQuery::new(room.id(), set, label);
+
+                        let latest_version = context
+                            .profiler()
+                            .measure(
+                                (
+                                    ProfilerKeys::EventLatestVersionQuery,
+                                    Some(reqp.method().to_owned()),
+                                ),
+                                latest_query.execute(&mut txn),
+                            )
+                            .await
+                            .context("Failed to find latest event version")
+                            .error(AppErrorKind::DbQueryFailed)?;
+
+                        if latest_version != Some(expected_version) {
+                            let current_version = latest_version
+                                .map(|id| id.to_string())
+                                .unwrap_or_else(|| String::from("none"));
+
+                            let mut err = AppError::new(
+                                AppErrorKind::Conflict,
+                                anyhow!(
+                                    "Expected version {} but the current one is {}",
+                                    expected_version,
+                                    current_version
+                                ),
+                            );
+
+                            err.tag("current_version", &current_version);
+                            return Err(err);
+                        }
+                    }
+
+                    let event = insert_event(context, query, &room, reqp, &mut txn).await?;
+
+                    txn.commit()
+                        .await
+                        .context("Failed to commit transaction")
+                        .error(AppErrorKind::DbQueryFailed)?;
+
+                    event
+                }
+                None => {
+                    let mut conn = context.get_conn().await?;
+                    insert_event(context, query, &room, reqp, &mut conn).await?
+                }
+            };
+
+            context.add_logger_tags(o!("event_id" => event.id().to_string()));
+            event
         } else {
             let CreateRequest {
                 kind,
                 data,
                 set,
                 label,
-                attribute,
+                attributes,
                 ..
             } = payload;
 
@@ -221,7 +425,8 @@ impl RequestHandler for CreateHandler {
                 .kind(&kind)
                 .data(&data)
                 .occurred_at(occurred_at)
-                .created_by(reqp.as_agent_id());
+                .created_by(reqp.as_agent_id())
+                .created_at(now);
 
             if let Some(ref set) = set {
                 builder = builder.set(set)
@@ -231,8 +436,8 @@ impl RequestHandler for CreateHandler {
                 builder = builder.label(label)
             }
 
-            if let Some(ref attribute) = attribute {
-                builder = builder.attribute(attribute)
+            if !attributes.is_empty() {
+                builder = builder.attributes(&attributes)
             }
 
             builder
@@ -243,10 +448,20 @@ impl RequestHandler for CreateHandler {
 
         let mut messages = Vec::with_capacity(3);
 
-        // Respond to the agent.
+        // Respond to the agent with the room closing time so it could stop in advance.
+        let room_closes_at = match room.time().map(|t| t.end().to_owned()) {
+            Ok(RoomTimeBound::Excluded(closes_at)) => Some(closes_at),
+            _ => None,
+        };
+
+        let response = CreateResponse {
+            event: event.clone(),
+            room_closes_at,
+        };
+
         messages.push(helpers::build_response(
             ResponseStatus::CREATED,
-            event.clone(),
+            response,
             reqp,
             context.start_timestamp(),
             Some(authz_time),
@@ -261,29 +476,133 @@ impl RequestHandler for CreateHandler {
 
             messages.push(helpers::build_notification(
                 "event.create",
-                &format!("audiences/{}/events", room.audience()),
+                &context.config().topics.audience_events(room.audience()),
                 claim_notification,
                 reqp,
                 context.start_timestamp(),
             ));
         }
 
-        // Notify room subscribers.
-        messages.push(helpers::build_notification(
-            "event.create",
-            &format!("rooms/{}/events", room.id()),
-            event,
-            reqp,
-            context.start_timestamp(),
-        ));
+        // Notify room subscribers unless asked to create the event silently.
+        if notify {
+            messages.push(helpers::build_notification(
+                "event.create",
+                &context.config().topics.room_events(room.id()),
+                event,
+                reqp,
+                context.start_timestamp(),
+            ));
+        }
 
         Ok(Box::new(stream::from_iter(messages)))
     }
 }
 
+async fn insert_event<C: Context>(
+    context: &mut C,
+    query: db::event::InsertQuery,
+    room: &Room,
+    reqp: &IncomingRequestProperties,
+    conn: &mut PgConnection,
+) -> StdResult<Event, AppError> {
+    context
+        .profiler()
+        .measure_for_audience(
+            (
+                ProfilerKeys::EventInsertQuery,
+                Some(reqp.method().to_owned()),
+            ),
+            room.audience(),
+            query.execute(conn),
+        )
+        .await
+        .context("Failed to insert event")
+        .error(AppErrorKind::DbQueryFailed)
+}
+
+/// Rejects deeply nested or huge arrays in event data before they get to the DB.
+fn validate_data_complexity(
+    data: &JsonValue,
+    max_depth: usize,
+    max_array_length: usize,
+) -> StdResult<(), AppError> {
+    // `depth` is the number of arrays and objects the value is nested in including itself.
+    fn check(value: &JsonValue, depth: usize, max_depth: usize, max_array_length: usize) -> bool {
+        match value {
+            JsonValue::Array(_) | JsonValue::Object(_) if depth > max_depth => false,
+            JsonValue::Array(items) => {
+                items.len() <= max_array_length
+                    && items
+                        .iter()
+                        .all(|item| check(item, depth + 1, max_depth, max_array_length))
+            }
+            JsonValue::Object(fields) => fields
+                .values()
+                .all(|field| check(field, depth + 1, max_depth, max_array_length)),
+            _ => true,
+        }
+    }
+
+    if check(data, 1, max_depth, max_array_length) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Event data exceeds max depth = {} or max array length = {}",
+            max_depth,
+            max_array_length
+        ))
+        .error(AppErrorKind::PayloadTooComplex)
+    }
+}
+
+/// Checks that `occurred_at` in nanoseconds falls within `[0, room_duration)`.
+fn validate_occurred_at(room: &Room, occurred_at: i64) -> StdResult<(), AppError> {
+    let time = room
+        .time()
+        .map_err(|err| anyhow!("Invalid room time: {}", err))
+        .error(AppErrorKind::InvalidRoomTime)?;
+
+    let max_occurred_at = time.duration().map(db::event::occurred_at_from_duration);
+
+    let is_in_range = match max_occurred_at {
+        Some(max_occurred_at) => occurred_at >= 0 && occurred_at < max_occurred_at,
+        None => occurred_at >= 0,
+    };
+
+    if is_in_range {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "occurred_at = {} is out of the room time, room_id = '{}'",
+            occurred_at,
+            room.id()
+        ))
+        .error(AppErrorKind::InvalidOccurredAt)
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 const MAX_LIMIT: usize = 100;
+const MAX_LIST_KINDS: usize = 32;
+
+// Serialized event field names allowed in `fields` projection.
+const EVENT_FIELDS: &[&str] = &[
+    "id",
+    "room_id",
+    "type",
+    "set",
+    "label",
+    "attributes",
+    "data",
+    "occurred_at",
+    "created_by",
+    "created_at",
+    "deleted_at",
+    "original_occurred_at",
+    "source_occurred_at",
+    "original_created_by",
+];
 
 #[derive(Debug, Deserialize, PartialEq)]
 #[serde(untagged)]
@@ -299,11 +618,40 @@ pub(crate) struct ListRequest {
     kind: Option<ListTypesFilter>,
     set: Option<String>,
     label: Option<String>,
-    attribute: Option<String>,
+    #[serde(
+        default,
+        alias = "attribute",
+        deserialize_with = "crate::serde::one_or_many::deserialize"
+    )]
+    attributes: Vec<String>,
     last_occurred_at: Option<i64>,
+    #[serde(default, with = "chrono::serde::ts_milliseconds_option")]
+    last_created_at: Option<DateTime<Utc>>,
+    last_id: Option<Uuid>,
     #[serde(default)]
     direction: db::event::Direction,
+    #[serde(default)]
+    order_by: db::event::ListOrder,
     limit: Option<usize>,
+    #[serde(default)]
+    with_total: bool,
+    #[serde(default)]
+    absolute_time: bool,
+    fields: Option<Vec<String>>,
+    #[serde(default)]
+    ndjson: bool,
+    data_filter: Option<JsonValue>,
+}
+
+#[derive(Debug, Serialize)]
+struct ListResponse {
+    events: JsonValue,
+    total: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct NdjsonListResponse {
+    ndjson: String,
 }
 
 pub(crate) struct ListHandler;
@@ -317,6 +665,46 @@ impl RequestHandler for ListHandler {
         payload: Self::Payload,
         reqp: &IncomingRequestProperties,
     ) -> Result {
+        if let Some(ListTypesFilter::Multiple(ref kinds)) = payload.kind {
+            if kinds.is_empty() || kinds.len() > MAX_LIST_KINDS {
+                return Err(anyhow!(
+                    "Invalid number of types: {}, expected 1 to {}",
+                    kinds.len(),
+                    MAX_LIST_KINDS
+                ))
+                .error(AppErrorKind::InvalidPayload);
+            }
+        }
+
+        if let Some(ref fields) = payload.fields {
+            let unknown_field = fields.iter().find(|f| !EVENT_FIELDS.contains(&f.as_str()));
+
+            if fields.is_empty() || unknown_field.is_some() {
+                return Err(anyhow!(
+                    "Invalid fields projection: {:?}, expected a non-empty subset of {:?}",
+                    fields,
+                    EVENT_FIELDS
+                ))
+                .error(AppErrorKind::InvalidPayload);
+            }
+        }
+
+        // An NDJSON body has no room for anything but the events.
+        if payload.ndjson && payload.with_total {
+            return Err(anyhow!("ndjson can't be combined with with_total"))
+                .error(AppErrorKind::InvalidPayload);
+        }
+
+        if let Some(ref data_filter) = payload.data_filter {
+            if !data_filter.is_object() {
+                return Err(anyhow!(
+                    "Invalid data filter: {}, expected an object",
+                    data_filter
+                ))
+                .error(AppErrorKind::InvalidPayload);
+            }
+        }
+
         let room = helpers::find_room(
             context,
             payload.room_id,
@@ -326,64 +714,81 @@ impl RequestHandler for ListHandler {
         .await?;
 
         // Authorize room events listing.
-        let room_id = room.id().to_string();
-        let object = AuthzObject::new(&["rooms", &room_id]).into();
-
-        let authz_time = context
-            .authz()
-            .authorize(
-                room.audience().into(),
-                reqp.as_account_id().to_owned(),
-                object,
-                "read".into(),
-            )
-            .await?;
+        let authz_time = helpers::authorize_room_read(context, &room, reqp).await?;
 
         // Retrieve events from the DB.
         let mut query = db::event::ListQuery::new().room_id(room.id());
 
+        let mut count_query = db::event::CountQuery::new(room.id());
+
         let ListRequest {
             kind,
             set,
             label,
-            attribute,
+            attributes,
+            data_filter,
             last_occurred_at,
+            last_created_at,
+            last_id,
             ..
         } = payload;
 
         query = match kind {
-            Some(ListTypesFilter::Single(kind)) => query.kind(kind),
-            Some(ListTypesFilter::Multiple(kinds)) => query.kinds(kinds),
+            Some(ListTypesFilter::Single(kind)) => {
+                count_query = count_query.kinds(vec![kind.clone()]);
+                query.kind(kind)
+            }
+            Some(ListTypesFilter::Multiple(kinds)) => {
+                count_query = count_query.kinds(kinds.clone());
+                query.kinds(kinds)
+            }
             None => query,
         };
 
         if let Some(ref set) = set {
             query = query.set(set);
+            count_query = count_query.set(set);
         }
 
         if let Some(ref label) = label {
             query = query.label(label);
+            count_query = count_query.label(label);
+        }
+
+        if !attributes.is_empty() {
+            query = query.attributes(&attributes);
+            count_query = count_query.attributes(attributes.clone());
         }
 
-        if let Some(ref attribute) = attribute {
-            query = query.attribute(attribute);
+        if let Some(ref data_filter) = data_filter {
+            query = query.data_filter(data_filter);
+            count_query = count_query.data_filter(data_filter.to_owned());
         }
 
         if let Some(last_occurred_at) = last_occurred_at {
             query = query.last_occurred_at(last_occurred_at);
         }
 
+        // The event id is a precise cursor while `created_at` is sent in milliseconds only.
+        if let Some(last_id) = last_id {
+            query = query.last_id(last_id);
+        } else if let Some(last_created_at) = last_created_at {
+            query = query.last_created_at(last_created_at);
+        }
+
         let events = {
-            let mut conn = context.get_ro_conn().await?;
+            let mut conn = context.get_read_conn(reqp.method()).await?;
 
             query = query
                 .direction(payload.direction)
+                .order(payload.order_by)
                 .limit(std::cmp::min(payload.limit.unwrap_or(MAX_LIMIT), MAX_LIMIT));
 
             context
                 .profiler()
-                .measure(
+                .measure_for_audience(
                     (ProfilerKeys::EventListQuery, Some(reqp.method().to_owned())),
+                    room.audience(),
                     query.execute(&mut conn),
                 )
                 .await
@@ -391,129 +796,603 @@ impl RequestHandler for ListHandler {
                 .error(AppErrorKind::DbQueryFailed)?
         };
 
-        // Respond with events list.
-        Ok(Box::new(stream::once(helpers::build_response(
-            ResponseStatus::OK,
-            events,
-            reqp,
-            context.start_timestamp(),
-            Some(authz_time),
-        ))))
-    }
-}
-
-///////////////////////////////////////////////////////////////////////////////
+        context.profiler().record_value(
+            (ProfilerKeys::EventListRows, Some(reqp.method().to_owned())),
+            room.audience(),
+            events.len(),
+        );
 
-#[cfg(test)]
-mod tests {
-    use serde_json::json;
+        // Clients hitting the maximum limit over and over probably never paginate.
+        if context.config().report_full_lists && events.len() == MAX_LIMIT {
+            warn!(
+                context.logger(),
+                "Listed a full page of {} events, the client may not paginate", MAX_LIMIT
+            );
 
-    use crate::db::event::{Direction, Object as Event};
-    use crate::test_helpers::outgoing_envelope::OutgoingEnvelopeProperties;
-    use crate::test_helpers::prelude::*;
+            context
+                .full_list_counter()
+                .increment(reqp.method(), reqp.as_agent_id());
+        }
 
-    use super::*;
+        let mut events = serde_json::to_value(events)
+            .context("Failed to serialize events")
+            .error(AppErrorKind::SerializationFailed)?;
 
-    ///////////////////////////////////////////////////////////////////////////
+        if payload.absolute_time {
+            let opened_at = room
+                .time()
+                .map(|t| *t.start())
+                .map_err(|e| anyhow!("Bad room time: {}", e))
+                .error(AppErrorKind::InvalidRoomTime)?;
 
-    #[test]
-    fn create_event() {
-        async_std::task::block_on(async {
-            let db = TestDb::new().await;
-            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            helpers::add_absolute_time(&mut events, opened_at);
+        }
 
-            let room = {
-                // Create room and put the agent online.
-                let mut conn = db.get_conn().await;
-                let room = shared_helpers::insert_room(&mut conn).await;
-                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
-                room
-            };
+        // Trim unrequested fields, `data` is usually the heaviest one.
+        if let Some(ref fields) = payload.fields {
+            let mut fields = fields.iter().map(|f| f.as_str()).collect::<Vec<_>>();
 
-            // Allow agent to create events of type `message` in the room.
-            let mut authz = TestAuthz::new();
-            let room_id = room.id().to_string();
-            let account_id = agent.account_id().to_string();
+            if payload.absolute_time {
+                fields.push("occurred_at_abs");
+            }
 
-            let object = vec![
-                "rooms",
-                &room_id,
-                "pinned",
-                "message",
-                "authors",
-                &account_id,
-            ];
+            helpers::project_fields(&mut events, &fields);
+        }
 
-            authz.allow(agent.account_id(), object, "create");
+        // Streaming consumers may process newline-delimited events one by one.
+        if payload.ndjson {
+            let response = NdjsonListResponse {
+                ndjson: helpers::to_ndjson(&events)?,
+            };
 
-            // Make event.create request.
-            let mut context = TestContext::new(db, authz);
+            return Ok(Box::new(stream::once(helpers::build_response(
+                ResponseStatus::OK,
+                response,
+                reqp,
+                context.start_timestamp(),
+                authz_time,
+            ))));
+        }
 
-            let payload = CreateRequest {
-                room_id: room.id(),
-                kind: String::from("message"),
-                set: Some(String::from("messages")),
-                label: Some(String::from("message-1")),
-                attribute: Some(String::from("pinned")),
-                data: json!({ "text": "hello" }),
-                is_claim: false,
-                is_persistent: true,
-            };
+        // Count all matching events only when asked since it's a full scan of the filter.
+        let response = if payload.with_total {
+            let mut conn = context.get_read_conn(reqp.method()).await?;
 
-            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+            let total = context
+                .profiler()
+                .measure_for_audience(
+                    (
+                        ProfilerKeys::EventCountQuery,
+                        Some(reqp.method().to_owned()),
+                    ),
+                    room.audience(),
+                    count_query.execute(&mut conn),
+                )
                 .await
-                .expect("Event creation failed");
-
-            assert_eq!(messages.len(), 2);
+                .context("Failed to count events")
+                .error(AppErrorKind::DbQueryFailed)?;
 
-            // Assert response.
-            let (event, respp, _) = find_response::<Event>(messages.as_slice());
-            assert_eq!(respp.status(), ResponseStatus::CREATED);
-            assert_eq!(event.room_id(), room.id());
-            assert_eq!(event.kind(), "message");
-            assert_eq!(event.set(), "messages");
-            assert_eq!(event.label(), Some("message-1"));
-            assert_eq!(event.attribute(), Some("pinned"));
-            assert_eq!(event.data(), &json!({ "text": "hello" }));
+            helpers::build_response(
+                ResponseStatus::OK,
+                ListResponse { events, total },
+                reqp,
+                context.start_timestamp(),
+                authz_time,
+            )
+        } else {
+            helpers::build_response(
+                ResponseStatus::OK,
+                events,
+                reqp,
+                context.start_timestamp(),
+                authz_time,
+            )
+        };
 
-            // Assert notification.
-            let (event, evp, topic) = find_event::<Event>(messages.as_slice());
-            assert!(topic.ends_with(&format!("/rooms/{}/events", room.id())));
-            assert_eq!(evp.label(), "event.create");
-            assert_eq!(event.room_id(), room.id());
-            assert_eq!(event.kind(), "message");
-            assert_eq!(event.set(), "messages");
-            assert_eq!(event.label(), Some("message-1"));
-            assert_eq!(event.attribute(), Some("pinned"));
-            assert_eq!(event.data(), &json!({ "text": "hello" }));
-        });
+        // Respond with events list.
+        Ok(Box::new(stream::once(response)))
     }
+}
 
-    #[test]
-    fn create_next_event() {
-        async_std::task::block_on(async {
-            let db = TestDb::new().await;
-            let original_author = TestAgent::new("web", "user123", USR_AUDIENCE);
-            let agent = TestAgent::new("web", "moderator", USR_AUDIENCE);
+///////////////////////////////////////////////////////////////////////////////
 
-            let room = {
-                // Create room.
-                let mut conn = db.get_conn().await;
-                let room = shared_helpers::insert_room(&mut conn).await;
+const MAX_LIST_MULTI_ROOMS: usize = 10;
 
-                // Add an event to the room.
-                factory::Event::new()
-                    .room_id(room.id())
-                    .kind("message")
-                    .set("messages")
-                    .label("message-1")
-                    .data(&json!({ "text": "original text" }))
-                    .occurred_at(1_000_000_000)
-                    .created_by(&original_author.agent_id())
-                    .insert(&mut conn)
-                    .await;
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListMultiRequest {
+    room_ids: Vec<Uuid>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    limit_per_room: Option<usize>,
+}
 
-                // Put the agent online.
+pub(crate) struct ListMultiHandler;
+
+#[async_trait]
+impl RequestHandler for ListMultiHandler {
+    type Payload = ListMultiRequest;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let mut room_ids = payload.room_ids;
+        room_ids.sort();
+        room_ids.dedup();
+
+        if room_ids.is_empty() || room_ids.len() > MAX_LIST_MULTI_ROOMS {
+            return Err(anyhow!(
+                "Invalid number of rooms: {}, expected 1 to {}",
+                room_ids.len(),
+                MAX_LIST_MULTI_ROOMS
+            ))
+            .error(AppErrorKind::InvalidPayload);
+        }
+
+        // Every room must exist and be readable otherwise the whole request fails.
+        let mut intents = Vec::with_capacity(room_ids.len());
+
+        for room_id in room_ids.iter() {
+            let room = helpers::find_room(
+                context,
+                *room_id,
+                helpers::RoomTimeRequirement::Any,
+                reqp.method(),
+            )
+            .await?;
+
+            if !context.config().is_public_read_audience(room.audience()) {
+                let room_id = room.id().to_string();
+                let object = AuthzObject::new(&["rooms", &room_id]);
+                intents.push(AuthzIntent::new(room.audience(), object, "read"));
+            }
+        }
+
+        // Rooms get authorized concurrently so the longest check is the time spent.
+        let mut authz_time: Option<Duration> = None;
+
+        for result in context
+            .authz()
+            .authorize_batch(reqp.as_account_id(), intents)
+            .await
+        {
+            let room_authz_time = result?;
+            authz_time = Some(authz_time.map_or(room_authz_time, |t| t.max(room_authz_time)));
+        }
+
+        // Retrieve the latest events of all the rooms with a single query.
+        let limit_per_room = std::cmp::min(payload.limit_per_room.unwrap_or(MAX_LIMIT), MAX_LIMIT);
+        let mut query = db::event::MultiRoomListQuery::new(room_ids.clone(), limit_per_room as i64);
+
+        if let Some(kind) = payload.kind {
+            query = query.kind(kind);
+        }
+
+        let events = {
+            let mut conn = context.get_read_conn(reqp.method()).await?;
+
+            context
+                .profiler()
+                .measure(
+                    (
+                        ProfilerKeys::EventListMultiQuery,
+                        Some(reqp.method().to_owned()),
+                    ),
+                    query.execute(&mut conn),
+                )
+                .await
+                .context("Failed to list events of multiple rooms")
+                .error(AppErrorKind::DbQueryFailed)?
+        };
+
+        // Group events by room keeping an empty list for rooms without events.
+        let mut events_by_room = room_ids
+            .into_iter()
+            .map(|room_id| (room_id, vec![]))
+            .collect::<HashMap<Uuid, Vec<Event>>>();
+
+        for event in events {
+            events_by_room
+                .entry(event.room_id())
+                .or_insert_with(Vec::new)
+                .push(event);
+        }
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            events_by_room,
+            reqp,
+            context.start_timestamp(),
+            authz_time,
+        );
+
+        Ok(Box::new(stream::once(response)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MoveRequest {
+    room_id: Uuid,
+    id: Uuid,
+    set: String,
+    label: Option<String>,
+}
+
+pub(crate) struct MoveHandler;
+
+#[async_trait]
+impl RequestHandler for MoveHandler {
+    type Payload = MoveRequest;
+    const IS_MUTATING: bool = true;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room = helpers::find_room(
+            context,
+            payload.room_id,
+            helpers::RoomTimeRequirement::Any,
+            reqp.method(),
+        )
+        .await?;
+
+        // Authorize events moderation in the room.
+        let object = {
+            let object = room.authz_object();
+            let mut object = object.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
+            object.push("events");
+            AuthzObject::new(&object).into()
+        };
+
+        let authz_time = context
+            .authz()
+            .authorize(
+                room.audience().into(),
+                reqp.as_account_id().to_owned(),
+                object,
+                "update".into(),
+            )
+            .await?;
+
+        context.add_logger_tags(o!("event_id" => payload.id.to_string()));
+        let mut txn = context.begin_txn().await?;
+
+        let query = db::event::FindQuery::new(room.id(), payload.id);
+
+        let event = context
+            .profiler()
+            .measure(
+                (ProfilerKeys::EventFindQuery, Some(reqp.method().to_owned())),
+                query.execute(&mut txn),
+            )
+            .await
+            .context("Failed to find event")
+            .error(AppErrorKind::DbQueryFailed)?
+            .ok_or_else(|| anyhow!("Event not found"))
+            .error(AppErrorKind::EventNotFound)?;
+
+        let original_set = event.set().to_owned();
+        let original_label = event.label().map(|label| label.to_owned());
+
+        // Keep the label unless a new one is given.
+        let label = payload
+            .label
+            .or_else(|| event.label().map(|label| label.to_owned()));
+
+        // A set is either a collection of labeled events or a simple one without labels.
+        // Mixing them breaks the state calculation.
+        let query = db::event::SetLabelingQuery::new(room.id(), &payload.set, event.id());
+
+        let maybe_is_labeled = context
+            .profiler()
+            .measure(
+                (
+                    ProfilerKeys::EventSetLabelingQuery,
+                    Some(reqp.method().to_owned()),
+                ),
+                query.execute(&mut txn),
+            )
+            .await
+            .context("Failed to check target set labeling")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        if let Some(is_labeled) = maybe_is_labeled {
+            if is_labeled != label.is_some() {
+                return Err(anyhow!(
+                    "Can't move {} event into {} set '{}'",
+                    if label.is_some() {
+                        "a labeled"
+                    } else {
+                        "an unlabeled"
+                    },
+                    if is_labeled { "labeled" } else { "unlabeled" },
+                    payload.set,
+                ))
+                .error(AppErrorKind::InvalidPayload);
+            }
+        }
+
+        let query = db::event::MoveQuery::new(room.id(), event.id(), payload.set, label);
+
+        let event = context
+            .profiler()
+            .measure_for_audience(
+                (ProfilerKeys::EventMoveQuery, Some(reqp.method().to_owned())),
+                room.audience(),
+                query.execute(&mut txn),
+            )
+            .await
+            .context("Failed to move event")
+            .error(AppErrorKind::DbQueryFailed)?
+            .ok_or_else(|| anyhow!("Event not found"))
+            .error(AppErrorKind::EventNotFound)?;
+
+        let query =
+            db::audit_log::InsertQuery::new(room.id(), "event.move", reqp.as_agent_id().to_owned())
+                .event_id(event.id())
+                .details(json!({
+                    "from_set": original_set,
+                    "from_label": original_label,
+                    "set": event.set(),
+                    "label": event.label(),
+                }));
+
+        helpers::write_audit_log(context, &mut txn, query, reqp).await?;
+
+        txn.commit()
+            .await
+            .context("Failed to commit transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            event.clone(),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        let notification = helpers::build_notification(
+            "event.update",
+            &context.config().topics.room_events(room.id()),
+            event,
+            reqp,
+            context.start_timestamp(),
+        );
+
+        Ok(Box::new(stream::from_iter(vec![response, notification])))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DeleteLabelRequest {
+    room_id: Uuid,
+    set: String,
+    label: String,
+}
+
+pub(crate) struct DeleteLabelHandler;
+
+#[async_trait]
+impl RequestHandler for DeleteLabelHandler {
+    type Payload = DeleteLabelRequest;
+    const IS_MUTATING: bool = true;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room = helpers::find_room(
+            context,
+            payload.room_id,
+            helpers::RoomTimeRequirement::Any,
+            reqp.method(),
+        )
+        .await?;
+
+        // Authorize events deletion in the room.
+        let object = {
+            let object = room.authz_object();
+            let mut object = object.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
+            object.push("events");
+            AuthzObject::new(&object).into()
+        };
+
+        let authz_time = context
+            .authz()
+            .authorize(
+                room.audience().into(),
+                reqp.as_account_id().to_owned(),
+                object,
+                "delete".into(),
+            )
+            .await?;
+
+        context.add_logger_tags(o!(
+            "set" => payload.set.clone(),
+            "label" => payload.label.clone(),
+        ));
+
+        // Put a tombstone on top of the label's versions so the state treats it as removed.
+        // The history stays in place and gets vacuumed unless the room preserves it.
+        let tombstone = {
+            let mut txn = context.begin_txn().await?;
+
+            let query = db::event::LabelLockQuery::new(
+                room.id(),
+                payload.set.to_owned(),
+                payload.label.to_owned(),
+            );
+
+            context
+                .profiler()
+                .measure(
+                    (
+                        ProfilerKeys::EventLabelLockQuery,
+                        Some(reqp.method().to_owned()),
+                    ),
+                    query.execute(&mut txn),
+                )
+                .await
+                .context("Failed to lock label")
+                .error(AppErrorKind::DbQueryFailed)?;
+
+            let query = db::event::LatestVersionQuery::new(
+                room.id(),
+                payload.set.to_owned(),
+                payload.label.to_owned(),
+            );
+
+            let maybe_latest_version_id = context
+                .profiler()
+                .measure(
+                    (
+                        ProfilerKeys::EventLatestVersionQuery,
+                        Some(reqp.method().to_owned()),
+                    ),
+                    query.execute(&mut txn),
+                )
+                .await
+                .context("Failed to find the latest version of the label")
+                .error(AppErrorKind::DbQueryFailed)?;
+
+            let maybe_latest_version = match maybe_latest_version_id {
+                Some(id) => {
+                    let query = db::event::FindQuery::new(room.id(), id);
+
+                    context
+                        .profiler()
+                        .measure(
+                            (ProfilerKeys::EventFindQuery, Some(reqp.method().to_owned())),
+                            query.execute(&mut txn),
+                        )
+                        .await
+                        .context("Failed to find event")
+                        .error(AppErrorKind::DbQueryFailed)?
+                }
+                None => None,
+            };
+
+            let latest_version = match maybe_latest_version {
+                Some(event) if !event.is_tombstone() => event,
+                _ => return Err(anyhow!("Label not found")).error(AppErrorKind::EventNotFound),
+            };
+
+            // The tombstone must be the latest version even if the label was written ahead.
+            let now = context.clock().now();
+
+            let occurred_at = match room.time() {
+                Ok(time) => db::event::occurred_at_from_duration(now - *time.start()),
+                _ => {
+                    return Err(anyhow!("Invalid room time")).error(AppErrorKind::InvalidRoomTime);
+                }
+            };
+
+            let query = db::event::InsertQuery::new(
+                room.id(),
+                latest_version.kind().to_owned(),
+                json!({}),
+                std::cmp::max(occurred_at, latest_version.occurred_at() + 1),
+                reqp.as_agent_id().to_owned(),
+            )
+            .set(payload.set.to_owned())
+            .label(payload.label.to_owned())
+            .attributes(vec![db::event::TOMBSTONE_ATTRIBUTE.to_owned()])
+            .created_at(now);
+
+            let tombstone = context
+                .profiler()
+                .measure_for_audience(
+                    (
+                        ProfilerKeys::EventInsertQuery,
+                        Some(reqp.method().to_owned()),
+                    ),
+                    room.audience(),
+                    query.execute(&mut txn),
+                )
+                .await
+                .context("Failed to insert tombstone")
+                .error(AppErrorKind::DbQueryFailed)?;
+
+            let query = db::audit_log::InsertQuery::new(
+                room.id(),
+                "event.delete_label",
+                reqp.as_agent_id().to_owned(),
+            )
+            .details(json!({
+                "set": payload.set,
+                "label": payload.label,
+                "tombstone_id": tombstone.id(),
+            }));
+
+            helpers::write_audit_log(context, &mut txn, query, reqp).await?;
+
+            txn.commit()
+                .await
+                .context("Failed to commit transaction")
+                .error(AppErrorKind::DbQueryFailed)?;
+
+            tombstone
+        };
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            tombstone.clone(),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        let notification = helpers::build_notification(
+            "event.delete_label",
+            &context.config().topics.room_events(room.id()),
+            tombstone,
+            reqp,
+            context.start_timestamp(),
+        );
+
+        Ok(Box::new(stream::from_iter(vec![response, notification])))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use chrono::{Duration, SubsecRound};
+    use serde_json::json;
+
+    use crate::app::endpoint::state::{ReadHandler, ReadRequest};
+    use crate::config::FeatureFlags;
+    use crate::db::event::{Direction, ListOrder, Object as Event};
+    use crate::test_helpers::context::FixedClock;
+    use crate::test_helpers::outgoing_envelope::OutgoingEnvelopeProperties;
+    use crate::test_helpers::prelude::*;
+
+    use super::*;
+
+    ///////////////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn create_event() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                // Create room and put the agent online.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
                 shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
                 room
             };
@@ -521,14 +1400,12 @@ mod tests {
             // Allow agent to create events of type `message` in the room.
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
-
-            // Should authorize with the author of the original event.
-            let account_id = original_author.agent_id().as_account_id().to_string();
+            let account_id = agent.account_id().to_string();
 
             let object = vec![
                 "rooms",
                 &room_id,
-                "events",
+                "pinned",
                 "message",
                 "authors",
                 &account_id,
@@ -536,7 +1413,7 @@ mod tests {
 
             authz.allow(agent.account_id(), object, "create");
 
-            // Make event.create request with the same set/label as existing event.
+            // Make event.create request.
             let mut context = TestContext::new(db, authz);
 
             let payload = CreateRequest {
@@ -544,10 +1421,1128 @@ mod tests {
                 kind: String::from("message"),
                 set: Some(String::from("messages")),
                 label: Some(String::from("message-1")),
-                attribute: None,
-                data: json!({ "text": "modified text" }),
+                attributes: vec![String::from("pinned")],
+                data: json!({ "text": "hello" }),
                 is_claim: false,
                 is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            assert_eq!(messages.len(), 2);
+
+            // Assert response.
+            let (event, respp, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::CREATED);
+            assert_eq!(event.room_id(), room.id());
+            assert_eq!(event.kind(), "message");
+            assert_eq!(event.set(), "messages");
+            assert_eq!(event.label(), Some("message-1"));
+            assert_eq!(event.attributes(), ["pinned"]);
+            assert_eq!(event.data(), &json!({ "text": "hello" }));
+
+            // Assert notification.
+            let (event, evp, topic) = find_event::<Event>(messages.as_slice());
+            assert!(topic.ends_with(&format!("/rooms/{}/events", room.id())));
+            assert_eq!(evp.label(), "event.create");
+            assert_eq!(event.room_id(), room.id());
+            assert_eq!(event.kind(), "message");
+            assert_eq!(event.set(), "messages");
+            assert_eq!(event.label(), Some("message-1"));
+            assert_eq!(event.attributes(), ["pinned"]);
+            assert_eq!(event.data(), &json!({ "text": "hello" }));
+        });
+    }
+
+    #[test]
+    fn create_event_occurred_at_in_nanoseconds() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+
+            authz.allow(agent.account_id(), object, "create");
+
+            // Create the event 1.5 seconds after the room opening.
+            let mut context = TestContext::new(db, authz);
+            let opened_at = *room.time().expect("Invalid room time").start();
+            context.set_clock(FixedClock(opened_at + Duration::milliseconds(1500)));
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            let (event, _, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(event.occurred_at(), 1_500_000_000);
+        });
+    }
+
+    #[test]
+    fn create_event_with_custom_topic() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+
+            // Publish room notifications into a custom topic hierarchy.
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().topics.room_events = String::from("classrooms/{room_id}/feed");
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            let (_event, evp, topic) = find_event::<Event>(messages.as_slice());
+            assert_eq!(evp.label(), "event.create");
+            assert!(topic.ends_with(&format!("/classrooms/{}/feed", room.id())));
+        });
+    }
+
+    #[test]
+    fn create_event_with_topic_prefix() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().topics.topic_prefix = String::from("staging");
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            let (_event, evp, topic) = find_event::<Event>(messages.as_slice());
+            assert_eq!(evp.label(), "event.create");
+            assert!(topic.ends_with(&format!("/staging/rooms/{}/events", room.id())));
+        });
+    }
+
+    #[test]
+    fn create_event_with_allowed_data_keys() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+
+            // Allow only `text` in message data.
+            let mut context = TestContext::new(db, authz);
+
+            context
+                .config_mut()
+                .allowed_data_keys
+                .insert(String::from("message"), vec![String::from("text")]);
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "hello", "email": "john@example.org" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            // Assert the disallowed key is dropped in the response.
+            let (event, respp, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::CREATED);
+            assert_eq!(event.data(), &json!({ "text": "hello" }));
+
+            // Assert the disallowed key is not stored.
+            let mut conn = context.db().acquire().await.expect("Failed conn checkout");
+
+            let db_event = db::event::FindQuery::new(room.id(), event.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to find event")
+                .expect("Event not found");
+
+            assert_eq!(db_event.data(), &json!({ "text": "hello" }));
+        });
+    }
+
+    #[test]
+    fn create_event_in_read_only_mode() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().read_only = true;
+
+            // Writes get rejected.
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success creating an event in read only mode");
+
+            assert_eq!(err.status(), ResponseStatus::SERVICE_UNAVAILABLE);
+            assert_eq!(err.kind(), "read_only_mode");
+
+            // Reads still work.
+            let payload = ListRequest {
+                room_id: room.id(),
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
+                limit: None,
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Events listing failed in read only mode");
+
+            let (events, respp, _) = find_response::<Vec<Event>>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert!(events.is_empty());
+        });
+    }
+
+    #[test]
+    fn create_event_with_too_complex_data() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().max_data_depth = Some(3);
+            context.config_mut().max_data_array_length = Some(10);
+
+            let too_deep = json!({ "a": { "b": { "c": { "d": "too deep" } } } });
+            let too_wide = json!({ "items": (0..11).collect::<Vec<_>>() });
+
+            for data in vec![too_deep, too_wide] {
+                let payload = CreateRequest {
+                    room_id: room.id(),
+                    kind: String::from("message"),
+                    set: None,
+                    label: None,
+                    attributes: vec![],
+                    data,
+                    is_claim: false,
+                    is_persistent: true,
+                    notify: true,
+                    expected_version: None,
+                };
+
+                let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success creating an event with too complex data");
+
+                assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+                assert_eq!(err.kind(), "payload_too_complex");
+            }
+
+            // Data within the limits is fine.
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "a": { "b": (0..10).collect::<Vec<_>>() } }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+        });
+    }
+
+    #[test]
+    fn create_event_with_expected_version() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, original_event) = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+
+                let event = factory::Event::new()
+                    .room_id(room.id())
+                    .kind("message")
+                    .set("messages")
+                    .label("message-1")
+                    .data(&json!({ "text": "hello" }))
+                    .occurred_at(1000)
+                    .created_by(&agent.agent_id())
+                    .insert(&mut conn)
+                    .await;
+
+                (room, event)
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+
+            let mut context = TestContext::new(db, authz);
+
+            // Update the event expecting the current version.
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: Some(String::from("messages")),
+                label: Some(String::from("message-1")),
+                attributes: vec![],
+                data: json!({ "text": "hello again" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: Some(original_event.id()),
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            let (new_event, respp, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::CREATED);
+
+            // Update it again with the now stale version.
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: Some(String::from("messages")),
+                label: Some(String::from("message-1")),
+                attributes: vec![],
+                data: json!({ "text": "stale edit" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: Some(original_event.id()),
+            };
+
+            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success creating an event with a stale version");
+
+            assert_eq!(err.status(), ResponseStatus::CONFLICT);
+            assert_eq!(err.kind(), "conflict");
+            assert!(err
+                .source()
+                .to_string()
+                .contains(&new_event.id().to_string()));
+        });
+    }
+
+    #[test]
+    fn create_event_within_close_grace_period() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let now = Utc::now().trunc_subsecs(0);
+
+            // The room has been closed a few seconds ago.
+            let room = {
+                let mut conn = db.get_conn().await;
+
+                let room = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(now - Duration::hours(1)),
+                        Bound::Excluded(now - Duration::seconds(5)),
+                    ))
+                    .insert(&mut conn)
+                    .await;
+
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().close_grace_period_s = Some(30);
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "trailing message" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            // Expect the event to be stamped at the room's end.
+            let (event, respp, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::CREATED);
+
+            let room_duration = Duration::hours(1) - Duration::seconds(5);
+            let expected_occurred_at = db::event::occurred_at_from_duration(room_duration) - 1;
+            assert_eq!(event.occurred_at(), expected_occurred_at);
+        });
+    }
+
+    #[test]
+    fn create_event_beyond_close_grace_period() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let now = Utc::now().trunc_subsecs(0);
+
+            // The room has been closed a minute ago.
+            let room = {
+                let mut conn = db.get_conn().await;
+
+                let room = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(now - Duration::hours(1)),
+                        Bound::Excluded(now - Duration::minutes(1)),
+                    ))
+                    .insert(&mut conn)
+                    .await;
+
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().close_grace_period_s = Some(30);
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "late message" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success creating an event beyond the grace period");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_closed");
+        });
+    }
+
+    #[test]
+    fn create_events_with_monotonic_occurred_at() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+
+            // Several instances whose wall clocks show exactly the same time.
+            let now = Utc::now();
+
+            let mut contexts = (0..5)
+                .map(|_| {
+                    let mut authz = TestAuthz::new();
+                    let object = vec![
+                        "rooms",
+                        &room_id,
+                        "events",
+                        "message",
+                        "authors",
+                        &account_id,
+                    ];
+                    authz.allow(agent.account_id(), object, "create");
+
+                    let mut context = TestContext::new(db.clone(), authz);
+                    context.config_mut().monotonic_occurred_at = true;
+                    context.set_clock(FixedClock(now));
+                    context
+                })
+                .collect::<Vec<_>>();
+
+            let requests = contexts.iter_mut().map(|context| {
+                let payload = CreateRequest {
+                    room_id: room.id(),
+                    kind: String::from("message"),
+                    set: None,
+                    label: None,
+                    attributes: vec![],
+                    data: json!({ "text": "hello" }),
+                    is_claim: false,
+                    is_persistent: true,
+                    notify: true,
+                    expected_version: None,
+                };
+
+                handle_request::<CreateHandler>(context, &agent, payload)
+            });
+
+            let mut occurred_ats = futures::future::join_all(requests)
+                .await
+                .into_iter()
+                .map(|result| {
+                    let messages = result.expect("Event creation failed");
+                    let (event, _, _) = find_response::<Event>(messages.as_slice());
+                    event.occurred_at()
+                })
+                .collect::<Vec<_>>();
+
+            occurred_ats.sort_unstable();
+
+            for pair in occurred_ats.windows(2) {
+                assert!(
+                    pair[0] < pair[1],
+                    "Duplicate occurred_at: {:?}",
+                    occurred_ats
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn create_events_with_audience_features() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let now = Utc::now();
+
+            // Enable monotonic `occurred_at` for another audience first, then for the room's one.
+            for (audience, expect_monotonic) in &[(SVC_AUDIENCE, false), (USR_AUDIENCE, true)] {
+                let mut authz = TestAuthz::new();
+                let object = vec![
+                    "rooms",
+                    &room_id,
+                    "events",
+                    "message",
+                    "authors",
+                    &account_id,
+                ];
+                authz.allow(agent.account_id(), object, "create");
+
+                let mut context = TestContext::new(db.clone(), authz);
+                context.set_clock(FixedClock(now));
+
+                let features = FeatureFlags {
+                    monotonic_occurred_at: true,
+                };
+
+                context
+                    .config_mut()
+                    .features
+                    .insert(audience.to_string(), features);
+
+                let mut occurred_ats = vec![];
+
+                for _ in 0..2 {
+                    let payload = CreateRequest {
+                        room_id: room.id(),
+                        kind: String::from("message"),
+                        set: None,
+                        label: None,
+                        attributes: vec![],
+                        data: json!({ "text": "hello" }),
+                        is_claim: false,
+                        is_persistent: true,
+                        notify: true,
+                        expected_version: None,
+                    };
+
+                    let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect("Event creation failed");
+
+                    let (event, _, _) = find_response::<Event>(messages.as_slice());
+                    occurred_ats.push(event.occurred_at());
+                }
+
+                if *expect_monotonic {
+                    assert!(occurred_ats[0] < occurred_ats[1]);
+                } else {
+                    assert_eq!(occurred_ats[0], occurred_ats[1]);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn validate_occurred_at_range() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let mut conn = db.get_conn().await;
+
+            // The room is one hour long.
+            let room = shared_helpers::insert_room(&mut conn).await;
+            let hour = 3600 * 1_000_000_000;
+
+            let err = super::validate_occurred_at(&room, -1)
+                .expect_err("Unexpected success on negative occurred_at");
+
+            assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+            assert_eq!(err.kind(), "invalid_occurred_at");
+
+            super::validate_occurred_at(&room, 0).expect("Room opening rejected");
+            super::validate_occurred_at(&room, hour - 1).expect("In-range occurred_at rejected");
+
+            let err = super::validate_occurred_at(&room, hour)
+                .expect_err("Unexpected success on occurred_at beyond close");
+
+            assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+            assert_eq!(err.kind(), "invalid_occurred_at");
+        });
+    }
+
+    #[test]
+    fn create_event_responds_with_room_closes_at() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+
+            // Make event.create request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            // Assert the response carries the room closing time.
+            let closes_at = match room.time().map(|t| t.end().to_owned()) {
+                Ok(RoomTimeBound::Excluded(closes_at)) => closes_at,
+                _ => panic!("Expected bounded room time"),
+            };
+
+            let (payload, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::CREATED);
+            assert_eq!(payload["room_closes_at"], json!(closes_at.timestamp()));
+            assert_eq!(payload["data"], json!({ "text": "hello" }));
+        });
+    }
+
+    #[test]
+    fn create_event_silently() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            // Allow agent to create events silently.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+            authz.allow(
+                agent.account_id(),
+                vec!["rooms", &room_id],
+                "create_silently",
+            );
+
+            // Make event.create request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: false,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            // Assert there's only the response and no notification.
+            assert_eq!(messages.len(), 1);
+            let (event, respp, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::CREATED);
+            assert_eq!(event.room_id(), room.id());
+        });
+    }
+
+    #[test]
+    fn create_event_silently_not_authorized() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            // Allow agent to create events but not silently.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+
+            let mut context = TestContext::new(db, authz);
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: false,
+                expected_version: None,
+            };
+
+            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on silent event creation");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        });
+    }
+
+    #[test]
+    fn create_event_with_fixed_clock() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+            authz.allow(agent.account_id(), object, "create");
+
+            // Freeze the clock five minutes after the room opening.
+            let opened_at = room
+                .time()
+                .map(|t| t.start().to_owned())
+                .expect("Invalid room time");
+
+            let now = opened_at + Duration::minutes(5);
+            let mut context = TestContext::new(db, authz);
+            context.set_clock(FixedClock(now));
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            // Assert `created_at` and `occurred_at` are derived from the clock.
+            let (event, _, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(event.created_at(), now);
+            assert_eq!(event.occurred_at(), 5 * 60 * 1_000_000_000);
+
+            let mut conn = context.db().acquire().await.expect("Failed conn checkout");
+
+            let db_event = db::event::ListQuery::new()
+                .room_id(room.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to list events")
+                .pop()
+                .expect("Missing event");
+
+            assert_eq!(db_event.created_at(), now);
+        });
+    }
+
+    #[test]
+    fn create_next_event() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let original_author = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let agent = TestAgent::new("web", "moderator", USR_AUDIENCE);
+
+            let room = {
+                // Create room.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // Add an event to the room.
+                factory::Event::new()
+                    .room_id(room.id())
+                    .kind("message")
+                    .set("messages")
+                    .label("message-1")
+                    .data(&json!({ "text": "original text" }))
+                    .occurred_at(1_000_000_000)
+                    .created_by(&original_author.agent_id())
+                    .insert(&mut conn)
+                    .await;
+
+                // Put the agent online.
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            // Allow agent to create events of type `message` in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+
+            // Should authorize with the author of the original event.
+            let account_id = original_author.agent_id().as_account_id().to_string();
+
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+
+            authz.allow(agent.account_id(), object, "create");
+
+            // Make event.create request with the same set/label as existing event.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: Some(String::from("messages")),
+                label: Some(String::from("message-1")),
+                attributes: vec![],
+                data: json!({ "text": "modified text" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
             };
 
             let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
@@ -562,351 +2557,1622 @@ mod tests {
     }
 
     #[test]
-    fn create_claim() {
+    fn create_claim() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                // Create room and put the agent online.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            // Allow agent to create claims of type `block` in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+            let object = vec!["rooms", &room_id, "claims", "block", "authors", &account_id];
+            authz.allow(agent.account_id(), object, "create");
+
+            // Make event.create request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("block"),
+                set: Some(String::from("blocks")),
+                label: Some(String::from("user-1")),
+                attributes: vec![],
+                data: json!({ "blocked": true }),
+                is_claim: true,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            assert_eq!(messages.len(), 3);
+
+            // Assert response.
+            let (event, respp, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::CREATED);
+            assert_eq!(event.room_id(), room.id());
+            assert_eq!(event.kind(), "block");
+            assert_eq!(event.set(), "blocks");
+            assert_eq!(event.label(), Some("user-1"));
+            assert_eq!(event.data(), &json!({ "blocked": true }));
+
+            // Assert tenant & room notifications.
+            let mut has_tenant_notification = false;
+            let mut has_room_notification = false;
+
+            for message in messages {
+                if let OutgoingEnvelopeProperties::Event(evp) = message.properties() {
+                    let topic = message.topic();
+
+                    if topic.ends_with(&format!("/audiences/{}/events", room.audience())) {
+                        has_tenant_notification = true;
+                    }
+
+                    if topic.ends_with(&format!("/rooms/{}/events", room.id())) {
+                        has_room_notification = true;
+                    }
+
+                    assert_eq!(evp.label(), "event.create");
+
+                    let event = message.payload::<Event>();
+                    assert_eq!(event.room_id(), room.id());
+                    assert_eq!(event.kind(), "block");
+                    assert_eq!(event.set(), "blocks");
+                    assert_eq!(event.label(), Some("user-1"));
+                    assert_eq!(event.data(), &json!({ "blocked": true }));
+                }
+            }
+
+            assert_eq!(has_tenant_notification, true);
+            assert_eq!(has_room_notification, true);
+        });
+    }
+
+    #[test]
+    fn create_transient_event() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                // Create room and put the agent online.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            // Allow agent to create events of type `message` in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "cursor",
+                "authors",
+                &account_id,
+            ];
+
+            authz.allow(agent.account_id(), object, "create");
+
+            // Make event.create request.
+            let mut context = TestContext::new(db, authz);
+
+            let data = json!({
+                "agent_id": agent.agent_id().to_string(),
+                "x": 123,
+                "y": 456,
+            });
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("cursor"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: data.clone(),
+                is_claim: false,
+                is_persistent: false,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            assert_eq!(messages.len(), 2);
+
+            // Assert response.
+            let (event, respp, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::CREATED);
+            assert_eq!(event.room_id(), room.id());
+            assert_eq!(event.kind(), "cursor");
+            assert_eq!(event.set(), "cursor");
+            assert_eq!(event.label(), None);
+            assert_eq!(event.data(), &data);
+
+            // Assert notification.
+            let (event, evp, topic) = find_event::<Event>(messages.as_slice());
+            assert!(topic.ends_with(&format!("/rooms/{}/events", room.id())));
+            assert_eq!(evp.label(), "event.create");
+            assert_eq!(event.room_id(), room.id());
+            assert_eq!(event.kind(), "cursor");
+            assert_eq!(event.set(), "cursor");
+            assert_eq!(event.label(), None);
+            assert_eq!(event.data(), &data);
+        });
+    }
+
+    #[test]
+    fn create_event_with_default_set() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                // Create room and put the agent online.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            // Allow agent to create events of types `message` and `cursor` in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+
+            for kind in &["message", "cursor"] {
+                let object = vec!["rooms", &room_id, "events", kind, "authors", &account_id];
+                authz.allow(agent.account_id(), object, "create");
+            }
+
+            // Configure default sets.
+            let mut context = TestContext::new(db, authz);
+            let config = context.config_mut();
+            config.default_event_set = Some(String::from("misc"));
+
+            config
+                .event_sets_by_kind
+                .insert(String::from("message"), String::from("messages"));
+
+            // Make event.create requests without set.
+            for (kind, expected_set) in &[("message", "messages"), ("cursor", "misc")] {
+                let payload = CreateRequest {
+                    room_id: room.id(),
+                    kind: kind.to_string(),
+                    set: None,
+                    label: None,
+                    attributes: vec![],
+                    data: json!({ "text": "hello" }),
+                    is_claim: false,
+                    is_persistent: true,
+                    notify: true,
+                    expected_version: None,
+                };
+
+                let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Event creation failed");
+
+                let (event, respp, _) = find_response::<Event>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::CREATED);
+                assert_eq!(event.kind(), *kind);
+                assert_eq!(event.set(), *expected_set);
+            }
+        });
+    }
+
+    #[test]
+    fn create_event_rate_limited() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                // Create room and put the agent online.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            // Allow agent to create events of type `message` in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+
+            authz.allow(agent.account_id(), object, "create");
+
+            // Limit the room to 2 events per second.
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().event_rate_limit = Some(2);
+            let now = Utc::now().trunc_subsecs(0) + Duration::milliseconds(100);
+            context.set_clock(FixedClock(now));
+
+            let payload = || CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            // Unauthorized requests don't spend the room's budget.
+            let intruder = TestAgent::new("web", "intruder", USR_AUDIENCE);
+
+            for _ in 0..3 {
+                let err = handle_request::<CreateHandler>(&mut context, &intruder, payload())
+                    .await
+                    .expect_err("Unexpected success creating event without permission");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            }
+
+            // Burst past the limit.
+            for _ in 0..2 {
+                handle_request::<CreateHandler>(&mut context, &agent, payload())
+                    .await
+                    .expect("Event creation failed");
+            }
+
+            let err = handle_request::<CreateHandler>(&mut context, &agent, payload())
+                .await
+                .expect_err("Unexpected success creating event over the rate limit");
+
+            assert_eq!(err.status(), ResponseStatus::TOO_MANY_REQUESTS);
+            assert_eq!(err.kind(), "room_rate_limit_exceeded");
+
+            // Recover in the next window.
+            context.set_clock(FixedClock(now + Duration::seconds(1)));
+
+            handle_request::<CreateHandler>(&mut context, &agent, payload())
+                .await
+                .expect("Event creation failed after the rate limit window");
+        });
+    }
+
+    #[test]
+    fn create_event_not_authorized() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                // Create room and put the agent online.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            // Make event.create request.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: Some(String::from("messages")),
+                label: Some(String::from("message-1")),
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on event creation");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+        });
+    }
+
+    #[test]
+    fn create_event_not_entered() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                // Create room.
+                let mut conn = db.get_conn().await;
+                shared_helpers::insert_room(&mut conn).await
+            };
+
+            // Allow agent to create events of type `message` in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+
+            authz.allow(agent.account_id(), object, "create");
+
+            // Make event.create request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: Some(String::from("messages")),
+                label: Some(String::from("message-1")),
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            assert_eq!(messages.len(), 2);
+        });
+    }
+
+    #[test]
+    fn create_event_closed_room() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                // Create closed room and put the agent online.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_closed_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            // Allow agent to create events of type `message` in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+
+            authz.allow(agent.account_id(), object, "create");
+
+            // Make event.create request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: Some(String::from("messages")),
+                label: Some(String::from("message-1")),
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on event creation");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_closed");
+        });
+    }
+
+    #[test]
+    fn create_event_missing_room() {
+        async_std::task::block_on(async {
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+
+            let payload = CreateRequest {
+                room_id: Uuid::new_v4(),
+                kind: String::from("message"),
+                set: Some(String::from("messages")),
+                label: Some(String::from("message-1")),
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on event creation");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_not_found");
+        });
+    }
+
+    ///////////////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn list_events() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, db_events) = {
+                // Create room.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // Create events in the room.
+                let mut events = vec![];
+
+                for i in 1..4 {
+                    let event = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .data(&json!({ "text": format!("message {}", i) }))
+                        .occurred_at(i * 1000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    events.push(event);
+                }
+
+                (room, events)
+            };
+
+            // Allow agent to list events in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            // Make event.list request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = ListRequest {
+                room_id: room.id(),
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
+                limit: Some(2),
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Events listing failed (page 1)");
+
+            // Assert last two events response.
+            let (events, respp, _) = find_response::<Vec<Event>>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[0].id(), db_events[2].id());
+            assert_eq!(events[1].id(), db_events[1].id());
+
+            // Request the next page.
+            let payload = ListRequest {
+                room_id: room.id(),
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: Some(events[1].occurred_at()),
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
+                limit: Some(2),
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Events listing failed (page 2)");
+
+            // Assert the first event.
+            let (events, respp, _) = find_response::<Vec<Event>>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].id(), db_events[0].id());
+        });
+    }
+
+    #[test]
+    fn list_events_ordered_by_created_at() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, db_events) = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // Arrival order differs from the occurrence order.
+                let created_at = Utc::now();
+                let mut events = vec![];
+
+                for (i, occurred_at) in [3000, 1000, 2000].iter().enumerate() {
+                    let event = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .data(&json!({ "text": format!("message {}", i) }))
+                        .occurred_at(*occurred_at)
+                        .created_by(&agent.agent_id())
+                        .created_at(created_at + Duration::seconds(i as i64))
+                        .insert(&mut conn)
+                        .await;
+
+                    events.push(event);
+                }
+
+                (room, events)
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            let mut context = TestContext::new(db, authz);
+
+            // Default ordering is by occurred_at.
+            let payload = ListRequest {
+                room_id: room.id(),
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Forward,
+                order_by: ListOrder::OccurredAt,
+                limit: None,
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Events listing failed (occurred_at)");
+
+            let (events, _, _) = find_response::<Vec<Event>>(messages.as_slice());
+            let ids = events.iter().map(|e| e.id()).collect::<Vec<_>>();
+            let expected_ids = [1, 2, 0]
+                .iter()
+                .map(|i| db_events[*i].id())
+                .collect::<Vec<_>>();
+            assert_eq!(ids, expected_ids);
+
+            // Arrival order, first page.
+            let payload = ListRequest {
+                room_id: room.id(),
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Forward,
+                order_by: ListOrder::CreatedAt,
+                limit: Some(2),
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Events listing failed (created_at, page 1)");
+
+            let (events, _, _) = find_response::<Vec<Event>>(messages.as_slice());
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[0].id(), db_events[0].id());
+            assert_eq!(events[1].id(), db_events[1].id());
+
+            // Arrival order, next page by the last seen event cursor.
+            let payload = ListRequest {
+                room_id: room.id(),
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: Some(events[1].id()),
+                direction: Direction::Forward,
+                order_by: ListOrder::CreatedAt,
+                limit: Some(2),
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Events listing failed (created_at, page 2)");
+
+            let (events, _, _) = find_response::<Vec<Event>>(messages.as_slice());
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].id(), db_events[2].id());
+        });
+    }
+
+    #[test]
+    fn list_events_ordered_by_created_at_with_ties() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, mut db_events) = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // Events inserted in a single transaction share `created_at`
+                // which also has sub-millisecond precision.
+                let created_at = Utc::now().trunc_subsecs(0) + Duration::microseconds(1500);
+                let mut events = vec![];
+
+                for i in 0..5 {
+                    let event = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .data(&json!({ "text": format!("message {}", i) }))
+                        .occurred_at(i * 1000)
+                        .created_by(&agent.agent_id())
+                        .created_at(created_at)
+                        .insert(&mut conn)
+                        .await;
+
+                    events.push(event);
+                }
+
+                (room, events)
+            };
+
+            db_events.sort_by_key(|event| event.id());
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            let mut context = TestContext::new(db, authz);
+
+            for direction in &[Direction::Forward, Direction::Backward] {
+                let mut expected_ids = db_events.iter().map(|e| e.id()).collect::<Vec<_>>();
+
+                if let Direction::Backward = direction {
+                    expected_ids.reverse();
+                }
+
+                // Page through all the events two by two.
+                let mut ids = vec![];
+                let mut last_id = None;
+
+                loop {
+                    let payload = ListRequest {
+                        room_id: room.id(),
+                        kind: None,
+                        set: None,
+                        label: None,
+                        attributes: vec![],
+                        last_occurred_at: None,
+                        last_created_at: None,
+                        last_id,
+                        direction: *direction,
+                        order_by: ListOrder::CreatedAt,
+                        limit: Some(2),
+                        with_total: false,
+                        absolute_time: false,
+                        fields: None,
+                        ndjson: false,
+                        data_filter: None,
+                    };
+
+                    let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect("Events listing failed");
+
+                    let (events, _, _) = find_response::<Vec<Event>>(messages.as_slice());
+
+                    match events.last() {
+                        Some(event) => last_id = Some(event.id()),
+                        None => break,
+                    }
+
+                    ids.extend(events.iter().map(|e| e.id()));
+                }
+
+                // Each event is listed exactly once in the `(created_at, id)` order.
+                assert_eq!(ids, expected_ids);
+            }
+        });
+    }
+
+    #[test]
+    fn list_events_with_total() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                // Create room with three events.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                for i in 1..4 {
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .data(&json!({ "text": format!("message {}", i) }))
+                        .occurred_at(i * 1000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+                }
+
+                room
+            };
+
+            // Allow agent to list events in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            // Make event.list request asking for the total count.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = ListRequest {
+                room_id: room.id(),
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
+                limit: Some(2),
+                with_total: true,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Events listing failed");
+
+            // Assert the page and the total count.
+            let (payload, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            let events = payload["events"].as_array().expect("Missing events");
+            assert_eq!(events.len(), 2);
+            assert_eq!(payload["total"], json!(3));
+        });
+    }
+
+    #[test]
+    fn move_event() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, event) = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                let event = factory::Event::new()
+                    .room_id(room.id())
+                    .kind("message")
+                    .set("messages")
+                    .label("message-1")
+                    .data(&json!({ "text": "hello" }))
+                    .occurred_at(1000)
+                    .created_by(&agent.agent_id())
+                    .insert(&mut conn)
+                    .await;
+
+                // A simple set without labels.
+                factory::Event::new()
+                    .room_id(room.id())
+                    .kind("layout")
+                    .set("layout")
+                    .data(&json!({ "name": "presentation" }))
+                    .occurred_at(2000)
+                    .created_by(&agent.agent_id())
+                    .insert(&mut conn)
+                    .await;
+
+                (room, event)
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(
+                agent.account_id(),
+                vec!["rooms", &room_id, "events"],
+                "update",
+            );
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            let mut context = TestContext::new(db, authz);
+
+            // Move the event into another set.
+            let payload = MoveRequest {
+                room_id: room.id(),
+                id: event.id(),
+                set: String::from("pinned"),
+                label: None,
+            };
+
+            let messages = handle_request::<MoveHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event moving failed");
+
+            let (moved_event, respp, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(moved_event.id(), event.id());
+            assert_eq!(moved_event.set(), "pinned");
+            assert_eq!(moved_event.label(), Some("message-1"));
+
+            let (_, evp, topic) = find_event::<Event>(messages.as_slice());
+            assert_eq!(evp.label(), "event.update");
+            assert!(topic.ends_with(&format!("/rooms/{}/events", room.id())));
+
+            // The state has the event in the new set only.
+            let payload = serde_json::from_value::<ReadRequest>(json!({
+                "room_id": room.id(),
+                "sets": ["messages", "pinned"],
+            }))
+            .expect("Failed to build state.read request");
+
+            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect("State reading failed");
+
+            let (state, _, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(state["messages"], json!([]));
+
+            let pinned = state["pinned"].as_array().expect("Missing pinned set");
+            assert_eq!(pinned.len(), 1);
+            assert_eq!(pinned[0]["id"], event.id().to_string());
+
+            // A labeled event can't get into a simple set.
+            let payload = MoveRequest {
+                room_id: room.id(),
+                id: event.id(),
+                set: String::from("layout"),
+                label: None,
+            };
+
+            let err = handle_request::<MoveHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success moving event into a simple set");
+
+            assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+            assert_eq!(err.kind(), "invalid_payload");
+        });
+    }
+
+    #[test]
+    fn delete_label() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, other_event) = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // A few versions of the same label.
+                for i in 1..4 {
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .set("messages")
+                        .label("message-1")
+                        .data(&json!({ "text": format!("version {}", i) }))
+                        .occurred_at(i * 1000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+                }
+
+                let other_event = factory::Event::new()
+                    .room_id(room.id())
+                    .kind("message")
+                    .set("messages")
+                    .label("message-2")
+                    .data(&json!({ "text": "hello" }))
+                    .occurred_at(4000)
+                    .created_by(&agent.agent_id())
+                    .insert(&mut conn)
+                    .await;
+
+                (room, other_event)
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(
+                agent.account_id(),
+                vec!["rooms", &room_id, "events"],
+                "delete",
+            );
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            let mut context = TestContext::new(db, authz);
+
+            let payload = DeleteLabelRequest {
+                room_id: room.id(),
+                set: String::from("messages"),
+                label: String::from("message-1"),
+            };
+
+            let messages = handle_request::<DeleteLabelHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Label deletion failed");
+
+            let (tombstone, respp, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(tombstone.set(), "messages");
+            assert_eq!(tombstone.label(), Some("message-1"));
+            assert_eq!(tombstone.attributes(), &[String::from("deleted")]);
+            assert!(tombstone.occurred_at() > 3000);
+
+            let (_, evp, topic) = find_event::<JsonValue>(messages.as_slice());
+            assert_eq!(evp.label(), "event.delete_label");
+            assert!(topic.ends_with(&format!("/rooms/{}/events", room.id())));
+
+            // The label has gone from the state.
+            let payload = serde_json::from_value::<ReadRequest>(json!({
+                "room_id": room.id(),
+                "sets": ["messages"],
+            }))
+            .expect("Failed to build state.read request");
+
+            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect("State reading failed");
+
+            let (state, _, _) = find_response::<JsonValue>(messages.as_slice());
+            let state_messages = state["messages"].as_array().expect("Missing messages set");
+            assert_eq!(state_messages.len(), 1);
+            assert_eq!(state_messages[0]["id"], other_event.id().to_string());
+
+            // The state before the deletion still has the label.
+            let payload = serde_json::from_value::<ReadRequest>(json!({
+                "room_id": room.id(),
+                "sets": ["messages"],
+                "occurred_at": 3001,
+            }))
+            .expect("Failed to build state.read request");
+
+            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect("State reading failed");
+
+            let (state, _, _) = find_response::<JsonValue>(messages.as_slice());
+            let state_messages = state["messages"].as_array().expect("Missing messages set");
+            assert_eq!(state_messages.len(), 1);
+            assert_eq!(state_messages[0]["data"]["text"], "version 3");
+
+            // The label's history is kept with the tombstone on top.
+            let mut conn = context.db().acquire().await.expect("Failed conn checkout");
+
+            let versions = db::event::ListQuery::new()
+                .room_id(room.id())
+                .label("message-1")
+                .execute(&mut conn)
+                .await
+                .expect("Failed to list events");
+
+            assert_eq!(versions.len(), 4);
+            assert!(versions.iter().any(|event| event.id() == tombstone.id()));
+            drop(conn);
+
+            // Deleting it again fails.
+            let payload = DeleteLabelRequest {
+                room_id: room.id(),
+                set: String::from("messages"),
+                label: String::from("message-1"),
+            };
+
+            let err = handle_request::<DeleteLabelHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success deleting a missing label");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "event_not_found");
+        });
+    }
+
+    #[test]
+    fn delete_label_audit_log() {
         async_std::task::block_on(async {
             let db = TestDb::new().await;
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
             let room = {
-                // Create room and put the agent online.
                 let mut conn = db.get_conn().await;
                 let room = shared_helpers::insert_room(&mut conn).await;
-                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+
+                factory::Event::new()
+                    .room_id(room.id())
+                    .kind("message")
+                    .set("messages")
+                    .label("message-1")
+                    .data(&json!({ "text": "hello" }))
+                    .occurred_at(1000)
+                    .created_by(&agent.agent_id())
+                    .insert(&mut conn)
+                    .await;
+
                 room
             };
 
-            // Allow agent to create claims of type `block` in the room.
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
-            let account_id = agent.account_id().to_string();
-            let object = vec!["rooms", &room_id, "claims", "block", "authors", &account_id];
-            authz.allow(agent.account_id(), object, "create");
+            authz.allow(
+                agent.account_id(),
+                vec!["rooms", &room_id, "events"],
+                "delete",
+            );
 
-            // Make event.create request.
             let mut context = TestContext::new(db, authz);
 
-            let payload = CreateRequest {
-                room_id: room.id(),
-                kind: String::from("block"),
-                set: Some(String::from("blocks")),
-                label: Some(String::from("user-1")),
-                attribute: None,
-                data: json!({ "blocked": true }),
-                is_claim: true,
-                is_persistent: true,
-            };
+            // Delete the label twice. The second attempt fails and must not be audited.
+            for _ in 0..2 {
+                let payload = DeleteLabelRequest {
+                    room_id: room.id(),
+                    set: String::from("messages"),
+                    label: String::from("message-1"),
+                };
 
-            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
-                .await
-                .expect("Event creation failed");
+                let _ = handle_request::<DeleteLabelHandler>(&mut context, &agent, payload).await;
+            }
 
-            assert_eq!(messages.len(), 3);
+            // Assert a single audit log record of the deletion.
+            let mut conn = context.db().acquire().await.expect("Failed conn checkout");
 
-            // Assert response.
-            let (event, respp, _) = find_response::<Event>(messages.as_slice());
-            assert_eq!(respp.status(), ResponseStatus::CREATED);
-            assert_eq!(event.room_id(), room.id());
-            assert_eq!(event.kind(), "block");
-            assert_eq!(event.set(), "blocks");
-            assert_eq!(event.label(), Some("user-1"));
-            assert_eq!(event.data(), &json!({ "blocked": true }));
+            let records = db::audit_log::ListQuery::new()
+                .room_id(room.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to list audit log");
 
-            // Assert tenant & room notifications.
-            let mut has_tenant_notification = false;
-            let mut has_room_notification = false;
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].room_id(), room.id());
+            assert_eq!(records[0].action(), "event.delete_label");
+            assert_eq!(records[0].created_by(), agent.agent_id());
 
-            for message in messages {
-                if let OutgoingEnvelopeProperties::Event(evp) = message.properties() {
-                    let topic = message.topic();
+            let details = records[0].details();
+            assert_eq!(details["set"], "messages");
+            assert_eq!(details["label"], "message-1");
+            assert!(details["tombstone_id"].is_string());
+        });
+    }
 
-                    if topic.ends_with(&format!("/audiences/{}/events", room.audience())) {
-                        has_tenant_notification = true;
-                    }
+    #[test]
+    fn list_multi_events() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
-                    if topic.ends_with(&format!("/rooms/{}/events", room.id())) {
-                        has_room_notification = true;
+            let (rooms, unauthorized_room) = {
+                let mut conn = db.get_conn().await;
+                let mut rooms = vec![];
+
+                for _ in 0..2 {
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    for i in 1..4 {
+                        factory::Event::new()
+                            .room_id(room.id())
+                            .kind("message")
+                            .data(&json!({ "text": format!("message {}", i) }))
+                            .occurred_at(i * 1000)
+                            .created_by(&agent.agent_id())
+                            .insert(&mut conn)
+                            .await;
                     }
 
-                    assert_eq!(evp.label(), "event.create");
-
-                    let event = message.payload::<Event>();
-                    assert_eq!(event.room_id(), room.id());
-                    assert_eq!(event.kind(), "block");
-                    assert_eq!(event.set(), "blocks");
-                    assert_eq!(event.label(), Some("user-1"));
-                    assert_eq!(event.data(), &json!({ "blocked": true }));
+                    rooms.push(room);
                 }
+
+                let unauthorized_room = shared_helpers::insert_room(&mut conn).await;
+                (rooms, unauthorized_room)
+            };
+
+            let mut authz = TestAuthz::new();
+
+            for room in rooms.iter() {
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
             }
 
-            assert_eq!(has_tenant_notification, true);
-            assert_eq!(has_room_notification, true);
+            let mut context = TestContext::new(db, authz);
+
+            // List the latest two events of each authorized room.
+            let payload = ListMultiRequest {
+                room_ids: rooms.iter().map(|room| room.id()).collect(),
+                kind: Some(String::from("message")),
+                limit_per_room: Some(2),
+            };
+
+            let messages = handle_request::<ListMultiHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Multiple rooms events listing failed");
+
+            let (events_by_room, respp, _) =
+                find_response::<HashMap<Uuid, Vec<Event>>>(messages.as_slice());
+
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(events_by_room.len(), 2);
+
+            for room in rooms.iter() {
+                let events = events_by_room.get(&room.id()).expect("Missing room events");
+                assert_eq!(events.len(), 2);
+                assert!(events.iter().all(|event| event.room_id() == room.id()));
+                assert_eq!(events[0].occurred_at(), 3000);
+                assert_eq!(events[1].occurred_at(), 2000);
+            }
+
+            // Adding a room without access fails the whole request.
+            let payload = ListMultiRequest {
+                room_ids: vec![rooms[0].id(), unauthorized_room.id()],
+                kind: None,
+                limit_per_room: None,
+            };
+
+            let err = handle_request::<ListMultiHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success listing events of an unauthorized room");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
         });
     }
 
     #[test]
-    fn create_transient_event() {
+    fn list_events_with_absolute_time() {
         async_std::task::block_on(async {
             let db = TestDb::new().await;
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
-            let room = {
-                // Create room and put the agent online.
+            let (room, event) = {
                 let mut conn = db.get_conn().await;
                 let room = shared_helpers::insert_room(&mut conn).await;
-                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
-                room
+
+                let event = factory::Event::new()
+                    .room_id(room.id())
+                    .kind("message")
+                    .data(&json!({ "text": "hello" }))
+                    .occurred_at(1_500_000_123)
+                    .created_by(&agent.agent_id())
+                    .insert(&mut conn)
+                    .await;
+
+                (room, event)
             };
 
-            // Allow agent to create events of type `message` in the room.
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
-            let account_id = agent.account_id().to_string();
-
-            let object = vec![
-                "rooms",
-                &room_id,
-                "events",
-                "cursor",
-                "authors",
-                &account_id,
-            ];
-
-            authz.allow(agent.account_id(), object, "create");
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
 
-            // Make event.create request.
             let mut context = TestContext::new(db, authz);
 
-            let data = json!({
-                "agent_id": agent.agent_id().to_string(),
-                "x": 123,
-                "y": 456,
-            });
-
-            let payload = CreateRequest {
+            let payload = ListRequest {
                 room_id: room.id(),
-                kind: String::from("cursor"),
+                kind: None,
                 set: None,
                 label: None,
-                attribute: None,
-                data: data.clone(),
-                is_claim: false,
-                is_persistent: false,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
+                limit: None,
+                with_total: false,
+                absolute_time: true,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
             };
 
-            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
                 .await
-                .expect("Event creation failed");
+                .expect("Events listing failed");
 
-            assert_eq!(messages.len(), 2);
+            let (events, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
 
-            // Assert response.
-            let (event, respp, _) = find_response::<Event>(messages.as_slice());
-            assert_eq!(respp.status(), ResponseStatus::CREATED);
-            assert_eq!(event.room_id(), room.id());
-            assert_eq!(event.kind(), "cursor");
-            assert_eq!(event.set(), "cursor");
-            assert_eq!(event.label(), None);
-            assert_eq!(event.data(), &data);
+            // The relative time stays the primary field.
+            assert_eq!(events[0]["occurred_at"], event.occurred_at());
 
-            // Assert notification.
-            let (event, evp, topic) = find_event::<Event>(messages.as_slice());
-            assert!(topic.ends_with(&format!("/rooms/{}/events", room.id())));
-            assert_eq!(evp.label(), "event.create");
-            assert_eq!(event.room_id(), room.id());
-            assert_eq!(event.kind(), "cursor");
-            assert_eq!(event.set(), "cursor");
-            assert_eq!(event.label(), None);
-            assert_eq!(event.data(), &data);
+            let absolute_time = events[0]["occurred_at_abs"]
+                .as_str()
+                .expect("Missing occurred_at_abs");
+
+            let absolute_time = DateTime::parse_from_rfc3339(absolute_time)
+                .expect("Failed to parse occurred_at_abs")
+                .with_timezone(&Utc);
+
+            let opened_at = *room.time().expect("Invalid room time").start();
+            let expected_time = opened_at + Duration::nanoseconds(event.occurred_at());
+            assert_eq!(absolute_time, expected_time);
         });
     }
 
     #[test]
-    fn create_event_not_authorized() {
+    fn list_events_with_fields_projection() {
         async_std::task::block_on(async {
             let db = TestDb::new().await;
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
-            let room = {
-                // Create room and put the agent online.
+            let (room, event) = {
                 let mut conn = db.get_conn().await;
                 let room = shared_helpers::insert_room(&mut conn).await;
-                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
-                room
+
+                let event = factory::Event::new()
+                    .room_id(room.id())
+                    .kind("message")
+                    .label("message-1")
+                    .data(&json!({ "text": "hello" }))
+                    .occurred_at(1000)
+                    .created_by(&agent.agent_id())
+                    .insert(&mut conn)
+                    .await;
+
+                (room, event)
             };
 
-            // Make event.create request.
-            let mut context = TestContext::new(db, TestAuthz::new());
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
 
-            let payload = CreateRequest {
+            let mut context = TestContext::new(db, authz);
+
+            let fields = vec!["id", "type", "occurred_at", "label"];
+
+            let payload = ListRequest {
                 room_id: room.id(),
-                kind: String::from("message"),
-                set: Some(String::from("messages")),
-                label: Some(String::from("message-1")),
-                attribute: None,
-                data: json!({ "text": "hello" }),
-                is_claim: false,
-                is_persistent: true,
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
+                limit: None,
+                with_total: false,
+                absolute_time: false,
+                fields: Some(fields.iter().map(|f| f.to_string()).collect()),
+                ndjson: false,
+                data_filter: None,
             };
 
-            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Unexpected success on event creation");
+                .expect("Events listing failed");
 
-            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            let (events, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            let event_json = events[0].as_object().expect("Event is not an object");
+            assert_eq!(event_json.len(), fields.len());
+            assert!(event_json.get("data").is_none());
+            assert_eq!(event_json["id"], event.id().to_string());
+            assert_eq!(event_json["type"], "message");
+            assert_eq!(event_json["occurred_at"], 1000);
+            assert_eq!(event_json["label"], "message-1");
+
+            // Unknown fields are rejected.
+            let payload = ListRequest {
+                room_id: room.id(),
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
+                limit: None,
+                with_total: false,
+                absolute_time: false,
+                fields: Some(vec![String::from("kind")]),
+                ndjson: false,
+                data_filter: None,
+            };
+
+            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success listing events with unknown fields");
+
+            assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+            assert_eq!(err.kind(), "invalid_payload");
         });
     }
 
     #[test]
-    fn create_event_not_entered() {
+    fn list_events_records_row_count() {
         async_std::task::block_on(async {
             let db = TestDb::new().await;
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
             let room = {
-                // Create room.
                 let mut conn = db.get_conn().await;
-                shared_helpers::insert_room(&mut conn).await
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                for i in 1..4 {
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .data(&json!({ "text": format!("message {}", i) }))
+                        .occurred_at(i * 1000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+                }
+
+                room
             };
 
-            // Allow agent to create events of type `message` in the room.
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
-            let account_id = agent.account_id().to_string();
-
-            let object = vec![
-                "rooms",
-                &room_id,
-                "events",
-                "message",
-                "authors",
-                &account_id,
-            ];
-
-            authz.allow(agent.account_id(), object, "create");
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
 
-            // Make event.create request.
             let mut context = TestContext::new(db, authz);
 
-            let payload = CreateRequest {
+            let payload = ListRequest {
                 room_id: room.id(),
-                kind: String::from("message"),
-                set: Some(String::from("messages")),
-                label: Some(String::from("message-1")),
-                attribute: None,
-                data: json!({ "text": "hello" }),
-                is_claim: false,
-                is_persistent: true,
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
+                limit: None,
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
             };
 
-            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+            handle_request::<ListHandler>(&mut context, &agent, payload)
                 .await
-                .expect("Event creation failed");
+                .expect("Events listing failed");
 
-            assert_eq!(messages.len(), 2);
+            let report = context
+                .profiler()
+                .flush(60)
+                .expect("Failed to flush profiler");
+
+            let (_, entry) = report
+                .into_iter()
+                .find(|((key, _), _)| *key == ProfilerKeys::EventListRows)
+                .expect("Missing event list row count");
+
+            assert_eq!(entry.max, 3);
         });
     }
 
     #[test]
-    fn create_event_closed_room() {
+    fn list_events_as_ndjson() {
         async_std::task::block_on(async {
             let db = TestDb::new().await;
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
-            let room = {
-                // Create closed room and put the agent online.
+            let (room, db_events) = {
                 let mut conn = db.get_conn().await;
-                let room = shared_helpers::insert_closed_room(&mut conn).await;
-                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
-                room
+                let room = shared_helpers::insert_room(&mut conn).await;
+                let mut events = vec![];
+
+                for i in 1..4 {
+                    let event = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .data(&json!({ "text": format!("line {}\nline {}", i, i + 1) }))
+                        .occurred_at(i * 1000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    events.push(event);
+                }
+
+                (room, events)
             };
 
-            // Allow agent to create events of type `message` in the room.
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
-            let account_id = agent.account_id().to_string();
-
-            let object = vec![
-                "rooms",
-                &room_id,
-                "events",
-                "message",
-                "authors",
-                &account_id,
-            ];
-
-            authz.allow(agent.account_id(), object, "create");
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
 
-            // Make event.create request.
             let mut context = TestContext::new(db, authz);
 
-            let payload = CreateRequest {
+            let payload = ListRequest {
                 room_id: room.id(),
-                kind: String::from("message"),
-                set: Some(String::from("messages")),
-                label: Some(String::from("message-1")),
-                attribute: None,
-                data: json!({ "text": "hello" }),
-                is_claim: false,
-                is_persistent: true,
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Forward,
+                order_by: ListOrder::OccurredAt,
+                limit: None,
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: true,
+                data_filter: None,
             };
 
-            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Unexpected success on event creation");
+                .expect("Events listing failed");
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_closed");
-        });
-    }
+            let (response, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
 
-    #[test]
-    fn create_event_missing_room() {
-        async_std::task::block_on(async {
-            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-            let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+            let ndjson = response["ndjson"].as_str().expect("Missing ndjson body");
+            assert!(ndjson.ends_with('\n'));
 
-            let payload = CreateRequest {
-                room_id: Uuid::new_v4(),
-                kind: String::from("message"),
-                set: Some(String::from("messages")),
-                label: Some(String::from("message-1")),
-                attribute: None,
-                data: json!({ "text": "hello" }),
-                is_claim: false,
-                is_persistent: true,
+            // Each line is a complete event even though event data contains newlines.
+            let events = ndjson
+                .lines()
+                .map(|line| serde_json::from_str::<Event>(line).expect("Invalid NDJSON line"))
+                .collect::<Vec<_>>();
+
+            assert_eq!(events.len(), db_events.len());
+
+            for (event, db_event) in events.iter().zip(db_events.iter()) {
+                assert_eq!(event.id(), db_event.id());
+                assert_eq!(event.data(), db_event.data());
+            }
+
+            // Total has no place in an NDJSON body.
+            let payload = ListRequest {
+                room_id: room.id(),
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Forward,
+                order_by: ListOrder::OccurredAt,
+                limit: None,
+                with_total: true,
+                absolute_time: false,
+                fields: None,
+                ndjson: true,
+                data_filter: None,
             };
 
-            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Unexpected success on event creation");
+                .expect_err("Unexpected success listing events as NDJSON with total");
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_not_found");
+            assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+            assert_eq!(err.kind(), "invalid_payload");
         });
     }
 
-    ///////////////////////////////////////////////////////////////////////////
-
     #[test]
-    fn list_events() {
+    fn list_events_with_data_filter() {
         async_std::task::block_on(async {
             let db = TestDb::new().await;
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
             let (room, db_events) = {
-                // Create room.
                 let mut conn = db.get_conn().await;
                 let room = shared_helpers::insert_room(&mut conn).await;
-
-                // Create events in the room.
                 let mut events = vec![];
 
-                for i in 1..4 {
+                let datas = vec![
+                    json!({ "author": "alice", "text": "hello" }),
+                    json!({ "author": "bob", "text": "hi" }),
+                    json!({ "author": "alice", "text": "bye", "pinned": true }),
+                    json!({ "text": "anonymous" }),
+                ];
+
+                for (i, data) in datas.iter().enumerate() {
                     let event = factory::Event::new()
                         .room_id(room.id())
                         .kind("message")
-                        .data(&json!({ "text": format!("message {}", i) }))
-                        .occurred_at(i * 1000)
+                        .data(data)
+                        .occurred_at(i as i64 * 1000)
                         .created_by(&agent.agent_id())
                         .insert(&mut conn)
                         .await;
@@ -917,58 +4183,58 @@ mod tests {
                 (room, events)
             };
 
-            // Allow agent to list events in the room.
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
             let object = vec!["rooms", &room_id];
             authz.allow(agent.account_id(), object, "read");
 
-            // Make event.list request.
             let mut context = TestContext::new(db, authz);
 
-            let payload = ListRequest {
+            let build_payload = |data_filter| ListRequest {
                 room_id: room.id(),
                 kind: None,
                 set: None,
                 label: None,
-                attribute: None,
+                attributes: vec![],
                 last_occurred_at: None,
-                direction: Direction::Backward,
-                limit: Some(2),
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Forward,
+                order_by: ListOrder::OccurredAt,
+                limit: None,
+                with_total: true,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: Some(data_filter),
             };
 
+            let payload = build_payload(json!({ "author": "alice" }));
+
             let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
                 .await
-                .expect("Events listing failed (page 1)");
+                .expect("Events listing failed");
 
-            // Assert last two events response.
-            let (events, respp, _) = find_response::<Vec<Event>>(messages.as_slice());
+            // Expect only the events whose data contains the filter.
+            let (response, respp, _) = find_response::<JsonValue>(messages.as_slice());
             assert_eq!(respp.status(), ResponseStatus::OK);
-            assert_eq!(events.len(), 2);
-            assert_eq!(events[0].id(), db_events[2].id());
-            assert_eq!(events[1].id(), db_events[1].id());
+            assert_eq!(response["total"], 2);
 
-            // Request the next page.
-            let payload = ListRequest {
-                room_id: room.id(),
-                kind: None,
-                set: None,
-                label: None,
-                attribute: None,
-                last_occurred_at: Some(events[1].occurred_at()),
-                direction: Direction::Backward,
-                limit: Some(2),
-            };
+            let events: Vec<Event> =
+                serde_json::from_value(response["events"].clone()).expect("Invalid events");
 
-            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+            let ids = events.iter().map(|e| e.id()).collect::<Vec<_>>();
+            assert_eq!(ids, vec![db_events[0].id(), db_events[2].id()]);
+
+            // Non-object filters are rejected.
+            let payload = build_payload(json!("alice"));
+
+            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
                 .await
-                .expect("Events listing failed (page 2)");
+                .expect_err("Unexpected success listing events with a non-object data filter");
 
-            // Assert the first event.
-            let (events, respp, _) = find_response::<Vec<Event>>(messages.as_slice());
-            assert_eq!(respp.status(), ResponseStatus::OK);
-            assert_eq!(events.len(), 1);
-            assert_eq!(events[0].id(), db_events[0].id());
+            assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+            assert_eq!(err.kind(), "invalid_payload");
         });
     }
 
@@ -1012,10 +4278,18 @@ mod tests {
                 kind: Some(ListTypesFilter::Single("B".to_string())),
                 set: None,
                 label: None,
-                attribute: None,
+                attributes: vec![],
                 last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
                 direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
                 limit: None,
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
             };
 
             let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
@@ -1035,10 +4309,18 @@ mod tests {
                 ])),
                 set: None,
                 label: None,
-                attribute: None,
+                attributes: vec![],
                 last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
                 direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
                 limit: None,
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
             };
 
             let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
@@ -1052,6 +4334,45 @@ mod tests {
         });
     }
 
+    #[test]
+    fn list_events_with_invalid_number_of_kinds() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let too_many_kinds: Vec<String> = (0..=MAX_LIST_KINDS).map(|i| i.to_string()).collect();
+
+            for kinds in vec![vec![], too_many_kinds] {
+                let payload = ListRequest {
+                    room_id: Uuid::new_v4(),
+                    kind: Some(ListTypesFilter::Multiple(kinds)),
+                    set: None,
+                    label: None,
+                    attributes: vec![],
+                    last_occurred_at: None,
+                    last_created_at: None,
+                    last_id: None,
+                    direction: Direction::Backward,
+                    order_by: ListOrder::OccurredAt,
+                    limit: None,
+                    with_total: false,
+                    absolute_time: false,
+                    fields: None,
+                    ndjson: false,
+                    data_filter: None,
+                };
+
+                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success listing events");
+
+                assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+                assert_eq!(err.kind(), "invalid_payload");
+            }
+        });
+    }
+
     #[test]
     fn list_events_filter_by_attribute() {
         async_std::task::block_on(async {
@@ -1096,10 +4417,18 @@ mod tests {
                 kind: None,
                 set: None,
                 label: None,
-                attribute: Some(String::from("pinned")),
+                attributes: vec![String::from("pinned")],
                 last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
                 direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
                 limit: None,
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
             };
 
             let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
@@ -1110,7 +4439,80 @@ mod tests {
             let (events, respp, _) = find_response::<Vec<Event>>(messages.as_slice());
             assert_eq!(respp.status(), ResponseStatus::OK);
             assert_eq!(events.len(), 1);
-            assert_eq!(events[0].attribute(), Some("pinned"));
+            assert_eq!(events[0].attributes(), ["pinned"]);
+        });
+    }
+
+    #[test]
+    fn list_events_reports_full_page() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                for i in 0..MAX_LIMIT {
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .data(&json!({ "text": format!("message {}", i) }))
+                        .occurred_at(i as i64 * 1000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+                }
+
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().report_full_lists = true;
+
+            let build_payload = |limit| ListRequest {
+                room_id: room.id(),
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
+                limit,
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
+            };
+
+            // One event short of the maximum limit.
+            handle_request::<ListHandler>(&mut context, &agent, build_payload(Some(MAX_LIMIT - 1)))
+                .await
+                .expect("Events listing failed");
+
+            assert!(context.full_list_counter().flush().is_empty());
+
+            // Exactly the maximum limit.
+            handle_request::<ListHandler>(&mut context, &agent, build_payload(None))
+                .await
+                .expect("Events listing failed");
+
+            let counters = context.full_list_counter().flush();
+            assert_eq!(counters.len(), 1);
+
+            let ((method, agent_id), count) = &counters[0];
+            assert_eq!(method, "ignore");
+            assert_eq!(agent_id, &agent.agent_id().to_string());
+            assert_eq!(*count, 1);
         });
     }
 
@@ -1132,10 +4534,18 @@ mod tests {
                 kind: None,
                 set: None,
                 label: None,
-                attribute: None,
+                attributes: vec![],
                 last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
                 direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
                 limit: Some(2),
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
             };
 
             let err = handle_request::<ListHandler>(&mut context, &agent, payload)
@@ -1146,6 +4556,71 @@ mod tests {
         });
     }
 
+    #[test]
+    fn list_events_in_public_audience() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            // Nothing is allowed in authz.
+            let mut context = TestContext::new(db, TestAuthz::new());
+            context.config_mut().public_read_audiences = vec![USR_AUDIENCE.to_owned()];
+
+            let payload = ListRequest {
+                room_id: room.id(),
+                kind: None,
+                set: None,
+                label: None,
+                attributes: vec![],
+                last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
+                direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
+                limit: None,
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Events listing failed");
+
+            let (_, respp, _) = find_response::<Vec<Event>>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            // Writes are still authorized.
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: None,
+                label: None,
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on event creation");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+        });
+    }
+
     #[test]
     fn list_events_missing_room() {
         async_std::task::block_on(async {
@@ -1157,10 +4632,18 @@ mod tests {
                 kind: None,
                 set: None,
                 label: None,
-                attribute: None,
+                attributes: vec![],
                 last_occurred_at: None,
+                last_created_at: None,
+                last_id: None,
                 direction: Direction::Backward,
+                order_by: ListOrder::OccurredAt,
                 limit: Some(2),
+                with_total: false,
+                absolute_time: false,
+                fields: None,
+                ndjson: false,
+                data_filter: None,
             };
 
             let err = handle_request::<ListHandler>(&mut context, &agent, payload)
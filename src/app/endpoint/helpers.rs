@@ -1,13 +1,18 @@
 use anyhow::Context as AnyhowContext;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, FixedOffset, SecondsFormat, Utc};
 use serde::ser::Serialize;
+use serde_derive::Serialize;
+use serde_json::Value as JsonValue;
+use sqlx::postgres::PgConnection;
 use svc_agent::mqtt::{
     IncomingRequestProperties, IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties,
     OutgoingResponse, ResponseStatus, ShortTermTimingProperties,
 };
+use svc_authn::Authenticable;
 use uuid::Uuid;
 
 use crate::app::context::Context;
+use crate::app::endpoint::authz::AuthzObject;
 use crate::app::error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind};
 use crate::app::metrics::ProfilerKeys;
 use crate::app::API_VERSION;
@@ -51,6 +56,8 @@ pub(crate) enum RoomTimeRequirement {
     Any,
     NotClosed,
     Open,
+    /// Same as `Open` but also allows rooms closed by their time less than the given period ago.
+    OpenWithGracePeriod(Duration),
 }
 
 pub(crate) async fn find_room<C: Context>(
@@ -98,7 +105,42 @@ pub(crate) async fn find_room<C: Context>(
                     .error(AppErrorKind::RoomClosed)
             }
         }
+        RoomTimeRequirement::OpenWithGracePeriod(grace_period) => {
+            if room.is_open() || room.is_within_close_grace_period(grace_period) {
+                Ok(room)
+            } else {
+                Err(anyhow!("Room already closed or not yet opened"))
+                    .error(AppErrorKind::RoomClosed)
+            }
+        }
+    }
+}
+
+/// Authorizes reading events of the room unless its audience is configured as public for reads.
+/// Returns the authorization time if the authz service has been called.
+pub(crate) async fn authorize_room_read<C: Context>(
+    context: &mut C,
+    room: &db::room::Object,
+    reqp: &IncomingRequestProperties,
+) -> Result<Option<Duration>, AppError> {
+    if context.config().is_public_read_audience(room.audience()) {
+        return Ok(None);
     }
+
+    let room_id = room.id().to_string();
+    let object = AuthzObject::new(&["rooms", &room_id]).into();
+
+    let authz_time = context
+        .authz()
+        .authorize(
+            room.audience().into(),
+            reqp.as_account_id().to_owned(),
+            object,
+            "read".into(),
+        )
+        .await?;
+
+    Ok(Some(authz_time))
 }
 
 pub(crate) fn add_room_logger_tags<C: Context>(context: &mut C, room: &db::room::Object) {
@@ -110,3 +152,195 @@ pub(crate) fn add_room_logger_tags<C: Context>(context: &mut C, room: &db::room:
         }
     }
 }
+
+/// Converts a client-provided datetime to UTC. A non-UTC offset usually means a client bug
+/// shifting the data so it's rejected with `strict_utc_datetimes` and converted with
+/// a warning otherwise.
+pub(crate) fn to_utc<C: Context>(
+    context: &C,
+    datetime: DateTime<FixedOffset>,
+) -> Result<DateTime<Utc>, AppError> {
+    if datetime.offset().local_minus_utc() != 0 {
+        if context.config().strict_utc_datetimes {
+            return Err(anyhow!(
+                "Datetime must be in UTC, got offset {}",
+                datetime.offset()
+            ))
+            .error(AppErrorKind::InvalidPayload);
+        }
+
+        warn!(
+            context.logger(),
+            "Normalizing non-UTC datetime to UTC: {}",
+            datetime.to_rfc3339()
+        );
+    }
+
+    Ok(datetime.with_timezone(&Utc))
+}
+
+/// Appends a moderation action to the audit log. Pass the transaction of the action itself
+/// so that both get either applied or rolled back together.
+pub(crate) async fn write_audit_log<C: Context>(
+    context: &mut C,
+    conn: &mut PgConnection,
+    query: db::audit_log::InsertQuery,
+    reqp: &IncomingRequestProperties,
+) -> Result<(), AppError> {
+    context
+        .profiler()
+        .measure(
+            (
+                ProfilerKeys::AuditLogInsertQuery,
+                Some(reqp.method().to_owned()),
+            ),
+            query.execute(conn),
+        )
+        .await
+        .context("Failed to write audit log")
+        .error(AppErrorKind::DbQueryFailed)?;
+
+    Ok(())
+}
+
+/// Adds `occurred_at_abs` RFC3339 timestamp computed from the room's opening time
+/// to a serialized event or to each of the events in an array.
+pub(crate) fn add_absolute_time(value: &mut JsonValue, opened_at: DateTime<Utc>) {
+    match value {
+        JsonValue::Array(events) => {
+            for event in events.iter_mut() {
+                add_absolute_time(event, opened_at);
+            }
+        }
+        JsonValue::Object(event) => {
+            if let Some(occurred_at) = event.get("occurred_at").and_then(|v| v.as_i64()) {
+                let absolute_time = opened_at + db::event::occurred_at_to_duration(occurred_at);
+                let absolute_time = absolute_time.to_rfc3339_opts(SecondsFormat::Nanos, true);
+                event.insert(
+                    "occurred_at_abs".to_owned(),
+                    JsonValue::String(absolute_time),
+                );
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Leaves only the given keys in a serialized event or in each of the events in an array.
+pub(crate) fn project_fields(value: &mut JsonValue, fields: &[&str]) {
+    match value {
+        JsonValue::Array(events) => {
+            for event in events.iter_mut() {
+                project_fields(event, fields);
+            }
+        }
+        JsonValue::Object(event) => {
+            let omitted_keys = event
+                .keys()
+                .filter(|key| !fields.contains(&key.as_str()))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            for key in omitted_keys {
+                event.remove(&key);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Serializes each of the events in an array into a separate line of newline-delimited JSON.
+/// Serialized JSON never contains raw newlines so each line is a complete event.
+pub(crate) fn to_ndjson(value: &JsonValue) -> Result<String, AppError> {
+    let events = match value {
+        JsonValue::Array(events) => events.iter().collect::<Vec<_>>(),
+        event => vec![event],
+    };
+
+    let mut ndjson = String::new();
+
+    for event in events {
+        let line = serde_json::to_string(event)
+            .context("Failed to serialize event")
+            .error(AppErrorKind::SerializationFailed)?;
+
+        ndjson.push_str(&line);
+        ndjson.push('\n');
+    }
+
+    Ok(ndjson)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Serialize)]
+struct ValidationIssue {
+    kind: &'static str,
+    detail: String,
+}
+
+/// Accumulates payload validation issues to report all of them in a single error.
+/// The error has the kind of the first issue and lists all of them in the `errors` extra
+/// as JSON array of `{"kind": …, "detail": …}` objects.
+#[derive(Default)]
+pub(crate) struct Validator {
+    kind: Option<AppErrorKind>,
+    issues: Vec<ValidationIssue>,
+    tags: Vec<(String, String)>,
+}
+
+impl Validator {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn check(&mut self, is_valid: bool, kind: AppErrorKind, detail: &str) -> &mut Self {
+        if !is_valid {
+            self.kind.get_or_insert(kind);
+
+            self.issues.push(ValidationIssue {
+                kind: kind.kind(),
+                detail: detail.to_owned(),
+            });
+        }
+
+        self
+    }
+
+    pub(crate) fn tag(&mut self, k: &str, v: &str) -> &mut Self {
+        self.tags.push((k.to_owned(), v.to_owned()));
+        self
+    }
+
+    pub(crate) fn validate(self) -> Result<(), AppError> {
+        let kind = match self.kind {
+            Some(kind) => kind,
+            None => return Ok(()),
+        };
+
+        let detail = self
+            .issues
+            .iter()
+            .map(|issue| issue.detail.as_str())
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        let mut err = AppError::new(kind, anyhow!(detail));
+
+        for (k, v) in self.tags.iter() {
+            err.tag(k, v);
+        }
+
+        match serde_json::to_string(&self.issues) {
+            Ok(issues) => err.tag("errors", &issues),
+            Err(serialization_err) => {
+                error!(
+                    crate::LOG,
+                    "Failed to serialize validation issues: {}", serialization_err
+                );
+            }
+        }
+
+        Err(err)
+    }
+}
@@ -14,6 +14,7 @@ use crate::config::TelemetryConfig;
 pub(crate) struct PullPayload {
     #[serde(default = "default_duration")]
     duration: u64,
+    audience: Option<String>,
 }
 
 fn default_duration() -> u64 {
@@ -35,9 +36,11 @@ impl EventHandler for PullHandler {
             TelemetryConfig {
                 id: Some(ref account_id),
             } => {
-                let metrics = context
-                    .get_metrics(payload.duration)
-                    .error(AppErrorKind::StatsCollectionFailed)?;
+                let metrics = match payload.audience {
+                    Some(ref audience) => context.get_audience_metrics(payload.duration, audience),
+                    None => context.get_metrics(payload.duration),
+                }
+                .error(AppErrorKind::StatsCollectionFailed)?;
 
                 let metrics2 = metrics
                     .clone()
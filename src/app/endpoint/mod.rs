@@ -9,7 +9,7 @@ use svc_agent::mqtt::{
 };
 
 use crate::app::context::Context;
-use crate::app::error::Error as AppError;
+use crate::app::error::{Error as AppError, ErrorKind as AppErrorKind};
 pub(self) use crate::app::message_handler::MessageStream;
 use crate::app::message_handler::{
     EventEnvelopeHandler, RequestEnvelopeHandler, ResponseEnvelopeHandler,
@@ -23,6 +23,9 @@ pub(crate) type Result = StdResult<MessageStream, AppError>;
 pub(crate) trait RequestHandler {
     type Payload: Send + DeserializeOwned;
 
+    /// Whether the handler changes data. Such handlers get rejected in read only mode.
+    const IS_MUTATING: bool = false;
+
     async fn handle<C: Context>(
         context: &mut C,
         payload: Self::Payload,
@@ -30,6 +33,20 @@ pub(crate) trait RequestHandler {
     ) -> Result;
 }
 
+/// Rejects mutating requests when the service is in read only mode.
+pub(crate) fn check_read_only<H: RequestHandler, C: Context>(
+    context: &C,
+) -> StdResult<(), AppError> {
+    if H::IS_MUTATING && context.config().read_only {
+        Err(AppError::new(
+            AppErrorKind::ReadOnlyMode,
+            anyhow!("The service is in read only mode"),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 macro_rules! request_routes {
     ($($m: pat => $h: ty),*) => {
         pub(crate) async fn route_request<C: Context>(
@@ -56,19 +73,37 @@ request_routes!(
     "change.delete" => change::DeleteHandler,
     "change.list" => change::ListHandler,
     "edition.commit" => edition::CommitHandler,
+    "edition.commit.cancel" => edition::CommitCancelHandler,
     "edition.create" => edition::CreateHandler,
     "edition.list" => edition::ListHandler,
+    "edition.read_segments" => edition::ReadSegmentsHandler,
     "edition.delete" => edition::DeleteHandler,
+    "edition.estimate" => edition::EstimateHandler,
+    "edition.validate" => edition::ValidateHandler,
     "event.create" => event::CreateHandler,
     "event.list" => event::ListHandler,
+    "event.list_multi" => event::ListMultiHandler,
+    "event.delete_label" => event::DeleteLabelHandler,
+    "event.move" => event::MoveHandler,
     "room.adjust" => room::AdjustHandler,
+    "room.adjust.cancel" => room::AdjustCancelHandler,
     "room.create" => room::CreateHandler,
+    "room.cut_segments" => room::CutSegmentsHandler,
     "room.dump_events" => room::EventsDumpHandler,
     "room.enter" => room::EnterHandler,
     "room.leave" => room::LeaveHandler,
+    "room.list_derived" => room::ListDerivedHandler,
     "room.read" => room::ReadHandler,
+    "room.tail" => room::TailHandler,
     "room.update" => room::UpdateHandler,
+    "room.update_tags_bulk" => room::UpdateTagsBulkHandler,
     "state.read" => state::ReadHandler,
+    "state.snapshot" => state::SnapshotHandler,
+    "system.audit_list" => system::AuditListHandler,
+    "system.close_expired_rooms" => system::CloseExpiredRoomsHandler,
+    "system.compact" => system::CompactHandler,
+    "system.profiler.read" => system::ProfilerReadHandler,
+    "system.top_agents" => system::TopAgentsHandler,
     "system.vacuum" => system::VacuumHandler
 );
 
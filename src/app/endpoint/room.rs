@@ -1,13 +1,15 @@
 use std::ops::Bound;
+use std::result::Result as StdResult;
 
 use anyhow::Context as AnyhowContext;
 use async_std::prelude::*;
 use async_std::stream;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use futures::FutureExt;
 use serde_derive::{Deserialize, Serialize};
-use serde_json::{json, Value as JsonValue};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+use slog::Logger;
 use svc_agent::{
     mqtt::{
         IncomingRequestProperties, IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties,
@@ -19,14 +21,20 @@ use svc_error::Error as SvcError;
 use uuid::Uuid;
 
 use crate::app::context::Context;
+use crate::app::endpoint::authz::{AuthzIntent, BatchAuthorize};
 use crate::app::endpoint::prelude::*;
 use crate::app::endpoint::subscription::CorrelationDataPayload;
 use crate::app::operations::adjust_room;
+use crate::app::task_registry::TaskKind;
 use crate::app::API_VERSION;
 use crate::db::adjustment::Segments;
 use crate::db::agent;
-use crate::db::room::{InsertQuery, UpdateQuery};
-use crate::db::room_time::{BoundedDateTimeTuple, RoomTime};
+use crate::db::audit_log::InsertQuery as AuditLogInsertQuery;
+use crate::db::room::{
+    DerivedListQuery, InsertQuery, Object as Room, Stats as RoomStats,
+    StatsQuery as RoomStatsQuery, UpdateQuery, UpdateTagsQuery,
+};
+use crate::db::room_time::{BoundedDateTimeTuple, RoomTime, RoomTimeBound};
 
 ///////////////////////////////////////////////////////////////////////////////
 
@@ -59,6 +67,7 @@ pub(crate) struct CreateHandler;
 #[async_trait]
 impl RequestHandler for CreateHandler {
     type Payload = CreateRequest;
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
@@ -67,7 +76,9 @@ impl RequestHandler for CreateHandler {
     ) -> Result {
         // Validate opening time.
         match RoomTime::new(payload.time) {
-            Some(_room_time) => (),
+            Some(room_time) => {
+                validate_room_duration(&room_time, context.config().min_room_duration())?
+            }
             _ => {
                 return Err(anyhow!("Invalid room time"))
                     .error(AppErrorKind::InvalidRoomTime)
@@ -90,6 +101,10 @@ impl RequestHandler for CreateHandler {
             }
         }
 
+        if let Some(ref tags) = payload.tags {
+            validate_tags(tags, context.config().max_room_tags_size())?;
+        }
+
         let object = AuthzObject::new(&["rooms"]).into();
 
         // Authorize room creation on the tenant.
@@ -148,7 +163,7 @@ impl RequestHandler for CreateHandler {
 
         let notification = helpers::build_notification(
             "room.create",
-            &format!("audiences/{}/events", payload.audience),
+            &context.config().topics.audience_events(&payload.audience),
             room,
             reqp,
             context.start_timestamp(),
@@ -163,6 +178,16 @@ impl RequestHandler for CreateHandler {
 #[derive(Debug, Deserialize)]
 pub(crate) struct ReadRequest {
     id: Uuid,
+    #[serde(default)]
+    with_stats: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ReadResponse {
+    #[serde(flatten)]
+    room: Room,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<RoomStats>,
 }
 
 pub(crate) struct ReadHandler;
@@ -197,9 +222,95 @@ impl RequestHandler for ReadHandler {
             )
             .await?;
 
+        // Collect aggregates on demand since they're much heavier than the room itself.
+        let stats = if payload.with_stats {
+            let query = RoomStatsQuery::new(room.id());
+            let mut conn = context.get_read_conn(reqp.method()).await?;
+
+            let stats = context
+                .profiler()
+                .measure(
+                    (ProfilerKeys::RoomStatsQuery, Some(reqp.method().to_owned())),
+                    query.execute(&mut conn),
+                )
+                .await
+                .context("Failed to collect room stats")
+                .error(AppErrorKind::DbQueryFailed)?;
+
+            Some(stats)
+        } else {
+            None
+        };
+
         Ok(Box::new(stream::once(helpers::build_response(
             ResponseStatus::OK,
-            room,
+            ReadResponse { room, stats },
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListDerivedRequest {
+    id: Uuid,
+}
+
+pub(crate) struct ListDerivedHandler;
+
+#[async_trait]
+impl RequestHandler for ListDerivedHandler {
+    type Payload = ListDerivedRequest;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room = helpers::find_room(
+            context,
+            payload.id,
+            helpers::RoomTimeRequirement::Any,
+            reqp.method(),
+        )
+        .await?;
+
+        // Authorize source room reading on the tenant.
+        let object = AuthzObject::room(&room).into();
+
+        let authz_time = context
+            .authz()
+            .authorize(
+                room.audience().into(),
+                reqp.as_account_id().to_owned(),
+                object,
+                "read".into(),
+            )
+            .await?;
+
+        // List rooms derived from the source room by edition commits.
+        let query = DerivedListQuery::new(room.id());
+        let mut conn = context.get_read_conn(reqp.method()).await?;
+
+        let rooms = context
+            .profiler()
+            .measure(
+                (
+                    ProfilerKeys::RoomDerivedListQuery,
+                    Some(reqp.method().to_owned()),
+                ),
+                query.execute(&mut conn),
+            )
+            .await
+            .context("Failed to list derived rooms")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            rooms,
             reqp,
             context.start_timestamp(),
             Some(authz_time),
@@ -207,6 +318,90 @@ impl RequestHandler for ReadHandler {
     }
 }
 
+/// Rooms with a too short time window break state and commit calculations.
+/// Unbounded rooms are always fine.
+fn validate_room_duration(time: &RoomTime, min_duration: Duration) -> StdResult<(), AppError> {
+    if let Some(duration) = time.duration() {
+        if duration < min_duration {
+            return Err(anyhow!(
+                "Room duration {} ms is less than the minimum of {} ms",
+                duration.num_milliseconds(),
+                min_duration.num_milliseconds()
+            ))
+            .error(AppErrorKind::RoomTooShort);
+        }
+    }
+
+    Ok(())
+}
+
+/// Tags must be a flat JSON object with scalar values so tag containment queries stay cheap.
+/// `null` is allowed meaning no tags.
+fn validate_tags(tags: &JsonValue, max_size: usize) -> StdResult<(), AppError> {
+    let object = match tags {
+        JsonValue::Null => return Ok(()),
+        JsonValue::Object(object) => object,
+        _ => return Err(anyhow!("Tags must be a JSON object")).error(AppErrorKind::InvalidTags),
+    };
+
+    validate_scalar_tags(object)?;
+    validate_tags_size(tags, max_size)
+}
+
+fn validate_tags_size(tags: &JsonValue, max_size: usize) -> StdResult<(), AppError> {
+    let size = tags.to_string().len();
+
+    if size > max_size {
+        return Err(anyhow!("Tags size {} exceeds {} bytes", size, max_size))
+            .error(AppErrorKind::InvalidTags);
+    }
+
+    Ok(())
+}
+
+/// A tags patch must be an object with scalar values, `null` meaning removal of the tag.
+/// This keeps patched tags flat as long as they were flat before.
+fn validate_tags_patch(tags_patch: &JsonValue) -> StdResult<&JsonMap<String, JsonValue>, AppError> {
+    match tags_patch {
+        JsonValue::Object(object) => validate_scalar_tags(object).map(|()| object),
+        _ => Err(anyhow!("Tags patch must be a JSON object")).error(AppErrorKind::InvalidTags),
+    }
+}
+
+fn validate_scalar_tags(object: &JsonMap<String, JsonValue>) -> StdResult<(), AppError> {
+    if let Some(key) = object
+        .iter()
+        .find(|(_, value)| value.is_object() || value.is_array())
+        .map(|(key, _)| key)
+    {
+        return Err(anyhow!("Tag '{}' must have a scalar value", key))
+            .error(AppErrorKind::InvalidTags);
+    }
+
+    Ok(())
+}
+
+/// Same as `jsonb_merge_patch` in the DB for a patch with scalar values.
+fn merge_tags_patch(
+    tags: Option<&JsonValue>,
+    tags_patch: &JsonMap<String, JsonValue>,
+) -> JsonValue {
+    let mut merged = match tags {
+        Some(JsonValue::Object(object)) => object.to_owned(),
+        _ => JsonMap::new(),
+    };
+
+    for (key, value) in tags_patch {
+        if value.is_null() {
+            merged.remove(key);
+        } else {
+            merged.insert(key.to_owned(), value.to_owned());
+        }
+    }
+
+    JsonValue::Object(merged)
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug, Deserialize)]
@@ -224,12 +419,17 @@ pub(crate) struct UpdateHandler;
 #[async_trait]
 impl RequestHandler for UpdateHandler {
     type Payload = UpdateRequest;
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
         payload: Self::Payload,
         reqp: &IncomingRequestProperties,
     ) -> Result {
+        if let Some(ref tags) = payload.tags {
+            validate_tags(tags, context.config().max_room_tags_size())?;
+        }
+
         let time_requirement = if payload.time.is_some() {
             // Forbid changing time of a closed room.
             helpers::RoomTimeRequirement::NotClosed
@@ -259,7 +459,10 @@ impl RequestHandler for UpdateHandler {
                 .map_err(|e| anyhow!(e))
                 .error(AppErrorKind::InvalidRoomTime)?;
             match room_time.update(new_time) {
-                Some(nt) => Some(nt.into()),
+                Some(nt) => {
+                    validate_room_duration(&nt, context.config().min_room_duration())?;
+                    Some(nt.into())
+                }
                 None => {
                     return Err(anyhow!("Invalid room time")).error(AppErrorKind::InvalidRoomTime)
                 }
@@ -277,20 +480,37 @@ impl RequestHandler for UpdateHandler {
                 .tags(payload.tags)
                 .classroom_id(payload.classroom_id);
 
-            let mut conn = context.get_conn().await?;
+            let mut txn = context.begin_txn().await?;
 
-            context
+            let room = context
                 .profiler()
                 .measure(
                     (
                         ProfilerKeys::RoomUpdateQuery,
                         Some(reqp.method().to_owned()),
                     ),
-                    query.execute(&mut conn),
+                    query.execute(&mut txn),
                 )
                 .await
                 .context("Failed to update room")
-                .error(AppErrorKind::DbQueryFailed)?
+                .error(AppErrorKind::DbQueryFailed)?;
+
+            let details = serde_json::to_value(&room)
+                .context("Failed to serialize room")
+                .error(AppErrorKind::SerializationFailed)?;
+
+            let query =
+                AuditLogInsertQuery::new(room.id(), "room.update", reqp.as_agent_id().to_owned())
+                    .details(details);
+
+            helpers::write_audit_log(context, &mut txn, query, reqp).await?;
+
+            txn.commit()
+                .await
+                .context("Failed to commit transaction")
+                .error(AppErrorKind::DbQueryFailed)?;
+
+            room
         };
 
         // Respond and broadcast to the audience topic.
@@ -304,7 +524,7 @@ impl RequestHandler for UpdateHandler {
 
         let notification = helpers::build_notification(
             "room.update",
-            &format!("audiences/{}/events", room.audience()),
+            &context.config().topics.audience_events(room.audience()),
             room.clone(),
             reqp,
             context.start_timestamp(),
@@ -315,7 +535,7 @@ impl RequestHandler for UpdateHandler {
         let append_closed_notification = || {
             let closed_notification = helpers::build_notification(
                 "room.close",
-                &format!("rooms/{}/events", room.id()),
+                &context.config().topics.room_events(room.id()),
                 room,
                 reqp,
                 context.start_timestamp(),
@@ -326,11 +546,13 @@ impl RequestHandler for UpdateHandler {
         // Publish room closed notification
         if room_was_open {
             if let Some(time) = payload.time {
+                let now = context.clock().now();
+
                 match time.1 {
-                    Bound::Included(t) if Utc::now() > t => {
+                    Bound::Included(t) if now > t => {
                         append_closed_notification();
                     }
-                    Bound::Excluded(t) if Utc::now() >= t => {
+                    Bound::Excluded(t) if now >= t => {
                         append_closed_notification();
                     }
                     _ => {}
@@ -344,6 +566,166 @@ impl RequestHandler for UpdateHandler {
 
 ///////////////////////////////////////////////////////////////////////////////
 
+const MAX_UPDATE_TAGS_BULK_ROOMS: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct UpdateTagsBulkRequest {
+    room_ids: Vec<Uuid>,
+    tags_patch: JsonValue,
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateTagsBulkResult {
+    id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    room: Option<Room>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<SvcError>,
+}
+
+pub(crate) struct UpdateTagsBulkHandler;
+
+#[async_trait]
+impl RequestHandler for UpdateTagsBulkHandler {
+    type Payload = UpdateTagsBulkRequest;
+    const IS_MUTATING: bool = true;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        if payload.room_ids.len() > MAX_UPDATE_TAGS_BULK_ROOMS {
+            return Err(anyhow!(
+                "Too many rooms: {}, max = {}",
+                payload.room_ids.len(),
+                MAX_UPDATE_TAGS_BULK_ROOMS
+            ))
+            .error(AppErrorKind::InvalidPayload);
+        }
+
+        let tags_patch = validate_tags_patch(&payload.tags_patch)?;
+        let max_tags_size = context.config().max_room_tags_size();
+
+        // Authorize update of each room collecting failures.
+        let mut rooms = Vec::with_capacity(payload.room_ids.len());
+        let mut results = Vec::with_capacity(payload.room_ids.len());
+
+        for id in payload.room_ids {
+            match helpers::find_room(
+                context,
+                id,
+                helpers::RoomTimeRequirement::Any,
+                reqp.method(),
+            )
+            .await
+            {
+                Ok(room) => rooms.push(room),
+                Err(err) => results.push(UpdateTagsBulkResult::failure(id, &err)),
+            }
+        }
+
+        let intents = rooms
+            .iter()
+            .map(|room| AuthzIntent::new(room.audience(), AuthzObject::room(room), "update"))
+            .collect();
+
+        let authz_results = context
+            .authz()
+            .authorize_batch(reqp.as_account_id(), intents)
+            .await;
+
+        // Rooms get authorized concurrently so the longest check is the time spent.
+        let mut authz_time = Duration::zero();
+        let mut authorized_ids = Vec::with_capacity(rooms.len());
+
+        for (room, authz_result) in rooms.iter().zip(authz_results) {
+            match authz_result {
+                Ok(time) => {
+                    authz_time = authz_time.max(time);
+
+                    // Skip rooms whose tags would exceed the size limit after patching.
+                    let merged_tags = merge_tags_patch(room.tags(), tags_patch);
+
+                    match validate_tags_size(&merged_tags, max_tags_size) {
+                        Ok(()) => authorized_ids.push(room.id()),
+                        Err(err) => results.push(UpdateTagsBulkResult::failure(room.id(), &err)),
+                    }
+                }
+                Err(err) => results.push(UpdateTagsBulkResult::failure(
+                    room.id(),
+                    &AppError::from(err),
+                )),
+            }
+        }
+
+        // Patch tags of all authorized rooms in a single statement.
+        let rooms = if authorized_ids.is_empty() {
+            vec![]
+        } else {
+            let query = UpdateTagsQuery::new(authorized_ids, payload.tags_patch.clone());
+            let mut conn = context.get_conn().await?;
+
+            context
+                .profiler()
+                .measure(
+                    (
+                        ProfilerKeys::RoomUpdateTagsQuery,
+                        Some(reqp.method().to_owned()),
+                    ),
+                    query.execute(&mut conn),
+                )
+                .await
+                .context("Failed to update rooms tags")
+                .error(AppErrorKind::DbQueryFailed)?
+        };
+
+        // Respond with per-room results and broadcast updates to the audience topics.
+        let mut responses = Vec::with_capacity(rooms.len() + 1);
+
+        for room in rooms {
+            responses.push(helpers::build_notification(
+                "room.update",
+                &context.config().topics.audience_events(room.audience()),
+                room.clone(),
+                reqp,
+                context.start_timestamp(),
+            ));
+
+            results.push(UpdateTagsBulkResult {
+                id: room.id(),
+                room: Some(room),
+                error: None,
+            });
+        }
+
+        responses.insert(
+            0,
+            helpers::build_response(
+                ResponseStatus::OK,
+                results,
+                reqp,
+                context.start_timestamp(),
+                Some(authz_time),
+            ),
+        );
+
+        Ok(Box::new(stream::from_iter(responses)))
+    }
+}
+
+impl UpdateTagsBulkResult {
+    fn failure(id: Uuid, err: &AppError) -> Self {
+        Self {
+            id,
+            room: None,
+            error: Some(err.to_svc_error()),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct EnterRequest {
     id: Uuid,
@@ -356,6 +738,8 @@ pub(crate) struct EnterHandler;
 #[async_trait]
 impl RequestHandler for EnterHandler {
     type Payload = EnterRequest;
+    // Registers the agent in the room.
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
@@ -565,6 +949,7 @@ pub(crate) struct AdjustHandler;
 #[async_trait]
 impl RequestHandler for AdjustHandler {
     type Payload = AdjustRequest;
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
@@ -597,48 +982,38 @@ impl RequestHandler for AdjustHandler {
         let db = context.db().to_owned();
         let profiler = context.profiler();
         let logger = context.logger().new(o!());
+        let max_cut_gaps = context.config().max_cut_gaps();
+
+        let tracking = reqp.tracking().to_owned();
+        let path = context.config().topics.audience_events(room.audience());
+
+        let task_registry = context.task_registry();
 
         let notification_future = async_std::task::spawn(async move {
-            let operation_result = adjust_room(
+            let operation = adjust_room(
                 &db,
                 &profiler,
                 &room,
                 payload.started_at,
                 &payload.segments,
                 payload.offset,
-            )
-            .await;
+                max_cut_gaps,
+            );
 
-            // Handle result.
-            let result = match operation_result {
-                Ok((original_room, modified_room, modified_segments)) => {
-                    RoomAdjustResult::Success {
-                        original_room_id: original_room.id(),
-                        modified_room_id: modified_room.id(),
-                        modified_segments,
-                    }
-                }
-                Err(err) => {
-                    error!(logger, "Room adjustment job failed: {}", err);
-                    let app_error = AppError::new(AppErrorKind::RoomAdjustTaskFailed, err);
-                    app_error.notify_sentry(&logger);
-                    RoomAdjustResult::Error {
-                        error: app_error.to_svc_error(),
-                    }
+            // Publish success/failure/cancellation notification.
+            let notification = match task_registry
+                .track(TaskKind::RoomAdjust, room.id(), operation)
+                .await
+            {
+                Some(operation_result) => {
+                    RoomAdjustNotification::new(&room, operation_result, &logger)
                 }
-            };
-
-            // Publish success/failure notification.
-            let notification = RoomAdjustNotification {
-                room_id: payload.id,
-                status: result.status(),
-                tags: room.tags().map(|t| t.to_owned()),
-                result,
+                None => RoomAdjustNotification::cancelled(&room),
             };
 
             let timing = ShortTermTimingProperties::new(Utc::now());
-            let props = OutgoingEventProperties::new("room.adjust", timing);
-            let path = format!("audiences/{}/events", room.audience());
+            let mut props = OutgoingEventProperties::new("room.adjust", timing);
+            props.set_tracking(tracking);
             let event = OutgoingEvent::broadcast(notification, props, &path);
 
             Box::new(event) as Box<dyn IntoPublishableMessage + Send>
@@ -660,7 +1035,7 @@ impl RequestHandler for AdjustHandler {
 }
 
 #[derive(Serialize)]
-struct RoomAdjustNotification {
+pub(crate) struct RoomAdjustNotification {
     room_id: Uuid,
     status: &'static str,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -681,6 +1056,47 @@ enum RoomAdjustResult {
     Error {
         error: SvcError,
     },
+    Cancelled {},
+}
+
+impl RoomAdjustNotification {
+    pub(crate) fn new(
+        room: &Room,
+        operation_result: anyhow::Result<(Room, Room, Segments)>,
+        logger: &Logger,
+    ) -> Self {
+        let result = match operation_result {
+            Ok((original_room, modified_room, modified_segments)) => RoomAdjustResult::Success {
+                original_room_id: original_room.id(),
+                modified_room_id: modified_room.id(),
+                modified_segments,
+            },
+            Err(err) => {
+                error!(logger, "Room adjustment job failed: {}", err);
+                let app_error = AppError::new(AppErrorKind::RoomAdjustTaskFailed, err);
+                app_error.notify_sentry(logger);
+                RoomAdjustResult::Error {
+                    error: app_error.to_svc_error(),
+                }
+            }
+        };
+
+        Self {
+            room_id: room.id(),
+            status: result.status(),
+            tags: room.tags().map(|t| t.to_owned()),
+            result,
+        }
+    }
+
+    fn cancelled(room: &Room) -> Self {
+        Self {
+            room_id: room.id(),
+            status: "cancelled",
+            tags: room.tags().map(|t| t.to_owned()),
+            result: RoomAdjustResult::Cancelled {},
+        }
+    }
 }
 
 impl RoomAdjustResult {
@@ -688,13 +1104,75 @@ impl RoomAdjustResult {
         match self {
             Self::Success { .. } => "success",
             Self::Error { .. } => "error",
+            Self::Cancelled {} => "cancelled",
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct AdjustCancelRequest {
+    id: Uuid,
+}
+
+pub(crate) struct AdjustCancelHandler;
+
+#[async_trait]
+impl RequestHandler for AdjustCancelHandler {
+    type Payload = AdjustCancelRequest;
+    // Not mutating on its own: cancellation rolls the adjustment back so it's left available
+    // in read only mode to stop writes that are still in flight.
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room = helpers::find_room(
+            context,
+            payload.id,
+            helpers::RoomTimeRequirement::Any,
+            reqp.method(),
+        )
+        .await?;
+
+        let object = AuthzObject::room(&room).into();
+
+        let authz_time = context
+            .authz()
+            .authorize(
+                room.audience().into(),
+                reqp.as_account_id().to_owned(),
+                object,
+                "update".into(),
+            )
+            .await?;
+
+        // The adjustment task itself rolls back and notifies with `cancelled` status.
+        if !context
+            .task_registry()
+            .cancel(TaskKind::RoomAdjust, room.id())
+        {
+            return Err(anyhow!("No running adjustment for the room"))
+                .error(AppErrorKind::TaskNotFound);
         }
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
 
+pub(crate) use cut_segments::CutSegmentsHandler;
 pub(crate) use dump_events::EventsDumpHandler;
+pub(crate) use tail::TailHandler;
 
 ///////////////////////////////////////////////////////////////////////////////
 
@@ -922,7 +1400,142 @@ mod tests {
                 assert_eq!(err.kind(), "invalid_room_time");
             });
         }
-    }
+
+        #[test]
+        fn create_room_too_short() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                authz.allow(agent.account_id(), vec!["rooms"], "create");
+
+                let mut context = TestContext::new(TestDb::new().await, authz);
+                let now = Utc::now().trunc_subsecs(0);
+                let min_duration = context.config().min_room_duration();
+
+                let payload = CreateRequest {
+                    time: (
+                        Bound::Included(now + Duration::hours(1)),
+                        Bound::Excluded(
+                            now + Duration::hours(1) + min_duration - Duration::milliseconds(1),
+                        ),
+                    ),
+                    audience: USR_AUDIENCE.to_owned(),
+                    tags: None,
+                    preserve_history: None,
+                    classroom_id: None,
+                };
+
+                let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room creation");
+
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "room_too_short");
+            });
+        }
+
+        #[test]
+        fn create_room_of_min_duration() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                authz.allow(agent.account_id(), vec!["rooms"], "create");
+
+                let mut context = TestContext::new(TestDb::new().await, authz);
+                let now = Utc::now().trunc_subsecs(0);
+                let min_duration = context.config().min_room_duration();
+
+                let time = (
+                    Bound::Included(now + Duration::hours(1)),
+                    Bound::Excluded(now + Duration::hours(1) + min_duration),
+                );
+
+                let payload = CreateRequest {
+                    time: BoundedDateTimeTuple::from(time),
+                    audience: USR_AUDIENCE.to_owned(),
+                    tags: None,
+                    preserve_history: None,
+                    classroom_id: None,
+                };
+
+                let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room creation failed");
+
+                let (room, respp, _) = find_response::<Room>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::CREATED);
+                assert_eq!(room.time().map(|t| t.into()), Ok(time));
+            });
+        }
+
+        #[test]
+        fn create_room_with_non_object_tags() {
+            async_std::task::block_on(async {
+                // Allow agent to create rooms.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                authz.allow(agent.account_id(), vec!["rooms"], "create");
+
+                let mut context = TestContext::new(TestDb::new().await, authz);
+                let now = Utc::now().trunc_subsecs(0);
+
+                for tags in vec![
+                    json!(["webinar_id", "123"]),
+                    json!({ "webinar": { "id": "123" } }),
+                ] {
+                    let payload = CreateRequest {
+                        time: (
+                            Bound::Included(now + Duration::hours(1)),
+                            Bound::Excluded(now + Duration::hours(2)),
+                        ),
+                        audience: USR_AUDIENCE.to_owned(),
+                        tags: Some(tags),
+                        preserve_history: None,
+                        classroom_id: None,
+                    };
+
+                    let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect_err("Unexpected success on room creation");
+
+                    assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                    assert_eq!(err.kind(), "invalid_tags");
+                }
+            });
+        }
+
+        #[test]
+        fn create_room_with_oversized_tags() {
+            async_std::task::block_on(async {
+                // Allow agent to create rooms.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                authz.allow(agent.account_id(), vec!["rooms"], "create");
+
+                let mut context = TestContext::new(TestDb::new().await, authz);
+                let now = Utc::now().trunc_subsecs(0);
+                let max_size = context.config().max_room_tags_size();
+
+                let payload = CreateRequest {
+                    time: (
+                        Bound::Included(now + Duration::hours(1)),
+                        Bound::Excluded(now + Duration::hours(2)),
+                    ),
+                    audience: USR_AUDIENCE.to_owned(),
+                    tags: Some(json!({ "description": "x".repeat(max_size) })),
+                    preserve_history: None,
+                    classroom_id: None,
+                };
+
+                let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room creation");
+
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "invalid_tags");
+            });
+        }
+    }
 
     mod read {
         use crate::db::room::Object as Room;
@@ -949,7 +1562,10 @@ mod tests {
 
                 // Make room.read request.
                 let mut context = TestContext::new(db, authz);
-                let payload = ReadRequest { id: room.id() };
+                let payload = ReadRequest {
+                    id: room.id(),
+                    with_stats: false,
+                };
 
                 let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
                     .await
@@ -965,6 +1581,84 @@ mod tests {
             });
         }
 
+        #[test]
+        fn read_room_with_stats() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    // Seed events.
+                    for occurred_at in &[1000, 2000, 3000] {
+                        factory::Event::new()
+                            .room_id(room.id())
+                            .kind("message")
+                            .data(&json!({ "text": "hello" }))
+                            .occurred_at(*occurred_at)
+                            .created_by(agent.agent_id())
+                            .insert(&mut conn)
+                            .await;
+                    }
+
+                    // Seed agents, only the ready one is online.
+                    shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+
+                    let pending_agent = TestAgent::new("web", "user456", USR_AUDIENCE);
+
+                    factory::Agent::new()
+                        .agent_id(pending_agent.agent_id().to_owned())
+                        .room_id(room.id())
+                        .status(crate::db::agent::Status::InProgress)
+                        .insert(&mut conn)
+                        .await;
+
+                    // Seed editions.
+                    for _ in 0..2 {
+                        shared_helpers::insert_edition(&mut conn, &room, agent.agent_id()).await;
+                    }
+
+                    room
+                };
+
+                // Allow agent to read the room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+                // Make room.read request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ReadRequest {
+                    id: room.id(),
+                    with_stats: true,
+                };
+
+                let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room reading failed");
+
+                // Assert response.
+                let (resp, respp, _) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(resp["id"], json!(room.id()));
+
+                let stats = serde_json::from_value::<RoomStats>(resp["stats"].to_owned())
+                    .expect("Failed to parse room stats");
+
+                let expected_stats = RoomStats {
+                    event_count: 3,
+                    agent_count: 1,
+                    edition_count: 2,
+                    last_event_occurred_at: Some(3000),
+                };
+
+                assert_eq!(stats, expected_stats);
+            });
+        }
+
         #[test]
         fn read_room_not_authorized() {
             async_std::task::block_on(async {
@@ -979,7 +1673,10 @@ mod tests {
 
                 // Make room.read request.
                 let mut context = TestContext::new(db, TestAuthz::new());
-                let payload = ReadRequest { id: room.id() };
+                let payload = ReadRequest {
+                    id: room.id(),
+                    with_stats: false,
+                };
 
                 let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
                     .await
@@ -994,7 +1691,10 @@ mod tests {
             async_std::task::block_on(async {
                 let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
                 let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
-                let payload = ReadRequest { id: Uuid::new_v4() };
+                let payload = ReadRequest {
+                    id: Uuid::new_v4(),
+                    with_stats: false,
+                };
 
                 let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
                     .await
@@ -1006,6 +1706,104 @@ mod tests {
         }
     }
 
+    mod list_derived {
+        use crate::app::operations::commit_edition;
+        use crate::config::{CutConfig, DEFAULT_MAX_CUT_GAPS};
+        use crate::db::room::Object as Room;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn list_derived_rooms() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (source, other, editions) = {
+                    let mut conn = db.get_conn().await;
+                    let source = shared_helpers::insert_room(&mut conn).await;
+                    let other = shared_helpers::insert_room(&mut conn).await;
+
+                    let editions = vec![
+                        shared_helpers::insert_edition(&mut conn, &source, agent.agent_id()).await,
+                        shared_helpers::insert_edition(&mut conn, &source, agent.agent_id()).await,
+                        shared_helpers::insert_edition(&mut conn, &other, agent.agent_id()).await,
+                    ];
+
+                    (source, other, editions)
+                };
+
+                // Commit two editions of the source room and one of the other room.
+                let mut authz = TestAuthz::new();
+                let room_id = source.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+                let mut context = TestContext::new(db, authz);
+                let mut destination_ids = vec![];
+
+                for (edition, room) in editions.iter().zip(&[&source, &source, &other]) {
+                    let (destination, _) = commit_edition::call(
+                        context.db(),
+                        &context.profiler(),
+                        edition,
+                        room,
+                        DEFAULT_MAX_CUT_GAPS,
+                        &CutConfig::default(),
+                        room.audience(),
+                        None,
+                    )
+                    .await
+                    .expect("Failed to commit edition");
+
+                    destination_ids.push(destination.id());
+                }
+
+                // Make room.list_derived request.
+                let payload = ListDerivedRequest { id: source.id() };
+
+                let messages = handle_request::<ListDerivedHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Derived rooms listing failed");
+
+                // Assert only the source room's destinations are listed.
+                let (rooms, respp, _) = find_response::<Vec<Room>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+
+                let mut ids = rooms.iter().map(|room| room.id()).collect::<Vec<_>>();
+                ids.sort();
+                let mut expected_ids = destination_ids[..2].to_vec();
+                expected_ids.sort();
+                assert_eq!(ids, expected_ids);
+
+                for room in rooms.iter() {
+                    assert_eq!(room.source_room_id(), Some(source.id()));
+                }
+            });
+        }
+
+        #[test]
+        fn list_derived_rooms_not_authorized() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new().await;
+
+                let room = {
+                    let mut conn = db.get_conn().await;
+                    shared_helpers::insert_room(&mut conn).await
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let payload = ListDerivedRequest { id: room.id() };
+
+                let err = handle_request::<ListDerivedHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on derived rooms listing");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            });
+        }
+    }
+
     mod update {
         use std::ops::Bound;
 
@@ -1134,6 +1932,78 @@ mod tests {
             });
         }
 
+        #[test]
+        fn update_closed_at_in_room_marked_closed() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let now = Utc::now().trunc_subsecs(0);
+
+                let room = {
+                    let mut conn = db.get_conn().await;
+
+                    // Create room.
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((
+                            Bound::Included(now - Duration::hours(1)),
+                            Bound::Excluded(now + Duration::hours(1)),
+                        ))
+                        .insert(&mut conn)
+                        .await;
+
+                    // Mark it closed as if closing has been run with a clock ahead.
+                    crate::db::room::CloseExpiredQuery::new(now + Duration::hours(2), 1)
+                        .execute(&mut conn)
+                        .await
+                        .expect("Failed to close room");
+
+                    room
+                };
+
+                // Allow agent to update the room.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+                // Make room.update request to move the closing further.
+                let mut context = TestContext::new(db.clone(), authz);
+
+                let time = (
+                    Bound::Included(now - Duration::hours(1)),
+                    Bound::Excluded(now + Duration::hours(3)),
+                );
+
+                let payload = UpdateRequest {
+                    id: room.id(),
+                    time: Some(time),
+                    tags: None,
+                    classroom_id: None,
+                };
+
+                let messages = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room update failed");
+
+                let (_, respp, _) = find_response::<Room>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+
+                // Assert the room is reopened.
+                let mut conn = db.get_conn().await;
+
+                let room = crate::db::room::FindQuery::new(room.id())
+                    .execute(&mut conn)
+                    .await
+                    .expect("Failed to find room")
+                    .expect("Room not found");
+
+                assert_eq!(room.closed_at(), None);
+                assert!(room.is_open());
+                assert!(!room.is_closed());
+                assert!(!room.is_within_close_grace_period(Duration::hours(1)));
+            });
+        }
+
         #[test]
         fn update_closed_at_in_the_past_in_already_open_room() {
             async_std::task::block_on(async {
@@ -1198,18 +2068,68 @@ mod tests {
                     v => panic!("Expected Excluded bound, got {:?}", v),
                 }
 
-                // since we just closed the room we must receive a room.close event
-                let (ev_room, _, _) =
-                    find_event_by_predicate::<Room, _>(messages.as_slice(), |evp| {
-                        evp.label() == "room.close"
-                    })
-                    .expect("Failed to find room.close event");
-                assert_eq!(ev_room.id(), room.id());
+                // since we just closed the room we must receive a room.close event
+                let (ev_room, _, _) =
+                    find_event_by_predicate::<Room, _>(messages.as_slice(), |evp| {
+                        evp.label() == "room.close"
+                    })
+                    .expect("Failed to find room.close event");
+                assert_eq!(ev_room.id(), room.id());
+            });
+        }
+
+        #[test]
+        fn update_room_invalid_time() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let now = Utc::now().trunc_subsecs(0);
+
+                let room = {
+                    let mut conn = db.get_conn().await;
+
+                    // Create room.
+                    factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((
+                            Bound::Included(now + Duration::hours(1)),
+                            Bound::Excluded(now + Duration::hours(2)),
+                        ))
+                        .insert(&mut conn)
+                        .await
+                };
+
+                // Allow agent to update the room.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+                // Make room.update request.
+                let mut context = TestContext::new(db, authz);
+
+                let time = (
+                    Bound::Included(now + Duration::hours(1)),
+                    Bound::Excluded(now - Duration::hours(2)),
+                );
+
+                let payload = UpdateRequest {
+                    id: room.id(),
+                    time: Some(time),
+                    tags: None,
+                    classroom_id: None,
+                };
+
+                let err = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room update");
+
+                assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+                assert_eq!(err.kind(), "invalid_room_time");
             });
         }
 
         #[test]
-        fn update_room_invalid_time() {
+        fn update_room_too_short() {
             async_std::task::block_on(async {
                 let db = TestDb::new().await;
                 let now = Utc::now().trunc_subsecs(0);
@@ -1217,7 +2137,6 @@ mod tests {
                 let room = {
                     let mut conn = db.get_conn().await;
 
-                    // Create room.
                     factory::Room::new()
                         .audience(USR_AUDIENCE)
                         .time((
@@ -1228,23 +2147,19 @@ mod tests {
                         .await
                 };
 
-                // Allow agent to update the room.
                 let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
                 let mut authz = TestAuthz::new();
                 let room_id = room.id().to_string();
                 authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
 
-                // Make room.update request.
                 let mut context = TestContext::new(db, authz);
 
-                let time = (
-                    Bound::Included(now + Duration::hours(1)),
-                    Bound::Excluded(now - Duration::hours(2)),
-                );
-
                 let payload = UpdateRequest {
                     id: room.id(),
-                    time: Some(time),
+                    time: Some((
+                        Bound::Included(now + Duration::hours(1)),
+                        Bound::Excluded(now + Duration::hours(1) + Duration::milliseconds(1)),
+                    )),
                     tags: None,
                     classroom_id: None,
                 };
@@ -1253,8 +2168,8 @@ mod tests {
                     .await
                     .expect_err("Unexpected success on room update");
 
-                assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
-                assert_eq!(err.kind(), "invalid_room_time");
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "room_too_short");
             });
         }
 
@@ -1344,6 +2259,230 @@ mod tests {
                 assert_eq!(err.kind(), "room_closed");
             });
         }
+
+        #[test]
+        fn update_room_with_non_object_tags() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+
+                let payload = UpdateRequest {
+                    id: Uuid::new_v4(),
+                    time: None,
+                    tags: Some(json!("webinar_id")),
+                    classroom_id: None,
+                };
+
+                let err = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room update");
+
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "invalid_tags");
+            });
+        }
+    }
+
+    mod update_tags_bulk {
+        use crate::db::room::FindQuery;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn update_tags_bulk() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+
+                let (room1, room2, forbidden_room) = {
+                    let mut conn = db.get_conn().await;
+
+                    (
+                        shared_helpers::insert_room(&mut conn).await,
+                        shared_helpers::insert_room(&mut conn).await,
+                        shared_helpers::insert_room(&mut conn).await,
+                    )
+                };
+
+                // Allow agent to update only the first two rooms.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+
+                for room in &[&room1, &room2] {
+                    let room_id = room.id().to_string();
+                    authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+                }
+
+                // Make room.update_tags_bulk request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = UpdateTagsBulkRequest {
+                    room_ids: vec![room1.id(), room2.id(), forbidden_room.id()],
+                    tags_patch: json!({"archived": true}),
+                };
+
+                let messages =
+                    handle_request::<UpdateTagsBulkHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect("Rooms tags bulk update failed");
+
+                // Assert per-room results.
+                let (results, respp, _) = find_response::<Vec<JsonValue>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(results.len(), 3);
+
+                let find_result = |id: Uuid| {
+                    results
+                        .iter()
+                        .find(|r| r["id"] == json!(id))
+                        .expect("Missing room result")
+                };
+
+                let expected_tags = json!({"webinar_id": "123", "archived": true});
+
+                for room in &[&room1, &room2] {
+                    let result = find_result(room.id());
+                    assert_eq!(result["room"]["tags"], expected_tags);
+                    assert!(result.get("error").is_none());
+                }
+
+                let result = find_result(forbidden_room.id());
+                assert!(result.get("room").is_none());
+                assert_eq!(result["error"]["status"], json!(403));
+
+                // Assert tags in the DB.
+                let mut conn = context.db().acquire().await.expect("Failed conn checkout");
+
+                for (room, tags) in &[
+                    (&room1, &expected_tags),
+                    (&room2, &expected_tags),
+                    (&forbidden_room, &json!({"webinar_id": "123"})),
+                ] {
+                    let db_room = FindQuery::new(room.id())
+                        .execute(&mut conn)
+                        .await
+                        .expect("Failed to find room")
+                        .expect("Room not found");
+
+                    assert_eq!(db_room.tags(), Some(*tags));
+                }
+            });
+        }
+
+        #[test]
+        fn update_tags_bulk_with_invalid_patch() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+
+                let room = {
+                    let mut conn = db.get_conn().await;
+                    shared_helpers::insert_room(&mut conn).await
+                };
+
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+                let mut context = TestContext::new(db, authz);
+
+                for tags_patch in &[
+                    json!(null),
+                    json!(["archived"]),
+                    json!({"archived": {"at": 123}}),
+                    json!({"labels": ["archived"]}),
+                ] {
+                    let payload = UpdateTagsBulkRequest {
+                        room_ids: vec![room.id()],
+                        tags_patch: tags_patch.to_owned(),
+                    };
+
+                    let err =
+                        handle_request::<UpdateTagsBulkHandler>(&mut context, &agent, payload)
+                            .await
+                            .expect_err("Unexpected success on rooms tags bulk update");
+
+                    assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                    assert_eq!(err.kind(), "invalid_tags");
+                }
+            });
+        }
+
+        #[test]
+        fn update_tags_bulk_exceeding_size() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let max_size = TestContext::new(db.clone(), TestAuthz::new())
+                    .config()
+                    .max_room_tags_size();
+
+                let (small_room, large_room) = {
+                    let mut conn = db.get_conn().await;
+                    let small_room = shared_helpers::insert_room(&mut conn).await;
+
+                    let large_room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                        .tags(&json!({ "description": "x".repeat(max_size / 2) }))
+                        .insert(&mut conn)
+                        .await;
+
+                    (small_room, large_room)
+                };
+
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+
+                for room in &[&small_room, &large_room] {
+                    let room_id = room.id().to_string();
+                    authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+                }
+
+                let mut context = TestContext::new(db, authz);
+
+                // The patch fits the limit by itself but not merged into the large room tags.
+                let payload = UpdateTagsBulkRequest {
+                    room_ids: vec![small_room.id(), large_room.id()],
+                    tags_patch: json!({ "notes": "x".repeat(max_size / 2) }),
+                };
+
+                let messages =
+                    handle_request::<UpdateTagsBulkHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect("Rooms tags bulk update failed");
+
+                let (results, respp, _) = find_response::<Vec<JsonValue>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(results.len(), 2);
+
+                let find_result = |id: Uuid| {
+                    results
+                        .iter()
+                        .find(|r| r["id"] == json!(id))
+                        .expect("Missing room result")
+                };
+
+                let result = find_result(small_room.id());
+                assert_eq!(result["room"]["tags"]["webinar_id"], json!("123"));
+                assert!(result.get("error").is_none());
+
+                let result = find_result(large_room.id());
+                assert!(result.get("room").is_none());
+                assert_eq!(result["error"]["status"], json!(422));
+                assert_eq!(result["error"]["type"], json!("invalid_tags"));
+
+                // Tags of the large room stay intact.
+                let mut conn = context.db().acquire().await.expect("Failed conn checkout");
+
+                let db_room = FindQuery::new(large_room.id())
+                    .execute(&mut conn)
+                    .await
+                    .expect("Failed to find room")
+                    .expect("Room not found");
+
+                assert_eq!(db_room.tags(), large_room.tags());
+            });
+        }
     }
 
     mod enter {
@@ -1451,6 +2590,40 @@ mod tests {
             });
         }
 
+        #[test]
+        fn enter_room_in_read_only_mode() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new().await;
+
+                let room = {
+                    // Create room.
+                    let mut conn = db.get_conn().await;
+                    shared_helpers::insert_room(&mut conn).await
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+                // Make room.enter request.
+                let mut context = TestContext::new(db, authz);
+                context.config_mut().read_only = true;
+
+                let payload = EnterRequest {
+                    id: room.id(),
+                    broadcast_subscription: false,
+                };
+
+                let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success entering a room in read only mode");
+
+                assert_eq!(err.status(), ResponseStatus::SERVICE_UNAVAILABLE);
+                assert_eq!(err.kind(), "read_only_mode");
+            });
+        }
+
         #[test]
         fn enter_room_missing() {
             async_std::task::block_on(async {
@@ -1601,10 +2774,208 @@ mod tests {
     mod adjust {
         use chrono::Utc;
 
+        use crate::db::room::{DerivedListQuery, FindQuery};
         use crate::test_helpers::prelude::*;
 
         use super::super::*;
 
+        #[test]
+        fn adjust_room() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new().await;
+
+                let room = {
+                    // Create room.
+                    let mut conn = db.get_conn().await;
+                    shared_helpers::insert_room(&mut conn).await
+                };
+
+                // Allow agent to update the room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+                // Make room.adjust request.
+                let mut context = TestContext::new(db, authz);
+                let opened_at = room.time().map(|t| *t.start()).expect("Invalid room time");
+
+                let payload = AdjustRequest {
+                    id: room.id(),
+                    started_at: opened_at,
+                    segments: vec![(Bound::Included(0), Bound::Excluded(10000))].into(),
+                    offset: 0,
+                };
+
+                let messages = handle_request::<AdjustHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room adjustment failed");
+
+                // Assert notification.
+                let (payload, evp, topic) = find_event::<JsonValue>(messages.as_slice());
+                assert_eq!(evp.label(), "room.adjust");
+                assert!(topic.ends_with(&format!("/audiences/{}/events", room.audience())));
+                assert_eq!(payload["room_id"], json!(room.id()));
+                assert_eq!(payload["status"], "success");
+                assert_eq!(payload["tags"], json!({"webinar_id": "123"}));
+                assert_eq!(payload["modified_segments"], json!([[0, 10000]]));
+
+                let original_room_id: Uuid =
+                    serde_json::from_value(payload["original_room_id"].to_owned())
+                        .expect("Missing original room id");
+
+                let modified_room_id: Uuid =
+                    serde_json::from_value(payload["modified_room_id"].to_owned())
+                        .expect("Missing modified room id");
+
+                // Assert both rooms are derived from the adjusted one.
+                let mut conn = context.db().acquire().await.expect("Failed conn checkout");
+
+                let original_room = FindQuery::new(original_room_id)
+                    .execute(&mut conn)
+                    .await
+                    .expect("Failed to find original room")
+                    .expect("Original room not found");
+
+                let modified_room = FindQuery::new(modified_room_id)
+                    .execute(&mut conn)
+                    .await
+                    .expect("Failed to find modified room")
+                    .expect("Modified room not found");
+
+                assert_eq!(original_room.source_room_id(), Some(room.id()));
+                assert_eq!(modified_room.source_room_id(), Some(original_room.id()));
+            });
+        }
+
+        #[test]
+        fn cancel_adjust_room() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new().await;
+
+                let room = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .data(&json!({ "text": "hello" }))
+                        .occurred_at(1_000_000_000)
+                        .created_by(agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    room
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+                let mut context = TestContext::new(db, authz);
+
+                // Block event insertions so the adjustment hangs on cloning events
+                // into the original room which has already been inserted by that moment.
+                let mut lock_txn = context.db().begin().await.expect("Failed to begin txn");
+
+                sqlx::query("LOCK TABLE event IN SHARE MODE")
+                    .execute(&mut lock_txn)
+                    .await
+                    .expect("Failed to lock event table");
+
+                let opened_at = room.time().map(|t| *t.start()).expect("Invalid room time");
+
+                let payload = AdjustRequest {
+                    id: room.id(),
+                    started_at: opened_at,
+                    segments: vec![(Bound::Included(0), Bound::Excluded(10000))].into(),
+                    offset: 0,
+                };
+
+                let reqp = build_reqp(agent.agent_id(), "room.adjust");
+
+                let adjust_messages = AdjustHandler::handle(&mut context, payload, &reqp)
+                    .await
+                    .expect("Room adjustment failed");
+
+                // Wait until the adjustment has inserted the original room and got stuck
+                // on cloning events into it.
+                let mut attempts = 0;
+
+                loop {
+                    let mut conn = context.db().acquire().await.expect("Failed conn checkout");
+
+                    let (is_waiting,): (bool,) = sqlx::query_as(
+                        "
+                        SELECT EXISTS(
+                            SELECT 1 FROM pg_locks
+                            WHERE relation = 'event'::regclass
+                            AND   NOT granted
+                        )
+                        ",
+                    )
+                    .fetch_one(&mut conn)
+                    .await
+                    .expect("Failed to check event table locks");
+
+                    if is_waiting {
+                        break;
+                    }
+
+                    attempts += 1;
+                    assert!(
+                        attempts < 100,
+                        "Room adjustment didn't reach the event table"
+                    );
+                    async_std::task::sleep(std::time::Duration::from_millis(50)).await;
+                }
+
+                // Cancel the hanging adjustment.
+                let payload = AdjustCancelRequest { id: room.id() };
+
+                let messages = handle_request::<AdjustCancelHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room adjustment cancellation failed");
+
+                let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+
+                lock_txn
+                    .rollback()
+                    .await
+                    .expect("Failed to unlock event table");
+
+                // Assert notification.
+                let messages = parse_messages(adjust_messages).await;
+                let (payload, evp, _) = find_event::<JsonValue>(messages.as_slice());
+                assert_eq!(evp.label(), "room.adjust");
+                assert_eq!(payload["room_id"], json!(room.id()));
+                assert_eq!(payload["status"], "cancelled");
+
+                // Assert the partially created original room is rolled back.
+                let mut conn = context.db().acquire().await.expect("Failed conn checkout");
+
+                let derived_rooms = DerivedListQuery::new(room.id())
+                    .execute(&mut conn)
+                    .await
+                    .expect("Failed to list derived rooms");
+
+                assert!(derived_rooms.is_empty());
+
+                // Nothing to cancel anymore.
+                let payload = AdjustCancelRequest { id: room.id() };
+
+                let err = handle_request::<AdjustCancelHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success cancelling room adjustment");
+
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "task_not_found");
+            });
+        }
+
         #[test]
         fn adjust_room_not_authorized() {
             async_std::task::block_on(async {
@@ -1659,4 +3030,6 @@ mod tests {
     }
 }
 
+mod cut_segments;
 mod dump_events;
+mod tail;
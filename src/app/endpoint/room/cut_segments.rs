@@ -0,0 +1,200 @@
+use anyhow::Context as AnyhowContext;
+use async_std::stream;
+use async_trait::async_trait;
+use serde_derive::{Deserialize, Serialize};
+use svc_agent::mqtt::{IncomingRequestProperties, ResponseStatus};
+use uuid::Uuid;
+
+use crate::app::context::Context;
+use crate::app::endpoint::prelude::*;
+use crate::app::operations::cut_segments;
+use crate::db;
+use crate::db::adjustment::Segments;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CutSegmentsRequest {
+    room_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+struct CutSegmentsResponse {
+    /// Intervals cut out by the room's cut events in milliseconds.
+    gaps: Segments,
+    /// Intervals left after cutting the gaps out in milliseconds.
+    segments: Segments,
+}
+
+pub(crate) struct CutSegmentsHandler;
+
+#[async_trait]
+impl RequestHandler for CutSegmentsHandler {
+    type Payload = CutSegmentsRequest;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room = helpers::find_room(
+            context,
+            payload.room_id,
+            helpers::RoomTimeRequirement::Any,
+            reqp.method(),
+        )
+        .await?;
+
+        let authz_time = helpers::authorize_room_read(context, &room, reqp).await?;
+
+        let room_duration = match room.duration() {
+            Ok(Some(duration)) => duration,
+            _ => {
+                return Err(anyhow!("Room time must be bounded"))
+                    .error(AppErrorKind::InvalidRoomTime);
+            }
+        };
+
+        // Only the room's own cuts are taken into account, not the ones from edition changes.
+        let query = db::event::ListQuery::new()
+            .room_id(room.id())
+            .kind("stream".to_string());
+
+        let cut_events = {
+            let mut conn = context.get_read_conn(reqp.method()).await?;
+
+            context
+                .profiler()
+                .measure_for_audience(
+                    (ProfilerKeys::EventListQuery, Some(reqp.method().to_owned())),
+                    room.audience(),
+                    query.execute(&mut conn),
+                )
+                .await
+                .context("Failed to list cut events")
+                .error(AppErrorKind::DbQueryFailed)?
+        };
+
+        let (gaps, segments) = cut_segments(&cut_events, room_duration, &context.config().cut)
+            .context("Failed to collect cut segments")
+            .error(AppErrorKind::InvalidCuts)?;
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            CutSegmentsResponse { gaps, segments },
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        Ok(Box::new(stream::once(response)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use serde_json::{json, Value as JsonValue};
+
+    use super::*;
+    use crate::app::operations::{invert_segments, NANOSECONDS_IN_MILLISECOND};
+    use crate::test_helpers::prelude::*;
+
+    #[test]
+    fn cut_segments() {
+        async_std::task::block_on(async {
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let db = TestDb::new().await;
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                let cuts = [
+                    ("start", 1_000_000_000),
+                    ("stop", 2_000_000_000),
+                    ("start", 5_000_000_000),
+                    ("stop", 7_000_000_000),
+                ];
+
+                for (command, occurred_at) in cuts.iter() {
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind("stream")
+                        .data(&json!({ "cut": command }))
+                        .occurred_at(*occurred_at)
+                        .created_by(agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+                }
+
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            let mut context = TestContext::new(db, authz);
+            let payload = CutSegmentsRequest { room_id: room.id() };
+
+            let messages = handle_request::<CutSegmentsHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Failed to get cut segments");
+
+            let (response, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            let gaps = vec![
+                (1_000_000_000, 2_000_000_000),
+                (5_000_000_000, 7_000_000_000),
+            ];
+
+            assert_eq!(response["gaps"], to_json_segments(&gaps));
+
+            let room_duration = room
+                .duration()
+                .ok()
+                .flatten()
+                .expect("Room time must be bounded");
+
+            let segments = invert_segments(&gaps, room_duration).expect("Failed to invert gaps");
+            assert_eq!(response["segments"], to_json_segments(&segments));
+        });
+    }
+
+    #[test]
+    fn cut_segments_not_authorized() {
+        async_std::task::block_on(async {
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let db = TestDb::new().await;
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                shared_helpers::insert_room(&mut conn).await
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = CutSegmentsRequest { room_id: room.id() };
+
+            let err = handle_request::<CutSegmentsHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success getting cut segments");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+        });
+    }
+
+    fn to_json_segments(nano_segments: &[(i64, i64)]) -> JsonValue {
+        let segments = nano_segments
+            .iter()
+            .map(|(start, stop)| {
+                (
+                    Bound::Included(start / NANOSECONDS_IN_MILLISECOND),
+                    Bound::Excluded(stop / NANOSECONDS_IN_MILLISECOND),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_value(Segments::from(segments)).expect("Failed to serialize segments")
+    }
+}
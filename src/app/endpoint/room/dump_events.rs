@@ -14,11 +14,13 @@ use uuid::Uuid;
 
 use crate::app::context::Context;
 use crate::app::endpoint::prelude::*;
-use crate::app::operations::dump_events_to_s3;
+use crate::app::operations::{dump_events_to_s3, EventsFilter};
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct EventsDumpRequest {
     id: Uuid,
+    set: Option<String>,
+    kind: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -32,8 +34,14 @@ struct EventsDumpNotification {
 #[derive(Serialize)]
 #[serde(untagged)]
 enum EventsDumpResult {
-    Success { room_id: Uuid, s3_uri: String },
-    Error { error: SvcError },
+    Success {
+        room_id: Uuid,
+        s3_uri: String,
+        event_count: usize,
+    },
+    Error {
+        error: SvcError,
+    },
 }
 
 impl EventsDumpResult {
@@ -89,14 +97,37 @@ impl RequestHandler for EventsDumpHandler {
             })
             .error(AppErrorKind::NoS3Client)?;
 
+        // Refuse instead of queueing when the node is already busy with the maximum of dumps.
+        let dump_permit = match context.dump_gate() {
+            Some(dump_gate) => {
+                let permit = dump_gate
+                    .try_acquire_owned()
+                    .map_err(|err| anyhow!("Too many concurrent dumps: {}", err))
+                    .error(AppErrorKind::TooManyDumps)?;
+
+                Some(permit)
+            }
+            None => None,
+        };
+
+        let filter = EventsFilter {
+            set: payload.set,
+            kind: payload.kind,
+        };
+
+        let tracking = reqp.tracking().to_owned();
+        let path = context.config().topics.audience_events(room.audience());
+
         let notification_future = async_std::task::spawn(async move {
-            let result = dump_events_to_s3(&db, &profiler, s3_client, &room).await;
+            let result = dump_events_to_s3(&db, &profiler, s3_client, &room, &filter).await;
+            drop(dump_permit);
 
             // Handle result.
             let result = match result {
-                Ok(s3_uri) => EventsDumpResult::Success {
+                Ok((s3_uri, event_count)) => EventsDumpResult::Success {
                     room_id: room.id(),
                     s3_uri,
+                    event_count,
                 },
                 Err(err) => {
                     error!(logger, "Events dump job failed: {}", err);
@@ -116,8 +147,8 @@ impl RequestHandler for EventsDumpHandler {
             };
 
             let timing = ShortTermTimingProperties::new(Utc::now());
-            let props = OutgoingEventProperties::new("room.dump_events", timing);
-            let path = format!("audiences/{}/events", room.audience());
+            let mut props = OutgoingEventProperties::new("room.dump_events", timing);
+            props.set_tracking(tracking);
             let event = OutgoingEvent::broadcast(notification, props, &path);
 
             Box::new(event) as Box<dyn IntoPublishableMessage + Send>
@@ -154,7 +185,11 @@ mod tests {
 
             let mut context = TestContext::new(db, TestAuthz::new());
 
-            let payload = EventsDumpRequest { id: room.id() };
+            let payload = EventsDumpRequest {
+                id: room.id(),
+                set: None,
+                kind: None,
+            };
 
             let err = handle_request::<EventsDumpHandler>(&mut context, &agent, payload)
                 .await
@@ -170,7 +205,11 @@ mod tests {
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
             let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
 
-            let payload = EventsDumpRequest { id: Uuid::new_v4() };
+            let payload = EventsDumpRequest {
+                id: Uuid::new_v4(),
+                set: None,
+                kind: None,
+            };
 
             let err = handle_request::<EventsDumpHandler>(&mut context, &agent, payload)
                 .await
@@ -196,7 +235,11 @@ mod tests {
 
             let mut context = TestContext::new(TestDb::new().await, authz);
 
-            let payload = EventsDumpRequest { id: room.id() };
+            let payload = EventsDumpRequest {
+                id: room.id(),
+                set: None,
+                kind: None,
+            };
 
             let err = handle_request::<EventsDumpHandler>(&mut context, &agent, payload)
                 .await
@@ -217,13 +260,30 @@ mod tests {
 
             let room = {
                 let mut conn = db.get_conn().await;
-                shared_helpers::insert_room(&mut conn).await
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                for occurred_at in &[1_000_000_000, 2_000_000_000] {
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .data(&json!({"text": "hello"}))
+                        .occurred_at(*occurred_at)
+                        .created_by(agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+                }
+
+                room
             };
 
             let mut context = TestContext::new(TestDb::new().await, authz);
             context.set_s3(shared_helpers::mock_s3());
 
-            let payload = EventsDumpRequest { id: room.id() };
+            let payload = EventsDumpRequest {
+                id: room.id(),
+                set: None,
+                kind: None,
+            };
 
             let messages = handle_request::<EventsDumpHandler>(&mut context, &agent, payload)
                 .await
@@ -251,6 +311,66 @@ mod tests {
                 ))
                 .as_deref()
             );
+            assert_eq!(
+                ev.get("result")
+                    .and_then(|v| v.get("event_count"))
+                    .and_then(|v| v.as_u64()),
+                Some(2)
+            );
+        });
+    }
+    #[test]
+    fn dump_events_too_many_dumps() {
+        async_std::task::block_on(async {
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let db = TestDb::new().await;
+            let mut authz = TestAuthz::new();
+            authz.allow(agent.account_id(), vec!["rooms"], "dump_events");
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                shared_helpers::insert_room(&mut conn).await
+            };
+
+            let mut context = TestContext::new(db, authz);
+            context.set_s3(shared_helpers::mock_s3());
+            context.set_dump_gate(1);
+
+            // Saturate the gate as if another dump is running.
+            let dump_gate = context.dump_gate().expect("Missing dump gate");
+
+            let permit = dump_gate
+                .clone()
+                .try_acquire_owned()
+                .expect("Failed to acquire dump permit");
+
+            let payload = EventsDumpRequest {
+                id: room.id(),
+                set: None,
+                kind: None,
+            };
+
+            let err = handle_request::<EventsDumpHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room dump");
+
+            assert_eq!(err.status(), ResponseStatus::SERVICE_UNAVAILABLE);
+            assert_eq!(err.kind(), "too_many_dumps");
+
+            // The dump goes after the running one finishes and releases the permit on its own.
+            drop(permit);
+
+            let payload = EventsDumpRequest {
+                id: room.id(),
+                set: None,
+                kind: None,
+            };
+
+            handle_request::<EventsDumpHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Failed to dump room events");
+
+            assert_eq!(dump_gate.available_permits(), 1);
         });
     }
 }
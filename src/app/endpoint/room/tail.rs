@@ -0,0 +1,191 @@
+use anyhow::Context as AnyhowContext;
+use async_std::stream;
+use async_trait::async_trait;
+use serde_derive::{Deserialize, Serialize};
+use svc_agent::mqtt::{IncomingRequestProperties, ResponseStatus};
+use uuid::Uuid;
+
+use crate::app::context::Context;
+use crate::app::endpoint::prelude::*;
+use crate::db;
+use crate::db::event::{Direction, Object as Event};
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct TailRequest {
+    id: Uuid,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct TailResponse {
+    /// `occurred_at` of the latest event in the room to start a live subscription from.
+    last_occurred_at: Option<i64>,
+    /// The latest events in chronological order.
+    events: Vec<Event>,
+}
+
+pub(crate) struct TailHandler;
+
+#[async_trait]
+impl RequestHandler for TailHandler {
+    type Payload = TailRequest;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room = helpers::find_room(
+            context,
+            payload.id,
+            helpers::RoomTimeRequirement::Any,
+            reqp.method(),
+        )
+        .await?;
+
+        // Authorize room events listing.
+        let room_id = room.id().to_string();
+        let object = AuthzObject::new(&["rooms", &room_id]).into();
+
+        let authz_time = context
+            .authz()
+            .authorize(
+                room.audience().into(),
+                reqp.as_account_id().to_owned(),
+                object,
+                "read".into(),
+            )
+            .await?;
+
+        // Take the latest events with a single query so the cursor is consistent with them.
+        let limit = std::cmp::min(payload.limit.unwrap_or(DEFAULT_LIMIT), MAX_LIMIT);
+
+        let query = db::event::ListQuery::new()
+            .room_id(room.id())
+            .direction(Direction::Backward)
+            .limit(limit);
+
+        let mut events = {
+            let mut conn = context.get_read_conn(reqp.method()).await?;
+
+            context
+                .profiler()
+                .measure_for_audience(
+                    (ProfilerKeys::EventListQuery, Some(reqp.method().to_owned())),
+                    room.audience(),
+                    query.execute(&mut conn),
+                )
+                .await
+                .context("Failed to list events")
+                .error(AppErrorKind::DbQueryFailed)?
+        };
+
+        let last_occurred_at = events.first().map(|event| event.occurred_at());
+        events.reverse();
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            TailResponse {
+                last_occurred_at,
+                events,
+            },
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        Ok(Box::new(stream::once(response)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value as JsonValue};
+
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    #[test]
+    fn tail_room() {
+        async_std::task::block_on(async {
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let db = TestDb::new().await;
+
+            let (room, db_events) = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                let mut events = vec![];
+
+                for i in 1..=3 {
+                    let event = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .data(&json!({ "text": format!("message {}", i) }))
+                        .occurred_at(i * 1000)
+                        .created_by(agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    events.push(event);
+                }
+
+                (room, events)
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            let mut context = TestContext::new(db, authz);
+
+            let payload = TailRequest {
+                id: room.id(),
+                limit: Some(2),
+            };
+
+            let messages = handle_request::<TailHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room tail failed");
+
+            let (tail, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            // The cursor points to the latest event.
+            assert_eq!(tail["last_occurred_at"], json!(db_events[2].occurred_at()));
+
+            let events = tail["events"].as_array().expect("Events missing");
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[0]["id"], json!(db_events[1].id()));
+            assert_eq!(events[1]["id"], json!(db_events[2].id()));
+        });
+    }
+
+    #[test]
+    fn tail_room_not_authorized() {
+        async_std::task::block_on(async {
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let db = TestDb::new().await;
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                shared_helpers::insert_room(&mut conn).await
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = TailRequest {
+                id: room.id(),
+                limit: None,
+            };
+
+            let err = handle_request::<TailHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room tail");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+        });
+    }
+}
@@ -1,9 +1,10 @@
+use std::collections::HashSet;
 use std::ops::Bound;
 
 use anyhow::Context as AnyhowContext;
 use async_std::stream;
 use async_trait::async_trait;
-use serde_derive::Deserialize;
+use serde_derive::{Deserialize, Serialize};
 use serde_json::{map::Map as JsonMap, Value as JsonValue};
 use svc_agent::mqtt::{IncomingRequestProperties, ResponseStatus};
 use uuid::Uuid;
@@ -16,15 +17,32 @@ use crate::db;
 
 const MAX_SETS: usize = 10;
 const MAX_LIMIT_PER_SET: i64 = 100;
+const MAX_SNAPSHOT_EVENTS: i64 = 1000;
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct ReadRequest {
     room_id: Uuid,
     sets: Vec<String>,
-    attribute: Option<String>,
+    #[serde(
+        default,
+        alias = "attribute",
+        deserialize_with = "crate::serde::one_or_many::deserialize"
+    )]
+    attributes: Vec<String>,
     occurred_at: Option<i64>,
     original_occurred_at: Option<i64>,
+    as_of_event_id: Option<Uuid>,
     limit: Option<i64>,
+    #[serde(default)]
+    absolute_time: bool,
+    #[serde(default = "ReadRequest::default_with_has_next")]
+    with_has_next: bool,
+}
+
+impl ReadRequest {
+    fn default_with_has_next() -> bool {
+        true
+    }
 }
 
 pub(crate) struct ReadHandler;
@@ -38,17 +56,50 @@ impl RequestHandler for ReadHandler {
         payload: Self::Payload,
         reqp: &IncomingRequestProperties,
     ) -> Result {
-        // Validate parameters.
-        let validation_error = match payload.sets.len() {
-            0 => Some(anyhow!("'sets' can't be empty")),
-            len if len > MAX_SETS => Some(anyhow!("too many 'sets'")),
-            _ => None,
-        };
-
-        if let Some(err) = validation_error {
-            return Err(err).error(AppErrorKind::InvalidStateSets);
+        // Validate parameters reporting all the issues at once.
+        let sets_count = payload.sets.len();
+        let mut validator = helpers::Validator::new();
+
+        validator
+            .check(
+                sets_count > 0,
+                AppErrorKind::InvalidStateSets,
+                "'sets' can't be empty",
+            )
+            .check(
+                sets_count <= MAX_SETS,
+                AppErrorKind::InvalidStateSets,
+                &format!("too many 'sets': sent = {}, max = {}", sets_count, MAX_SETS),
+            )
+            .check(
+                payload.limit.map_or(true, |limit| limit > 0),
+                AppErrorKind::InvalidPayload,
+                "'limit' must be positive",
+            )
+            .check(
+                payload.occurred_at.map_or(true, |t| t >= 0),
+                AppErrorKind::InvalidOccurredAt,
+                "'occurred_at' can't be negative",
+            )
+            .check(
+                payload.original_occurred_at.map_or(true, |t| t >= 0),
+                AppErrorKind::InvalidOccurredAt,
+                "'original_occurred_at' can't be negative",
+            )
+            .check(
+                payload.occurred_at.is_none() || payload.as_of_event_id.is_none(),
+                AppErrorKind::InvalidPayload,
+                "'occurred_at' and 'as_of_event_id' are mutually exclusive",
+            );
+
+        if sets_count > MAX_SETS {
+            validator
+                .tag("sent", &sets_count.to_string())
+                .tag("max", &MAX_SETS.to_string());
         }
 
+        validator.validate()?;
+
         // Choose limit.
         let limit = std::cmp::min(
             payload.limit.unwrap_or(MAX_LIMIT_PER_SET),
@@ -65,38 +116,74 @@ impl RequestHandler for ReadHandler {
         .await?;
 
         // Authorize room events listing.
-        let room_id = room.id().to_string();
-        let object = AuthzObject::new(&["rooms", &room_id]).into();
-
-        let authz_time = context
-            .authz()
-            .authorize(
-                room.audience().into(),
-                reqp.as_account_id().to_owned(),
-                object,
-                "read".into(),
-            )
-            .await?;
+        let authz_time = helpers::authorize_room_read(context, &room, reqp).await?;
+
+        // Shed load instead of piling up queries when a hot room is read by too many clients.
+        // The permit is held until the handler returns.
+        let _state_read_permit = match context.config().max_concurrent_state_reads_per_room {
+            Some(limit) => {
+                let permit = context
+                    .state_read_limiter()
+                    .try_acquire(room.id(), limit)
+                    .ok_or_else(|| anyhow!("Too many concurrent state reads in the room"))
+                    .error(AppErrorKind::TooManyStateReads)?;
+
+                Some(permit)
+            }
+            None => None,
+        };
 
         // Default `occurred_at`: closing time of the room.
         let time = room.time().map(|t| t.into());
         let original_occurred_at = if let Some(original_occurred_at) = payload.original_occurred_at
         {
             original_occurred_at
-        } else if let Ok((_, Bound::Unbounded)) = time {
-            std::i64::MAX
-        } else if let Ok((Bound::Included(open), Bound::Excluded(close))) = time {
-            (close - open)
-                .num_nanoseconds()
-                .map(|n| n + 1)
-                .unwrap_or(std::i64::MAX)
         } else {
-            return Err(anyhow!("Bad room time")).error(AppErrorKind::InvalidRoomTime);
+            match room.duration() {
+                Ok(Some(duration)) => {
+                    db::event::occurred_at_from_duration(duration).saturating_add(1)
+                }
+                Ok(None) => std::i64::MAX,
+                Err(_) => {
+                    return Err(anyhow!("Bad room time")).error(AppErrorKind::InvalidRoomTime);
+                }
+            }
+        };
+
+        let opened_at = match time {
+            Ok((Bound::Included(open), _)) if payload.absolute_time => Some(open),
+            _ => None,
+        };
+
+        let mut conn = context.get_read_conn(reqp.method()).await?;
+
+        // Resolve the moment of state calculation right after the given event including it.
+        let occurred_at = match payload.as_of_event_id {
+            Some(event_id) => {
+                let query = db::event::FindQuery::new(room.id(), event_id);
+
+                let maybe_event = context
+                    .profiler()
+                    .measure(
+                        (ProfilerKeys::EventFindQuery, Some(reqp.method().to_owned())),
+                        query.execute(&mut conn),
+                    )
+                    .await
+                    .context("Failed to find event")
+                    .error(AppErrorKind::DbQueryFailed)?;
+
+                match maybe_event {
+                    Some(event) => Some(event.occurred_at() + 1),
+                    None => {
+                        return Err(anyhow!("Event not found")).error(AppErrorKind::EventNotFound);
+                    }
+                }
+            }
+            None => payload.occurred_at,
         };
 
         // Retrieve state for each set from the DB and put them into a map.
         let mut state = JsonMap::new();
-        let mut conn = context.get_ro_conn().await?;
 
         for set in payload.sets.iter() {
             context.add_logger_tags(o!("set" => set.to_string()));
@@ -105,27 +192,66 @@ impl RequestHandler for ReadHandler {
             let mut query =
                 db::event::SetStateQuery::new(room.id(), set.clone(), original_occurred_at, limit);
 
-            if let Some(ref attribute) = payload.attribute {
-                query = query.attribute(attribute);
+            if !payload.attributes.is_empty() {
+                query = query.attributes(payload.attributes.clone());
             }
 
-            if let Some(occurred_at) = payload.occurred_at {
+            if let Some(occurred_at) = occurred_at {
                 query = query.occurred_at(occurred_at);
             }
 
-            // If it is the only set specified at first execute a total count query and
-            // add `has_next` pagination flag to the state.
-            if payload.sets.len() == 1 {
-                let total_count = context
-                    .profiler()
-                    .measure(
-                        (
-                            ProfilerKeys::StateTotalCountQuery,
-                            Some(reqp.method().to_owned()),
-                        ),
-                        query.total_count(&mut conn),
+            // If it is the only set specified also execute a total count query and
+            // add `has_next` pagination flag to the state unless the client opted out.
+            let with_total_count = payload.sets.len() == 1 && payload.with_has_next;
+            let profiler = context.profiler();
+            let total_count_key = (
+                ProfilerKeys::StateTotalCountQuery,
+                Some(reqp.method().to_owned()),
+            );
+            let state_key = (ProfilerKeys::StateQuery, Some(reqp.method().to_owned()));
+
+            // Count on a separate connection so it doesn't add up to the state latency.
+            // Waiting for it while holding `conn` could exhaust the pool so it's only taken
+            // when there's a free one at hand.
+            let maybe_count_conn = if with_total_count && context.config().concurrent_state_count {
+                context.try_get_read_conn(reqp.method())
+            } else {
+                None
+            };
+
+            let (total_count, set_state) = if let Some(mut count_conn) = maybe_count_conn {
+                let count_query = query.clone();
+
+                let (total_count, set_state) = futures::join!(
+                    profiler.measure(total_count_key, count_query.total_count(&mut count_conn)),
+                    profiler.measure_for_audience(
+                        state_key,
+                        room.audience(),
+                        query.execute(&mut conn)
+                    ),
+                );
+
+                (Some(total_count), set_state)
+            } else {
+                let total_count = if with_total_count {
+                    Some(
+                        profiler
+                            .measure(total_count_key, query.total_count(&mut conn))
+                            .await,
                     )
-                    .await
+                } else {
+                    None
+                };
+
+                let set_state = profiler
+                    .measure_for_audience(state_key, room.audience(), query.execute(&mut conn))
+                    .await;
+
+                (total_count, set_state)
+            };
+
+            if let Some(total_count) = total_count {
+                let total_count = total_count
                     .context("Failed to get state total count")
                     .error(AppErrorKind::DbQueryFailed)?;
 
@@ -133,22 +259,25 @@ impl RequestHandler for ReadHandler {
                 state.insert(String::from("has_next"), JsonValue::Bool(has_next));
             }
 
-            // Limit the query and retrieve the state.
-            let set_state = context
-                .profiler()
-                .measure(
-                    (ProfilerKeys::StateQuery, Some(reqp.method().to_owned())),
-                    query.execute(&mut conn),
-                )
-                .await
+            let set_state = set_state
                 .context("Failed to get state")
                 .error(AppErrorKind::DbQueryFailed)?;
 
+            context.profiler().record_value(
+                (ProfilerKeys::StateRows, Some(reqp.method().to_owned())),
+                room.audience(),
+                set_state.len(),
+            );
+
             // Serialize to JSON and add to the state map.
-            let serialized_set_state = serde_json::to_value(set_state)
+            let mut serialized_set_state = serde_json::to_value(set_state)
                 .context("Failed to serialize state")
                 .error(AppErrorKind::SerializationFailed)?;
 
+            if let Some(opened_at) = opened_at {
+                helpers::add_absolute_time(&mut serialized_set_state, opened_at);
+            }
+
             match serialized_set_state.as_array().and_then(|a| a.first()) {
                 Some(event) if event.get("label").is_none() => {
                     // The first event has no label => simple set with a single event…
@@ -161,10 +290,157 @@ impl RequestHandler for ReadHandler {
             }
         }
 
+        // Huge states strain the broker so the client has to ask for less.
+        let state = JsonValue::Object(state);
+        let state_size = state.to_string().len();
+        let max_state_size = context.config().max_state_size();
+
+        if state_size > max_state_size {
+            context
+                .oversized_response_counter()
+                .increment(reqp.method());
+
+            return Err(anyhow!(
+                "State size {} exceeds {} bytes, request fewer sets or a smaller limit",
+                state_size,
+                max_state_size
+            ))
+            .error(AppErrorKind::ResponseTooLarge);
+        }
+
         // Respond with state.
         Ok(Box::new(stream::once(helpers::build_response(
             ResponseStatus::OK,
-            JsonValue::Object(state),
+            state,
+            reqp,
+            context.start_timestamp(),
+            authz_time,
+        ))))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SnapshotRequest {
+    room_id: Uuid,
+    occurred_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct SnapshotResponse {
+    state: JsonMap<String, JsonValue>,
+    #[serde(skip_serializing_if = "JsonMap::is_empty")]
+    unlabelled: JsonMap<String, JsonValue>,
+    truncated: bool,
+}
+
+pub(crate) struct SnapshotHandler;
+
+#[async_trait]
+impl RequestHandler for SnapshotHandler {
+    type Payload = SnapshotRequest;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room = helpers::find_room(
+            context,
+            payload.room_id,
+            helpers::RoomTimeRequirement::Any,
+            reqp.method(),
+        )
+        .await?;
+
+        // Authorize room events listing.
+        let room_id = room.id().to_string();
+        let object = AuthzObject::new(&["rooms", &room_id]).into();
+
+        let authz_time = context
+            .authz()
+            .authorize(
+                room.audience().into(),
+                reqp.as_account_id().to_owned(),
+                object,
+                "list".into(),
+            )
+            .await?;
+
+        // Fetch one extra event to find out whether the snapshot is truncated.
+        let mut query = db::event::SnapshotQuery::new(room.id(), MAX_SNAPSHOT_EVENTS + 1);
+
+        if let Some(occurred_at) = payload.occurred_at {
+            query = query.occurred_at(occurred_at);
+        }
+
+        let mut events = {
+            let mut conn = context.get_read_conn(reqp.method()).await?;
+
+            context
+                .profiler()
+                .measure(
+                    (
+                        ProfilerKeys::StateSnapshotQuery,
+                        Some(reqp.method().to_owned()),
+                    ),
+                    query.execute(&mut conn),
+                )
+                .await
+                .context("Failed to get state snapshot")
+                .error(AppErrorKind::DbQueryFailed)?
+        };
+
+        let truncated = events.len() as i64 > MAX_SNAPSHOT_EVENTS;
+        events.truncate(MAX_SNAPSHOT_EVENTS as usize);
+
+        // Sets mixing labelled and unlabelled events can't be represented by a single value
+        // so their unlabelled event goes to a separate map.
+        let labelled_sets = events
+            .iter()
+            .filter(|event| event.label().is_some())
+            .map(|event| event.set().to_owned())
+            .collect::<HashSet<String>>();
+
+        // Build `set -> label -> event` map. Simple sets without labels map directly to the event.
+        let mut state = JsonMap::new();
+        let mut unlabelled = JsonMap::new();
+
+        for event in events {
+            let set = event.set().to_owned();
+            let label = event.label().map(|l| l.to_owned());
+
+            let serialized_event = serde_json::to_value(event)
+                .context("Failed to serialize event")
+                .error(AppErrorKind::SerializationFailed)?;
+
+            match label {
+                None if labelled_sets.contains(&set) => {
+                    unlabelled.insert(set, serialized_event);
+                }
+                None => {
+                    state.insert(set, serialized_event);
+                }
+                Some(label) => {
+                    let labels = state
+                        .entry(set)
+                        .or_insert_with(|| JsonValue::Object(JsonMap::new()));
+
+                    if let Some(labels) = labels.as_object_mut() {
+                        labels.insert(label, serialized_event);
+                    }
+                }
+            }
+        }
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            SnapshotResponse {
+                state,
+                unlabelled,
+                truncated,
+            },
             reqp,
             context.start_timestamp(),
             Some(authz_time),
@@ -176,10 +452,13 @@ impl RequestHandler for ReadHandler {
 
 #[cfg(test)]
 mod tests {
+    use chrono::{Duration, Utc};
     use serde_derive::Deserialize;
     use serde_json::json;
 
+    use crate::app::endpoint::event::{CreateHandler, CreateRequest};
     use crate::db::event::Object as Event;
+    use crate::test_helpers::context::FixedClock;
     use crate::test_helpers::prelude::*;
 
     use super::*;
@@ -240,10 +519,13 @@ mod tests {
             let payload = ReadRequest {
                 room_id: room.id(),
                 sets: vec![String::from("messages"), String::from("layout")],
-                attribute: None,
+                attributes: vec![],
                 occurred_at: None,
                 original_occurred_at: None,
+                as_of_event_id: None,
                 limit: None,
+                absolute_time: false,
+                with_has_next: true,
             };
 
             let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
@@ -311,10 +593,13 @@ mod tests {
             let payload = ReadRequest {
                 room_id: room.id(),
                 sets: vec![String::from("messages")],
-                attribute: None,
+                attributes: vec![],
                 occurred_at: Some(2001),
                 original_occurred_at: None,
+                as_of_event_id: None,
                 limit: Some(2),
+                absolute_time: false,
+                with_has_next: true,
             };
 
             let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
@@ -333,10 +618,13 @@ mod tests {
             let payload = ReadRequest {
                 room_id: room.id(),
                 sets: vec![String::from("messages")],
-                attribute: None,
+                attributes: vec![],
                 occurred_at: Some(1),
                 original_occurred_at: Some(state.messages[1].original_occurred_at()),
+                as_of_event_id: None,
                 limit: Some(2),
+                absolute_time: false,
+                with_has_next: true,
             };
 
             let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
@@ -352,6 +640,169 @@ mod tests {
         });
     }
 
+    #[test]
+    fn read_state_collection_with_tied_occurred_at() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, db_events) = {
+                // Create room.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // Create two versions of two labels all occurred at the same moment.
+                let now = Utc::now();
+                let mut events = vec![];
+
+                for i in 0..4 {
+                    let event = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .set("messages")
+                        .label(&format!("message-{}", i % 2 + 1))
+                        .data(&json!({
+                            "text": format!("message {}, version {}", i % 2 + 1, i / 2 + 1),
+                        }))
+                        .occurred_at(1000)
+                        .created_by(&agent.agent_id())
+                        .created_at(now + Duration::seconds(i))
+                        .insert(&mut conn)
+                        .await;
+
+                    events.push(event);
+                }
+
+                (room, events)
+            };
+
+            // Allow agent to list events in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            // Make the same state.read request several times.
+            let mut context = TestContext::new(db, authz);
+
+            for _ in 0..3 {
+                let payload = ReadRequest {
+                    room_id: room.id(),
+                    sets: vec![String::from("messages")],
+                    attributes: vec![],
+                    occurred_at: None,
+                    original_occurred_at: None,
+                    as_of_event_id: None,
+                    limit: None,
+                    absolute_time: false,
+                    with_has_next: true,
+                };
+
+                let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("State reading failed");
+
+                // Assert the latest created versions come in the same order each time.
+                let (state, respp, _) = find_response::<CollectionState>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(state.messages.len(), 2);
+                assert_eq!(state.messages[0].id(), db_events[2].id());
+                assert_eq!(state.messages[1].id(), db_events[3].id());
+            }
+        });
+    }
+
+    #[test]
+    fn read_state_of_created_event() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                shared_helpers::insert_agent(&mut conn, agent.agent_id(), room.id()).await;
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let account_id = agent.account_id().to_string();
+
+            let object = vec![
+                "rooms",
+                &room_id,
+                "events",
+                "message",
+                "authors",
+                &account_id,
+            ];
+
+            authz.allow(agent.account_id(), object, "create");
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            // Create the event through the API 1.5 seconds after the room opening.
+            let mut context = TestContext::new(db, authz);
+            let opened_at = *room.time().expect("Invalid room time").start();
+            context.set_clock(FixedClock(opened_at + Duration::milliseconds(1500)));
+
+            let payload = CreateRequest {
+                room_id: room.id(),
+                kind: String::from("message"),
+                set: Some(String::from("messages")),
+                label: Some(String::from("message-1")),
+                attributes: vec![],
+                data: json!({ "text": "hello" }),
+                is_claim: false,
+                is_persistent: true,
+                notify: true,
+                expected_version: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Event creation failed");
+
+            let (event, _, _) = find_response::<Event>(messages.as_slice());
+            assert_eq!(event.occurred_at(), 1_500_000_000);
+
+            // Both the default moment and explicit nanosecond moments must agree on the unit.
+            let cases = [
+                (None, Some(event.id())),
+                (Some(1_500_000_001), Some(event.id())),
+                (Some(1_500_000_000), None),
+                (Some(1_501), None),
+            ];
+
+            for (occurred_at, expected_event_id) in cases.iter() {
+                let payload = ReadRequest {
+                    room_id: room.id(),
+                    sets: vec![String::from("messages")],
+                    attributes: vec![],
+                    occurred_at: *occurred_at,
+                    original_occurred_at: None,
+                    as_of_event_id: None,
+                    limit: None,
+                    absolute_time: false,
+                    with_has_next: true,
+                };
+
+                let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("State reading failed");
+
+                let (state, _, _) = find_response::<CollectionState>(messages.as_slice());
+                let event_ids = state.messages.iter().map(|e| e.id()).collect::<Vec<_>>();
+                let expected_event_ids = expected_event_id.iter().copied().collect::<Vec<_>>();
+                assert_eq!(
+                    event_ids, expected_event_ids,
+                    "occurred_at = {:?}",
+                    occurred_at
+                );
+            }
+        });
+    }
+
     #[test]
     fn read_state_collection_with_attribute_filter() {
         async_std::task::block_on(async {
@@ -401,10 +852,13 @@ mod tests {
             let payload = ReadRequest {
                 room_id: room.id(),
                 sets: vec![String::from("messages")],
-                attribute: Some(String::from("pinned")),
+                attributes: vec![String::from("pinned")],
                 occurred_at: None,
                 original_occurred_at: None,
+                as_of_event_id: None,
                 limit: None,
+                absolute_time: false,
+                with_has_next: true,
             };
 
             let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
@@ -415,7 +869,83 @@ mod tests {
             let (state, respp, _) = find_response::<CollectionState>(messages.as_slice());
             assert_eq!(respp.status(), ResponseStatus::OK);
             assert_eq!(state.messages.len(), 1);
-            assert_eq!(state.messages[0].attribute(), Some("pinned"));
+            assert_eq!(state.messages[0].attributes(), ["pinned"]);
+        });
+    }
+
+    #[test]
+    fn read_state_collection_with_attribute_filter_and_tied_occurred_at() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, db_events) = {
+                // Create room.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // Create two versions of three labels all occurred at the same moment.
+                // Only the latest versions of the first two labels are pinned.
+                let now = Utc::now();
+                let mut events = vec![];
+
+                for i in 0..6 {
+                    let mut factory = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .set("messages")
+                        .label(&format!("message-{}", i % 3 + 1))
+                        .data(&json!({
+                            "text": format!("message {}, version {}", i % 3 + 1, i / 3 + 1),
+                        }))
+                        .occurred_at(1000)
+                        .created_by(&agent.agent_id())
+                        .created_at(now + Duration::seconds(i));
+
+                    if i >= 3 && i % 3 != 2 {
+                        factory = factory.attribute("pinned");
+                    }
+
+                    let event = factory.insert(&mut conn).await;
+                    events.push(event);
+                }
+
+                (room, events)
+            };
+
+            // Allow agent to list events in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            // Make the same state.read request several times.
+            let mut context = TestContext::new(db, authz);
+
+            for _ in 0..3 {
+                let payload = ReadRequest {
+                    room_id: room.id(),
+                    sets: vec![String::from("messages")],
+                    attributes: vec![String::from("pinned")],
+                    occurred_at: None,
+                    original_occurred_at: None,
+                    as_of_event_id: None,
+                    limit: None,
+                    absolute_time: false,
+                    with_has_next: true,
+                };
+
+                let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("State reading failed");
+
+                // Assert the latest pinned versions come in the same order each time.
+                let (state, respp, _) = find_response::<CollectionState>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(state.messages.len(), 2);
+                assert_eq!(state.messages[0].id(), db_events[3].id());
+                assert_eq!(state.messages[1].id(), db_events[4].id());
+            }
         });
     }
 
@@ -465,10 +995,13 @@ mod tests {
             let payload = ReadRequest {
                 room_id: room.id(),
                 sets: vec![String::from("messages")],
-                attribute: None,
+                attributes: vec![],
                 occurred_at: Some(2001),
                 original_occurred_at: None,
+                as_of_event_id: None,
                 limit: Some(2),
+                absolute_time: false,
+                with_has_next: true,
             };
 
             let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
@@ -487,10 +1020,13 @@ mod tests {
             let payload = ReadRequest {
                 room_id: room.id(),
                 sets: vec![String::from("messages")],
-                attribute: None,
+                attributes: vec![],
                 occurred_at: Some(1),
                 original_occurred_at: Some(state.messages[1].original_occurred_at()),
+                as_of_event_id: None,
                 limit: Some(2),
+                absolute_time: false,
+                with_has_next: true,
             };
 
             let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
@@ -507,19 +1043,317 @@ mod tests {
     }
 
     #[test]
-    fn read_state_pinned_messages() {
+    fn read_state_collection_with_concurrent_count() {
         async_std::task::block_on(async {
             let db = TestDb::new().await;
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
-            let (room, pinned_message) = {
+            let room = {
                 // Create room.
                 let mut conn = db.get_conn().await;
                 let room = shared_helpers::insert_room(&mut conn).await;
 
-                // Create a not pinned message.
-                let base_message_factory = factory::Event::new()
-                    .room_id(room.id())
+                // Create events in the room.
+                for i in 0..6 {
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .set("messages")
+                        .label(&format!("message-{}", i % 3 + 1))
+                        .data(&json!({
+                            "text": format!("message {}, version {}", i % 3 + 1, i / 3 + 1),
+                        }))
+                        .occurred_at(i * 1000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+                }
+
+                room
+            };
+
+            // Allow agent to list events in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            let mut context = TestContext::new(db, authz);
+            let mut responses = vec![];
+
+            // Make the same state.read request with sequential total count, with concurrent
+            // total count falling back to sequential since the single connection is taken and
+            // with concurrent total count on a pool with a spare connection.
+            for (concurrent_state_count, pool_size) in &[(false, 1), (true, 1), (true, 2)] {
+                context.config_mut().concurrent_state_count = *concurrent_state_count;
+                context.set_ro_db(TestDb::with_pool_size(*pool_size).await);
+
+                let payload = ReadRequest {
+                    room_id: room.id(),
+                    sets: vec![String::from("messages")],
+                    attributes: vec![],
+                    occurred_at: None,
+                    original_occurred_at: None,
+                    as_of_event_id: None,
+                    limit: Some(2),
+                    absolute_time: false,
+                    with_has_next: true,
+                };
+
+                let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("State reading failed");
+
+                let (state, respp, _) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                responses.push(state);
+            }
+
+            // Expect identical responses.
+            assert_eq!(
+                responses[0]["messages"].as_array().map(|m| m.len()),
+                Some(2)
+            );
+            assert_eq!(responses[0]["has_next"], json!(true));
+            assert_eq!(responses[0], responses[1]);
+            assert_eq!(responses[0], responses[2]);
+        });
+    }
+
+    #[test]
+    fn read_state_collection_without_has_next() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                // Create room.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // Create events in the room.
+                for i in 0..3 {
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .set("messages")
+                        .label(&format!("message-{}", i + 1))
+                        .data(&json!({ "text": format!("message {}", i + 1) }))
+                        .occurred_at(i * 1000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+                }
+
+                room
+            };
+
+            // Allow agent to list events in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            // Make state.read request without `has_next`.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = ReadRequest {
+                room_id: room.id(),
+                sets: vec![String::from("messages")],
+                attributes: vec![],
+                occurred_at: None,
+                original_occurred_at: None,
+                as_of_event_id: None,
+                limit: Some(2),
+                absolute_time: false,
+                with_has_next: false,
+            };
+
+            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect("State reading failed");
+
+            // Expect the page without `has_next`.
+            let (state, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(state["messages"].as_array().map(|m| m.len()), Some(2));
+            assert!(state.get("has_next").is_none());
+
+            // Expect the total count query not to be run.
+            let reports = context
+                .profiler()
+                .flush(60)
+                .expect("Failed to flush profiler");
+
+            assert!(reports
+                .iter()
+                .any(|((key, _), _)| *key == ProfilerKeys::StateQuery));
+
+            assert!(reports
+                .iter()
+                .all(|((key, _), _)| *key != ProfilerKeys::StateTotalCountQuery));
+        });
+    }
+
+    #[test]
+    fn read_state_over_room_concurrency_limit() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                shared_helpers::insert_room(&mut conn).await
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().max_concurrent_state_reads_per_room = Some(1);
+
+            let build_payload = || ReadRequest {
+                room_id: room.id(),
+                sets: vec![String::from("messages")],
+                attributes: vec![],
+                occurred_at: None,
+                original_occurred_at: None,
+                as_of_event_id: None,
+                limit: None,
+                absolute_time: false,
+                with_has_next: true,
+            };
+
+            // Saturate the room's limit as if another read was running.
+            let permit = context
+                .state_read_limiter()
+                .try_acquire(room.id(), 1)
+                .expect("Failed to acquire state read permit");
+
+            let err = handle_request::<ReadHandler>(&mut context, &agent, build_payload())
+                .await
+                .expect_err("Unexpected success reading state over the limit");
+
+            assert_eq!(err.status(), ResponseStatus::SERVICE_UNAVAILABLE);
+            assert_eq!(err.kind(), "too_many_state_reads");
+
+            // Reads in other rooms are not affected.
+            assert!(context
+                .state_read_limiter()
+                .try_acquire(Uuid::new_v4(), 1)
+                .is_some());
+
+            // The slot becomes available once the running read finishes.
+            drop(permit);
+
+            handle_request::<ReadHandler>(&mut context, &agent, build_payload())
+                .await
+                .expect("State reading failed");
+        });
+    }
+
+    #[test]
+    fn read_state_as_of_event() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, db_events) = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // Two versions of three messages.
+                let mut events = vec![];
+
+                for i in 0..6 {
+                    let event = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .set("messages")
+                        .label(&format!("message-{}", i % 3 + 1))
+                        .data(&json!({
+                            "text": format!("message {}, version {}", i % 3 + 1, i / 3 + 1),
+                        }))
+                        .occurred_at(i * 1000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    events.push(event);
+                }
+
+                (room, events)
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            let mut context = TestContext::new(db, authz);
+
+            // Read the state right after the second version of the second message.
+            let payload = ReadRequest {
+                room_id: room.id(),
+                sets: vec![String::from("messages")],
+                attributes: vec![],
+                occurred_at: None,
+                original_occurred_at: None,
+                as_of_event_id: Some(db_events[4].id()),
+                limit: None,
+                absolute_time: false,
+                with_has_next: true,
+            };
+
+            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect("State reading failed");
+
+            let (state, respp, _) = find_response::<CollectionState>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            let ids = state.messages.iter().map(|e| e.id()).collect::<Vec<_>>();
+            let expected_ids = vec![db_events[2].id(), db_events[4].id(), db_events[3].id()];
+            assert_eq!(ids, expected_ids);
+
+            // An event from elsewhere is not found.
+            let payload = ReadRequest {
+                room_id: room.id(),
+                sets: vec![String::from("messages")],
+                attributes: vec![],
+                occurred_at: None,
+                original_occurred_at: None,
+                as_of_event_id: Some(Uuid::new_v4()),
+                limit: None,
+                absolute_time: false,
+                with_has_next: true,
+            };
+
+            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success reading state");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "event_not_found");
+        });
+    }
+
+    #[test]
+    fn read_state_pinned_messages() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, pinned_message) = {
+                // Create room.
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // Create a not pinned message.
+                let base_message_factory = factory::Event::new()
+                    .room_id(room.id())
                     .kind("message")
                     .set("messages")
                     .data(&json!({"text": "hello"}))
@@ -544,11 +1378,12 @@ mod tests {
                 let pinned_message = pinned_message_factory
                     .occurred_at(3000)
                     .attribute("pinned")
+                    .attribute("important")
                     .insert(&mut conn)
                     .await;
 
                 // Create an unpinned message.
-                let unpinned_message_factory = base_message_factory.label("message-3");
+                let unpinned_message_factory = base_message_factory.clone().label("message-3");
 
                 unpinned_message_factory
                     .clone()
@@ -568,6 +1403,14 @@ mod tests {
                     .insert(&mut conn)
                     .await;
 
+                // Create a pinned message which is not important.
+                base_message_factory
+                    .label("message-4")
+                    .occurred_at(7000)
+                    .attribute("pinned")
+                    .insert(&mut conn)
+                    .await;
+
                 (room, pinned_message)
             };
 
@@ -583,17 +1426,20 @@ mod tests {
             let payload = ReadRequest {
                 room_id: room.id(),
                 sets: vec![String::from("messages")],
-                attribute: Some(String::from("pinned")),
+                attributes: vec![String::from("pinned"), String::from("important")],
                 occurred_at: None,
                 original_occurred_at: None,
+                as_of_event_id: None,
                 limit: None,
+                absolute_time: false,
+                with_has_next: true,
             };
 
             let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
                 .await
                 .expect("State reading failed");
 
-            // Assert last two events response.
+            // Expect only the message having both attributes in its latest version.
             let (state, respp, _) = find_response::<CollectionState>(messages.as_slice());
             assert_eq!(respp.status(), ResponseStatus::OK);
             assert_eq!(state.messages.len(), 1);
@@ -617,10 +1463,13 @@ mod tests {
             let payload = ReadRequest {
                 room_id: room.id(),
                 sets: vec![String::from("messages"), String::from("layout")],
-                attribute: None,
+                attributes: vec![],
                 occurred_at: None,
                 original_occurred_at: None,
+                as_of_event_id: None,
                 limit: None,
+                absolute_time: false,
+                with_has_next: true,
             };
 
             let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
@@ -631,6 +1480,190 @@ mod tests {
         });
     }
 
+    #[test]
+    fn read_state_in_public_audience() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                shared_helpers::insert_room(&mut conn).await
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+            context.config_mut().public_read_audiences = vec![USR_AUDIENCE.to_owned()];
+
+            let payload = ReadRequest {
+                room_id: room.id(),
+                sets: vec![String::from("messages")],
+                attributes: vec![],
+                occurred_at: None,
+                original_occurred_at: None,
+                as_of_event_id: None,
+                limit: None,
+                absolute_time: false,
+                with_has_next: true,
+            };
+
+            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect("State reading failed");
+
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+        });
+    }
+
+    #[test]
+    fn read_state_too_many_sets() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                shared_helpers::insert_room(&mut conn).await
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = ReadRequest {
+                room_id: room.id(),
+                sets: (0..MAX_SETS + 2).map(|i| format!("set{}", i)).collect(),
+                attributes: vec![],
+                occurred_at: None,
+                original_occurred_at: None,
+                as_of_event_id: None,
+                limit: None,
+                absolute_time: false,
+                with_has_next: true,
+            };
+
+            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success reading state");
+
+            assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+            assert_eq!(err.kind(), "invalid_state_sets");
+
+            let detail = err.source().to_string();
+            assert!(detail.contains(&format!("sent = {}", MAX_SETS + 2)));
+            assert!(detail.contains(&format!("max = {}", MAX_SETS)));
+        });
+    }
+
+    #[test]
+    fn read_state_multiple_validation_issues() {
+        async_std::task::block_on(async {
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+
+            let payload = ReadRequest {
+                room_id: Uuid::new_v4(),
+                sets: vec![],
+                attributes: vec![],
+                occurred_at: None,
+                original_occurred_at: None,
+                as_of_event_id: None,
+                limit: Some(-1),
+                absolute_time: false,
+                with_has_next: true,
+            };
+
+            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success reading state");
+
+            // The first issue defines the error kind but all of them get reported.
+            assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+            assert_eq!(err.kind(), "invalid_state_sets");
+
+            let detail = err.source().to_string();
+            assert!(detail.contains("'sets' can't be empty"));
+            assert!(detail.contains("'limit' must be positive"));
+        });
+    }
+
+    #[test]
+    fn read_state_too_large() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                for i in 0..10 {
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .set("messages")
+                        .label(&format!("message-{}", i))
+                        .data(&json!({ "text": "x".repeat(1024) }))
+                        .occurred_at(i * 1000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+                }
+
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().max_state_size = Some(8 * 1024);
+
+            let payload = ReadRequest {
+                room_id: room.id(),
+                sets: vec![String::from("messages")],
+                attributes: vec![],
+                occurred_at: None,
+                original_occurred_at: None,
+                as_of_event_id: None,
+                limit: None,
+                absolute_time: false,
+                with_has_next: true,
+            };
+
+            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success reading oversized state");
+
+            assert_eq!(err.status(), ResponseStatus::PAYLOAD_TOO_LARGE);
+            assert_eq!(err.kind(), "response_too_large");
+
+            assert_eq!(
+                context.oversized_response_counter().flush(),
+                vec![("ignore".to_owned(), 1)]
+            );
+
+            // A smaller limit fits.
+            let payload = ReadRequest {
+                room_id: room.id(),
+                sets: vec![String::from("messages")],
+                attributes: vec![],
+                occurred_at: None,
+                original_occurred_at: None,
+                as_of_event_id: None,
+                limit: Some(2),
+                absolute_time: false,
+                with_has_next: true,
+            };
+
+            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect("State reading failed");
+
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+        });
+    }
+
     #[test]
     fn read_state_missing_room() {
         async_std::task::block_on(async {
@@ -640,10 +1673,13 @@ mod tests {
             let payload = ReadRequest {
                 room_id: Uuid::new_v4(),
                 sets: vec![String::from("messages"), String::from("layout")],
-                attribute: None,
+                attributes: vec![],
                 occurred_at: None,
                 original_occurred_at: None,
+                as_of_event_id: None,
                 limit: None,
+                absolute_time: false,
+                with_has_next: true,
             };
 
             let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
@@ -654,4 +1690,259 @@ mod tests {
             assert_eq!(err.kind(), "room_not_found");
         });
     }
+
+    ///////////////////////////////////////////////////////////////////////////
+
+    #[derive(Deserialize)]
+    struct Snapshot {
+        state: SnapshotState,
+        truncated: bool,
+    }
+
+    #[derive(Deserialize)]
+    struct SnapshotState {
+        messages: std::collections::HashMap<String, Event>,
+        layout: Event,
+    }
+
+    #[test]
+    fn snapshot_state() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, db_events) = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                let mut events = vec![];
+
+                for i in 0..4 {
+                    let event = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .set("messages")
+                        .label(&format!("message-{}", i % 2 + 1))
+                        .data(&json!({ "text": format!("message {}", i) }))
+                        .occurred_at(i * 1000)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    events.push(event);
+                }
+
+                for i in 0..2 {
+                    let event = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("layout")
+                        .set("layout")
+                        .data(&json!({ "name": format!("layout {}", i) }))
+                        .occurred_at(i * 1000 + 500)
+                        .created_by(&agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+
+                    events.push(event);
+                }
+
+                (room, events)
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "list");
+
+            let mut context = TestContext::new(db, authz);
+
+            let payload = SnapshotRequest {
+                room_id: room.id(),
+                occurred_at: None,
+            };
+
+            let messages = handle_request::<SnapshotHandler>(&mut context, &agent, payload)
+                .await
+                .expect("State snapshot failed");
+
+            let (snapshot, respp, _) = find_response::<Snapshot>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert!(!snapshot.truncated);
+            assert_eq!(snapshot.state.messages.len(), 2);
+            assert_eq!(snapshot.state.messages["message-1"].id(), db_events[2].id());
+            assert_eq!(snapshot.state.messages["message-2"].id(), db_events[3].id());
+            assert_eq!(snapshot.state.layout.id(), db_events[5].id());
+        });
+    }
+
+    #[test]
+    fn snapshot_state_with_tied_occurred_at() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, db_events) = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // Create two versions of each label all occurred at the same moment.
+                let now = Utc::now();
+                let mut events = vec![];
+
+                for i in 0..4 {
+                    let event = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("message")
+                        .set("messages")
+                        .label(&format!("message-{}", i % 2 + 1))
+                        .data(&json!({ "text": format!("message {}", i) }))
+                        .occurred_at(1000)
+                        .created_by(&agent.agent_id())
+                        .created_at(now + Duration::seconds(i))
+                        .insert(&mut conn)
+                        .await;
+
+                    events.push(event);
+                }
+
+                for i in 0..2 {
+                    let event = factory::Event::new()
+                        .room_id(room.id())
+                        .kind("layout")
+                        .set("layout")
+                        .data(&json!({ "name": format!("layout {}", i) }))
+                        .occurred_at(1000)
+                        .created_by(&agent.agent_id())
+                        .created_at(now + Duration::seconds(i))
+                        .insert(&mut conn)
+                        .await;
+
+                    events.push(event);
+                }
+
+                (room, events)
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "list");
+
+            // Make the same snapshot request several times.
+            let mut context = TestContext::new(db, authz);
+
+            for _ in 0..3 {
+                let payload = SnapshotRequest {
+                    room_id: room.id(),
+                    occurred_at: None,
+                };
+
+                let messages = handle_request::<SnapshotHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("State snapshot failed");
+
+                // Assert the latest created versions win each time.
+                let (snapshot, respp, _) = find_response::<Snapshot>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(snapshot.state.messages.len(), 2);
+                assert_eq!(snapshot.state.messages["message-1"].id(), db_events[2].id());
+                assert_eq!(snapshot.state.messages["message-2"].id(), db_events[3].id());
+                assert_eq!(snapshot.state.layout.id(), db_events[5].id());
+            }
+        });
+    }
+
+    #[test]
+    fn snapshot_state_with_mixed_labels() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (room, labelled_event, unlabelled_event) = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+
+                // Put both a labelled and an unlabelled event into the same set.
+                let labelled_event = factory::Event::new()
+                    .room_id(room.id())
+                    .kind("message")
+                    .set("messages")
+                    .label("message-1")
+                    .data(&json!({ "text": "labelled" }))
+                    .occurred_at(1000)
+                    .created_by(&agent.agent_id())
+                    .insert(&mut conn)
+                    .await;
+
+                let unlabelled_event = factory::Event::new()
+                    .room_id(room.id())
+                    .kind("message")
+                    .set("messages")
+                    .data(&json!({ "text": "unlabelled" }))
+                    .occurred_at(2000)
+                    .created_by(&agent.agent_id())
+                    .insert(&mut conn)
+                    .await;
+
+                (room, labelled_event, unlabelled_event)
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "list");
+
+            let mut context = TestContext::new(db, authz);
+
+            let payload = SnapshotRequest {
+                room_id: room.id(),
+                occurred_at: None,
+            };
+
+            let messages = handle_request::<SnapshotHandler>(&mut context, &agent, payload)
+                .await
+                .expect("State snapshot failed");
+
+            // Assert the unlabelled event doesn't overwrite the labels map.
+            let (snapshot, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            let labelled_id = snapshot["state"]["messages"]["message-1"]["id"]
+                .as_str()
+                .expect("Missing labelled event");
+
+            assert_eq!(labelled_id, labelled_event.id().to_string());
+
+            let unlabelled_id = snapshot["unlabelled"]["messages"]["id"]
+                .as_str()
+                .expect("Missing unlabelled event");
+
+            assert_eq!(unlabelled_id, unlabelled_event.id().to_string());
+        });
+    }
+
+    #[test]
+    fn snapshot_state_not_authorized() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                shared_helpers::insert_room(&mut conn).await
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = SnapshotRequest {
+                room_id: room.id(),
+                occurred_at: None,
+            };
+
+            let err = handle_request::<SnapshotHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on state snapshot");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+        });
+    }
 }
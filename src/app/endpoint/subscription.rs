@@ -144,7 +144,7 @@ impl ResponseHandler for CreateResponseHandler {
 
         let notification = helpers::build_notification(
             "room.enter",
-            &format!("rooms/{}/events", room_id),
+            &context.config().topics.room_events(room_id),
             RoomEnterEvent {
                 id: room_id,
                 agent_id: corr_data.subject.to_owned(),
@@ -257,7 +257,7 @@ impl ResponseHandler for DeleteResponseHandler {
 
         let notification = helpers::build_notification(
             "room.leave",
-            &format!("rooms/{}/events", room_id),
+            &context.config().topics.room_events(room_id),
             RoomLeaveEvent {
                 id: room_id,
                 agent_id: corr_data.subject.to_owned(),
@@ -365,7 +365,7 @@ impl EventHandler for DeleteEventHandler {
         let start_timestamp = context.start_timestamp();
         let short_term_timing = ShortTermTimingProperties::until_now(start_timestamp);
         let props = evp.to_event("room.leave", short_term_timing);
-        let to_uri = format!("rooms/{}/events", room_id);
+        let to_uri = context.config().topics.room_events(room_id);
         let outgoing_event = OutgoingEvent::broadcast(outgoing_event_payload, props, &to_uri);
         let boxed_event = Box::new(outgoing_event) as Box<dyn IntoPublishableMessage + Send>;
         Ok(Box::new(stream::once(boxed_event)))
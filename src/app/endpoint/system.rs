@@ -1,13 +1,28 @@
+use anyhow::Context as AnyhowContext;
 use async_std::stream;
 use async_trait::async_trait;
-use serde_derive::Deserialize;
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
 use serde_json::json;
-use svc_agent::mqtt::{IncomingRequestProperties, ResponseStatus};
+use svc_agent::mqtt::{
+    IncomingRequestProperties, IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties,
+    ResponseStatus, ShortTermTimingProperties,
+};
+use svc_agent::AgentId;
 use svc_error::{extension::sentry, Error as SvcError};
+use uuid::Uuid;
 
 use crate::app::context::Context;
 use crate::app::endpoint::prelude::*;
-use crate::app::operations::vacuum;
+use crate::app::operations::{compact, vacuum};
+use crate::app::rate_limiter::AgentRequestCounter;
+use crate::db;
+
+const MAX_CLOSE_EXPIRED_ROOMS_LIMIT: usize = 100;
+const DEFAULT_TOP_AGENTS_LIMIT: usize = 10;
+const MAX_TOP_AGENTS_LIMIT: usize = 100;
+const MAX_AUDIT_LIST_LIMIT: i64 = 100;
+const DEFAULT_PROFILER_READ_DURATION: u64 = 60;
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct VacuumRequest {}
@@ -17,6 +32,7 @@ pub(crate) struct VacuumHandler;
 #[async_trait]
 impl RequestHandler for VacuumHandler {
     type Payload = VacuumRequest;
+    const IS_MUTATING: bool = true;
 
     async fn handle<C: Context>(
         context: &mut C,
@@ -39,20 +55,53 @@ impl RequestHandler for VacuumHandler {
         let profiler = context.profiler();
         let logger = context.logger().new(o!());
         let config = context.config().vacuum.to_owned();
+        let topics = context.config().topics.clone();
+        let tracking = reqp.tracking().to_owned();
+
+        // Notifications of vacuumed rooms get published by the agent when vacuum finishes
+        // so the response doesn't have to wait for it.
+        let tx = if config.notify {
+            context.outgoing_message_sender()
+        } else {
+            None
+        };
 
         async_std::task::spawn(async move {
-            if let Err(err) = vacuum(&db, &profiler, &config).await {
-                error!(logger, "Vacuum failed: {}", err);
-
-                let svc_error = SvcError::builder()
-                    .status(ResponseStatus::INTERNAL_SERVER_ERROR)
-                    .kind("vacuum_failed", "Vacuum failed")
-                    .detail(&err.to_string())
-                    .build();
-
-                sentry::send(svc_error).unwrap_or_else(|err| {
-                    warn!(logger, "Error sending error to Sentry: {}", err);
-                });
+            match vacuum(&db, &profiler, &config).await {
+                Ok(vacuumed_rooms) => {
+                    let tx = match tx {
+                        Some(tx) => tx,
+                        None => return,
+                    };
+
+                    for vacuumed_room in vacuumed_rooms {
+                        let notification = RoomVacuumNotification {
+                            room_id: vacuumed_room.room_id,
+                            deleted_events: vacuumed_room.deleted_count,
+                        };
+
+                        let timing = ShortTermTimingProperties::new(Utc::now());
+                        let mut props = OutgoingEventProperties::new("room.vacuum", timing);
+                        props.set_tracking(tracking.clone());
+                        let path = topics.room_events(vacuumed_room.room_id);
+                        let event = OutgoingEvent::broadcast(notification, props, &path);
+                        let message = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
+                        let _ = tx.unbounded_send(message);
+                    }
+                }
+                Err(err) => {
+                    error!(logger, "Vacuum failed: {}", err);
+
+                    let svc_error = SvcError::builder()
+                        .status(ResponseStatus::INTERNAL_SERVER_ERROR)
+                        .kind("vacuum_failed", "Vacuum failed")
+                        .detail(&err.to_string())
+                        .build();
+
+                    sentry::send(svc_error).unwrap_or_else(|err| {
+                        warn!(logger, "Error sending error to Sentry: {}", err);
+                    });
+                }
             }
         });
 
@@ -67,12 +116,426 @@ impl RequestHandler for VacuumHandler {
     }
 }
 
+#[derive(Debug, Serialize)]
+struct RoomVacuumNotification {
+    room_id: Uuid,
+    deleted_events: i64,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CompactRequest {}
+
+pub(crate) struct CompactHandler;
+
+#[async_trait]
+impl RequestHandler for CompactHandler {
+    type Payload = CompactRequest;
+    const IS_MUTATING: bool = true;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        _payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        // Authz: only trusted subjects.
+        let authz_time = context
+            .authz()
+            .authorize(
+                context.agent_id().as_account_id().audience().into(),
+                reqp.as_account_id().to_owned(),
+                AuthzObject::new(&["system"]).into(),
+                "update".into(),
+            )
+            .await?;
+
+        // Run compaction asynchronously.
+        let db = context.db().to_owned();
+        let profiler = context.profiler();
+        let logger = context.logger().new(o!());
+        let config = context.config().compact.to_owned();
+
+        async_std::task::spawn(async move {
+            match compact(&db, &profiler, &config).await {
+                Ok(compacted_rooms) => {
+                    for compacted_room in compacted_rooms {
+                        info!(
+                            logger,
+                            "Compacted {} event versions in room {}",
+                            compacted_room.deleted_count,
+                            compacted_room.room_id
+                        );
+                    }
+                }
+                Err(err) => {
+                    error!(logger, "Compaction failed: {}", err);
+
+                    let svc_error = SvcError::builder()
+                        .status(ResponseStatus::INTERNAL_SERVER_ERROR)
+                        .kind("compaction_failed", "Compaction failed")
+                        .detail(&err.to_string())
+                        .build();
+
+                    sentry::send(svc_error).unwrap_or_else(|err| {
+                        warn!(logger, "Error sending error to Sentry: {}", err);
+                    });
+                }
+            }
+        });
+
+        // Return empty 202 response.
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::ACCEPTED,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CloseExpiredRoomsRequest {
+    limit: Option<usize>,
+}
+
+pub(crate) struct CloseExpiredRoomsHandler;
+
+#[async_trait]
+impl RequestHandler for CloseExpiredRoomsHandler {
+    type Payload = CloseExpiredRoomsRequest;
+    const IS_MUTATING: bool = true;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        // Authz: only trusted subjects.
+        let authz_time = context
+            .authz()
+            .authorize(
+                context.agent_id().as_account_id().audience().into(),
+                reqp.as_account_id().to_owned(),
+                AuthzObject::new(&["system"]).into(),
+                "update".into(),
+            )
+            .await?;
+
+        // Close a bounded batch of rooms whose time has passed.
+        let limit = std::cmp::min(
+            payload.limit.unwrap_or(MAX_CLOSE_EXPIRED_ROOMS_LIMIT),
+            MAX_CLOSE_EXPIRED_ROOMS_LIMIT,
+        );
+
+        let query = db::room::CloseExpiredQuery::new(context.clock().now(), limit as i64);
+        let mut conn = context.get_conn().await?;
+
+        let rooms = context
+            .profiler()
+            .measure(
+                (
+                    ProfilerKeys::RoomCloseExpiredQuery,
+                    Some(reqp.method().to_owned()),
+                ),
+                query.execute(&mut conn),
+            )
+            .await
+            .context("Failed to close expired rooms")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        // Respond with closed rooms and notify each room's subscribers.
+        let mut responses = Vec::with_capacity(rooms.len() + 1);
+
+        for room in rooms.iter() {
+            responses.push(helpers::build_notification(
+                "room.close",
+                &context.config().topics.room_events(room.id()),
+                room.clone(),
+                reqp,
+                context.start_timestamp(),
+            ));
+        }
+
+        responses.insert(
+            0,
+            helpers::build_response(
+                ResponseStatus::OK,
+                rooms,
+                reqp,
+                context.start_timestamp(),
+                Some(authz_time),
+            ),
+        );
+
+        Ok(Box::new(stream::from_iter(responses)))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct TopAgentsRequest {
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct TopAgentsResponse {
+    window: i64,
+    agents: Vec<AgentRequests>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct AgentRequests {
+    agent_id: String,
+    requests: u64,
+}
+
+pub(crate) struct TopAgentsHandler;
+
+#[async_trait]
+impl RequestHandler for TopAgentsHandler {
+    type Payload = TopAgentsRequest;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        // Authz: only trusted subjects.
+        let authz_time = context
+            .authz()
+            .authorize(
+                context.agent_id().as_account_id().audience().into(),
+                reqp.as_account_id().to_owned(),
+                AuthzObject::new(&["system"]).into(),
+                "read".into(),
+            )
+            .await?;
+
+        let limit = std::cmp::min(
+            payload.limit.unwrap_or(DEFAULT_TOP_AGENTS_LIMIT),
+            MAX_TOP_AGENTS_LIMIT,
+        );
+
+        let agents = context
+            .agent_request_counter()
+            .top(context.clock().now(), limit)
+            .into_iter()
+            .map(|(agent_id, requests)| AgentRequests { agent_id, requests })
+            .collect();
+
+        let response = TopAgentsResponse {
+            window: AgentRequestCounter::window().num_seconds(),
+            agents,
+        };
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            response,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct AuditListRequest {
+    room_id: Option<Uuid>,
+    agent_id: Option<AgentId>,
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    since: Option<DateTime<Utc>>,
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    until: Option<DateTime<Utc>>,
+    limit: Option<i64>,
+}
+
+pub(crate) struct AuditListHandler;
+
+#[async_trait]
+impl RequestHandler for AuditListHandler {
+    type Payload = AuditListRequest;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        // Authz: only trusted subjects.
+        let authz_time = context
+            .authz()
+            .authorize(
+                context.agent_id().as_account_id().audience().into(),
+                reqp.as_account_id().to_owned(),
+                AuthzObject::new(&["system"]).into(),
+                "read".into(),
+            )
+            .await?;
+
+        let mut query = db::audit_log::ListQuery::new().limit(std::cmp::min(
+            payload.limit.unwrap_or(MAX_AUDIT_LIST_LIMIT),
+            MAX_AUDIT_LIST_LIMIT,
+        ));
+
+        if let Some(room_id) = payload.room_id {
+            query = query.room_id(room_id);
+        }
+
+        if let Some(agent_id) = payload.agent_id {
+            query = query.created_by(agent_id);
+        }
+
+        if let Some(since) = payload.since {
+            query = query.since(since);
+        }
+
+        if let Some(until) = payload.until {
+            query = query.until(until);
+        }
+
+        let mut conn = context.get_read_conn(reqp.method()).await?;
+
+        let records = context
+            .profiler()
+            .measure(
+                (
+                    ProfilerKeys::AuditLogListQuery,
+                    Some(reqp.method().to_owned()),
+                ),
+                query.execute(&mut conn),
+            )
+            .await
+            .context("Failed to list audit log")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            records,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ProfilerReadRequest {
+    /// Report window in seconds.
+    #[serde(default = "ProfilerReadRequest::default_duration")]
+    duration: u64,
+}
+
+impl ProfilerReadRequest {
+    fn default_duration() -> u64 {
+        DEFAULT_PROFILER_READ_DURATION
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ProfilerReadResponse {
+    queries: Vec<ProfilerQueryEntry>,
+    handlers: Vec<ProfilerHandlerEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProfilerQueryEntry {
+    key: ProfilerKeys,
+    method: Option<String>,
+    p95: usize,
+    p99: usize,
+    max: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct ProfilerHandlerEntry {
+    method: String,
+    p95: usize,
+    p99: usize,
+    max: usize,
+}
+
+pub(crate) struct ProfilerReadHandler;
+
+#[async_trait]
+impl RequestHandler for ProfilerReadHandler {
+    type Payload = ProfilerReadRequest;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        // Authz: only trusted subjects.
+        let authz_time = context
+            .authz()
+            .authorize(
+                context.agent_id().as_account_id().audience().into(),
+                reqp.as_account_id().to_owned(),
+                AuthzObject::new(&["system"]).into(),
+                "read".into(),
+            )
+            .await?;
+
+        // Take a snapshot so that the periodic metrics report isn't affected.
+        let snapshot = context
+            .profiler()
+            .snapshot(payload.duration)
+            .context("Failed to take profiler snapshot")
+            .error(AppErrorKind::StatsCollectionFailed)?;
+
+        let queries = snapshot
+            .entries
+            .into_iter()
+            .map(|((key, method), report)| ProfilerQueryEntry {
+                key,
+                method,
+                p95: report.p95,
+                p99: report.p99,
+                max: report.max,
+            })
+            .collect();
+
+        let handlers = snapshot
+            .handler_timings
+            .into_iter()
+            .map(|(method, report)| ProfilerHandlerEntry {
+                method,
+                p95: report.p95,
+                p99: report.p99,
+                max: report.max,
+            })
+            .collect();
+
+        let response = ProfilerReadResponse { queries, handlers };
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            response,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
 mod tests {
     mod vacuum {
+        use std::ops::Bound;
+
+        use chrono::{Duration, SubsecRound, Utc};
         use serde_json::Value as JsonValue;
+        use serial_test::serial;
 
         use crate::test_helpers::prelude::*;
 
@@ -102,6 +565,97 @@ mod tests {
             });
         }
 
+        #[test]
+        #[serial]
+        fn vacuum_notifications() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let creator = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (trimmed_room, preserved_room) = {
+                    let mut conn = db.get_conn().await;
+
+                    let now = Utc::now().trunc_subsecs(0);
+                    let time = (
+                        Bound::Included(now),
+                        Bound::Excluded(now + Duration::hours(1)),
+                    );
+
+                    let trimmed_room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time(time)
+                        .preserve_history(false)
+                        .insert(&mut conn)
+                        .await;
+
+                    let preserved_room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time(time)
+                        .preserve_history(true)
+                        .insert(&mut conn)
+                        .await;
+
+                    // Put two versions of the same label into each room.
+                    for room in &[&trimmed_room, &preserved_room] {
+                        for occurred_at in &[1000, 2000] {
+                            factory::Event::new()
+                                .room_id(room.id())
+                                .kind("draw")
+                                .set("page1")
+                                .label("drawing1")
+                                .occurred_at(*occurred_at)
+                                .data(&json!({}))
+                                .created_by(creator.agent_id())
+                                .insert(&mut conn)
+                                .await;
+                        }
+                    }
+
+                    (trimmed_room, preserved_room)
+                };
+
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["system"], "update");
+
+                // Keep only the latest version of each label and notify vacuumed rooms.
+                let mut context = TestContext::new(db, authz);
+                context.config_mut().vacuum.max_history_size = 1;
+                context.config_mut().vacuum.notify = true;
+                let outgoing_messages = context.outgoing_messages();
+
+                let messages =
+                    handle_request::<VacuumHandler>(&mut context, &agent, VacuumRequest {})
+                        .await
+                        .expect("System vacuum failed");
+
+                // Only the response is sent right away.
+                assert_eq!(messages.len(), 1);
+
+                // The vacuum task publishes notifications and drops the last sender when done.
+                drop(context);
+                let messages = parse_messages(Box::new(outgoing_messages)).await;
+
+                let room_notifications = |room_id: Uuid| {
+                    let topic = format!("/rooms/{}/events", room_id);
+
+                    messages
+                        .iter()
+                        .filter(|message| message.topic().ends_with(&topic))
+                        .map(|message| message.payload::<JsonValue>())
+                        .collect::<Vec<_>>()
+                };
+
+                let trimmed_room_notifications = room_notifications(trimmed_room.id());
+                assert_eq!(trimmed_room_notifications.len(), 1);
+                assert_eq!(trimmed_room_notifications[0]["deleted_events"], 1);
+
+                assert!(room_notifications(preserved_room.id()).is_empty());
+            });
+        }
+
         #[test]
         fn vacuum_unauthorized() {
             async_std::task::block_on(async {
@@ -118,4 +672,343 @@ mod tests {
             });
         }
     }
+    mod close_expired_rooms {
+        use std::ops::Bound;
+
+        use chrono::{Duration, SubsecRound, Utc};
+
+        use crate::db::room::{FindQuery as RoomFindQuery, Object as Room};
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn close_expired_rooms() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+
+                let (expired_room, active_room) = {
+                    let mut conn = db.get_conn().await;
+                    let now = Utc::now().trunc_subsecs(0);
+
+                    // Expired long ago so that it's the first in the batch.
+                    let expired_room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((
+                            Bound::Included(now - Duration::weeks(5300)),
+                            Bound::Excluded(now - Duration::weeks(5200)),
+                        ))
+                        .insert(&mut conn)
+                        .await;
+
+                    let active_room = shared_helpers::insert_room(&mut conn).await;
+                    (expired_room, active_room)
+                };
+
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["system"], "update");
+
+                // Make system.close_expired_rooms request.
+                let mut context = TestContext::new(db.clone(), authz);
+                let payload = CloseExpiredRoomsRequest { limit: Some(1) };
+
+                let messages =
+                    handle_request::<CloseExpiredRoomsHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect("Closing expired rooms failed");
+
+                // Assert response.
+                let (rooms, respp, _) = find_response::<Vec<Room>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(rooms.len(), 1);
+                assert_eq!(rooms[0].id(), expired_room.id());
+
+                // Assert notification.
+                let (room, evp, topic) = find_event::<Room>(messages.as_slice());
+                assert_eq!(evp.label(), "room.close");
+                assert!(topic.ends_with(&format!("/rooms/{}/events", expired_room.id())));
+                assert_eq!(room.id(), expired_room.id());
+
+                // Assert only the expired room got closed.
+                let mut conn = db.get_conn().await;
+
+                let expired_room = RoomFindQuery::new(expired_room.id())
+                    .execute(&mut conn)
+                    .await
+                    .expect("Failed to find room")
+                    .expect("Room not found");
+
+                assert!(expired_room.closed_at().is_some());
+
+                let active_room = RoomFindQuery::new(active_room.id())
+                    .execute(&mut conn)
+                    .await
+                    .expect("Failed to find room")
+                    .expect("Room not found");
+
+                assert!(active_room.closed_at().is_none());
+            });
+        }
+
+        #[test]
+        fn close_expired_rooms_unauthorized() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+                let payload = CloseExpiredRoomsRequest { limit: None };
+
+                let err = handle_request::<CloseExpiredRoomsHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on closing expired rooms");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+    }
+    mod top_agents {
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn top_agents() {
+            async_std::task::block_on(async {
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["system"], "read");
+
+                let mut context = TestContext::new(TestDb::new().await, authz);
+
+                // Seed requests from two agents.
+                let noisy = TestAgent::new("web", "noisy", USR_AUDIENCE);
+                let quiet = TestAgent::new("web", "quiet", USR_AUDIENCE);
+                let now = context.clock().now();
+
+                for _ in 0..3 {
+                    context
+                        .agent_request_counter()
+                        .register(noisy.agent_id(), now);
+                }
+
+                context
+                    .agent_request_counter()
+                    .register(quiet.agent_id(), now);
+
+                // Make system.top_agents request.
+                let payload = TopAgentsRequest { limit: None };
+
+                let messages = handle_request::<TopAgentsHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Top agents listing failed");
+
+                let (response, respp, _) = find_response::<TopAgentsResponse>(messages.as_slice());
+
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(response.agents.len(), 2);
+                assert_eq!(response.agents[0].agent_id, noisy.agent_id().to_string());
+                assert_eq!(response.agents[0].requests, 3);
+                assert_eq!(response.agents[1].agent_id, quiet.agent_id().to_string());
+                assert_eq!(response.agents[1].requests, 1);
+            });
+        }
+
+        #[test]
+        fn top_agents_unauthorized() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+                let payload = TopAgentsRequest { limit: None };
+
+                let err = handle_request::<TopAgentsHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on listing top agents");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+    }
+
+    mod audit_list {
+        use serde_json::json;
+
+        use crate::db::audit_log::Object as AuditLogRecord;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn audit_list() {
+            async_std::task::block_on(async {
+                let db = TestDb::new().await;
+                let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+
+                let (room, other_room) = {
+                    let mut conn = db.get_conn().await;
+                    let room = shared_helpers::insert_room(&mut conn).await;
+                    let other_room = shared_helpers::insert_room(&mut conn).await;
+
+                    for room_id in &[room.id(), other_room.id()] {
+                        db::audit_log::InsertQuery::new(
+                            *room_id,
+                            "event.delete_label",
+                            moderator.agent_id().to_owned(),
+                        )
+                        .details(json!({ "set": "messages", "label": "message-1" }))
+                        .execute(&mut conn)
+                        .await
+                        .expect("Failed to insert audit log record");
+                    }
+
+                    (room, other_room)
+                };
+
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["system"], "read");
+
+                let mut context = TestContext::new(db, authz);
+
+                // Make system.audit_list request filtered by room.
+                let payload = AuditListRequest {
+                    room_id: Some(room.id()),
+                    agent_id: Some(moderator.agent_id().to_owned()),
+                    since: None,
+                    until: None,
+                    limit: None,
+                };
+
+                let messages = handle_request::<AuditListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Audit log listing failed");
+
+                let (records, respp, _) = find_response::<Vec<AuditLogRecord>>(messages.as_slice());
+
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(records.len(), 1);
+                assert_eq!(records[0].room_id(), room.id());
+                assert_eq!(records[0].action(), "event.delete_label");
+                assert_eq!(records[0].created_by(), moderator.agent_id());
+                assert_ne!(records[0].room_id(), other_room.id());
+            });
+        }
+
+        #[test]
+        fn audit_list_unauthorized() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+
+                let payload = AuditListRequest {
+                    room_id: None,
+                    agent_id: None,
+                    since: None,
+                    until: None,
+                    limit: None,
+                };
+
+                let err = handle_request::<AuditListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on listing audit log");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+    }
+
+    mod profiler_read {
+        use std::time::Duration as StdDuration;
+
+        use chrono::Duration;
+        use serde_json::Value as JsonValue;
+
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn profiler_read() {
+            async_std::task::block_on(async {
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["system"], "read");
+
+                let mut context = TestContext::new(TestDb::new().await, authz);
+
+                context.profiler().record(
+                    (
+                        ProfilerKeys::EventListQuery,
+                        Some(String::from("event.list")),
+                    ),
+                    StdDuration::from_millis(5),
+                );
+
+                context
+                    .profiler()
+                    .record_future_time(Duration::milliseconds(5), String::from("event.list"));
+
+                let payload = ProfilerReadRequest { duration: 60 };
+
+                let messages = handle_request::<ProfilerReadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Profiler reading failed");
+
+                let (response, respp, _) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+
+                let queries = response["queries"].as_array().expect("Missing queries");
+
+                let query = queries
+                    .iter()
+                    .find(|entry| entry["key"] == "EventListQuery")
+                    .expect("Missing event list query timings");
+
+                assert_eq!(query["method"], "event.list");
+                assert_eq!(query["max"], 5000);
+
+                let handlers = response["handlers"].as_array().expect("Missing handlers");
+
+                let handler = handlers
+                    .iter()
+                    .find(|entry| entry["method"] == "event.list")
+                    .expect("Missing event.list handler timings");
+
+                assert_eq!(handler["max"], 5000);
+
+                // Reading doesn't take the handler timings away from the metrics report.
+                let timings = context
+                    .profiler()
+                    .get_handler_timings()
+                    .expect("Failed to get handler timings");
+
+                assert!(timings.iter().any(|(method, _)| method == "event.list"));
+            });
+        }
+
+        #[test]
+        fn profiler_read_unauthorized() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(TestDb::new().await, TestAuthz::new());
+                let payload = ProfilerReadRequest { duration: 60 };
+
+                let err = handle_request::<ProfilerReadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on reading profiler");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            });
+        }
+    }
 }
@@ -21,23 +21,38 @@ pub(crate) enum ErrorKind {
     AuthorizationFailed,
     BrokerRequestFailed,
     ChangeNotFound,
+    Conflict,
     DbConnAcquisitionFailed,
     DbQueryFailed,
     EditionCommitTaskFailed,
+    EditionNotCommitted,
     EditionNotFound,
+    EventNotFound,
+    InvalidCuts,
+    InvalidOccurredAt,
     InvalidPayload,
     InvalidRoomTime,
     InvalidStateSets,
     InvalidSubscriptionObject,
+    InvalidTags,
     MessageHandlingFailed,
     NoS3Client,
+    PayloadTooComplex,
     StatsCollectionFailed,
     PublishFailed,
+    ReadOnlyMode,
     RoomAdjustTaskFailed,
     RoomClosed,
     RoomNotFound,
+    RoomRateLimitExceeded,
+    RoomTooShort,
+    ResponseTooLarge,
     SerializationFailed,
+    TaskNotFound,
+    TooManyDumps,
+    TooManyStateReads,
     TransientEventCreationFailed,
+    UnknownEventLabel,
     UnknownMethod,
 }
 
@@ -98,8 +113,14 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 title: "Change not found",
                 is_notify_sentry: false,
             },
+            Self::Conflict => ErrorKindProperties {
+                status: ResponseStatus::CONFLICT,
+                kind: "conflict",
+                title: "Conflict",
+                is_notify_sentry: false,
+            },
             Self::DbConnAcquisitionFailed => ErrorKindProperties {
-                status: ResponseStatus::UNPROCESSABLE_ENTITY,
+                status: ResponseStatus::SERVICE_UNAVAILABLE,
                 kind: "database_connection_acquisition_failed",
                 title: "Database connection acquisition failed",
                 is_notify_sentry: true,
@@ -116,12 +137,36 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 title: "Edition commit task failed",
                 is_notify_sentry: true,
             },
+            Self::EditionNotCommitted => ErrorKindProperties {
+                status: ResponseStatus::NOT_FOUND,
+                kind: "edition_not_committed",
+                title: "Edition not committed",
+                is_notify_sentry: false,
+            },
             Self::EditionNotFound => ErrorKindProperties {
                 status: ResponseStatus::NOT_FOUND,
                 kind: "edition_not_found",
                 title: "Edition not found",
                 is_notify_sentry: false,
             },
+            Self::EventNotFound => ErrorKindProperties {
+                status: ResponseStatus::NOT_FOUND,
+                kind: "event_not_found",
+                title: "Event not found",
+                is_notify_sentry: false,
+            },
+            Self::InvalidCuts => ErrorKindProperties {
+                status: ResponseStatus::UNPROCESSABLE_ENTITY,
+                kind: "invalid_cuts",
+                title: "Invalid cuts",
+                is_notify_sentry: false,
+            },
+            Self::InvalidOccurredAt => ErrorKindProperties {
+                status: ResponseStatus::UNPROCESSABLE_ENTITY,
+                kind: "invalid_occurred_at",
+                title: "Invalid occurred_at",
+                is_notify_sentry: false,
+            },
             Self::InvalidPayload => ErrorKindProperties {
                 status: ResponseStatus::BAD_REQUEST,
                 kind: "invalid_payload",
@@ -146,6 +191,12 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 title: "Invalid subscription object",
                 is_notify_sentry: true,
             },
+            Self::InvalidTags => ErrorKindProperties {
+                status: ResponseStatus::UNPROCESSABLE_ENTITY,
+                kind: "invalid_tags",
+                title: "Invalid tags",
+                is_notify_sentry: false,
+            },
             Self::MessageHandlingFailed => ErrorKindProperties {
                 status: ResponseStatus::UNPROCESSABLE_ENTITY,
                 kind: "message_handling_failed",
@@ -164,6 +215,12 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 title: "Serialization failed",
                 is_notify_sentry: true,
             },
+            Self::PayloadTooComplex => ErrorKindProperties {
+                status: ResponseStatus::BAD_REQUEST,
+                kind: "payload_too_complex",
+                title: "Payload too complex",
+                is_notify_sentry: false,
+            },
             Self::StatsCollectionFailed => ErrorKindProperties {
                 status: ResponseStatus::UNPROCESSABLE_ENTITY,
                 kind: "stats_collection_failed",
@@ -176,6 +233,12 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 title: "Publish failed",
                 is_notify_sentry: true,
             },
+            Self::ReadOnlyMode => ErrorKindProperties {
+                status: ResponseStatus::SERVICE_UNAVAILABLE,
+                kind: "read_only_mode",
+                title: "Read only mode",
+                is_notify_sentry: false,
+            },
             Self::RoomAdjustTaskFailed => ErrorKindProperties {
                 status: ResponseStatus::UNPROCESSABLE_ENTITY,
                 kind: "room_adjust_task_failed",
@@ -194,12 +257,54 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 title: "Room not found",
                 is_notify_sentry: false,
             },
+            Self::RoomRateLimitExceeded => ErrorKindProperties {
+                status: ResponseStatus::TOO_MANY_REQUESTS,
+                kind: "room_rate_limit_exceeded",
+                title: "Room rate limit exceeded",
+                is_notify_sentry: false,
+            },
+            Self::RoomTooShort => ErrorKindProperties {
+                status: ResponseStatus::UNPROCESSABLE_ENTITY,
+                kind: "room_too_short",
+                title: "Room too short",
+                is_notify_sentry: false,
+            },
+            Self::ResponseTooLarge => ErrorKindProperties {
+                status: ResponseStatus::PAYLOAD_TOO_LARGE,
+                kind: "response_too_large",
+                title: "Response too large",
+                is_notify_sentry: false,
+            },
+            Self::TaskNotFound => ErrorKindProperties {
+                status: ResponseStatus::NOT_FOUND,
+                kind: "task_not_found",
+                title: "Task not found",
+                is_notify_sentry: false,
+            },
+            Self::TooManyDumps => ErrorKindProperties {
+                status: ResponseStatus::SERVICE_UNAVAILABLE,
+                kind: "too_many_dumps",
+                title: "Too many dumps",
+                is_notify_sentry: false,
+            },
+            Self::TooManyStateReads => ErrorKindProperties {
+                status: ResponseStatus::SERVICE_UNAVAILABLE,
+                kind: "too_many_state_reads",
+                title: "Too many state reads",
+                is_notify_sentry: false,
+            },
             Self::TransientEventCreationFailed => ErrorKindProperties {
                 status: ResponseStatus::UNPROCESSABLE_ENTITY,
                 kind: "transient_event_creation_failed",
                 title: "Transient event creation failed",
                 is_notify_sentry: true,
             },
+            Self::UnknownEventLabel => ErrorKindProperties {
+                status: ResponseStatus::UNPROCESSABLE_ENTITY,
+                kind: "unknown_event_label",
+                title: "Unknown event label",
+                is_notify_sentry: true,
+            },
             Self::UnknownMethod => ErrorKindProperties {
                 status: ResponseStatus::METHOD_NOT_ALLOWED,
                 kind: "unknown_method",
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::app::metrics::ProfilerKeys;
+use crate::profiler::Profiler;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Limits the number of incoming messages handled concurrently.
+/// Time spent waiting for a permit is recorded under `ProfilerKeys::HandlerQueueWait`
+/// to tell queuing because of overload apart from slow handling.
+pub(crate) struct HandlerGate {
+    semaphore: Semaphore,
+    profiler: Arc<Profiler<(ProfilerKeys, Option<String>)>>,
+}
+
+impl HandlerGate {
+    pub(crate) fn new(
+        permits: usize,
+        profiler: Arc<Profiler<(ProfilerKeys, Option<String>)>>,
+    ) -> Self {
+        Self {
+            semaphore: Semaphore::new(permits),
+            profiler,
+        }
+    }
+
+    pub(crate) async fn acquire(&self) -> SemaphorePermit<'_> {
+        self.profiler
+            .measure(
+                (ProfilerKeys::HandlerQueueWait, None),
+                self.semaphore.acquire(),
+            )
+            .await
+            .expect("Handler gate semaphore closed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn saturated_gate_records_queue_wait() {
+        async_std::task::block_on(async {
+            let profiler = Arc::new(Profiler::<(ProfilerKeys, Option<String>)>::start());
+            let gate = Arc::new(HandlerGate::new(1, profiler.clone()));
+
+            // Take the only permit so the next handler has to wait.
+            let permit = gate.acquire().await;
+
+            let waiting_gate = gate.clone();
+
+            let waiter = async_std::task::spawn(async move {
+                let _permit = waiting_gate.acquire().await;
+            });
+
+            async_std::task::sleep(Duration::from_millis(50)).await;
+            drop(permit);
+            waiter.await;
+
+            let report = profiler.flush(60).expect("Failed to flush profiler");
+
+            let (_, entry) = report
+                .into_iter()
+                .find(|((key, _), _)| *key == ProfilerKeys::HandlerQueueWait)
+                .expect("Missing handler queue wait timings");
+
+            assert!(entry.max >= 50_000);
+        });
+    }
+}
@@ -1,5 +1,6 @@
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 
 use anyhow::Context as AnyhowContext;
 use async_std::prelude::*;
@@ -16,7 +17,9 @@ use svc_agent::{
 
 use crate::app::context::{AppMessageContext, Context, GlobalContext, MessageContext};
 use crate::app::error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind};
+use crate::app::metrics::ProfilerKeys;
 use crate::app::{endpoint, API_VERSION};
+use crate::profiler::Profiler;
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -26,15 +29,13 @@ pub(crate) type MessageStream =
 pub(crate) struct MessageHandler<C: GlobalContext> {
     agent: Agent,
     global_context: C,
-    tx: TimingChannel,
 }
 
 impl<C: GlobalContext + Sync> MessageHandler<C> {
-    pub(crate) fn new(agent: Agent, global_context: C, tx: TimingChannel) -> Self {
+    pub(crate) fn new(agent: Agent, global_context: C) -> Self {
         Self {
             agent,
             global_context,
-            tx,
         }
     }
 
@@ -47,7 +48,8 @@ impl<C: GlobalContext + Sync> MessageHandler<C> {
     }
 
     pub(crate) async fn handle(&self, message: &Result<IncomingMessage<String>, String>) {
-        let mut msg_context = AppMessageContext::new(&self.global_context, Utc::now());
+        let mut msg_context =
+            AppMessageContext::new(&self.global_context, self.global_context.clock().now());
 
         match message {
             Ok(ref msg) => {
@@ -85,7 +87,10 @@ impl<C: GlobalContext + Sync> MessageHandler<C> {
         msg_context: &mut AppMessageContext<'_, C>,
         message: &IncomingMessage<String>,
     ) -> Result<(), AppError> {
-        let mut timer = MessageHandlerTiming::new(msg_context.start_timestamp(), self.tx.clone());
+        let mut timer = MessageHandlerTiming::new(
+            msg_context.start_timestamp(),
+            self.global_context.profiler(),
+        );
 
         match message {
             IncomingMessage::Request(req) => {
@@ -122,6 +127,17 @@ impl<C: GlobalContext + Sync> MessageHandler<C> {
             "method" => request.properties().method().to_owned()
         ));
 
+        // Tracking id is shared by the request's response and notifications
+        // so it correlates them in logs.
+        let tracking_id = request.properties().tracking().tracking_id().to_string();
+        msg_context.add_logger_tags(o!("tracking_id" => tracking_id));
+
+        check_broker_skew(msg_context, request.properties().broker_timestamp());
+
+        msg_context
+            .agent_request_counter()
+            .register(agent_id, msg_context.clock().now());
+
         let outgoing_message_stream = endpoint::route_request(msg_context, request)
             .await
             .unwrap_or_else(|| {
@@ -190,15 +206,14 @@ impl<C: GlobalContext + Sync> MessageHandler<C> {
             msg_context.add_logger_tags(o!("label" => label.to_owned()));
         }
 
+        check_broker_skew(msg_context, event.properties().broker_timestamp());
+
         match event.properties().label() {
             Some(label) => {
                 let outgoing_message_stream = endpoint::route_event(msg_context, event)
                     .await
                     .unwrap_or_else(|| {
-                        warn!(
-                            msg_context.logger(),
-                            "Unexpected event with label = '{}'", label
-                        );
+                        report_unknown_label(msg_context, label);
                         Box::new(stream::empty())
                     });
 
@@ -227,6 +242,25 @@ impl<C: GlobalContext + Sync> MessageHandler<C> {
     }
 }
 
+// Unknown labels are never an error for the broker but in strict mode they're counted
+// and sent to Sentry since they usually mean an integration bug.
+fn report_unknown_label<C: Context>(context: &C, label: &str) {
+    warn!(
+        context.logger(),
+        "Unexpected event with label = '{}'", label
+    );
+
+    if context.config().strict_event_labels {
+        context.unknown_label_counter().increment(label);
+
+        AppError::new(
+            AppErrorKind::UnknownEventLabel,
+            anyhow!("Unexpected event with label = '{}'", label),
+        )
+        .notify_sentry(context.logger());
+    }
+}
+
 fn error_response(
     err: AppError,
     reqp: &IncomingRequestProperties,
@@ -242,6 +276,30 @@ fn error_response(
     ))
 }
 
+// A broker clock far from ours makes occurred_at and timings garbage so it's reported
+// on each message. The skew is returned when it exceeds `max_broker_skew_ms`.
+fn check_broker_skew<C: Context>(
+    context: &mut C,
+    broker_timestamp: Option<DateTime<Utc>>,
+) -> Option<Duration> {
+    let max_skew = Duration::milliseconds(context.config().max_broker_skew_ms?);
+    let skew = context.start_timestamp() - broker_timestamp?;
+
+    if skew > max_skew || skew < -max_skew {
+        context.add_logger_tags(o!("broker_skew_ms" => skew.num_milliseconds()));
+
+        warn!(
+            context.logger(),
+            "Broker timestamp differs from the local clock by {} ms",
+            skew.num_milliseconds()
+        );
+
+        return Some(skew);
+    }
+
+    None
+}
+
 pub(crate) fn publish_message(
     agent: &mut Agent,
     message: Box<dyn IntoPublishableMessage>,
@@ -290,25 +348,28 @@ impl<'async_trait, H: 'async_trait + Sync + endpoint::RequestHandler>
             match payload {
                 // Call handler.
                 Ok(payload) => {
-                    H::handle(context, payload, reqp)
-                        .await
-                        .unwrap_or_else(|app_error| {
-                            context.add_logger_tags(o!(
-                                "status" => app_error.status().as_u16(),
-                                "kind" => app_error.kind().to_owned(),
-                            ));
+                    let result = match endpoint::check_read_only::<H, C>(context) {
+                        Ok(()) => H::handle(context, payload, reqp).await,
+                        Err(err) => Err(err),
+                    };
 
-                            error!(
-                                context.logger(),
-                                "Failed to handle request: {}",
-                                app_error.source(),
-                            );
+                    result.unwrap_or_else(|app_error| {
+                        context.add_logger_tags(o!(
+                            "status" => app_error.status().as_u16(),
+                            "kind" => app_error.kind().to_owned(),
+                        ));
 
-                            app_error.notify_sentry(context.logger());
+                        error!(
+                            context.logger(),
+                            "Failed to handle request: {}",
+                            app_error.source(),
+                        );
 
-                            // Handler returned an error.
-                            error_response(app_error, reqp, context.start_timestamp())
-                        })
+                        app_error.notify_sentry(context.logger());
+
+                        // Handler returned an error.
+                        error_response(app_error, reqp, context.start_timestamp())
+                    })
                 }
                 // Bad envelope or payload format => 400.
                 Err(err) => {
@@ -451,20 +512,19 @@ impl endpoint::CorrelationData {
     }
 }
 
-type TimingChannel = crossbeam_channel::Sender<(Duration, String)>;
-
+/// Records the message handling duration into the profiler on drop.
 struct MessageHandlerTiming {
     start: DateTime<Utc>,
-    sender: TimingChannel,
+    profiler: Arc<Profiler<(ProfilerKeys, Option<String>)>>,
     method: String,
 }
 
 impl MessageHandlerTiming {
-    fn new(start: DateTime<Utc>, sender: TimingChannel) -> Self {
+    fn new(start: DateTime<Utc>, profiler: Arc<Profiler<(ProfilerKeys, Option<String>)>>) -> Self {
         Self {
             method: "none".into(),
             start,
-            sender,
+            profiler,
         }
     }
 
@@ -475,14 +535,103 @@ impl MessageHandlerTiming {
 
 impl Drop for MessageHandlerTiming {
     fn drop(&mut self) {
-        if let Err(e) = self
-            .sender
-            .try_send((Utc::now() - self.start, self.method.clone()))
-        {
-            warn!(
-                crate::LOG,
-                "Failed to send msg handler future timing, reason = {:?}", e
+        let method = std::mem::take(&mut self.method);
+        self.profiler
+            .record_future_time(Utc::now() - self.start, method);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::test_helpers::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn record_handler_timing() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let context = TestContext::new(db, TestAuthz::new());
+
+            {
+                let mut timer = MessageHandlerTiming::new(Utc::now(), context.profiler());
+                timer.set_method("event.list".into());
+            }
+
+            let timings = context
+                .profiler()
+                .get_handler_timings()
+                .expect("Failed to get handler timings");
+
+            assert!(timings.iter().any(|(method, _)| method == "event.list"));
+
+            // Timings are reset after being taken.
+            let timings = context
+                .profiler()
+                .get_handler_timings()
+                .expect("Failed to get handler timings");
+
+            assert!(timings.is_empty());
+        });
+    }
+
+    #[test]
+    fn check_broker_skew_exceeded() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let mut reqp_json = serde_json::to_value(build_reqp(agent.agent_id(), "event.list"))
+                .expect("Failed to serialize reqp");
+
+            let skewed_timestamp = Utc::now() - Duration::minutes(5);
+            reqp_json["broker_timestamp"] = json!(skewed_timestamp.timestamp_millis().to_string());
+
+            let reqp = serde_json::from_value::<IncomingRequestProperties>(reqp_json)
+                .expect("Failed to parse reqp");
+
+            // Disabled by default.
+            assert_eq!(
+                check_broker_skew(&mut context, reqp.broker_timestamp()),
+                None
             );
-        }
+
+            context.config_mut().max_broker_skew_ms = Some(60_000);
+            let skew = check_broker_skew(&mut context, reqp.broker_timestamp())
+                .expect("Skew not reported");
+            assert!(skew >= Duration::minutes(5));
+            assert!(format!("{:?}", context.logger().list()).contains("broker_skew_ms"));
+
+            // A timestamp within the threshold is fine.
+            let reqp = build_reqp(agent.agent_id(), "event.list");
+            assert_eq!(
+                check_broker_skew(&mut context, reqp.broker_timestamp()),
+                None
+            );
+        });
+    }
+
+    #[test]
+    fn report_unknown_label_in_strict_mode() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            // Only a warning by default.
+            report_unknown_label(&context, "foo.bar");
+            assert!(context.unknown_label_counter().flush().is_empty());
+
+            context.config_mut().strict_event_labels = true;
+            report_unknown_label(&context, "foo.bar");
+            report_unknown_label(&context, "foo.bar");
+
+            assert_eq!(
+                context.unknown_label_counter().flush(),
+                vec![("foo.bar".to_owned(), 2)]
+            );
+        });
     }
 }
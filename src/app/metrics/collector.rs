@@ -5,6 +5,7 @@ use chrono::{DateTime, Utc};
 
 use crate::app::context::GlobalContext;
 use crate::app::metrics::{Metric, MetricValue, ProfilerKeys, Tags};
+use crate::profiler::EntryReport;
 
 pub(crate) struct Collector<'a, C: GlobalContext> {
     context: &'a C,
@@ -24,7 +25,15 @@ impl<'a, C: GlobalContext> Collector<'a, C> {
         append_internal_stats(&mut metrics, self.context, now);
         append_redis_pool_metrics(&mut metrics, self.context, now);
 
-        append_profiler_stats(&mut metrics, self.context, now, self.duration)?;
+        // Keep reporting other metrics when the profiler is dead; its health is reported below.
+        if let Err(err) = append_profiler_stats(&mut metrics, self.context, now, self.duration) {
+            warn!(crate::LOG, "Failed to collect profiler stats: {:?}", err);
+        }
+
+        append_profiler_health(&mut metrics, self.context, now);
+        append_full_list_stats(&mut metrics, self.context, now);
+        append_unknown_label_stats(&mut metrics, self.context, now);
+        append_oversized_response_stats(&mut metrics, self.context, now);
 
         if let Some(counter) = self.context.running_requests() {
             let tags = Tags::build_internal_tags(crate::APP_VERSION, &self.context.agent_id());
@@ -37,6 +46,28 @@ impl<'a, C: GlobalContext> Collector<'a, C> {
 
         Ok(metrics)
     }
+
+    /// Returns only DB query metrics recorded for the given audience.
+    pub(crate) fn get_for_audience(&self, audience: &str) -> anyhow::Result<Vec<Metric>> {
+        let now = Utc::now();
+        let mut metrics = vec![];
+
+        let profiler_report = self
+            .context
+            .profiler()
+            .flush_for_audience(self.duration, audience)
+            .context("Failed to flush profiler")?;
+
+        append_profiler_report(
+            &mut metrics,
+            self.context,
+            now,
+            profiler_report,
+            Some(audience),
+        );
+
+        Ok(metrics)
+    }
 }
 
 fn append_mqtt_stats(
@@ -155,6 +186,70 @@ fn append_redis_pool_metrics(
     }
 }
 
+fn append_full_list_stats(
+    metrics: &mut Vec<Metric>,
+    context: &impl GlobalContext,
+    now: DateTime<Utc>,
+) {
+    for ((method, client_agent_id), value) in context.full_list_counter().flush() {
+        let tags = Tags::build_full_list_tags(
+            crate::APP_VERSION,
+            context.agent_id(),
+            method,
+            client_agent_id,
+        );
+
+        metrics.push(Metric::FullListRequests(MetricValue::new(value, now, tags)));
+    }
+}
+
+fn append_unknown_label_stats(
+    metrics: &mut Vec<Metric>,
+    context: &impl GlobalContext,
+    now: DateTime<Utc>,
+) {
+    for (label, value) in context.unknown_label_counter().flush() {
+        let tags = Tags::build_unknown_label_tags(crate::APP_VERSION, context.agent_id(), label);
+        metrics.push(Metric::UnknownLabelEvents(MetricValue::new(
+            value, now, tags,
+        )));
+    }
+}
+
+fn append_oversized_response_stats(
+    metrics: &mut Vec<Metric>,
+    context: &impl GlobalContext,
+    now: DateTime<Utc>,
+) {
+    for (method, value) in context.oversized_response_counter().flush() {
+        let tags =
+            Tags::build_oversized_response_tags(crate::APP_VERSION, context.agent_id(), method);
+
+        metrics.push(Metric::OversizedResponses(MetricValue::new(
+            value, now, tags,
+        )));
+    }
+}
+
+fn append_profiler_health(
+    metrics: &mut Vec<Metric>,
+    context: &impl GlobalContext,
+    now: DateTime<Utc>,
+) {
+    let healthy = context.profiler().is_healthy();
+
+    if !healthy {
+        error!(
+            crate::LOG,
+            "Profiler is unhealthy, DB metrics are not collected"
+        );
+    }
+
+    let tags = Tags::build_internal_tags(crate::APP_VERSION, context.agent_id());
+    let value = MetricValue::new(healthy as u64, now, tags);
+    metrics.push(Metric::ProfilerHealthy(value));
+}
+
 fn append_profiler_stats(
     metrics: &mut Vec<Metric>,
     context: &impl GlobalContext,
@@ -166,8 +261,42 @@ fn append_profiler_stats(
         .flush(duration)
         .context("Failed to flush profiler")?;
 
+    append_profiler_report(metrics, context, now, profiler_report, None);
+
+    let profiler_report = context
+        .profiler()
+        .get_handler_timings()
+        .context("Failed to flush profiler")?;
+
+    for (method, EntryReport { p95, p99, max }) in profiler_report {
+        let tags = Tags::build_running_futures_tags(crate::APP_VERSION, context.agent_id(), method);
+
+        let metric_value_p95 = MetricValue::new(p95 as u64, now, tags.clone());
+        let metric_value_p99 = MetricValue::new(p99 as u64, now, tags.clone());
+        let metric_value_max = MetricValue::new(max as u64, now, tags.clone());
+
+        metrics.push(Metric::RunningRequestDurationP95(metric_value_p95));
+        metrics.push(Metric::RunningRequestDurationP99(metric_value_p99));
+        metrics.push(Metric::RunningRequestDurationMax(metric_value_max));
+    }
+    Ok(())
+}
+
+fn append_profiler_report(
+    metrics: &mut Vec<Metric>,
+    context: &impl GlobalContext,
+    now: DateTime<Utc>,
+    profiler_report: Vec<((ProfilerKeys, Option<String>), EntryReport)>,
+    audience: Option<&str>,
+) {
     for ((key, method), entry_report) in profiler_report {
-        let tags = Tags::build_queries_tags(crate::APP_VERSION, context.agent_id(), key, method);
+        let tags = Tags::build_queries_tags(
+            crate::APP_VERSION,
+            context.agent_id(),
+            key,
+            method,
+            audience.map(|a| a.to_owned()),
+        );
         let metric_value_p95 = MetricValue::new(entry_report.p95 as u64, now, tags.clone());
         let metric_value_p99 = MetricValue::new(entry_report.p99 as u64, now, tags.clone());
         let metric_value_max = MetricValue::new(entry_report.max as u64, now, tags.clone());
@@ -203,6 +332,16 @@ fn append_profiler_stats(
                 metrics.push(Metric::AgentUpdateQueryP99(metric_value_p99));
                 metrics.push(Metric::AgentUpdateQueryMax(metric_value_max));
             }
+            ProfilerKeys::AuditLogInsertQuery => {
+                metrics.push(Metric::AuditLogInsertQueryP95(metric_value_p95));
+                metrics.push(Metric::AuditLogInsertQueryP99(metric_value_p99));
+                metrics.push(Metric::AuditLogInsertQueryMax(metric_value_max));
+            }
+            ProfilerKeys::AuditLogListQuery => {
+                metrics.push(Metric::AuditLogListQueryP95(metric_value_p95));
+                metrics.push(Metric::AuditLogListQueryP99(metric_value_p99));
+                metrics.push(Metric::AuditLogListQueryMax(metric_value_max));
+            }
             ProfilerKeys::BanDeleteQuery => {
                 metrics.push(Metric::BanDeleteQueryP95(metric_value_p95));
                 metrics.push(Metric::BanDeleteQueryP99(metric_value_p99));
@@ -238,16 +377,41 @@ fn append_profiler_stats(
                 metrics.push(Metric::ChangeListQueryP99(metric_value_p99));
                 metrics.push(Metric::ChangeListQueryMax(metric_value_max));
             }
+            ProfilerKeys::ChangeCountQuery => {
+                metrics.push(Metric::ChangeCountQueryP95(metric_value_p95));
+                metrics.push(Metric::ChangeCountQueryP99(metric_value_p99));
+                metrics.push(Metric::ChangeCountQueryMax(metric_value_max));
+            }
+            ProfilerKeys::ChangeDanglingListQuery => {
+                metrics.push(Metric::ChangeDanglingListQueryP95(metric_value_p95));
+                metrics.push(Metric::ChangeDanglingListQueryP99(metric_value_p99));
+                metrics.push(Metric::ChangeDanglingListQueryMax(metric_value_max));
+            }
             ProfilerKeys::EditionCloneEventsQuery => {
                 metrics.push(Metric::EditionCloneEventsQueryP95(metric_value_p95));
                 metrics.push(Metric::EditionCloneEventsQueryP99(metric_value_p99));
                 metrics.push(Metric::EditionCloneEventsQueryMax(metric_value_max));
             }
+            ProfilerKeys::EditionEstimateQuery => {
+                metrics.push(Metric::EditionEstimateQueryP95(metric_value_p95));
+                metrics.push(Metric::EditionEstimateQueryP99(metric_value_p99));
+                metrics.push(Metric::EditionEstimateQueryMax(metric_value_max));
+            }
+            ProfilerKeys::EditionCommitQuery => {
+                metrics.push(Metric::EditionCommitQueryP95(metric_value_p95));
+                metrics.push(Metric::EditionCommitQueryP99(metric_value_p99));
+                metrics.push(Metric::EditionCommitQueryMax(metric_value_max));
+            }
             ProfilerKeys::EditionCommitTxnCommit => {
                 metrics.push(Metric::EditionCommitTxnCommitP95(metric_value_p95));
                 metrics.push(Metric::EditionCommitTxnCommitP99(metric_value_p99));
                 metrics.push(Metric::EditionCommitTxnCommitMax(metric_value_max));
             }
+            ProfilerKeys::EditionCommitTotal => {
+                metrics.push(Metric::EditionCommitTotalP95(metric_value_p95));
+                metrics.push(Metric::EditionCommitTotalP99(metric_value_p99));
+                metrics.push(Metric::EditionCommitTotalMax(metric_value_max));
+            }
             ProfilerKeys::EditionDeleteQuery => {
                 metrics.push(Metric::EditionDeleteQueryP95(metric_value_p95));
                 metrics.push(Metric::EditionDeleteQueryP99(metric_value_p99));
@@ -273,6 +437,21 @@ fn append_profiler_stats(
                 metrics.push(Metric::EventDeleteQueryP99(metric_value_p99));
                 metrics.push(Metric::EventDeleteQueryMax(metric_value_max));
             }
+            ProfilerKeys::EventMoveQuery => {
+                metrics.push(Metric::EventMoveQueryP95(metric_value_p95));
+                metrics.push(Metric::EventMoveQueryP99(metric_value_p99));
+                metrics.push(Metric::EventMoveQueryMax(metric_value_max));
+            }
+            ProfilerKeys::EventSetLabelingQuery => {
+                metrics.push(Metric::EventSetLabelingQueryP95(metric_value_p95));
+                metrics.push(Metric::EventSetLabelingQueryP99(metric_value_p99));
+                metrics.push(Metric::EventSetLabelingQueryMax(metric_value_max));
+            }
+            ProfilerKeys::EventFindQuery => {
+                metrics.push(Metric::EventFindQueryP95(metric_value_p95));
+                metrics.push(Metric::EventFindQueryP99(metric_value_p99));
+                metrics.push(Metric::EventFindQueryMax(metric_value_max));
+            }
             ProfilerKeys::EventDumpQuery => {
                 metrics.push(Metric::EventDumpQueryP95(metric_value_p95));
                 metrics.push(Metric::EventDumpQueryP99(metric_value_p99));
@@ -283,11 +462,36 @@ fn append_profiler_stats(
                 metrics.push(Metric::EventInsertQueryP99(metric_value_p99));
                 metrics.push(Metric::EventInsertQueryMax(metric_value_max));
             }
+            ProfilerKeys::EventLabelLockQuery => {
+                metrics.push(Metric::EventLabelLockQueryP95(metric_value_p95));
+                metrics.push(Metric::EventLabelLockQueryP99(metric_value_p99));
+                metrics.push(Metric::EventLabelLockQueryMax(metric_value_max));
+            }
+            ProfilerKeys::EventLatestVersionQuery => {
+                metrics.push(Metric::EventLatestVersionQueryP95(metric_value_p95));
+                metrics.push(Metric::EventLatestVersionQueryP99(metric_value_p99));
+                metrics.push(Metric::EventLatestVersionQueryMax(metric_value_max));
+            }
             ProfilerKeys::EventListQuery => {
                 metrics.push(Metric::EventListQueryP95(metric_value_p95));
                 metrics.push(Metric::EventListQueryP99(metric_value_p99));
                 metrics.push(Metric::EventListQueryMax(metric_value_max));
             }
+            ProfilerKeys::EventListRows => {
+                metrics.push(Metric::EventListRowsP95(metric_value_p95));
+                metrics.push(Metric::EventListRowsP99(metric_value_p99));
+                metrics.push(Metric::EventListRowsMax(metric_value_max));
+            }
+            ProfilerKeys::EventListMultiQuery => {
+                metrics.push(Metric::EventListMultiQueryP95(metric_value_p95));
+                metrics.push(Metric::EventListMultiQueryP99(metric_value_p99));
+                metrics.push(Metric::EventListMultiQueryMax(metric_value_max));
+            }
+            ProfilerKeys::EventCountQuery => {
+                metrics.push(Metric::EventCountQueryP95(metric_value_p95));
+                metrics.push(Metric::EventCountQueryP99(metric_value_p99));
+                metrics.push(Metric::EventCountQueryMax(metric_value_max));
+            }
             ProfilerKeys::EventOriginalEventQuery => {
                 metrics.push(Metric::EventOriginalQueryP95(metric_value_p95));
                 metrics.push(Metric::EventOriginalQueryP99(metric_value_p99));
@@ -298,11 +502,21 @@ fn append_profiler_stats(
                 metrics.push(Metric::EventVacuumQueryP99(metric_value_p99));
                 metrics.push(Metric::EventVacuumQueryMax(metric_value_max));
             }
+            ProfilerKeys::EventCompactQuery => {
+                metrics.push(Metric::EventCompactQueryP95(metric_value_p95));
+                metrics.push(Metric::EventCompactQueryP99(metric_value_p99));
+                metrics.push(Metric::EventCompactQueryMax(metric_value_max));
+            }
             ProfilerKeys::RoomAdjustCloneEventsQuery => {
                 metrics.push(Metric::RoomAdjustCloneEventsQueryP95(metric_value_p95));
                 metrics.push(Metric::RoomAdjustCloneEventsQueryP99(metric_value_p99));
                 metrics.push(Metric::RoomAdjustCloneEventsQueryMax(metric_value_max));
             }
+            ProfilerKeys::RoomAdjustTxnCommit => {
+                metrics.push(Metric::RoomAdjustTxnCommitP95(metric_value_p95));
+                metrics.push(Metric::RoomAdjustTxnCommitP99(metric_value_p99));
+                metrics.push(Metric::RoomAdjustTxnCommitMax(metric_value_max));
+            }
             ProfilerKeys::RoomFindQuery => {
                 metrics.push(Metric::RoomFindQueryP95(metric_value_p95));
                 metrics.push(Metric::RoomFindQueryP99(metric_value_p99));
@@ -318,6 +532,26 @@ fn append_profiler_stats(
                 metrics.push(Metric::RoomUpdateQueryP99(metric_value_p99));
                 metrics.push(Metric::RoomUpdateQueryMax(metric_value_max));
             }
+            ProfilerKeys::RoomUpdateTagsQuery => {
+                metrics.push(Metric::RoomUpdateTagsQueryP95(metric_value_p95));
+                metrics.push(Metric::RoomUpdateTagsQueryP99(metric_value_p99));
+                metrics.push(Metric::RoomUpdateTagsQueryMax(metric_value_max));
+            }
+            ProfilerKeys::RoomCloseExpiredQuery => {
+                metrics.push(Metric::RoomCloseExpiredQueryP95(metric_value_p95));
+                metrics.push(Metric::RoomCloseExpiredQueryP99(metric_value_p99));
+                metrics.push(Metric::RoomCloseExpiredQueryMax(metric_value_max));
+            }
+            ProfilerKeys::RoomDerivedListQuery => {
+                metrics.push(Metric::RoomDerivedListQueryP95(metric_value_p95));
+                metrics.push(Metric::RoomDerivedListQueryP99(metric_value_p99));
+                metrics.push(Metric::RoomDerivedListQueryMax(metric_value_max));
+            }
+            ProfilerKeys::RoomStatsQuery => {
+                metrics.push(Metric::RoomStatsQueryP95(metric_value_p95));
+                metrics.push(Metric::RoomStatsQueryP99(metric_value_p99));
+                metrics.push(Metric::RoomStatsQueryMax(metric_value_max));
+            }
             ProfilerKeys::StateTotalCountQuery => {
                 metrics.push(Metric::StateTotalCountQueryP95(metric_value_p95));
                 metrics.push(Metric::StateTotalCountQueryP99(metric_value_p99));
@@ -328,24 +562,105 @@ fn append_profiler_stats(
                 metrics.push(Metric::StateQueryP99(metric_value_p99));
                 metrics.push(Metric::StateQueryMax(metric_value_max));
             }
+            ProfilerKeys::StateRows => {
+                metrics.push(Metric::StateRowsP95(metric_value_p95));
+                metrics.push(Metric::StateRowsP99(metric_value_p99));
+                metrics.push(Metric::StateRowsMax(metric_value_max));
+            }
+            ProfilerKeys::StateSnapshotQuery => {
+                metrics.push(Metric::StateSnapshotQueryP95(metric_value_p95));
+                metrics.push(Metric::StateSnapshotQueryP99(metric_value_p99));
+                metrics.push(Metric::StateSnapshotQueryMax(metric_value_max));
+            }
+            ProfilerKeys::HandlerQueueWait => {
+                metrics.push(Metric::HandlerQueueWaitP95(metric_value_p95));
+                metrics.push(Metric::HandlerQueueWaitP99(metric_value_p99));
+                metrics.push(Metric::HandlerQueueWaitMax(metric_value_max));
+            }
         }
     }
+}
 
-    let profiler_report = context
-        .profiler()
-        .get_handler_timings()
-        .context("Failed to flush profiler")?;
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value as JsonValue};
+    use serial_test::serial;
 
-    for (method, crate::profiler::EntryReport { p95, p99, max }) in profiler_report {
-        let tags = Tags::build_running_futures_tags(crate::APP_VERSION, context.agent_id(), method);
+    use crate::app::operations::vacuum;
+    use crate::config::VacuumConfig;
+    use crate::test_helpers::prelude::*;
 
-        let metric_value_p95 = MetricValue::new(p95 as u64, now, tags.clone());
-        let metric_value_p99 = MetricValue::new(p99 as u64, now, tags.clone());
-        let metric_value_max = MetricValue::new(max as u64, now, tags.clone());
+    use super::*;
 
-        metrics.push(Metric::RunningRequestDurationP95(metric_value_p95));
-        metrics.push(Metric::RunningRequestDurationP99(metric_value_p99));
-        metrics.push(Metric::RunningRequestDurationMax(metric_value_max));
+    #[test]
+    fn get_for_audience() {
+        async_std::task::block_on(async {
+            let context = TestContext::new(TestDb::new().await, TestAuthz::new());
+            let profiler = context.profiler();
+
+            let key = (ProfilerKeys::EventListQuery, Some("event.list".to_owned()));
+
+            profiler
+                .measure_for_audience(key.clone(), "foo.example.org", async {})
+                .await;
+
+            profiler
+                .measure_for_audience(key.clone(), "bar.example.org", async {})
+                .await;
+
+            profiler.measure(key, async {}).await;
+
+            let metrics = Collector::new(&context, 5)
+                .get_for_audience("foo.example.org")
+                .expect("Failed to collect metrics");
+
+            assert_eq!(metrics.len(), 3);
+
+            for metric in metrics {
+                let metric = serde_json::to_value(metric).expect("Failed to serialize metric");
+
+                assert_eq!(
+                    metric["tags"]["audience"],
+                    JsonValue::from("foo.example.org")
+                );
+            }
+        });
+    }
+
+    #[test]
+    #[serial]
+    fn vacuum_query_metrics() {
+        async_std::task::block_on(async {
+            let context = TestContext::new(TestDb::new().await, TestAuthz::new());
+
+            // Retention is long enough not to delete anything.
+            let config: VacuumConfig = serde_json::from_value(json!({
+                "max_history_size": 1_000_000,
+                "max_history_lifetime": 1_000_000,
+                "max_deleted_lifetime": 1_000_000,
+            }))
+            .expect("Failed to parse vacuum config");
+
+            vacuum(context.db(), &context.profiler(), &config)
+                .await
+                .expect("Vacuum failed");
+
+            let metrics = Collector::new(&context, 5)
+                .get()
+                .expect("Failed to collect metrics");
+
+            let metric_names = metrics
+                .into_iter()
+                .map(|metric| {
+                    let metric = serde_json::to_value(metric).expect("Failed to serialize metric");
+                    metric["metric"].as_str().unwrap_or_default().to_owned()
+                })
+                .collect::<Vec<_>>();
+
+            for suffix in &["p95", "p99", "max"] {
+                let name = format!("apps.event.event_vacuum_query_{}_microseconds", suffix);
+                assert!(metric_names.contains(&name), "Missing {} metric", name);
+            }
+        });
     }
-    Ok(())
 }
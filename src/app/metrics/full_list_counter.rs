@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use svc_agent::AgentId;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Counts list requests which returned a full page, i.e. hit the server-side maximum limit.
+/// Clients that never paginate keep hitting it so the counter is keyed by method and agent.
+#[derive(Debug, Default)]
+pub(crate) struct FullListCounter {
+    counters: Mutex<HashMap<(String, String), u64>>,
+}
+
+impl FullListCounter {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn increment(&self, method: &str, agent_id: &AgentId) {
+        let mut counters = self
+            .counters
+            .lock()
+            .expect("Full list counter mutex poisoned");
+
+        *counters
+            .entry((method.to_owned(), agent_id.to_string()))
+            .or_insert(0) += 1;
+    }
+
+    /// Returns `((method, agent_id), count)` pairs collected since the previous flush.
+    pub(crate) fn flush(&self) -> Vec<((String, String), u64)> {
+        let mut counters = self
+            .counters
+            .lock()
+            .expect("Full list counter mutex poisoned");
+
+        counters.drain().collect()
+    }
+}
@@ -36,6 +36,8 @@ pub enum Tags {
         account_audience: String,
         request_method: Option<String>,
         query_label: ProfilerKeys,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        audience: Option<String>,
     },
     RunningFuture {
         version: String,
@@ -44,6 +46,28 @@ pub enum Tags {
         account_audience: String,
         method: String,
     },
+    FullList {
+        version: String,
+        agent_label: String,
+        account_label: String,
+        account_audience: String,
+        method: String,
+        client_agent_id: String,
+    },
+    UnknownLabel {
+        version: String,
+        agent_label: String,
+        account_label: String,
+        account_audience: String,
+        label: String,
+    },
+    OversizedResponse {
+        version: String,
+        agent_label: String,
+        account_label: String,
+        account_audience: String,
+        method: String,
+    },
 }
 
 impl Tags {
@@ -71,6 +95,7 @@ impl Tags {
         agent_id: &AgentId,
         query_label: ProfilerKeys,
         request_method: Option<String>,
+        audience: Option<String>,
     ) -> Self {
         Tags::Queries {
             version: version.to_owned(),
@@ -79,6 +104,7 @@ impl Tags {
             account_audience: agent_id.as_account_id().audience().to_owned(),
             query_label,
             request_method,
+            audience,
         }
     }
 
@@ -91,6 +117,46 @@ impl Tags {
             method,
         }
     }
+
+    pub fn build_full_list_tags(
+        version: &str,
+        agent_id: &AgentId,
+        method: String,
+        client_agent_id: String,
+    ) -> Self {
+        Tags::FullList {
+            version: version.to_owned(),
+            agent_label: agent_id.label().to_owned(),
+            account_label: agent_id.as_account_id().label().to_owned(),
+            account_audience: agent_id.as_account_id().audience().to_owned(),
+            method,
+            client_agent_id,
+        }
+    }
+
+    pub fn build_unknown_label_tags(version: &str, agent_id: &AgentId, label: String) -> Self {
+        Tags::UnknownLabel {
+            version: version.to_owned(),
+            agent_label: agent_id.label().to_owned(),
+            account_label: agent_id.as_account_id().label().to_owned(),
+            account_audience: agent_id.as_account_id().audience().to_owned(),
+            label,
+        }
+    }
+
+    pub fn build_oversized_response_tags(
+        version: &str,
+        agent_id: &AgentId,
+        method: String,
+    ) -> Self {
+        Tags::OversizedResponse {
+            version: version.to_owned(),
+            agent_label: agent_id.label().to_owned(),
+            account_label: agent_id.as_account_id().label().to_owned(),
+            account_audience: agent_id.as_account_id().audience().to_owned(),
+            method,
+        }
+    }
 }
 
 impl<T: serde::Serialize> MetricValue<T> {
@@ -169,6 +235,18 @@ pub(crate) enum Metric {
     AgentUpdateQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.agent_update_query_max_microseconds"))]
     AgentUpdateQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.audit_log_insert_query_p95_microseconds"))]
+    AuditLogInsertQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.audit_log_insert_query_p99_microseconds"))]
+    AuditLogInsertQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.audit_log_insert_query_max_microseconds"))]
+    AuditLogInsertQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.audit_log_list_query_p95_microseconds"))]
+    AuditLogListQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.audit_log_list_query_p99_microseconds"))]
+    AuditLogListQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.audit_log_list_query_max_microseconds"))]
+    AuditLogListQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.agent_list_query_p95_microseconds"))]
     AgentListQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.agent_list_query_p99_microseconds"))]
@@ -217,18 +295,48 @@ pub(crate) enum Metric {
     ChangeListQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.change_list_query_max_microseconds"))]
     ChangeListQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.change_count_query_p95_microseconds"))]
+    ChangeCountQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.change_count_query_p99_microseconds"))]
+    ChangeCountQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.change_count_query_max_microseconds"))]
+    ChangeCountQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.change_dangling_list_query_p95_microseconds"))]
+    ChangeDanglingListQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.change_dangling_list_query_p99_microseconds"))]
+    ChangeDanglingListQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.change_dangling_list_query_max_microseconds"))]
+    ChangeDanglingListQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.edition_clone_events_query_p95_microseconds"))]
     EditionCloneEventsQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.edition_clone_events_query_p99_microseconds"))]
     EditionCloneEventsQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.edition_clone_events_query_max_microseconds"))]
     EditionCloneEventsQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.edition_estimate_query_p95_microseconds"))]
+    EditionEstimateQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.edition_estimate_query_p99_microseconds"))]
+    EditionEstimateQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.edition_estimate_query_max_microseconds"))]
+    EditionEstimateQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.edition_commit_query_p95_microseconds"))]
+    EditionCommitQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.edition_commit_query_p99_microseconds"))]
+    EditionCommitQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.edition_commit_query_max_microseconds"))]
+    EditionCommitQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.edition_commit_txn_commit_max_p95_microseconds"))]
     EditionCommitTxnCommitP95(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.edition_commit_txn_commit_max_p99_microseconds"))]
     EditionCommitTxnCommitP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.edition_commit_txn_commit_max_microseconds"))]
     EditionCommitTxnCommitMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.edition_commit_total_p95_microseconds"))]
+    EditionCommitTotalP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.edition_commit_total_p99_microseconds"))]
+    EditionCommitTotalP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.edition_commit_total_max_microseconds"))]
+    EditionCommitTotalMax(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.edition_delete_query_p95_microseconds"))]
     EditionDeleteQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.edition_delete_query_p99_microseconds"))]
@@ -259,6 +367,24 @@ pub(crate) enum Metric {
     EventDeleteQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.event_delete_query_max_microseconds"))]
     EventDeleteQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_move_query_p95_microseconds"))]
+    EventMoveQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_move_query_p99_microseconds"))]
+    EventMoveQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_move_query_max_microseconds"))]
+    EventMoveQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_set_labeling_query_p95_microseconds"))]
+    EventSetLabelingQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_set_labeling_query_p99_microseconds"))]
+    EventSetLabelingQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_set_labeling_query_max_microseconds"))]
+    EventSetLabelingQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_find_query_p95_microseconds"))]
+    EventFindQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_find_query_p99_microseconds"))]
+    EventFindQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_find_query_max_microseconds"))]
+    EventFindQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.event_dump_query_p95_microseconds"))]
     EventDumpQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.event_dump_query_p99_microseconds"))]
@@ -271,12 +397,42 @@ pub(crate) enum Metric {
     EventInsertQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.event_insert_query_max_microseconds"))]
     EventInsertQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_label_lock_query_p95_microseconds"))]
+    EventLabelLockQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_label_lock_query_p99_microseconds"))]
+    EventLabelLockQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_label_lock_query_max_microseconds"))]
+    EventLabelLockQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_latest_version_query_p95_microseconds"))]
+    EventLatestVersionQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_latest_version_query_p99_microseconds"))]
+    EventLatestVersionQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_latest_version_query_max_microseconds"))]
+    EventLatestVersionQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.event_list_query_p95_microseconds"))]
     EventListQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.event_list_query_p99_microseconds"))]
     EventListQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.event_list_query_max_microseconds"))]
     EventListQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_list_rows_p95"))]
+    EventListRowsP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_list_rows_p99"))]
+    EventListRowsP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_list_rows_max"))]
+    EventListRowsMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_list_multi_query_p95_microseconds"))]
+    EventListMultiQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_list_multi_query_p99_microseconds"))]
+    EventListMultiQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_list_multi_query_max_microseconds"))]
+    EventListMultiQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_count_query_p95_microseconds"))]
+    EventCountQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_count_query_p99_microseconds"))]
+    EventCountQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_count_query_max_microseconds"))]
+    EventCountQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.event_original_query_p95_microseconds"))]
     EventOriginalQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.event_original_query_p99_microseconds"))]
@@ -289,12 +445,24 @@ pub(crate) enum Metric {
     EventVacuumQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.event_vacuum_query_max_microseconds"))]
     EventVacuumQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_compact_query_p95_microseconds"))]
+    EventCompactQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_compact_query_p99_microseconds"))]
+    EventCompactQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.event_compact_query_max_microseconds"))]
+    EventCompactQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.room_adjust_clone_events_query_p95_microseconds"))]
     RoomAdjustCloneEventsQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.room_adjust_clone_events_query_p99_microseconds"))]
     RoomAdjustCloneEventsQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.room_adjust_clone_events_query_max_microseconds"))]
     RoomAdjustCloneEventsQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_adjust_txn_commit_p95_microseconds"))]
+    RoomAdjustTxnCommitP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_adjust_txn_commit_p99_microseconds"))]
+    RoomAdjustTxnCommitP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_adjust_txn_commit_max_microseconds"))]
+    RoomAdjustTxnCommitMax(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.room_find_query_p95_microseconds"))]
     RoomFindQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.room_find_query_p99_microseconds"))]
@@ -313,6 +481,30 @@ pub(crate) enum Metric {
     RoomUpdateQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.room_update_query_max_microseconds"))]
     RoomUpdateQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_update_tags_query_p95_microseconds"))]
+    RoomUpdateTagsQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_update_tags_query_p99_microseconds"))]
+    RoomUpdateTagsQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_update_tags_query_max_microseconds"))]
+    RoomUpdateTagsQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_close_expired_query_p95_microseconds"))]
+    RoomCloseExpiredQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_close_expired_query_p99_microseconds"))]
+    RoomCloseExpiredQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_close_expired_query_max_microseconds"))]
+    RoomCloseExpiredQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_derived_list_query_p95_microseconds"))]
+    RoomDerivedListQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_derived_list_query_p99_microseconds"))]
+    RoomDerivedListQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_derived_list_query_max_microseconds"))]
+    RoomDerivedListQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_stats_query_p95_microseconds"))]
+    RoomStatsQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_stats_query_p99_microseconds"))]
+    RoomStatsQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.room_stats_query_max_microseconds"))]
+    RoomStatsQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.state_total_count_query_p95_microseconds"))]
     StateTotalCountQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.state_total_count_query_p99_microseconds"))]
@@ -325,6 +517,24 @@ pub(crate) enum Metric {
     StateQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.state_query_max_microseconds"))]
     StateQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.state_rows_p95"))]
+    StateRowsP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.state_rows_p99"))]
+    StateRowsP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.state_rows_max"))]
+    StateRowsMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.state_snapshot_query_p95_microseconds"))]
+    StateSnapshotQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.state_snapshot_query_p99_microseconds"))]
+    StateSnapshotQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.state_snapshot_query_max_microseconds"))]
+    StateSnapshotQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.handler_queue_wait_p95_microseconds"))]
+    HandlerQueueWaitP95(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.handler_queue_wait_p99_microseconds"))]
+    HandlerQueueWaitP99(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.handler_queue_wait_max_microseconds"))]
+    HandlerQueueWaitMax(MetricValue<u64>),
 
     // Misc.
     #[serde(rename(serialize = "apps.event.running_requests_total"))]
@@ -335,6 +545,14 @@ pub(crate) enum Metric {
     RunningRequestDurationP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.running_request_duration_max_microseconds"))]
     RunningRequestDurationMax(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.full_list_requests_total"))]
+    FullListRequests(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.unknown_label_events_total"))]
+    UnknownLabelEvents(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.oversized_responses_total"))]
+    OversizedResponses(MetricValue<u64>),
+    #[serde(rename(serialize = "apps.event.profiler_healthy"))]
+    ProfilerHealthy(MetricValue<u64>),
 }
 
 #[derive(Serialize, Clone)]
@@ -409,6 +627,18 @@ pub(crate) enum Metric2 {
     AgentUpdateQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "agent_update_query_max_microseconds"))]
     AgentUpdateQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "audit_log_insert_query_p95_microseconds"))]
+    AuditLogInsertQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "audit_log_insert_query_p99_microseconds"))]
+    AuditLogInsertQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "audit_log_insert_query_max_microseconds"))]
+    AuditLogInsertQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "audit_log_list_query_p95_microseconds"))]
+    AuditLogListQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "audit_log_list_query_p99_microseconds"))]
+    AuditLogListQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "audit_log_list_query_max_microseconds"))]
+    AuditLogListQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "ban_delete_query_max_microseconds"))]
     BanDeleteQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "ban_delete_query_max_microseconds"))]
@@ -451,18 +681,48 @@ pub(crate) enum Metric2 {
     ChangeListQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "change_list_query_max_microseconds"))]
     ChangeListQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "change_count_query_p95_microseconds"))]
+    ChangeCountQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "change_count_query_p99_microseconds"))]
+    ChangeCountQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "change_count_query_max_microseconds"))]
+    ChangeCountQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "change_dangling_list_query_p95_microseconds"))]
+    ChangeDanglingListQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "change_dangling_list_query_p99_microseconds"))]
+    ChangeDanglingListQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "change_dangling_list_query_max_microseconds"))]
+    ChangeDanglingListQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "edition_clone_events_query_p95_microseconds"))]
     EditionCloneEventsQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "edition_clone_events_query_p99_microseconds"))]
     EditionCloneEventsQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "edition_clone_events_query_max_microseconds"))]
     EditionCloneEventsQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "edition_estimate_query_p95_microseconds"))]
+    EditionEstimateQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "edition_estimate_query_p99_microseconds"))]
+    EditionEstimateQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "edition_estimate_query_max_microseconds"))]
+    EditionEstimateQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "edition_commit_query_p95_microseconds"))]
+    EditionCommitQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "edition_commit_query_p99_microseconds"))]
+    EditionCommitQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "edition_commit_query_max_microseconds"))]
+    EditionCommitQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "edition_commit_txn_commit_max_p95_microseconds"))]
     EditionCommitTxnCommitP95(MetricValue<u64>),
     #[serde(rename(serialize = "edition_commit_txn_commit_max_p99_microseconds"))]
     EditionCommitTxnCommitP99(MetricValue<u64>),
     #[serde(rename(serialize = "edition_commit_txn_commit_max_microseconds"))]
     EditionCommitTxnCommitMax(MetricValue<u64>),
+    #[serde(rename(serialize = "edition_commit_total_p95_microseconds"))]
+    EditionCommitTotalP95(MetricValue<u64>),
+    #[serde(rename(serialize = "edition_commit_total_p99_microseconds"))]
+    EditionCommitTotalP99(MetricValue<u64>),
+    #[serde(rename(serialize = "edition_commit_total_max_microseconds"))]
+    EditionCommitTotalMax(MetricValue<u64>),
     #[serde(rename(serialize = "edition_delete_query_p95_microseconds"))]
     EditionDeleteQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "edition_delete_query_p99_microseconds"))]
@@ -493,6 +753,24 @@ pub(crate) enum Metric2 {
     EventDeleteQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "event_delete_query_max_microseconds"))]
     EventDeleteQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "event_move_query_p95_microseconds"))]
+    EventMoveQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "event_move_query_p99_microseconds"))]
+    EventMoveQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "event_move_query_max_microseconds"))]
+    EventMoveQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "event_set_labeling_query_p95_microseconds"))]
+    EventSetLabelingQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "event_set_labeling_query_p99_microseconds"))]
+    EventSetLabelingQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "event_set_labeling_query_max_microseconds"))]
+    EventSetLabelingQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "event_find_query_p95_microseconds"))]
+    EventFindQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "event_find_query_p99_microseconds"))]
+    EventFindQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "event_find_query_max_microseconds"))]
+    EventFindQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "event_dump_query_p95_microseconds"))]
     EventDumpQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "event_dump_query_p99_microseconds"))]
@@ -505,12 +783,42 @@ pub(crate) enum Metric2 {
     EventInsertQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "event_insert_query_max_microseconds"))]
     EventInsertQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "event_label_lock_query_p95_microseconds"))]
+    EventLabelLockQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "event_label_lock_query_p99_microseconds"))]
+    EventLabelLockQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "event_label_lock_query_max_microseconds"))]
+    EventLabelLockQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "event_latest_version_query_p95_microseconds"))]
+    EventLatestVersionQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "event_latest_version_query_p99_microseconds"))]
+    EventLatestVersionQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "event_latest_version_query_max_microseconds"))]
+    EventLatestVersionQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "event_list_query_p95_microseconds"))]
     EventListQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "event_list_query_p99_microseconds"))]
     EventListQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "event_list_query_max_microseconds"))]
     EventListQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "event_list_rows_p95"))]
+    EventListRowsP95(MetricValue<u64>),
+    #[serde(rename(serialize = "event_list_rows_p99"))]
+    EventListRowsP99(MetricValue<u64>),
+    #[serde(rename(serialize = "event_list_rows_max"))]
+    EventListRowsMax(MetricValue<u64>),
+    #[serde(rename(serialize = "event_list_multi_query_p95_microseconds"))]
+    EventListMultiQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "event_list_multi_query_p99_microseconds"))]
+    EventListMultiQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "event_list_multi_query_max_microseconds"))]
+    EventListMultiQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "event_count_query_p95_microseconds"))]
+    EventCountQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "event_count_query_p99_microseconds"))]
+    EventCountQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "event_count_query_max_microseconds"))]
+    EventCountQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "event_original_query_p95_microseconds"))]
     EventOriginalQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "event_original_query_p99_microseconds"))]
@@ -523,12 +831,24 @@ pub(crate) enum Metric2 {
     EventVacuumQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "apps.event.event_vacuum_query_max_microseconds"))]
     EventVacuumQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "event_compact_query_p95_microseconds"))]
+    EventCompactQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "event_compact_query_p99_microseconds"))]
+    EventCompactQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "event_compact_query_max_microseconds"))]
+    EventCompactQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "room_adjust_clone_events_query_p95_microseconds"))]
     RoomAdjustCloneEventsQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "room_adjust_clone_events_query_p99_microseconds"))]
     RoomAdjustCloneEventsQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "room_adjust_clone_events_query_max_microseconds"))]
     RoomAdjustCloneEventsQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "room_adjust_txn_commit_p95_microseconds"))]
+    RoomAdjustTxnCommitP95(MetricValue<u64>),
+    #[serde(rename(serialize = "room_adjust_txn_commit_p99_microseconds"))]
+    RoomAdjustTxnCommitP99(MetricValue<u64>),
+    #[serde(rename(serialize = "room_adjust_txn_commit_max_microseconds"))]
+    RoomAdjustTxnCommitMax(MetricValue<u64>),
     #[serde(rename(serialize = "room_find_query_p95_microseconds"))]
     RoomFindQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "room_find_query_p99_microseconds"))]
@@ -547,6 +867,30 @@ pub(crate) enum Metric2 {
     RoomUpdateQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "room_update_query_max_microseconds"))]
     RoomUpdateQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "room_update_tags_query_p95_microseconds"))]
+    RoomUpdateTagsQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "room_update_tags_query_p99_microseconds"))]
+    RoomUpdateTagsQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "room_update_tags_query_max_microseconds"))]
+    RoomUpdateTagsQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "room_close_expired_query_p95_microseconds"))]
+    RoomCloseExpiredQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "room_close_expired_query_p99_microseconds"))]
+    RoomCloseExpiredQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "room_close_expired_query_max_microseconds"))]
+    RoomCloseExpiredQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "room_derived_list_query_p95_microseconds"))]
+    RoomDerivedListQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "room_derived_list_query_p99_microseconds"))]
+    RoomDerivedListQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "room_derived_list_query_max_microseconds"))]
+    RoomDerivedListQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "room_stats_query_p95_microseconds"))]
+    RoomStatsQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "room_stats_query_p99_microseconds"))]
+    RoomStatsQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "room_stats_query_max_microseconds"))]
+    RoomStatsQueryMax(MetricValue<u64>),
     #[serde(rename(serialize = "state_total_count_query_p95_microseconds"))]
     StateTotalCountQueryP95(MetricValue<u64>),
     #[serde(rename(serialize = "state_total_count_query_p99_microseconds"))]
@@ -559,6 +903,24 @@ pub(crate) enum Metric2 {
     StateQueryP99(MetricValue<u64>),
     #[serde(rename(serialize = "state_query_max_microseconds"))]
     StateQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "state_rows_p95"))]
+    StateRowsP95(MetricValue<u64>),
+    #[serde(rename(serialize = "state_rows_p99"))]
+    StateRowsP99(MetricValue<u64>),
+    #[serde(rename(serialize = "state_rows_max"))]
+    StateRowsMax(MetricValue<u64>),
+    #[serde(rename(serialize = "state_snapshot_query_p95_microseconds"))]
+    StateSnapshotQueryP95(MetricValue<u64>),
+    #[serde(rename(serialize = "state_snapshot_query_p99_microseconds"))]
+    StateSnapshotQueryP99(MetricValue<u64>),
+    #[serde(rename(serialize = "state_snapshot_query_max_microseconds"))]
+    StateSnapshotQueryMax(MetricValue<u64>),
+    #[serde(rename(serialize = "handler_queue_wait_p95_microseconds"))]
+    HandlerQueueWaitP95(MetricValue<u64>),
+    #[serde(rename(serialize = "handler_queue_wait_p99_microseconds"))]
+    HandlerQueueWaitP99(MetricValue<u64>),
+    #[serde(rename(serialize = "handler_queue_wait_max_microseconds"))]
+    HandlerQueueWaitMax(MetricValue<u64>),
 
     // Misc.
     #[serde(rename(serialize = "running_requests_total"))]
@@ -570,6 +932,14 @@ pub(crate) enum Metric2 {
     RunningRequestDurationP99(MetricValue<u64>),
     #[serde(rename(serialize = "running_request_duration_max_microseconds"))]
     RunningRequestDurationMax(MetricValue<u64>),
+    #[serde(rename(serialize = "full_list_requests_total"))]
+    FullListRequests(MetricValue<u64>),
+    #[serde(rename(serialize = "unknown_label_events_total"))]
+    UnknownLabelEvents(MetricValue<u64>),
+    #[serde(rename(serialize = "oversized_responses_total"))]
+    OversizedResponses(MetricValue<u64>),
+    #[serde(rename(serialize = "profiler_healthy"))]
+    ProfilerHealthy(MetricValue<u64>),
 }
 
 impl From<Metric> for Metric2 {
@@ -605,6 +975,12 @@ impl From<Metric> for Metric2 {
             Metric::AgentUpdateQueryP95(v) => Metric2::AgentUpdateQueryP95(v),
             Metric::AgentUpdateQueryP99(v) => Metric2::AgentUpdateQueryP99(v),
             Metric::AgentUpdateQueryMax(v) => Metric2::AgentUpdateQueryMax(v),
+            Metric::AuditLogInsertQueryP95(v) => Metric2::AuditLogInsertQueryP95(v),
+            Metric::AuditLogInsertQueryP99(v) => Metric2::AuditLogInsertQueryP99(v),
+            Metric::AuditLogInsertQueryMax(v) => Metric2::AuditLogInsertQueryMax(v),
+            Metric::AuditLogListQueryP95(v) => Metric2::AuditLogListQueryP95(v),
+            Metric::AuditLogListQueryP99(v) => Metric2::AuditLogListQueryP99(v),
+            Metric::AuditLogListQueryMax(v) => Metric2::AuditLogListQueryMax(v),
             Metric::BanDeleteQueryP95(v) => Metric2::BanDeleteQueryP95(v),
             Metric::BanDeleteQueryP99(v) => Metric2::BanDeleteQueryP99(v),
             Metric::BanDeleteQueryMax(v) => Metric2::BanDeleteQueryMax(v),
@@ -626,12 +1002,27 @@ impl From<Metric> for Metric2 {
             Metric::ChangeListQueryP95(v) => Metric2::ChangeListQueryP95(v),
             Metric::ChangeListQueryP99(v) => Metric2::ChangeListQueryP99(v),
             Metric::ChangeListQueryMax(v) => Metric2::ChangeListQueryMax(v),
+            Metric::ChangeCountQueryP95(v) => Metric2::ChangeCountQueryP95(v),
+            Metric::ChangeCountQueryP99(v) => Metric2::ChangeCountQueryP99(v),
+            Metric::ChangeCountQueryMax(v) => Metric2::ChangeCountQueryMax(v),
+            Metric::ChangeDanglingListQueryP95(v) => Metric2::ChangeDanglingListQueryP95(v),
+            Metric::ChangeDanglingListQueryP99(v) => Metric2::ChangeDanglingListQueryP99(v),
+            Metric::ChangeDanglingListQueryMax(v) => Metric2::ChangeDanglingListQueryMax(v),
             Metric::EditionCloneEventsQueryP95(v) => Metric2::EditionCloneEventsQueryP95(v),
             Metric::EditionCloneEventsQueryP99(v) => Metric2::EditionCloneEventsQueryP99(v),
             Metric::EditionCloneEventsQueryMax(v) => Metric2::EditionCloneEventsQueryMax(v),
+            Metric::EditionEstimateQueryP95(v) => Metric2::EditionEstimateQueryP95(v),
+            Metric::EditionEstimateQueryP99(v) => Metric2::EditionEstimateQueryP99(v),
+            Metric::EditionEstimateQueryMax(v) => Metric2::EditionEstimateQueryMax(v),
+            Metric::EditionCommitQueryP95(v) => Metric2::EditionCommitQueryP95(v),
+            Metric::EditionCommitQueryP99(v) => Metric2::EditionCommitQueryP99(v),
+            Metric::EditionCommitQueryMax(v) => Metric2::EditionCommitQueryMax(v),
             Metric::EditionCommitTxnCommitP95(v) => Metric2::EditionCommitTxnCommitP95(v),
             Metric::EditionCommitTxnCommitP99(v) => Metric2::EditionCommitTxnCommitP99(v),
             Metric::EditionCommitTxnCommitMax(v) => Metric2::EditionCommitTxnCommitMax(v),
+            Metric::EditionCommitTotalP95(v) => Metric2::EditionCommitTotalP95(v),
+            Metric::EditionCommitTotalP99(v) => Metric2::EditionCommitTotalP99(v),
+            Metric::EditionCommitTotalMax(v) => Metric2::EditionCommitTotalMax(v),
             Metric::EditionDeleteQueryP95(v) => Metric2::EditionDeleteQueryP95(v),
             Metric::EditionDeleteQueryP99(v) => Metric2::EditionDeleteQueryP99(v),
             Metric::EditionDeleteQueryMax(v) => Metric2::EditionDeleteQueryMax(v),
@@ -647,24 +1038,54 @@ impl From<Metric> for Metric2 {
             Metric::EventDeleteQueryP95(v) => Metric2::EventDeleteQueryP95(v),
             Metric::EventDeleteQueryP99(v) => Metric2::EventDeleteQueryP99(v),
             Metric::EventDeleteQueryMax(v) => Metric2::EventDeleteQueryMax(v),
+            Metric::EventMoveQueryP95(v) => Metric2::EventMoveQueryP95(v),
+            Metric::EventMoveQueryP99(v) => Metric2::EventMoveQueryP99(v),
+            Metric::EventMoveQueryMax(v) => Metric2::EventMoveQueryMax(v),
+            Metric::EventSetLabelingQueryP95(v) => Metric2::EventSetLabelingQueryP95(v),
+            Metric::EventSetLabelingQueryP99(v) => Metric2::EventSetLabelingQueryP99(v),
+            Metric::EventSetLabelingQueryMax(v) => Metric2::EventSetLabelingQueryMax(v),
+            Metric::EventFindQueryP95(v) => Metric2::EventFindQueryP95(v),
+            Metric::EventFindQueryP99(v) => Metric2::EventFindQueryP99(v),
+            Metric::EventFindQueryMax(v) => Metric2::EventFindQueryMax(v),
             Metric::EventDumpQueryP95(v) => Metric2::EventDumpQueryP95(v),
             Metric::EventDumpQueryP99(v) => Metric2::EventDumpQueryP99(v),
             Metric::EventDumpQueryMax(v) => Metric2::EventDumpQueryMax(v),
             Metric::EventInsertQueryP95(v) => Metric2::EventInsertQueryP95(v),
             Metric::EventInsertQueryP99(v) => Metric2::EventInsertQueryP99(v),
             Metric::EventInsertQueryMax(v) => Metric2::EventInsertQueryMax(v),
+            Metric::EventLabelLockQueryP95(v) => Metric2::EventLabelLockQueryP95(v),
+            Metric::EventLabelLockQueryP99(v) => Metric2::EventLabelLockQueryP99(v),
+            Metric::EventLabelLockQueryMax(v) => Metric2::EventLabelLockQueryMax(v),
+            Metric::EventLatestVersionQueryP95(v) => Metric2::EventLatestVersionQueryP95(v),
+            Metric::EventLatestVersionQueryP99(v) => Metric2::EventLatestVersionQueryP99(v),
+            Metric::EventLatestVersionQueryMax(v) => Metric2::EventLatestVersionQueryMax(v),
             Metric::EventListQueryP95(v) => Metric2::EventListQueryP95(v),
             Metric::EventListQueryP99(v) => Metric2::EventListQueryP99(v),
             Metric::EventListQueryMax(v) => Metric2::EventListQueryMax(v),
+            Metric::EventListRowsP95(v) => Metric2::EventListRowsP95(v),
+            Metric::EventListRowsP99(v) => Metric2::EventListRowsP99(v),
+            Metric::EventListRowsMax(v) => Metric2::EventListRowsMax(v),
+            Metric::EventListMultiQueryP95(v) => Metric2::EventListMultiQueryP95(v),
+            Metric::EventListMultiQueryP99(v) => Metric2::EventListMultiQueryP99(v),
+            Metric::EventListMultiQueryMax(v) => Metric2::EventListMultiQueryMax(v),
+            Metric::EventCountQueryP95(v) => Metric2::EventCountQueryP95(v),
+            Metric::EventCountQueryP99(v) => Metric2::EventCountQueryP99(v),
+            Metric::EventCountQueryMax(v) => Metric2::EventCountQueryMax(v),
             Metric::EventOriginalQueryP95(v) => Metric2::EventOriginalQueryP95(v),
             Metric::EventOriginalQueryP99(v) => Metric2::EventOriginalQueryP99(v),
             Metric::EventOriginalQueryMax(v) => Metric2::EventOriginalQueryMax(v),
             Metric::EventVacuumQueryP95(v) => Metric2::EventVacuumQueryP95(v),
             Metric::EventVacuumQueryP99(v) => Metric2::EventVacuumQueryP99(v),
             Metric::EventVacuumQueryMax(v) => Metric2::EventVacuumQueryMax(v),
+            Metric::EventCompactQueryP95(v) => Metric2::EventCompactQueryP95(v),
+            Metric::EventCompactQueryP99(v) => Metric2::EventCompactQueryP99(v),
+            Metric::EventCompactQueryMax(v) => Metric2::EventCompactQueryMax(v),
             Metric::RoomAdjustCloneEventsQueryP95(v) => Metric2::RoomAdjustCloneEventsQueryP95(v),
             Metric::RoomAdjustCloneEventsQueryP99(v) => Metric2::RoomAdjustCloneEventsQueryP99(v),
             Metric::RoomAdjustCloneEventsQueryMax(v) => Metric2::RoomAdjustCloneEventsQueryMax(v),
+            Metric::RoomAdjustTxnCommitP95(v) => Metric2::RoomAdjustTxnCommitP95(v),
+            Metric::RoomAdjustTxnCommitP99(v) => Metric2::RoomAdjustTxnCommitP99(v),
+            Metric::RoomAdjustTxnCommitMax(v) => Metric2::RoomAdjustTxnCommitMax(v),
             Metric::RoomFindQueryP95(v) => Metric2::RoomFindQueryP95(v),
             Metric::RoomFindQueryP99(v) => Metric2::RoomFindQueryP99(v),
             Metric::RoomFindQueryMax(v) => Metric2::RoomFindQueryMax(v),
@@ -674,16 +1095,41 @@ impl From<Metric> for Metric2 {
             Metric::RoomUpdateQueryP95(v) => Metric2::RoomUpdateQueryP95(v),
             Metric::RoomUpdateQueryP99(v) => Metric2::RoomUpdateQueryP99(v),
             Metric::RoomUpdateQueryMax(v) => Metric2::RoomUpdateQueryMax(v),
+            Metric::RoomUpdateTagsQueryP95(v) => Metric2::RoomUpdateTagsQueryP95(v),
+            Metric::RoomUpdateTagsQueryP99(v) => Metric2::RoomUpdateTagsQueryP99(v),
+            Metric::RoomUpdateTagsQueryMax(v) => Metric2::RoomUpdateTagsQueryMax(v),
+            Metric::RoomCloseExpiredQueryP95(v) => Metric2::RoomCloseExpiredQueryP95(v),
+            Metric::RoomCloseExpiredQueryP99(v) => Metric2::RoomCloseExpiredQueryP99(v),
+            Metric::RoomCloseExpiredQueryMax(v) => Metric2::RoomCloseExpiredQueryMax(v),
+            Metric::RoomDerivedListQueryP95(v) => Metric2::RoomDerivedListQueryP95(v),
+            Metric::RoomDerivedListQueryP99(v) => Metric2::RoomDerivedListQueryP99(v),
+            Metric::RoomDerivedListQueryMax(v) => Metric2::RoomDerivedListQueryMax(v),
+            Metric::RoomStatsQueryP95(v) => Metric2::RoomStatsQueryP95(v),
+            Metric::RoomStatsQueryP99(v) => Metric2::RoomStatsQueryP99(v),
+            Metric::RoomStatsQueryMax(v) => Metric2::RoomStatsQueryMax(v),
             Metric::StateTotalCountQueryP95(v) => Metric2::StateTotalCountQueryP95(v),
             Metric::StateTotalCountQueryP99(v) => Metric2::StateTotalCountQueryP99(v),
             Metric::StateTotalCountQueryMax(v) => Metric2::StateTotalCountQueryMax(v),
             Metric::StateQueryP95(v) => Metric2::StateQueryP95(v),
             Metric::StateQueryP99(v) => Metric2::StateQueryP99(v),
             Metric::StateQueryMax(v) => Metric2::StateQueryMax(v),
+            Metric::StateRowsP95(v) => Metric2::StateRowsP95(v),
+            Metric::StateRowsP99(v) => Metric2::StateRowsP99(v),
+            Metric::StateRowsMax(v) => Metric2::StateRowsMax(v),
+            Metric::StateSnapshotQueryP95(v) => Metric2::StateSnapshotQueryP95(v),
+            Metric::StateSnapshotQueryP99(v) => Metric2::StateSnapshotQueryP99(v),
+            Metric::StateSnapshotQueryMax(v) => Metric2::StateSnapshotQueryMax(v),
+            Metric::HandlerQueueWaitP95(v) => Metric2::HandlerQueueWaitP95(v),
+            Metric::HandlerQueueWaitP99(v) => Metric2::HandlerQueueWaitP99(v),
+            Metric::HandlerQueueWaitMax(v) => Metric2::HandlerQueueWaitMax(v),
             Metric::RunningRequests(v) => Metric2::RunningRequests(v),
             Metric::RunningRequestDurationP95(v) => Metric2::RunningRequestDurationP95(v),
             Metric::RunningRequestDurationP99(v) => Metric2::RunningRequestDurationP99(v),
             Metric::RunningRequestDurationMax(v) => Metric2::RunningRequestDurationMax(v),
+            Metric::FullListRequests(v) => Metric2::FullListRequests(v),
+            Metric::UnknownLabelEvents(v) => Metric2::UnknownLabelEvents(v),
+            Metric::OversizedResponses(v) => Metric2::OversizedResponses(v),
+            Metric::ProfilerHealthy(v) => Metric2::ProfilerHealthy(v),
         }
     }
 }
@@ -697,6 +1143,8 @@ pub enum ProfilerKeys {
     AgentInsertQuery,
     AgentListQuery,
     AgentUpdateQuery,
+    AuditLogInsertQuery,
+    AuditLogListQuery,
     BanDeleteQuery,
     BanFindQuery,
     BanInsertQuery,
@@ -704,22 +1152,44 @@ pub enum ProfilerKeys {
     ChangeFindWithRoomQuery,
     ChangeInsertQuery,
     ChangeListQuery,
+    ChangeCountQuery,
+    ChangeDanglingListQuery,
     EditionCloneEventsQuery,
+    EditionEstimateQuery,
+    EditionCommitQuery,
     EditionCommitTxnCommit,
+    EditionCommitTotal,
     EditionDeleteQuery,
     EditionFindWithRoomQuery,
     EditionInsertQuery,
     EditionListQuery,
     EventDeleteQuery,
+    EventMoveQuery,
+    EventSetLabelingQuery,
+    EventFindQuery,
     EventDumpQuery,
     EventInsertQuery,
+    EventLabelLockQuery,
+    EventLatestVersionQuery,
     EventListQuery,
+    EventListRows,
+    EventListMultiQuery,
+    EventCountQuery,
     EventOriginalEventQuery,
     EventVacuumQuery,
+    EventCompactQuery,
     RoomAdjustCloneEventsQuery,
+    RoomAdjustTxnCommit,
     RoomFindQuery,
     RoomInsertQuery,
     RoomUpdateQuery,
+    RoomUpdateTagsQuery,
+    RoomCloseExpiredQuery,
+    RoomDerivedListQuery,
+    RoomStatsQuery,
     StateTotalCountQuery,
     StateQuery,
+    StateRows,
+    StateSnapshotQuery,
+    HandlerQueueWait,
 }
@@ -1,7 +1,13 @@
 pub(crate) use collector::Collector;
+pub(crate) use full_list_counter::FullListCounter;
 pub(crate) use metric::{Metric, Metric2, MetricValue, ProfilerKeys, Tags};
+pub(crate) use oversized_response_counter::OversizedResponseCounter;
 pub(crate) use stats_route::StatsRoute;
+pub(crate) use unknown_label_counter::UnknownLabelCounter;
 
 mod collector;
+mod full_list_counter;
 mod metric;
+mod oversized_response_counter;
 mod stats_route;
+mod unknown_label_counter;
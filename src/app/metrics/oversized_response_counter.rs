@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Counts requests rejected because the response would exceed the size limit keyed by method.
+#[derive(Debug, Default)]
+pub(crate) struct OversizedResponseCounter {
+    counters: Mutex<HashMap<String, u64>>,
+}
+
+impl OversizedResponseCounter {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn increment(&self, method: &str) {
+        let mut counters = self
+            .counters
+            .lock()
+            .expect("Oversized response counter mutex poisoned");
+
+        *counters.entry(method.to_owned()).or_insert(0) += 1;
+    }
+
+    /// Returns `(method, count)` pairs collected since the previous flush.
+    pub(crate) fn flush(&self) -> Vec<(String, u64)> {
+        let mut counters = self
+            .counters
+            .lock()
+            .expect("Oversized response counter mutex poisoned");
+
+        counters.drain().collect()
+    }
+}
@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Counts incoming events that no handler is routed for keyed by their label.
+#[derive(Debug, Default)]
+pub(crate) struct UnknownLabelCounter {
+    counters: Mutex<HashMap<String, u64>>,
+}
+
+impl UnknownLabelCounter {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn increment(&self, label: &str) {
+        let mut counters = self
+            .counters
+            .lock()
+            .expect("Unknown label counter mutex poisoned");
+
+        *counters.entry(label.to_owned()).or_insert(0) += 1;
+    }
+
+    /// Returns `(label, count)` pairs collected since the previous flush.
+    pub(crate) fn flush(&self) -> Vec<(String, u64)> {
+        let mut counters = self
+            .counters
+            .lock()
+            .expect("Unknown label counter mutex poisoned");
+
+        counters.drain().collect()
+    }
+}
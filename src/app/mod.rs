@@ -19,10 +19,11 @@ use svc_authz::cache::{AuthzCache, ConnectionPool as RedisConnectionPool};
 use svc_error::{extension::sentry, Error as SvcError};
 
 use crate::app::context::GlobalContext;
+use crate::app::handler_gate::HandlerGate;
 use crate::app::metrics::StatsRoute;
 use crate::config::{self, Config, KruonisConfig};
 use context::AppContextBuilder;
-use message_handler::MessageHandler;
+use message_handler::{publish_message, MessageHandler};
 
 pub(crate) const API_VERSION: &str = "v1";
 
@@ -36,6 +37,7 @@ pub(crate) async fn run(
 ) -> Result<()> {
     // Config
     let config = config::load().context("Failed to load config")?;
+    config.topics.validate().context("Invalid topics config")?;
     info!(crate::LOG, "App config: {:?}", config);
 
     // Agent
@@ -105,24 +107,30 @@ pub(crate) async fn run(
 
     let running_requests = Arc::new(AtomicI64::new(0));
 
+    // Messages from background tasks get published outside of the message handler.
+    let (outgoing_tx, mut outgoing_rx) = futures_channel::mpsc::unbounded();
+    let mut outgoing_agent = agent.clone();
+
+    task::spawn(async move {
+        while let Some(message) = outgoing_rx.next().await {
+            if let Err(err) = publish_message(&mut outgoing_agent, message) {
+                error!(crate::LOG, "Failed to publish outgoing message: {}", err);
+            }
+        }
+    });
+
     let context = context_builder
         .queue_counter(agent.get_queue_counter())
         .running_requests(running_requests.clone())
+        .outgoing_message_sender(outgoing_tx)
         .build();
 
-    let profiler = context.profiler();
-    let (handler_timer_tx, handler_timer_rx) = crossbeam_channel::bounded(500);
-    std::thread::Builder::new()
-        .name("msg-handler-timings".into())
-        .spawn(move || {
-            for (dur, method) in handler_timer_rx {
-                profiler.record_future_time(dur, method);
-            }
-        })
-        .expect("Failed to start msg-handler-timings thread");
+    let handler_gate = config
+        .max_concurrent_handlers
+        .map(|permits| Arc::new(HandlerGate::new(permits, context.profiler())));
 
     // Message handler
-    let message_handler = Arc::new(MessageHandler::new(agent, context, handler_timer_tx));
+    let message_handler = Arc::new(MessageHandler::new(agent, context));
     StatsRoute::start(config, message_handler.clone());
 
     // Message loop
@@ -137,10 +145,16 @@ pub(crate) async fn run(
         if let Ok(Some(message)) = fut.await {
             let message_handler = message_handler.clone();
             let running_requests_ = running_requests.clone();
+            let handler_gate = handler_gate.clone();
 
             task::spawn(async move {
                 match message {
                     AgentNotification::Message(ref message, _) => {
+                        let _permit = match handler_gate {
+                            Some(ref gate) => Some(gate.acquire().await),
+                            None => None,
+                        };
+
                         running_requests_.fetch_add(1, Ordering::SeqCst);
                         message_handler.handle(message).await;
                         running_requests_.fetch_add(-1, Ordering::SeqCst);
@@ -238,7 +252,10 @@ fn resubscribe(agent: &mut Agent, agent_id: &AgentId, config: &Config) {
 pub(crate) mod context;
 pub(crate) mod endpoint;
 pub(crate) mod error;
+pub(crate) mod handler_gate;
 pub(crate) mod message_handler;
 pub(crate) mod metrics;
 pub(crate) mod operations;
+pub(crate) mod rate_limiter;
 pub(crate) mod s3_client;
+pub(crate) mod task_registry;
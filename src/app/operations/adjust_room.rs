@@ -8,7 +8,8 @@ use sqlx::postgres::{PgConnection, PgPool as Db};
 use crate::app::metrics::ProfilerKeys;
 use crate::db::adjustment::{InsertQuery as AdjustmentInsertQuery, Segments};
 use crate::db::event::{
-    DeleteQuery as EventDeleteQuery, ListQuery as EventListQuery, Object as Event,
+    occurred_at_from_duration, DeleteQuery as EventDeleteQuery, ListQuery as EventListQuery,
+    Object as Event,
 };
 use crate::db::room::{InsertQuery as RoomInsertQuery, Object as Room};
 use crate::db::room_time::RoomTimeBound;
@@ -25,6 +26,7 @@ pub(crate) async fn call(
     started_at: DateTime<Utc>,
     segments: &Segments,
     offset: i64,
+    max_cut_gaps: usize,
 ) -> Result<(Room, Room, Segments)> {
     info!(
         crate::LOG,
@@ -46,10 +48,12 @@ pub(crate) async fn call(
     }
 
     // Create adjustment.
-    let mut conn = db
-        .acquire()
+    // Everything goes in a single transaction so a failed or cancelled adjustment
+    // doesn't leave partially filled rooms behind.
+    let mut txn = db
+        .begin()
         .await
-        .context("Failed to acquire db connection")?;
+        .context("Failed to begin sqlx db transaction")?;
 
     let time = real_time_room
         .time()
@@ -75,7 +79,7 @@ pub(crate) async fn call(
         profiler
             .measure(
                 (ProfilerKeys::RoomUpdateQuery, Some("room.adjust".into())),
-                query.execute(&mut conn),
+                query.execute(&mut txn),
             )
             .await
             .with_context(|| {
@@ -99,7 +103,7 @@ pub(crate) async fn call(
                 ProfilerKeys::AdjustmentInsertQuery,
                 Some("room.adjust".into()),
             ),
-            query.execute(&mut conn),
+            query.execute(&mut txn),
         )
         .await
         .with_context(|| {
@@ -112,12 +116,11 @@ pub(crate) async fn call(
     ///////////////////////////////////////////////////////////////////////////
 
     // Get room opening time and duration.
-    let (room_opening, room_duration) = match real_time_room_new_time.end() {
-        RoomTimeBound::Excluded(stop) => {
-            let start = real_time_room_new_time.start();
-            (*start, stop.signed_duration_since(*start))
-        }
-        _ => bail!("invalid duration for room = '{}'", real_time_room.id()),
+    let room_opening = *real_time_room_new_time.start();
+
+    let room_duration = match real_time_room_new_time.duration() {
+        Some(duration) => duration,
+        None => bail!("invalid duration for room = '{}'", real_time_room.id()),
     };
 
     /*let (room_opening, room_duration) = match real_time_room.time() {
@@ -145,10 +148,10 @@ pub(crate) async fn call(
     let segment_gaps = invert_segments(&nano_segments, room_duration)?;
 
     // Create original room with events shifted according to segments.
-    let original_room = create_room(&mut conn, profiler, &real_time_room, started_at).await?;
+    let original_room = create_room(&mut txn, profiler, &real_time_room, started_at).await?;
 
     clone_events(
-        &mut conn,
+        &mut txn,
         profiler,
         &original_room,
         &segment_gaps,
@@ -166,7 +169,7 @@ pub(crate) async fn call(
     let cut_events = profiler
         .measure(
             (ProfilerKeys::EventListQuery, Some("room.adjust".into())),
-            query.execute(&mut conn),
+            query.execute(&mut txn),
         )
         .await
         .with_context(|| {
@@ -178,9 +181,18 @@ pub(crate) async fn call(
 
     let cut_gaps = cut_events_to_gaps(&cut_events)?;
 
+    if cut_gaps.len() > max_cut_gaps {
+        bail!(
+            "too many cut gaps for room_id = '{}': {}, max_cut_gaps = {}",
+            original_room.id(),
+            cut_gaps.len(),
+            max_cut_gaps
+        );
+    }
+
     // Create modified room with events shifted again according to cut events this time.
-    let modified_room = create_room(&mut conn, profiler, &original_room, started_at).await?;
-    clone_events(&mut conn, profiler, &modified_room, &cut_gaps, 0).await?;
+    let modified_room = create_room(&mut txn, profiler, &original_room, started_at).await?;
+    clone_events(&mut txn, profiler, &modified_room, &cut_gaps, 0).await?;
 
     // Delete cut events from the modified room.
     let query = EventDeleteQuery::new(modified_room.id(), "stream");
@@ -188,7 +200,7 @@ pub(crate) async fn call(
     profiler
         .measure(
             (ProfilerKeys::EventDeleteQuery, Some("room.adjust".into())),
-            query.execute(&mut conn),
+            query.execute(&mut txn),
         )
         .await
         .with_context(|| {
@@ -208,18 +220,28 @@ pub(crate) async fn call(
     let total_segments_duration = Duration::milliseconds(total_segments_millis);
 
     // Calculate modified segments by inverting cut gaps limited by total initial segments duration.
-    let modified_segments = invert_segments(&cut_gaps, total_segments_duration)?
-        .into_iter()
-        .map(|(start, stop)| {
-            (
-                Bound::Included(cmp::max(start / NANOSECONDS_IN_MILLISECOND, 0)),
-                Bound::Excluded(stop / NANOSECONDS_IN_MILLISECOND),
-            )
-        })
-        .collect::<Vec<(Bound<i64>, Bound<i64>)>>();
+    let mut segments_builder = Segments::builder().merge_overlapping();
+
+    for (start, stop) in invert_segments(&cut_gaps, total_segments_duration)? {
+        segments_builder = segments_builder.push(
+            cmp::max(start / NANOSECONDS_IN_MILLISECOND, 0),
+            stop / NANOSECONDS_IN_MILLISECOND,
+        )?;
+    }
 
     ///////////////////////////////////////////////////////////////////////////
 
+    profiler
+        .measure(
+            (
+                ProfilerKeys::RoomAdjustTxnCommit,
+                Some("room.adjust".into()),
+            ),
+            txn.commit(),
+        )
+        .await
+        .context("Failed to commit room adjustment transaction")?;
+
     // Done.
     info!(
         crate::LOG,
@@ -228,11 +250,7 @@ pub(crate) async fn call(
         (Utc::now() - start_timestamp).num_milliseconds()
     );
 
-    Ok((
-        original_room,
-        modified_room,
-        Segments::from(modified_segments),
-    ))
+    Ok((original_room, modified_room, segments_builder.build()))
 }
 
 /// Creates a derived room from the source room.
@@ -305,7 +323,18 @@ async fn clone_events(
                 FROM gap_starts, gap_stops
                 WHERE gap_stops.row_number = gap_starts.row_number
             )
-        INSERT INTO event (id, room_id, kind, set, label, data, occurred_at, created_by, created_at)
+        INSERT INTO event (
+            id,
+            room_id,
+            kind,
+            set,
+            label,
+            data,
+            occurred_at,
+            source_occurred_at,
+            created_by,
+            created_at
+        )
         SELECT
             id,
             room_id,
@@ -315,6 +344,7 @@ async fn clone_events(
             data,
             -- Monotonization
             occurred_at + ROW_NUMBER() OVER (PARTITION BY occurred_at ORDER BY created_at) - 1,
+            source_occurred_at,
             created_by,
             created_at
         FROM (
@@ -334,6 +364,7 @@ async fn clone_events(
                     AND   start > 0
                 )
                 END + $4 AS occurred_at,
+                occurred_at AS source_occurred_at,
                 created_by,
                 created_at
             FROM event
@@ -371,8 +402,14 @@ pub(crate) fn invert_segments(
     segments: &[(i64, i64)],
     room_duration: Duration,
 ) -> Result<Vec<(i64, i64)>> {
+    // Zero-length segments don't cover anything so skip them not to produce degenerate gaps.
+    let segments = segments
+        .iter()
+        .filter(|(start, stop)| start < stop)
+        .collect::<Vec<_>>();
+
     if segments.is_empty() {
-        let total_nanos = room_duration.num_nanoseconds().unwrap_or(std::i64::MAX);
+        let total_nanos = occurred_at_from_duration(room_duration);
         return Ok(vec![(0, total_nanos)]);
     }
 
@@ -387,12 +424,14 @@ pub(crate) fn invert_segments(
 
     // Gaps between segments.
     for ((_, segment_stop), (next_segment_start, _)) in segments.iter().zip(&segments[1..]) {
-        gaps.push((*segment_stop, *next_segment_start));
+        if segment_stop < next_segment_start {
+            gaps.push((*segment_stop, *next_segment_start));
+        }
     }
 
     // A possible gap after the last segment.
     if let Some((_, last_segment_stop)) = segments.last() {
-        let room_duration_nanos = room_duration.num_nanoseconds().unwrap_or(std::i64::MAX);
+        let room_duration_nanos = occurred_at_from_duration(room_duration);
 
         if *last_segment_stop < room_duration_nanos {
             gaps.push((*last_segment_stop, room_duration_nanos));
@@ -421,7 +460,10 @@ pub(crate) fn cut_events_to_gaps(cut_events: &[Event]) -> Result<Vec<(i64, i64)>
                 state = CutEventsToGapsState::Started(event.occurred_at());
             }
             (Some("stop"), CutEventsToGapsState::Started(start)) => {
-                gaps.push((start, event.occurred_at()));
+                if start < event.occurred_at() {
+                    gaps.push((start, event.occurred_at()));
+                }
+
                 state = CutEventsToGapsState::Stopped;
             }
             _ => bail!(
@@ -448,6 +490,7 @@ mod tests {
     use svc_agent::{AccountId, AgentId};
 
     use crate::app::metrics::ProfilerKeys;
+    use crate::config::DEFAULT_MAX_CUT_GAPS;
     use crate::db::adjustment::Segments;
     use crate::db::event::{
         InsertQuery as EventInsertQuery, ListQuery as EventListQuery, Object as Event,
@@ -496,6 +539,7 @@ mod tests {
                 started_at,
                 &segments,
                 3000 as i64,
+                DEFAULT_MAX_CUT_GAPS,
             )
             .await
             .expect("Room adjustment failed");
@@ -572,6 +616,7 @@ mod tests {
                 started_at,
                 &segments,
                 3000 as i64,
+                DEFAULT_MAX_CUT_GAPS,
             )
             .await
             .expect("Room adjustment failed");
@@ -611,6 +656,56 @@ mod tests {
         });
     }
 
+    #[test]
+    fn adjust_room_with_too_many_cut_gaps() {
+        async_std::task::block_on(async {
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+            let mut conn = db.get_conn().await;
+
+            // Create room.
+            let opened_at = DateTime::from_utc(NaiveDateTime::from_timestamp(1582002673, 0), Utc);
+            let closed_at = opened_at + Duration::seconds(50);
+            let time = RoomTime::from((Bound::Included(opened_at), Bound::Excluded(closed_at)));
+
+            let room = RoomInsertQuery::new(AUDIENCE, time)
+                .execute(&mut conn)
+                .await
+                .expect("Failed to insert room");
+
+            // There are two cut pairs among the events.
+            create_events(&mut conn, &room).await;
+
+            drop(conn);
+
+            let segments = Segments::from(vec![(Bound::Included(0), Bound::Excluded(11500))]);
+            let started_at = opened_at + Duration::seconds(10);
+
+            let err = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &room,
+                started_at,
+                &segments,
+                3000 as i64,
+                1,
+            )
+            .await
+            .expect_err("Room adjustment succeeded with too many cut gaps");
+
+            assert!(err.to_string().contains("too many cut gaps"));
+        });
+    }
+
+    #[test]
+    fn invert_segments_with_zero_length_gaps() {
+        let gaps = vec![(1000, 1000), (2000, 3000), (3000, 4000), (5000, 5000)];
+        let segments = super::invert_segments(&gaps, Duration::nanoseconds(6000))
+            .expect("Failed to invert segments");
+
+        assert_eq!(segments, vec![(0, 2000), (4000, 6000)]);
+    }
+
     async fn assert_events_original_room(mut conn: &mut PgConnection, original_room: &Room) {
         let events = EventListQuery::new()
             .room_id(original_room.id())
@@ -637,6 +732,9 @@ mod tests {
             &json!({"message": "m2"}),
         );
 
+        // The timestamp in the source room is kept to map the shifted timeline back.
+        assert_eq!(events[1].source_occurred_at(), Some(12_000_000_000));
+
         assert_event(
             &events[2],
             // 13e9 - 10e9 (rtc offset) + 3e9 (offset)
@@ -1,14 +1,19 @@
-use std::ops::Bound;
-
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{Duration, Utc};
+use serde_derive::Serialize;
+use serde_json::Value as JsonValue;
 use sqlx::postgres::{PgConnection, PgPool as Db};
+use uuid::Uuid;
 
 use crate::app::metrics::ProfilerKeys;
 use crate::app::operations::adjust_room::{invert_segments, NANOSECONDS_IN_MILLISECOND};
+use crate::config::CutConfig;
 use crate::db::adjustment::Segments;
-use crate::db::change::{ListQuery as ChangeListQuery, Object as Change};
-use crate::db::edition::Object as Edition;
+use crate::db::change::{
+    CountQuery as ChangeCountQuery, DanglingListQuery as ChangeDanglingListQuery, Object as Change,
+    OccurredAtListQuery as ChangeOccurredAtListQuery,
+};
+use crate::db::edition::{CommitQuery as EditionCommitQuery, Object as Edition};
 use crate::db::event::{
     DeleteQuery as EventDeleteQuery, ListQuery as EventListQuery, Object as Event,
 };
@@ -18,11 +23,17 @@ use crate::profiler::Profiler;
 
 ////////////////////////////////////////////////////////////////////////////////
 
+const CUT_CHANGES_PAGE_SIZE: usize = 1000;
+
 pub(crate) async fn call(
     db: &Db,
     profiler: &Profiler<(ProfilerKeys, Option<String>)>,
     edition: &Edition,
     source: &Room,
+    max_cut_gaps: usize,
+    cut_config: &CutConfig,
+    destination_audience: &str,
+    change_ids: Option<&[Uuid]>,
 ) -> Result<(Room, Segments)> {
     info!(
         crate::LOG,
@@ -38,11 +49,8 @@ pub(crate) async fn call(
         .await
         .context("Failed to begin sqlx db transaction")?;
 
-    let room_duration = match source.time() {
-        Ok(t) => match t.end() {
-            RoomTimeBound::Excluded(stop) => stop.signed_duration_since(*t.start()),
-            _ => bail!("invalid duration for room = '{}'", source.id()),
-        },
+    let room_duration = match source.duration() {
+        Ok(Some(duration)) => duration,
         _ => bail!("invalid duration for room = '{}'", source.id()),
     };
 
@@ -58,23 +66,29 @@ pub(crate) async fn call(
         .await
         .with_context(|| format!("failed to fetch cut events for room_id = '{}'", source.id()))?;
 
-    let query = ChangeListQuery::new(edition.id()).kind("stream");
-
-    let cut_changes = profiler
-        .measure(
-            (ProfilerKeys::ChangeListQuery, Some("edition.commit".into())),
-            query.execute(&mut txn),
-        )
-        .await
-        .with_context(|| {
-            format!(
-                "failed to fetch cut changes for room_id = '{}'",
-                source.id(),
-            )
-        })?;
+    let cut_gaps = collect_edition_gaps(
+        &mut txn,
+        profiler,
+        edition,
+        change_ids,
+        &cut_events,
+        cut_config,
+        CUT_CHANGES_PAGE_SIZE,
+    )
+    .await
+    .with_context(|| format!("failed to collect cut gaps for room_id = '{}'", source.id()))?;
+
+    if cut_gaps.len() > max_cut_gaps {
+        bail!(
+            "too many cut gaps for room_id = '{}': {}, max_cut_gaps = {}",
+            source.id(),
+            cut_gaps.len(),
+            max_cut_gaps
+        );
+    }
 
-    let cut_gaps = collect_gaps(&cut_events, &cut_changes)?;
-    let destination = clone_room(&mut txn, profiler, &source).await?;
+    let destination =
+        clone_room(&mut txn, profiler, &source, &edition, destination_audience).await?;
 
     clone_events(
         &mut txn,
@@ -82,6 +96,7 @@ pub(crate) async fn call(
         &source,
         &destination,
         &edition,
+        change_ids,
         &cut_gaps,
     )
     .await?;
@@ -104,15 +119,36 @@ pub(crate) async fn call(
             )
         })?;
 
-    let modified_segments = invert_segments(&cut_gaps, room_duration)?
-        .into_iter()
-        .map(|(start, stop)| {
+    let mut segments_builder = Segments::builder().merge_overlapping();
+
+    for (start, stop) in invert_segments(&cut_gaps, room_duration)? {
+        segments_builder = segments_builder.push(
+            start / NANOSECONDS_IN_MILLISECOND,
+            stop / NANOSECONDS_IN_MILLISECOND,
+        )?;
+    }
+
+    let segments = segments_builder.build();
+
+    // Mark the edition as committed in the same transaction so a concurrent or repeated commit
+    // of the same edition rolls back instead of producing another destination room.
+    let query =
+        EditionCommitQuery::new(edition.id(), destination.id(), segments.clone(), change_ids);
+
+    let updated_count = profiler
+        .measure(
             (
-                Bound::Included(start / NANOSECONDS_IN_MILLISECOND),
-                Bound::Excluded(stop / NANOSECONDS_IN_MILLISECOND),
-            )
-        })
-        .collect::<Vec<(Bound<i64>, Bound<i64>)>>();
+                ProfilerKeys::EditionCommitQuery,
+                Some("edition.commit".into()),
+            ),
+            query.execute(&mut txn),
+        )
+        .await
+        .with_context(|| format!("failed to mark edition_id = '{}' committed", edition.id()))?;
+
+    if updated_count != 1 {
+        bail!("edition_id = '{}' has already been committed", edition.id());
+    }
 
     profiler
         .measure(
@@ -131,20 +167,338 @@ pub(crate) async fn call(
         (Utc::now() - start_timestamp).num_milliseconds()
     );
 
-    Ok((destination, Segments::from(modified_segments))) as Result<(Room, Segments)>
+    Ok((destination, segments)) as Result<(Room, Segments)>
+}
+
+/// Problem found by `validate`.
+#[derive(Debug, Serialize)]
+pub(crate) struct ValidationIssue {
+    pub(crate) kind: String,
+    pub(crate) detail: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct ValidationReport {
+    pub(crate) ready: bool,
+    /// Problems making the commit fail.
+    pub(crate) errors: Vec<ValidationIssue>,
+    /// Problems that don't prevent the commit but are likely to be a mistake.
+    pub(crate) warnings: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    fn error(&mut self, kind: &str, detail: String) {
+        self.errors.push(ValidationIssue {
+            kind: kind.to_owned(),
+            detail,
+        });
+    }
+
+    fn warning(&mut self, kind: &str, detail: String) {
+        self.warnings.push(ValidationIssue {
+            kind: kind.to_owned(),
+            detail,
+        });
+    }
+}
+
+/// Runs the checks of `call` against the current state of the edition without writing anything.
+pub(crate) async fn validate(
+    conn: &mut PgConnection,
+    profiler: &Profiler<(ProfilerKeys, Option<String>)>,
+    edition: &Edition,
+    source: &Room,
+    max_cut_gaps: usize,
+    cut_config: &CutConfig,
+) -> Result<ValidationReport> {
+    let mut report = ValidationReport::default();
+
+    if edition.committed().is_some() {
+        report.warning(
+            "already_committed",
+            "Repeated commit returns the result of the first one".to_owned(),
+        );
+    }
+
+    let is_room_time_bounded = match source.time() {
+        Ok(t) => matches!(t.end(), RoomTimeBound::Excluded(_)),
+        Err(_) => false,
+    };
+
+    if !is_room_time_bounded {
+        report.error(
+            "invalid_room_time",
+            format!("Room = '{}' must have a bounded time", source.id()),
+        );
+    }
+
+    let query = EventListQuery::new()
+        .room_id(source.id())
+        .kind("stream".to_string());
+
+    let cut_events = profiler
+        .measure(
+            (
+                ProfilerKeys::EventListQuery,
+                Some("edition.validate".into()),
+            ),
+            query.execute(&mut *conn),
+        )
+        .await
+        .with_context(|| format!("failed to fetch cut events for room_id = '{}'", source.id()))?;
+
+    let query = ChangeCountQuery::new(edition.id());
+
+    let changes_count = profiler
+        .measure(
+            (
+                ProfilerKeys::ChangeCountQuery,
+                Some("edition.validate".into()),
+            ),
+            query.execute(&mut *conn),
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "failed to count changes for edition_id = '{}'",
+                edition.id()
+            )
+        })?;
+
+    if changes_count == 0 && cut_events.is_empty() {
+        report.warning(
+            "empty_edition",
+            "No changes and cuts so the committed room would be a copy of the source".to_owned(),
+        );
+    }
+
+    let gaps_result = collect_edition_gaps(
+        conn,
+        profiler,
+        edition,
+        None,
+        &cut_events,
+        cut_config,
+        CUT_CHANGES_PAGE_SIZE,
+    )
+    .await;
+
+    match gaps_result {
+        Ok(gaps) if gaps.len() > max_cut_gaps => report.error(
+            "too_many_cut_gaps",
+            format!("{} cut gaps, max_cut_gaps = {}", gaps.len(), max_cut_gaps),
+        ),
+        Ok(_) => (),
+        Err(err) => report.error("invalid_cuts", err.to_string()),
+    }
+
+    let query = ChangeDanglingListQuery::new(edition.id(), source.id());
+
+    let dangling_change_ids = profiler
+        .measure(
+            (
+                ProfilerKeys::ChangeDanglingListQuery,
+                Some("edition.validate".into()),
+            ),
+            query.execute(&mut *conn),
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "failed to fetch dangling changes for edition_id = '{}'",
+                edition.id()
+            )
+        })?;
+
+    for change_id in dangling_change_ids {
+        report.error(
+            "missing_change_event",
+            format!(
+                "Change = '{}' references an event missing in the room",
+                change_id
+            ),
+        );
+    }
+
+    report.ready = report.errors.is_empty();
+    Ok(report)
+}
+
+/// Counts events the commit would produce in the destination room without inserting them.
+/// Cut gaps only shift `occurred_at` so they don't affect the count
+/// while stream events get deleted after cloning so they're not counted.
+pub(crate) async fn estimate(
+    conn: &mut PgConnection,
+    profiler: &Profiler<(ProfilerKeys, Option<String>)>,
+    edition: &Edition,
+    source: &Room,
+) -> Result<i64> {
+    let query = sqlx::query!(
+        "
+        SELECT COUNT(*) AS \"count!: i64\"
+        FROM (
+            SELECT
+                (CASE change.kind
+                        WHEN 'addition' THEN change.event_kind
+                        WHEN 'modification' THEN COALESCE(change.event_kind, event.kind)
+                        ELSE event.kind
+                    END
+                ) AS kind
+            FROM
+                (SELECT * FROM event WHERE event.room_id = $1 AND deleted_at IS NULL)
+                AS event
+                FULL OUTER JOIN
+                (SELECT * FROM change WHERE change.edition_id = $2)
+                AS change
+                ON change.event_id = event.id
+            WHERE
+                ((event.room_id = $1 AND deleted_at IS NULL) OR event.id IS NULL)
+                AND
+                ((change.edition_id = $2 AND change.kind <> 'removal') OR change.id IS NULL)
+        ) AS subquery
+        WHERE kind <> 'stream'
+        ",
+        source.id(),
+        edition.id(),
+    );
+
+    let row = profiler
+        .measure(
+            (
+                ProfilerKeys::EditionEstimateQuery,
+                Some("edition.estimate".into()),
+            ),
+            query.fetch_one(conn),
+        )
+        .await
+        .with_context(|| format!("failed to estimate edition_id = '{}'", edition.id()))?;
+
+    Ok(row.count)
+}
+
+/// Gaps made by the room's own cut events and the segments left between them
+/// in the same milliseconds format as committed segments. Edition changes are not applied.
+pub(crate) fn cut_segments(
+    cut_events: &[Event],
+    room_duration: Duration,
+    cut_config: &CutConfig,
+) -> Result<(Segments, Segments)> {
+    let cuts = cut_events
+        .iter()
+        .map(|event| Cut::from_event(event, &cut_config.key));
+
+    let gaps = collect_gaps(cuts, cut_config)?;
+    let mut gaps_builder = Segments::builder().merge_overlapping();
+
+    for (start, stop) in gaps.iter() {
+        gaps_builder = gaps_builder.push(
+            start / NANOSECONDS_IN_MILLISECOND,
+            stop / NANOSECONDS_IN_MILLISECOND,
+        )?;
+    }
+
+    let mut segments_builder = Segments::builder().merge_overlapping();
+
+    for (start, stop) in invert_segments(&gaps, room_duration)? {
+        segments_builder = segments_builder.push(
+            start / NANOSECONDS_IN_MILLISECOND,
+            stop / NANOSECONDS_IN_MILLISECOND,
+        )?;
+    }
+
+    Ok((gaps_builder.build(), segments_builder.build()))
+}
+
+// Merges the room's cut events with the edition's cut changes in `occurred_at` order and folds
+// them into gaps. Changes are loaded page by page with a keyset cursor so only a single page
+// of them is kept in memory besides the events and the resulting gaps.
+async fn collect_edition_gaps(
+    conn: &mut PgConnection,
+    profiler: &Profiler<(ProfilerKeys, Option<String>)>,
+    edition: &Edition,
+    change_ids: Option<&[Uuid]>,
+    cut_events: &[Event],
+    cut_config: &CutConfig,
+    page_size: usize,
+) -> Result<Vec<(i64, i64)>> {
+    let mut event_cuts = cut_events
+        .iter()
+        .map(|event| Cut::from_event(event, &cut_config.key))
+        .collect::<Vec<_>>();
+
+    event_cuts.sort_by_key(|cut| cut.occurred_at);
+
+    let mut event_cuts = event_cuts.into_iter().peekable();
+    let mut collector = GapsCollector::new(cut_config);
+    let mut last = None;
+
+    loop {
+        let mut query =
+            ChangeOccurredAtListQuery::new(edition.id(), "stream").limit(page_size as i64);
+
+        if let Some((last_occurred_at, last_id)) = last {
+            query = query.last(last_occurred_at, last_id);
+        }
+
+        let changes = profiler
+            .measure(
+                (ProfilerKeys::ChangeListQuery, Some("edition.commit".into())),
+                query.execute(conn),
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to fetch cut changes for edition_id = '{}'",
+                    edition.id()
+                )
+            })?;
+
+        for change in changes.iter() {
+            let cut = Cut::from_change(change, &cut_config.key);
+            last = Some((cut.occurred_at, change.id()));
+
+            let is_committed = change_ids
+                .map(|ids| ids.contains(&change.id()))
+                .unwrap_or(true);
+
+            if !is_committed {
+                continue;
+            }
+
+            // Events go first when they occurred at the same time as the change.
+            while let Some(event_cut) = event_cuts.next_if(|c| c.occurred_at <= cut.occurred_at) {
+                collector.push(event_cut)?;
+            }
+
+            collector.push(cut)?;
+        }
+
+        if changes.len() < page_size {
+            break;
+        }
+    }
+
+    for event_cut in event_cuts {
+        collector.push(event_cut)?;
+    }
+
+    Ok(collector.finish())
 }
 
 async fn clone_room(
     conn: &mut PgConnection,
     profiler: &Profiler<(ProfilerKeys, Option<String>)>,
     source: &Room,
+    edition: &Edition,
+    audience: &str,
 ) -> Result<Room> {
     let time = match source.time() {
         Ok(t) => t.to_owned().into(),
         Err(_e) => bail!("invalid time for room = '{}'", source.id()),
     };
-    let mut query = RoomInsertQuery::new(&source.audience(), time);
+    let mut query = RoomInsertQuery::new(audience, time);
     query = query.source_room_id(source.id());
+    query = query.committed_edition(edition.id(), Utc::now());
 
     if let Some(tags) = source.tags() {
         query = query.tags(tags.to_owned());
@@ -165,6 +519,7 @@ async fn clone_events(
     source: &Room,
     destination: &Room,
     edition: &Edition,
+    change_ids: Option<&[Uuid]>,
     gaps: &[(i64, i64)],
 ) -> Result<()> {
     let mut starts = Vec::with_capacity(gaps.len());
@@ -191,7 +546,18 @@ async fn clone_events(
                 FROM gap_starts, gap_stops
                 WHERE gap_stops.row_number = gap_starts.row_number
             )
-        INSERT INTO event (id, room_id, kind, set, label, data, occurred_at, created_by, created_at)
+        INSERT INTO event (
+            id,
+            room_id,
+            kind,
+            set,
+            label,
+            data,
+            occurred_at,
+            source_occurred_at,
+            created_by,
+            created_at
+        )
         SELECT
             id,
             room_id,
@@ -200,6 +566,7 @@ async fn clone_events(
             label,
             data,
             occurred_at + ROW_NUMBER() OVER (partition by occurred_at order by created_at) - 1,
+            source_occurred_at,
             created_by,
             created_at
         FROM (
@@ -242,6 +609,12 @@ async fn clone_events(
                         WHERE start < occurred_at
                     )
                 ) AS occurred_at,
+                (CASE change.kind
+                    WHEN 'addition' THEN change.event_occurred_at
+                    WHEN 'modification' THEN COALESCE(change.event_occurred_at, event.occurred_at)
+                    ELSE event.occurred_at
+                    END
+                ) AS source_occurred_at,
                 (CASE change.kind
                     WHEN 'addition' THEN change.event_created_by
                     ELSE event.created_by
@@ -252,7 +625,11 @@ async fn clone_events(
                 (SELECT * FROM event WHERE event.room_id = $1 AND deleted_at IS NULL)
                 AS event
                 FULL OUTER JOIN
-                (SELECT * FROM change WHERE change.edition_id = $3)
+                (
+                    SELECT * FROM change
+                    WHERE change.edition_id = $3
+                    AND   ($6::UUID[] IS NULL OR change.id = ANY($6))
+                )
                 AS change
                 ON change.event_id = event.id
             WHERE
@@ -266,6 +643,7 @@ async fn clone_events(
         edition.id(),
         starts.as_slice(),
         stops.as_slice(),
+        change_ids,
     );
 
     profiler
@@ -293,82 +671,133 @@ enum CutEventsToGapsState {
     Stopped,
 }
 
-enum EventOrChangeAtDur<'a> {
-    Event(&'a Event, i64),
-    Change(&'a Change, i64),
+#[derive(Clone, Copy, Debug)]
+enum CutCommand {
+    Start,
+    Stop,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum CutSource {
+    Event(Uuid),
+    Change(Uuid),
+}
+
+// A cut start or stop command extracted either from an event or a change.
+#[derive(Debug)]
+struct Cut {
+    source: CutSource,
+    command: Option<String>,
+    occurred_at: i64,
+}
+
+impl Cut {
+    fn from_event(event: &Event, key: &str) -> Self {
+        Self {
+            source: CutSource::Event(event.id()),
+            command: cut_command(event.data(), key),
+            occurred_at: event.occurred_at(),
+        }
+    }
+
+    fn from_change(change: &Change, key: &str) -> Self {
+        let data = change.event_data().as_ref().expect("must have event_data");
+
+        Self {
+            source: CutSource::Change(change.id()),
+            command: cut_command(data, key),
+            occurred_at: change.event_occurred_at().expect("must have occurred_at"),
+        }
+    }
+}
+
+fn cut_command(data: &JsonValue, key: &str) -> Option<String> {
+    data.get(key).and_then(|v| v.as_str()).map(|v| v.to_owned())
 }
 
 // Transforms cut start-stop events and changes into a vec of (start, end) tuples.
-fn collect_gaps(cut_events: &[Event], cut_changes: &[Change]) -> Result<Vec<(i64, i64)>> {
-    let mut cut_vec = vec![];
-    cut_events
-        .iter()
-        .for_each(|ev| cut_vec.push(EventOrChangeAtDur::Event(&ev, ev.occurred_at())));
-
-    cut_changes.iter().for_each(|ch| {
-        cut_vec.push(EventOrChangeAtDur::Change(
-            &ch,
-            ch.event_occurred_at().expect("must have occurred_at"),
-        ))
-    });
-
-    cut_vec.sort_by_key(|v| match v {
-        EventOrChangeAtDur::Event(_, ref k) => *k,
-        EventOrChangeAtDur::Change(_, ref k) => *k,
-    });
-
-    let mut gaps = Vec::with_capacity(cut_events.len());
-    let mut state: CutEventsToGapsState = CutEventsToGapsState::Stopped;
-
-    for cut in cut_vec {
-        let (command, occurred_at) = match cut {
-            EventOrChangeAtDur::Event(ref event, _) => (
-                event.data().get("cut").and_then(|v| v.as_str()),
-                event.occurred_at(),
-            ),
-            EventOrChangeAtDur::Change(ref change, _) => (
-                change
-                    .event_data()
-                    .as_ref()
-                    .expect("must have event_data")
-                    .get("cut")
-                    .and_then(|v| v.as_str()),
-                change.event_occurred_at().expect("must have occurred_at"),
-            ),
+fn collect_gaps<I>(cuts: I, config: &CutConfig) -> Result<Vec<(i64, i64)>>
+where
+    I: IntoIterator<Item = Cut>,
+{
+    let mut cuts = cuts.into_iter().collect::<Vec<_>>();
+    cuts.sort_by_key(|cut| cut.occurred_at);
+
+    let mut collector = GapsCollector::new(config);
+
+    for cut in cuts {
+        collector.push(cut)?;
+    }
+
+    Ok(collector.finish())
+}
+
+// Folds cuts one by one into gaps. Cuts must be pushed in `occurred_at` order.
+struct GapsCollector<'a> {
+    config: &'a CutConfig,
+    state: CutEventsToGapsState,
+    gaps: Vec<(i64, i64)>,
+}
+
+impl<'a> GapsCollector<'a> {
+    fn new(config: &'a CutConfig) -> Self {
+        Self {
+            config,
+            state: CutEventsToGapsState::Stopped,
+            gaps: vec![],
+        }
+    }
+
+    fn push(&mut self, cut: Cut) -> Result<()> {
+        let occurred_at = cut.occurred_at;
+        let command = cut.command.as_deref();
+
+        let cut_command = match command {
+            Some(command) if command == self.config.start => Some(CutCommand::Start),
+            Some(command) if command == self.config.stop => Some(CutCommand::Stop),
+            _ => None,
         };
 
-        match (command, &mut state) {
-            (Some("start"), CutEventsToGapsState::Stopped) => {
-                state = CutEventsToGapsState::Started(occurred_at, 0);
+        match (cut_command, &mut self.state) {
+            (Some(CutCommand::Start), CutEventsToGapsState::Stopped) => {
+                self.state = CutEventsToGapsState::Started(occurred_at, 0);
             }
-            (Some("start"), CutEventsToGapsState::Started(_start, ref mut nest_lvl)) => {
+            (Some(CutCommand::Start), CutEventsToGapsState::Started(_start, ref mut nest_lvl)) => {
                 *nest_lvl += 1;
             }
-            (Some("stop"), CutEventsToGapsState::Started(start, 0)) => {
-                gaps.push((*start, occurred_at));
-                state = CutEventsToGapsState::Stopped;
+            (Some(CutCommand::Stop), CutEventsToGapsState::Started(start, 0)) => {
+                // Drop zero-length gaps since they don't cut anything.
+                if *start < occurred_at {
+                    self.gaps.push((*start, occurred_at));
+                }
+
+                self.state = CutEventsToGapsState::Stopped;
             }
-            (Some("stop"), CutEventsToGapsState::Started(_start, ref mut nest_lvl)) => {
+            (Some(CutCommand::Stop), CutEventsToGapsState::Started(_start, ref mut nest_lvl)) => {
                 *nest_lvl -= 1;
             }
-            _ => match cut {
-                EventOrChangeAtDur::Event(ref event, _) => bail!(
+            _ => match cut.source {
+                CutSource::Event(id) => bail!(
                     "invalid cut event, id = '{}', command = {:?}, state = {:?}",
-                    event.id(),
+                    id,
                     command,
-                    state
+                    self.state
                 ),
-                EventOrChangeAtDur::Change(ref change, _) => bail!(
+                CutSource::Change(id) => bail!(
                     "invalid cut change, id = '{}', command = {:?}, state = {:?}",
-                    change.id(),
+                    id,
                     command,
-                    state
+                    self.state
                 ),
             },
         }
+
+        Ok(())
     }
 
-    Ok(gaps)
+    fn finish(self) -> Vec<(i64, i64)> {
+        self.gaps
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -377,16 +806,19 @@ fn collect_gaps(cut_events: &[Event], cut_changes: &[Change]) -> Result<Vec<(i64
 mod tests {
     use std::ops::Bound;
 
-    use chrono::Duration;
+    use chrono::{Duration, SubsecRound, Utc};
     use serde_json::{json, Value as JsonValue};
     use sqlx::postgres::PgConnection;
     use svc_agent::{AccountId, AgentId};
     use svc_authn::Authenticable;
 
     use crate::app::metrics::ProfilerKeys;
-    use crate::db::change::ChangeType;
+    use crate::config::{CutConfig, DEFAULT_MAX_CUT_GAPS};
+    use crate::db::change::{ChangeType, ListQuery as ChangeListQuery};
     use crate::db::event::{ListQuery as EventListQuery, Object as Event};
-    use crate::db::room::Object as Room;
+    use crate::db::room::{
+        DerivedListQuery as RoomDerivedListQuery, FindQuery as RoomFindQuery, Object as Room,
+    };
     use crate::profiler::Profiler;
     use crate::test_helpers::db::TestDb;
     use crate::test_helpers::prelude::*;
@@ -494,10 +926,18 @@ mod tests {
 
             drop(conn);
 
-            let (destination, segments) =
-                super::call(&db.connection_pool(), &profiler, &edition, &room)
-                    .await
-                    .expect("edition commit failed");
+            let (destination, segments) = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                DEFAULT_MAX_CUT_GAPS,
+                &CutConfig::default(),
+                room.audience(),
+                None,
+            )
+            .await
+            .expect("edition commit failed");
 
             // Assert original room.
             assert_eq!(destination.source_room_id().unwrap(), room.id());
@@ -537,7 +977,7 @@ mod tests {
     }
 
     #[test]
-    fn commit_edition_with_cut_changes() {
+    fn commit_edition_with_custom_cut_config() {
         async_std::task::block_on(async {
             let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
             let db = TestDb::new().await;
@@ -545,48 +985,49 @@ mod tests {
             let mut conn = db.get_conn().await;
             let room = shared_helpers::insert_room(&mut conn).await;
 
+            // Seed events with cut commands stored under an alternate key.
             create_event(
                 &mut conn,
                 &room,
-                2_500_000_000,
+                1_000_000_000,
                 "message",
-                json!({"message": "passthrough"}),
+                json!({"message": "m1"}),
             )
             .await;
 
             create_event(
                 &mut conn,
                 &room,
-                3_500_000_000,
-                "message",
-                json!({"message": "cut out"}),
+                2_000_000_000,
+                "stream",
+                json!({"trim": "begin"}),
             )
             .await;
 
             create_event(
                 &mut conn,
                 &room,
-                4_200_000_000,
-                "stream",
-                json!({"cut": "start"}),
+                3_000_000_000,
+                "message",
+                json!({"message": "m2"}),
             )
             .await;
 
             create_event(
                 &mut conn,
                 &room,
-                4_500_000_000,
-                "message",
-                json!({"message": "some message"}),
+                4_000_000_000,
+                "stream",
+                json!({"trim": "end"}),
             )
             .await;
 
             create_event(
                 &mut conn,
                 &room,
-                4_800_000_000,
-                "stream",
-                json!({"cut": "stop"}),
+                5_000_000_000,
+                "message",
+                json!({"message": "m3"}),
             )
             .await;
 
@@ -594,14 +1035,117 @@ mod tests {
                 .insert(&mut conn)
                 .await;
 
-            factory::Change::new(edition.id(), ChangeType::Addition)
-                .event_data(json!({"cut": "start"}))
-                .event_kind("stream")
-                .event_set("stream")
-                .event_occurred_at(3_000_000_000)
-                .event_created_by(agent.agent_id())
-                .insert(&mut conn)
-                .await;
+            drop(conn);
+
+            let cut_config = CutConfig {
+                key: String::from("trim"),
+                start: String::from("begin"),
+                stop: String::from("end"),
+            };
+
+            let (destination, segments) = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                DEFAULT_MAX_CUT_GAPS,
+                &cut_config,
+                room.audience(),
+                None,
+            )
+            .await
+            .expect("edition commit failed");
+
+            let segments: Vec<(Bound<i64>, Bound<i64>)> = segments.into();
+            assert_eq!(segments.len(), 2);
+
+            let mut conn = db.get_conn().await;
+
+            let events = EventListQuery::new()
+                .room_id(destination.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to fetch events");
+
+            assert_eq!(events.len(), 3);
+
+            assert_eq!(events[0].occurred_at(), 1_000_000_000);
+            assert_eq!(events[0].data()["message"], "m1");
+
+            assert_eq!(events[1].occurred_at(), 2_000_000_001);
+            assert_eq!(events[1].data()["message"], "m2");
+
+            assert_eq!(events[2].occurred_at(), 3_000_000_000);
+            assert_eq!(events[2].data()["message"], "m3");
+        });
+    }
+
+    #[test]
+    fn commit_edition_with_cut_changes() {
+        async_std::task::block_on(async {
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut conn = db.get_conn().await;
+            let room = shared_helpers::insert_room(&mut conn).await;
+
+            create_event(
+                &mut conn,
+                &room,
+                2_500_000_000,
+                "message",
+                json!({"message": "passthrough"}),
+            )
+            .await;
+
+            create_event(
+                &mut conn,
+                &room,
+                3_500_000_000,
+                "message",
+                json!({"message": "cut out"}),
+            )
+            .await;
+
+            create_event(
+                &mut conn,
+                &room,
+                4_200_000_000,
+                "stream",
+                json!({"cut": "start"}),
+            )
+            .await;
+
+            create_event(
+                &mut conn,
+                &room,
+                4_500_000_000,
+                "message",
+                json!({"message": "some message"}),
+            )
+            .await;
+
+            create_event(
+                &mut conn,
+                &room,
+                4_800_000_000,
+                "stream",
+                json!({"cut": "stop"}),
+            )
+            .await;
+
+            let edition = factory::Edition::new(room.id(), agent.agent_id())
+                .insert(&mut conn)
+                .await;
+
+            factory::Change::new(edition.id(), ChangeType::Addition)
+                .event_data(json!({"cut": "start"}))
+                .event_kind("stream")
+                .event_set("stream")
+                .event_occurred_at(3_000_000_000)
+                .event_created_by(agent.agent_id())
+                .insert(&mut conn)
+                .await;
 
             factory::Change::new(edition.id(), ChangeType::Addition)
                 .event_data(json!({"cut": "stop"}))
@@ -614,10 +1158,18 @@ mod tests {
 
             drop(conn);
 
-            let (destination, segments) =
-                super::call(&db.connection_pool(), &profiler, &edition, &room)
-                    .await
-                    .expect("edition commit failed");
+            let (destination, segments) = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                DEFAULT_MAX_CUT_GAPS,
+                &CutConfig::default(),
+                room.audience(),
+                None,
+            )
+            .await
+            .expect("edition commit failed");
 
             // Assert original room.
             assert_eq!(destination.source_room_id().unwrap(), room.id());
@@ -722,10 +1274,18 @@ mod tests {
 
             drop(conn);
 
-            let (destination, segments) =
-                super::call(&db.connection_pool(), &profiler, &edition, &room)
-                    .await
-                    .expect("edition commit failed");
+            let (destination, segments) = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                DEFAULT_MAX_CUT_GAPS,
+                &CutConfig::default(),
+                room.audience(),
+                None,
+            )
+            .await
+            .expect("edition commit failed");
 
             // Assert original room.
             assert_eq!(destination.source_room_id().unwrap(), room.id());
@@ -752,6 +1312,549 @@ mod tests {
         });
     }
 
+    #[test]
+    fn commit_edition_with_zero_length_gap() {
+        async_std::task::block_on(async {
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut conn = db.get_conn().await;
+            let room = shared_helpers::insert_room(&mut conn).await;
+
+            create_event(
+                &mut conn,
+                &room,
+                2_500_000_000,
+                "message",
+                json!({"message": "passthrough"}),
+            )
+            .await;
+
+            create_event(
+                &mut conn,
+                &room,
+                3_000_000_000,
+                "stream",
+                json!({"cut": "start"}),
+            )
+            .await;
+
+            let edition = factory::Edition::new(room.id(), agent.agent_id())
+                .insert(&mut conn)
+                .await;
+
+            factory::Change::new(edition.id(), ChangeType::Addition)
+                .event_data(json!({"cut": "stop"}))
+                .event_kind("stream")
+                .event_set("stream")
+                .event_occurred_at(3_000_000_000)
+                .event_created_by(agent.agent_id())
+                .insert(&mut conn)
+                .await;
+
+            drop(conn);
+
+            let (_destination, segments) = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                DEFAULT_MAX_CUT_GAPS,
+                &CutConfig::default(),
+                room.audience(),
+                None,
+            )
+            .await
+            .expect("edition commit failed");
+
+            let segments: Vec<(Bound<i64>, Bound<i64>)> = segments.into();
+            assert_eq!(segments.len(), 1);
+
+            for segment in segments {
+                match segment {
+                    (Bound::Included(start), Bound::Excluded(stop)) => assert!(start < stop),
+                    _ => panic!("Invalid segment: {:?}", segment),
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn commit_edition_into_another_audience() {
+        async_std::task::block_on(async {
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut conn = db.get_conn().await;
+            let room = shared_helpers::insert_room(&mut conn).await;
+
+            let edition = factory::Edition::new(room.id(), agent.agent_id())
+                .insert(&mut conn)
+                .await;
+
+            drop(conn);
+
+            let (destination, _segments) = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                DEFAULT_MAX_CUT_GAPS,
+                &CutConfig::default(),
+                "publishing.example.org",
+                None,
+            )
+            .await
+            .expect("edition commit failed");
+
+            assert_eq!(destination.source_room_id(), Some(room.id()));
+            assert_eq!(destination.audience(), "publishing.example.org");
+        });
+    }
+
+    #[test]
+    fn commit_edition_provenance() {
+        async_std::task::block_on(async {
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut conn = db.get_conn().await;
+            let room = shared_helpers::insert_room(&mut conn).await;
+
+            let edition = factory::Edition::new(room.id(), agent.agent_id())
+                .insert(&mut conn)
+                .await;
+
+            drop(conn);
+            let started_at = Utc::now().trunc_subsecs(0);
+
+            let (destination, _segments) = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                DEFAULT_MAX_CUT_GAPS,
+                &CutConfig::default(),
+                USR_AUDIENCE,
+                None,
+            )
+            .await
+            .expect("edition commit failed");
+
+            assert_eq!(destination.source_edition_id(), Some(edition.id()));
+
+            // The provenance is stored so it's returned on reading the room as well.
+            let mut conn = db.get_conn().await;
+
+            let destination = RoomFindQuery::new(destination.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to find destination room")
+                .expect("Destination room not found");
+
+            assert_eq!(destination.source_edition_id(), Some(edition.id()));
+
+            let committed_at = destination.committed_at().expect("Missing committed_at");
+            assert!(committed_at >= started_at);
+            assert!(committed_at <= Utc::now());
+
+            let room_json = serde_json::to_value(&destination).expect("Failed to serialize room");
+            assert_eq!(room_json["source_edition_id"], edition.id().to_string());
+            assert_eq!(room_json["committed_at"], committed_at.timestamp());
+        });
+    }
+
+    #[test]
+    fn commit_edition_source_occurred_at() {
+        async_std::task::block_on(async {
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut conn = db.get_conn().await;
+            let room = shared_helpers::insert_room(&mut conn).await;
+
+            // A message after a one second cut.
+            create_event(
+                &mut conn,
+                &room,
+                1_000_000_000,
+                "stream",
+                json!({"cut": "start"}),
+            )
+            .await;
+
+            create_event(
+                &mut conn,
+                &room,
+                2_000_000_000,
+                "stream",
+                json!({"cut": "stop"}),
+            )
+            .await;
+
+            create_event(
+                &mut conn,
+                &room,
+                3_000_000_000,
+                "message",
+                json!({"message": "m1"}),
+            )
+            .await;
+
+            let edition = factory::Edition::new(room.id(), agent.agent_id())
+                .insert(&mut conn)
+                .await;
+
+            drop(conn);
+
+            let (destination, _segments) = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                DEFAULT_MAX_CUT_GAPS,
+                &CutConfig::default(),
+                USR_AUDIENCE,
+                None,
+            )
+            .await
+            .expect("edition commit failed");
+
+            // The cloned event keeps its timestamp on the source room timeline.
+            let mut conn = db.get_conn().await;
+
+            let events = EventListQuery::new()
+                .room_id(destination.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to fetch events");
+
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].occurred_at(), 2_000_000_000);
+            assert_eq!(events[0].source_occurred_at(), Some(3_000_000_000));
+
+            let event_json = serde_json::to_value(&events[0]).expect("Failed to serialize event");
+            assert_eq!(event_json["occurred_at"], 2_000_000_000i64);
+            assert_eq!(event_json["source_occurred_at"], 3_000_000_000i64);
+        });
+    }
+
+    #[test]
+    fn commit_edition_with_too_many_cut_gaps() {
+        async_std::task::block_on(async {
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut conn = db.get_conn().await;
+            let room = shared_helpers::insert_room(&mut conn).await;
+
+            // Three cut pairs.
+            for i in 0..3 {
+                let start = (i * 2 + 1) * 1_000_000_000;
+                create_event(&mut conn, &room, start, "stream", json!({"cut": "start"})).await;
+
+                let stop = start + 500_000_000;
+                create_event(&mut conn, &room, stop, "stream", json!({"cut": "stop"})).await;
+            }
+
+            let edition = factory::Edition::new(room.id(), agent.agent_id())
+                .insert(&mut conn)
+                .await;
+
+            drop(conn);
+
+            let err = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                2,
+                &CutConfig::default(),
+                room.audience(),
+                None,
+            )
+            .await
+            .expect_err("edition commit succeeded with too many cut gaps");
+
+            assert!(err.to_string().contains("too many cut gaps"));
+        });
+    }
+
+    #[test]
+    fn commit_edition_with_subset_of_changes() {
+        async_std::task::block_on(async {
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut conn = db.get_conn().await;
+            let room = shared_helpers::insert_room(&mut conn).await;
+
+            let e1 = create_event(
+                &mut conn,
+                &room,
+                1_000_000_000,
+                "message",
+                json!({"message": "m1"}),
+            )
+            .await;
+
+            let e2 = create_event(
+                &mut conn,
+                &room,
+                2_000_000_000,
+                "message",
+                json!({"message": "m2"}),
+            )
+            .await;
+
+            let edition = factory::Edition::new(room.id(), agent.agent_id())
+                .insert(&mut conn)
+                .await;
+
+            let approved_change = factory::Change::new(edition.id(), ChangeType::Modification)
+                .event_data(json![{"message": "m1 modified"}])
+                .event_id(e1.id())
+                .insert(&mut conn)
+                .await;
+
+            // This change is left pending so it must not affect the destination room.
+            factory::Change::new(edition.id(), ChangeType::Removal)
+                .event_id(e2.id())
+                .insert(&mut conn)
+                .await;
+
+            drop(conn);
+
+            let (destination, _segments) = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                DEFAULT_MAX_CUT_GAPS,
+                &CutConfig::default(),
+                room.audience(),
+                Some(&[approved_change.id()]),
+            )
+            .await
+            .expect("edition commit failed");
+
+            let mut conn = db.get_conn().await;
+
+            let events = EventListQuery::new()
+                .room_id(destination.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to fetch events");
+
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[0].data()["message"], "m1 modified");
+            assert_eq!(events[1].data()["message"], "m2");
+        });
+    }
+
+    #[test]
+    fn retry_failed_commit() {
+        async_std::task::block_on(async {
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut conn = db.get_conn().await;
+            let room = shared_helpers::insert_room(&mut conn).await;
+            create_event(
+                &mut conn,
+                &room,
+                1_000_000_000,
+                "stream",
+                json!({"cut": "start"}),
+            )
+            .await;
+
+            create_event(
+                &mut conn,
+                &room,
+                2_000_000_000,
+                "stream",
+                json!({"cut": "stop"}),
+            )
+            .await;
+
+            let edition = factory::Edition::new(room.id(), agent.agent_id())
+                .insert(&mut conn)
+                .await;
+
+            drop(conn);
+
+            // Fail the first attempt and make sure it left nothing behind.
+            super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                0,
+                &CutConfig::default(),
+                room.audience(),
+                None,
+            )
+            .await
+            .expect_err("edition commit succeeded with too many cut gaps");
+
+            let mut conn = db.get_conn().await;
+
+            let derived_rooms = RoomDerivedListQuery::new(room.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to list derived rooms");
+
+            assert!(derived_rooms.is_empty());
+
+            // Retry from scratch.
+            let (destination, _segments) = super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                DEFAULT_MAX_CUT_GAPS,
+                &CutConfig::default(),
+                room.audience(),
+                None,
+            )
+            .await
+            .expect("edition commit retry failed");
+
+            // Committing the same edition once more gets rejected.
+            super::call(
+                &db.connection_pool(),
+                &profiler,
+                &edition,
+                &room,
+                DEFAULT_MAX_CUT_GAPS,
+                &CutConfig::default(),
+                room.audience(),
+                None,
+            )
+            .await
+            .expect_err("edition committed twice");
+
+            let derived_rooms = RoomDerivedListQuery::new(room.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to list derived rooms");
+
+            assert_eq!(derived_rooms.len(), 1);
+            assert_eq!(derived_rooms[0].id(), destination.id());
+        });
+    }
+
+    #[test]
+    fn collect_gaps_from_paged_changes() {
+        async_std::task::block_on(async {
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut conn = db.get_conn().await;
+            let room = shared_helpers::insert_room(&mut conn).await;
+
+            let edition = factory::Edition::new(room.id(), agent.agent_id())
+                .insert(&mut conn)
+                .await;
+
+            // Nested cuts share `occurred_at` so ties get split between pages.
+            for i in 0..50 {
+                for (command, offset) in &[
+                    ("start", 0),
+                    ("start", 0),
+                    ("stop", 500_000_000),
+                    ("stop", 500_000_000),
+                ] {
+                    factory::Change::new(edition.id(), ChangeType::Addition)
+                        .event_data(json!({ "cut": command }))
+                        .event_kind("stream")
+                        .event_set("stream")
+                        .event_occurred_at(i * 1_000_000_000 + offset)
+                        .event_created_by(agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+                }
+            }
+
+            // Cut events get merged in between the changes.
+            let mut cut_events = vec![];
+
+            for i in 0..10 {
+                for (command, offset) in &[("start", 600_000_000), ("stop", 700_000_000)] {
+                    let event = create_event(
+                        &mut conn,
+                        &room,
+                        i * 1_000_000_000 + offset,
+                        "stream",
+                        json!({ "cut": command }),
+                    )
+                    .await;
+
+                    cut_events.push(event);
+                }
+            }
+
+            let cut_config = CutConfig::default();
+
+            let changes = ChangeListQuery::new(edition.id())
+                .kind("stream")
+                .limit(1000)
+                .execute(&mut conn)
+                .await
+                .expect("Failed to list changes");
+
+            assert_eq!(changes.len(), 200);
+
+            let cuts = cut_events
+                .iter()
+                .map(|event| super::Cut::from_event(event, &cut_config.key))
+                .chain(
+                    changes
+                        .iter()
+                        .map(|change| super::Cut::from_change(change, &cut_config.key)),
+                );
+
+            let gaps = super::collect_gaps(cuts, &cut_config).expect("Failed to collect gaps");
+
+            let paged_gaps = super::collect_edition_gaps(
+                &mut conn,
+                &profiler,
+                &edition,
+                None,
+                &cut_events,
+                &cut_config,
+                7,
+            )
+            .await
+            .expect("Failed to collect gaps");
+
+            assert_eq!(paged_gaps.len(), 60);
+            assert_eq!(paged_gaps, gaps);
+
+            // Only the picked changes make gaps.
+            let change_ids = changes
+                .iter()
+                .filter(|change| change.event_occurred_at() < Some(25_000_000_000))
+                .map(|change| change.id())
+                .collect::<Vec<_>>();
+
+            let paged_gaps = super::collect_edition_gaps(
+                &mut conn,
+                &profiler,
+                &edition,
+                Some(&change_ids),
+                &cut_events,
+                &cut_config,
+                7,
+            )
+            .await
+            .expect("Failed to collect gaps");
+
+            assert_eq!(paged_gaps.len(), 35);
+        });
+    }
+
     async fn create_event(
         conn: &mut PgConnection,
         room: &Room,
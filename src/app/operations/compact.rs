@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use sqlx::postgres::PgPool as Db;
+
+use crate::app::metrics::ProfilerKeys;
+use crate::config::CompactConfig;
+use crate::db::event::CompactedRoom;
+use crate::profiler::Profiler;
+
+pub(crate) async fn call(
+    db: &Db,
+    profiler: &Profiler<(ProfilerKeys, Option<String>)>,
+    config: &CompactConfig,
+) -> Result<Vec<CompactedRoom>> {
+    let mut conn = db
+        .acquire()
+        .await
+        .context("Failed to acquire db connection")?;
+
+    let query = crate::db::event::CompactQuery::new(config.max_versions);
+
+    let compacted_rooms = profiler
+        .measure(
+            (
+                ProfilerKeys::EventCompactQuery,
+                Some("system.compact".into()),
+            ),
+            query.execute(&mut conn),
+        )
+        .await?;
+
+    Ok(compacted_rooms)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use chrono::{Duration, SubsecRound, Utc};
+    use serde_json::json;
+    use serial_test::serial;
+    use sqlx::postgres::PgConnection;
+
+    use crate::app::metrics::ProfilerKeys;
+    use crate::config::CompactConfig;
+    use crate::db::event::{ListQuery as EventListQuery, Object as Event};
+    use crate::db::room::Object as Room;
+    use crate::profiler::Profiler;
+    use crate::test_helpers::prelude::*;
+
+    #[test]
+    #[serial]
+    fn compact_versions() {
+        async_std::task::block_on(async {
+            let config = CompactConfig { max_versions: 3 };
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+
+            // Seed many versions of the same label in a regular and a preserved room.
+            let mut conn = db.get_conn().await;
+            let room = insert_room(&mut conn, false).await;
+            let preserved_room = insert_room(&mut conn, true).await;
+            let mut versions = vec![];
+
+            for i in 0..10 {
+                versions.push(insert_event(&mut conn, &room, i).await);
+                insert_event(&mut conn, &preserved_room, i).await;
+            }
+
+            drop(conn);
+
+            // Run compaction.
+            let compacted_rooms = super::call(&db.connection_pool(), &profiler, &config)
+                .await
+                .expect("Compaction failed");
+
+            assert_eq!(compacted_rooms.len(), 1);
+            assert_eq!(compacted_rooms[0].room_id, room.id());
+            assert_eq!(compacted_rooms[0].deleted_count, 7);
+
+            // Assert only the latest versions to remain.
+            let mut conn = db.get_conn().await;
+            let events = fetch_room_events(&mut conn, &room).await;
+            assert_eq!(events.len(), 3);
+
+            for version in &versions[7..] {
+                assert!(events.iter().any(|event| event.id() == version.id()));
+            }
+
+            let preserved_events = fetch_room_events(&mut conn, &preserved_room).await;
+            assert_eq!(preserved_events.len(), 10);
+        });
+    }
+
+    async fn insert_room(conn: &mut PgConnection, preserve_history: bool) -> Room {
+        let now = Utc::now().trunc_subsecs(0);
+
+        let time = (
+            Bound::Included(now),
+            Bound::Excluded(now + Duration::hours(1)),
+        );
+
+        factory::Room::new()
+            .audience(USR_AUDIENCE)
+            .time(time)
+            .preserve_history(preserve_history)
+            .insert(conn)
+            .await
+    }
+
+    async fn insert_event(conn: &mut PgConnection, room: &Room, version: i64) -> Event {
+        let creator = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+        factory::Event::new()
+            .room_id(room.id())
+            .kind("draw")
+            .set("page1")
+            .label("drawing1")
+            .occurred_at(1_000_000_000 * (version + 1))
+            .data(&json!({ "version": version }))
+            .created_by(creator.agent_id())
+            .insert(conn)
+            .await
+    }
+
+    async fn fetch_room_events(conn: &mut PgConnection, room: &Room) -> Vec<Event> {
+        EventListQuery::new()
+            .room_id(room.id())
+            .execute(conn)
+            .await
+            .expect("Failed to list events")
+    }
+}
@@ -18,6 +18,13 @@ struct S3Destination {
     key: String,
 }
 
+/// Optional constraints on the dumped events.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct EventsFilter {
+    pub(crate) set: Option<String>,
+    pub(crate) kind: Option<String>,
+}
+
 #[derive(Serialize)]
 struct S3Content {
     room: Room,
@@ -29,7 +36,8 @@ pub(crate) async fn call(
     profiler: &Profiler<(ProfilerKeys, Option<String>)>,
     s3_client: S3Client,
     room: &Room,
-) -> Result<String> {
+    filter: &EventsFilter,
+) -> Result<(String, usize)> {
     info!(
         crate::LOG,
         "Dump events to S3 task started, room id = {}",
@@ -38,9 +46,10 @@ pub(crate) async fn call(
 
     let start_timestamp = Instant::now();
 
-    let destination = s3_destination(room);
+    let destination = s3_destination(room, filter);
 
-    let events = load_room_events(db, profiler, room).await?;
+    let events = load_room_events(db, profiler, room, filter).await?;
+    let event_count = events.len();
 
     let s3_uri = upload_events(s3_client, room, events, destination).await?;
 
@@ -51,17 +60,27 @@ pub(crate) async fn call(
         start_timestamp.elapsed().as_millis()
     );
 
-    Ok(s3_uri)
+    Ok((s3_uri, event_count))
 }
 
 async fn load_room_events(
     db: &Db,
     profiler: &Profiler<(ProfilerKeys, Option<String>)>,
     room: &Room,
+    filter: &EventsFilter,
 ) -> Result<Vec<Event>> {
     let mut conn = db.acquire().await.context("Failed to get db connection")?;
 
-    let query = EventListQuery::new().room_id(room.id());
+    let mut query = EventListQuery::new().room_id(room.id());
+
+    if let Some(ref set) = filter.set {
+        query = query.set(set);
+    }
+
+    if let Some(ref kind) = filter.kind {
+        query = query.kind(kind.to_owned());
+    }
+
     let events = profiler
         .measure(
             (
@@ -111,10 +130,20 @@ async fn upload_events(
     Ok(s3_uri)
 }
 
-fn s3_destination(room: &Room) -> S3Destination {
+fn s3_destination(room: &Room, filter: &EventsFilter) -> S3Destination {
+    let mut key = room.id().to_string();
+
+    if let Some(ref set) = filter.set {
+        key.push_str(&format!(".{}", set));
+    }
+
+    if let Some(ref kind) = filter.kind {
+        key.push_str(&format!(".kind-{}", kind));
+    }
+
     S3Destination {
         bucket: format!("eventsdump.{}", room.audience()),
-        key: format!("{}.json", room.id()),
+        key: format!("{}.json", key),
     }
 }
 
@@ -123,6 +152,10 @@ mod tests {
     use super::*;
     use crate::test_helpers::prelude::*;
 
+    use std::sync::{Arc, Mutex};
+
+    use rusoto_core::signature::{SignedRequest, SignedRequestPayload};
+    use rusoto_mock::{MockCredentialsProvider, MockRequestDispatcher};
     use serde_json::{json, Value as JsonValue};
     use sqlx::postgres::PgConnection;
 
@@ -174,11 +207,12 @@ mod tests {
             let mut context = TestContext::new(db, TestAuthz::new());
             context.set_s3(shared_helpers::mock_s3());
 
-            let s3_uri = super::call(
+            let (s3_uri, event_count) = super::call(
                 context.db(),
                 &context.profiler(),
                 context.s3_client().unwrap(),
                 &room,
+                &EventsFilter::default(),
             )
             .await
             .expect("No failure");
@@ -186,6 +220,98 @@ mod tests {
                 s3_uri,
                 format!("s3://eventsdump.{}/{}.json", room.audience(), room.id())
             );
+            assert_eq!(event_count, 3);
+        });
+    }
+
+    #[test]
+    fn test_upload_set() {
+        async_std::task::block_on(async {
+            let db = TestDb::new().await;
+
+            let room = {
+                let mut conn = db.get_conn().await;
+                let room = shared_helpers::insert_room(&mut conn).await;
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                for (occurred_at, kind, set) in &[
+                    (1_000_000_000, "message", "messages"),
+                    (2_000_000_000, "draw", "layout"),
+                    (3_000_000_000, "message", "messages"),
+                ] {
+                    factory::Event::new()
+                        .room_id(room.id())
+                        .kind(kind)
+                        .set(set)
+                        .data(&json!({"occurred_at": occurred_at}))
+                        .occurred_at(*occurred_at)
+                        .created_by(agent.agent_id())
+                        .insert(&mut conn)
+                        .await;
+                }
+
+                room
+            };
+
+            // Capture the uploaded object.
+            let uploaded_body = Arc::new(Mutex::new(None));
+            let uploaded_body_clone = uploaded_body.clone();
+
+            let dispatcher = MockRequestDispatcher::default().with_request_checker(
+                move |request: &SignedRequest| {
+                    if let Some(SignedRequestPayload::Buffer(ref body)) = request.payload {
+                        *uploaded_body_clone.lock().unwrap() = Some(body.to_vec());
+                    }
+                },
+            );
+
+            let s3 = rusoto_s3::S3Client::new_with(
+                dispatcher,
+                MockCredentialsProvider,
+                Default::default(),
+            );
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+            context.set_s3(S3Client::new_with_client(s3).expect("Failed to init S3 client"));
+
+            let filter = EventsFilter {
+                set: Some(String::from("messages")),
+                kind: None,
+            };
+
+            let (s3_uri, event_count) = super::call(
+                context.db(),
+                &context.profiler(),
+                context.s3_client().unwrap(),
+                &room,
+                &filter,
+            )
+            .await
+            .expect("No failure");
+
+            assert_eq!(
+                s3_uri,
+                format!(
+                    "s3://eventsdump.{}/{}.messages.json",
+                    room.audience(),
+                    room.id()
+                )
+            );
+
+            let body = uploaded_body
+                .lock()
+                .unwrap()
+                .take()
+                .expect("Nothing uploaded");
+
+            let content: JsonValue = serde_json::from_slice(&body).expect("Invalid dump");
+            let events = content["events"].as_array().expect("Missing events");
+            assert_eq!(events.len(), 2);
+            assert_eq!(event_count, 2);
+
+            for event in events {
+                assert_eq!(event["set"], "messages");
+            }
         });
     }
 
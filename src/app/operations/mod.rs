@@ -1,9 +1,17 @@
 pub(crate) use adjust_room::call as adjust_room;
-pub(crate) use commit_edition::call as commit_edition;
-pub(crate) use dump_events_to_s3::call as dump_events_to_s3;
+pub(crate) use commit_edition::{
+    call as commit_edition, cut_segments, estimate as estimate_edition,
+    validate as validate_edition,
+};
+pub(crate) use compact::call as compact;
+pub(crate) use dump_events_to_s3::{call as dump_events_to_s3, EventsFilter};
 pub(crate) use vacuum::call as vacuum;
 
+#[cfg(test)]
+pub(crate) use adjust_room::{invert_segments, NANOSECONDS_IN_MILLISECOND};
+
 mod adjust_room;
 mod commit_edition;
+mod compact;
 mod dump_events_to_s3;
 mod vacuum;
@@ -3,32 +3,40 @@ use sqlx::postgres::PgPool as Db;
 
 use crate::app::metrics::ProfilerKeys;
 use crate::config::VacuumConfig;
+use crate::db::event::VacuumedRoom;
 use crate::profiler::Profiler;
 
 pub(crate) async fn call(
     db: &Db,
     profiler: &Profiler<(ProfilerKeys, Option<String>)>,
     config: &VacuumConfig,
-) -> Result<()> {
+) -> Result<Vec<VacuumedRoom>> {
     let mut conn = db
         .acquire()
         .await
         .context("Failed to acquire db connection")?;
 
+    let kind_history_lifetimes = config
+        .per_kind
+        .iter()
+        .map(|(kind, kind_config)| (kind.to_owned(), kind_config.max_history_lifetime))
+        .collect();
+
     let query = crate::db::event::VacuumQuery::new(
         config.max_history_size,
         config.max_history_lifetime,
         config.max_deleted_lifetime,
-    );
+    )
+    .kind_history_lifetimes(kind_history_lifetimes);
 
-    profiler
+    let vacuumed_rooms = profiler
         .measure(
             (ProfilerKeys::EventVacuumQuery, Some("system.vacuum".into())),
             query.execute(&mut conn),
         )
         .await?;
 
-    Ok(())
+    Ok(vacuumed_rooms)
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -178,6 +186,58 @@ mod tests {
         });
     }
 
+    #[test]
+    #[serial]
+    fn vacuum_history_per_kind() {
+        async_std::task::block_on(async {
+            let config: VacuumConfig = serde_json::from_value(json!({
+                "max_history_size": 100,
+                "max_history_lifetime": 3600,
+                "max_deleted_lifetime": 1_000_000,
+                "per_kind": {
+                    "typing": {"max_history_lifetime": 600},
+                },
+            }))
+            .expect("Failed to parse vacuum config");
+
+            let profiler = Profiler::<(ProfilerKeys, Option<String>)>::start();
+            let db = TestDb::new().await;
+
+            // Two labels of different kinds with a half an hour old previous version.
+            let mut conn = db.get_conn().await;
+            let room = insert_room(&mut conn, false).await;
+
+            let typing1 = build_event_factory(&room, 30)
+                .kind("typing")
+                .label("typing1")
+                .insert(&mut conn)
+                .await;
+
+            let typing2 = build_event_factory(&room, 1)
+                .kind("typing")
+                .label("typing1")
+                .insert(&mut conn)
+                .await;
+
+            let drawing1 = insert_event(&mut conn, &room, 30).await;
+            let drawing2 = insert_event(&mut conn, &room, 1).await;
+            drop(conn);
+
+            // Run vacuum.
+            super::call(&db.connection_pool(), &profiler, &config)
+                .await
+                .expect("Vacuum failed");
+
+            // Assert the old version to be deleted only for the kind with a shorter lifetime.
+            let mut conn = db.get_conn().await;
+            let event_ids = fetch_room_event_ids(&mut conn, &room).await;
+            assert!(!event_ids.contains(&typing1.id()));
+            assert!(event_ids.contains(&typing2.id()));
+            assert!(event_ids.contains(&drawing1.id()));
+            assert!(event_ids.contains(&drawing2.id()));
+        });
+    }
+
     async fn insert_room(conn: &mut PgConnection, preserve_history: bool) -> Room {
         let now = Utc::now().trunc_subsecs(0);
 
@@ -200,6 +260,7 @@ mod tests {
 
     async fn insert_deleted_event(conn: &mut PgConnection, room: &Room, minutes_ago: i64) -> Event {
         build_event_factory(room, minutes_ago)
+            .attribute("pinned")
             .attribute("deleted")
             .insert(conn)
             .await
@@ -0,0 +1,196 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use svc_agent::AgentId;
+use uuid::Uuid;
+
+const AGENT_REQUESTS_WINDOW_SECONDS: i64 = 60;
+const MAX_TRACKED_AGENTS: usize = 10_000;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// In-memory fixed window limiter of event insertions per room.
+/// Windows are one second long and counters of all rooms reset when the window changes.
+#[derive(Debug, Default)]
+pub(crate) struct RoomRateLimiter {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    window: i64,
+    counters: HashMap<Uuid, u32>,
+}
+
+impl RoomRateLimiter {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a hit for the room and returns whether it fits into `limit` per second.
+    pub(crate) fn try_acquire(&self, room_id: Uuid, limit: u32, now: DateTime<Utc>) -> bool {
+        let mut state = self.state.lock().expect("Room rate limiter mutex poisoned");
+        let window = now.timestamp();
+
+        if state.window != window {
+            state.window = window;
+            state.counters.clear();
+        }
+
+        let counter = state.counters.entry(room_id).or_insert(0);
+
+        if *counter >= limit {
+            false
+        } else {
+            *counter += 1;
+            true
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// In-memory limiter of operations running concurrently per room.
+#[derive(Debug, Default)]
+pub(crate) struct RoomConcurrencyLimiter {
+    counters: Mutex<HashMap<Uuid, usize>>,
+}
+
+impl RoomConcurrencyLimiter {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    /// Takes a slot of the room unless `limit` slots are taken already.
+    /// The slot gets released when the returned permit is dropped.
+    pub(crate) fn try_acquire(
+        self: &Arc<Self>,
+        room_id: Uuid,
+        limit: usize,
+    ) -> Option<RoomConcurrencyPermit> {
+        let mut counters = self
+            .counters
+            .lock()
+            .expect("Room concurrency limiter mutex poisoned");
+
+        let counter = counters.entry(room_id).or_insert(0);
+
+        if *counter >= limit {
+            None
+        } else {
+            *counter += 1;
+
+            Some(RoomConcurrencyPermit {
+                limiter: self.clone(),
+                room_id,
+            })
+        }
+    }
+
+    fn release(&self, room_id: Uuid) {
+        let mut counters = self
+            .counters
+            .lock()
+            .expect("Room concurrency limiter mutex poisoned");
+
+        if let Some(counter) = counters.get_mut(&room_id) {
+            *counter -= 1;
+
+            // Don't keep counters of rooms nobody reads at the moment.
+            if *counter == 0 {
+                counters.remove(&room_id);
+            }
+        }
+    }
+}
+
+pub(crate) struct RoomConcurrencyPermit {
+    limiter: Arc<RoomConcurrencyLimiter>,
+    room_id: Uuid,
+}
+
+impl Drop for RoomConcurrencyPermit {
+    fn drop(&mut self) {
+        self.limiter.release(self.room_id);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// In-memory sliding window counter of requests per agent to find out which agents drive load.
+/// The window is made of one second buckets. An agent first showing up in a bucket which already
+/// tracks `MAX_TRACKED_AGENTS` agents is not counted there to keep memory bounded.
+#[derive(Debug, Default)]
+pub(crate) struct AgentRequestCounter {
+    buckets: Mutex<VecDeque<(i64, HashMap<String, u64>)>>,
+}
+
+impl AgentRequestCounter {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn window() -> Duration {
+        Duration::seconds(AGENT_REQUESTS_WINDOW_SECONDS)
+    }
+
+    pub(crate) fn register(&self, agent_id: &AgentId, now: DateTime<Utc>) {
+        let mut buckets = self
+            .buckets
+            .lock()
+            .expect("Agent request counter mutex poisoned");
+
+        let second = now.timestamp();
+        evict_buckets(&mut buckets, second);
+
+        if buckets.back().map(|(s, _)| *s < second).unwrap_or(true) {
+            buckets.push_back((second, HashMap::new()));
+        }
+
+        let (_, counters) = buckets.back_mut().expect("Missing current bucket");
+        let agent_id = agent_id.to_string();
+
+        if let Some(counter) = counters.get_mut(&agent_id) {
+            *counter += 1;
+        } else if counters.len() < MAX_TRACKED_AGENTS {
+            counters.insert(agent_id, 1);
+        }
+    }
+
+    /// Returns up to `limit` agents with the most requests within the window ending at `now`.
+    pub(crate) fn top(&self, now: DateTime<Utc>, limit: usize) -> Vec<(String, u64)> {
+        let mut buckets = self
+            .buckets
+            .lock()
+            .expect("Agent request counter mutex poisoned");
+
+        evict_buckets(&mut buckets, now.timestamp());
+        let mut totals = HashMap::new();
+
+        for (_, counters) in buckets.iter() {
+            for (agent_id, count) in counters {
+                *totals.entry(agent_id.as_str()).or_insert(0) += count;
+            }
+        }
+
+        let mut top = totals
+            .into_iter()
+            .map(|(agent_id, count)| (agent_id.to_owned(), count))
+            .collect::<Vec<_>>();
+
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(limit);
+        top
+    }
+}
+
+fn evict_buckets(buckets: &mut VecDeque<(i64, HashMap<String, u64>)>, second: i64) {
+    while let Some((bucket_second, _)) = buckets.front() {
+        if *bucket_second > second - AGENT_REQUESTS_WINDOW_SECONDS {
+            break;
+        }
+
+        buckets.pop_front();
+    }
+}
@@ -12,6 +12,8 @@ use rusoto_credential::StaticProvider;
 use rusoto_s3::S3Client as RusotoClient;
 use rusoto_s3::{PutObjectOutput, PutObjectRequest, S3};
 
+use crate::config::S3Config;
+
 type Message = (PutObjectRequest, OnceSender<AnyResult<PutObjectOutput>>);
 
 #[derive(Debug, Clone)]
@@ -20,8 +22,8 @@ pub struct S3Client {
 }
 
 impl S3Client {
-    pub fn new() -> Option<Self> {
-        Self::new_with_client(build_client()?)
+    pub fn new(config: Option<&S3Config>) -> Option<Self> {
+        Self::new_with_client(build_client(config)?)
     }
 
     pub fn new_with_client(s3_client: RusotoClient) -> Option<Self> {
@@ -61,10 +63,17 @@ impl S3Client {
     }
 }
 
-fn build_client() -> Option<RusotoClient> {
-    let (key, secret, endpoint, region) = match get_aws_creds() {
-        Some(creds) => creds,
-        None => {
+fn build_client(config: Option<&S3Config>) -> Option<RusotoClient> {
+    let creds = get_aws_creds();
+
+    let region = match config {
+        Some(config) => Some(build_region(config)),
+        None => get_aws_region(),
+    };
+
+    let ((key, secret), region) = match (creds, region) {
+        (Some(creds), Some(region)) => (creds, region),
+        _ => {
             warn!(
                 crate::LOG,
                 "No S3 credentials specified, room.dump_events will err"
@@ -73,11 +82,6 @@ fn build_client() -> Option<RusotoClient> {
         }
     };
 
-    let region = Region::Custom {
-        name: region,
-        endpoint,
-    };
-
     let credentials = StaticProvider::new_minimal(key, secret);
     let client = rusoto_s3::S3Client::new_with(
         rusoto_core::request::HttpClient::new().expect("Failed to build rusoto http client"),
@@ -88,10 +92,47 @@ fn build_client() -> Option<RusotoClient> {
     Some(client)
 }
 
-fn get_aws_creds() -> Option<(String, String, String, String)> {
+/// Rusoto addresses buckets path-style (`{endpoint}/{bucket}/{key}`) with a custom region
+/// which is what MinIO and other S3-compatible stores expect.
+fn build_region(config: &S3Config) -> Region {
+    Region::Custom {
+        name: config.region.to_owned(),
+        endpoint: config.endpoint.to_owned(),
+    }
+}
+
+fn get_aws_creds() -> Option<(String, String)> {
     let key = var("AWS_ACCESS_KEY_ID").ok()?;
     let secret = var("AWS_SECRET_ACCESS_KEY").ok()?;
+    Some((key, secret))
+}
+
+fn get_aws_region() -> Option<Region> {
     let endpoint = var("AWS_ENDPOINT").ok()?;
     let region = var("AWS_REGION").ok()?;
-    Some((key, secret, endpoint, region))
+    Some(Region::Custom {
+        name: region,
+        endpoint,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_region_from_config() {
+        let config = S3Config {
+            endpoint: "http://minio.local:9000".to_owned(),
+            region: "us-east-1".to_owned(),
+        };
+
+        let region = build_region(&config);
+        assert_eq!(region.name(), "us-east-1");
+
+        match region {
+            Region::Custom { endpoint, .. } => assert_eq!(endpoint, "http://minio.local:9000"),
+            other => panic!("Expected custom region, got {:?}", other),
+        }
+    }
 }
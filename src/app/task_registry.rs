@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures_util::future::{abortable, AbortHandle};
+use uuid::Uuid;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Asynchronous operation which may be cancelled while running.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum TaskKind {
+    RoomAdjust,
+    EditionCommit,
+}
+
+/// Keeps abort handles of running operations keyed by the kind and the id of the object
+/// they operate on, i.e. the room for an adjustment and the edition for a commit.
+/// When the same operation is started twice only the latest one may be cancelled.
+#[derive(Debug, Default)]
+pub(crate) struct TaskRegistry {
+    tasks: Mutex<HashMap<(TaskKind, Uuid), (u64, AbortHandle)>>,
+    last_serial: AtomicU64,
+}
+
+impl TaskRegistry {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    /// Wraps the future so it could be cancelled until completion.
+    /// The wrapped future resolves to `None` when cancelled.
+    /// Cancellation drops the inner future so an open transaction gets rolled back.
+    pub(crate) fn track<F>(
+        self: &Arc<Self>,
+        kind: TaskKind,
+        id: Uuid,
+        future: F,
+    ) -> impl Future<Output = Option<F::Output>>
+    where
+        F: Future,
+    {
+        let (future, abort_handle) = abortable(future);
+        let serial = self.register(kind, id, abort_handle);
+        let registry = self.clone();
+
+        async move {
+            let result = future.await.ok();
+            registry.deregister(kind, id, serial);
+            result
+        }
+    }
+
+    /// Returns `false` if there's no such running operation.
+    pub(crate) fn cancel(&self, kind: TaskKind, id: Uuid) -> bool {
+        let mut tasks = self.tasks.lock().expect("Task registry mutex poisoned");
+
+        match tasks.remove(&(kind, id)) {
+            Some((_serial, abort_handle)) => {
+                abort_handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn register(&self, kind: TaskKind, id: Uuid, abort_handle: AbortHandle) -> u64 {
+        let serial = self.last_serial.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut tasks = self.tasks.lock().expect("Task registry mutex poisoned");
+        tasks.insert((kind, id), (serial, abort_handle));
+        serial
+    }
+
+    fn deregister(&self, kind: TaskKind, id: Uuid, serial: u64) {
+        let mut tasks = self.tasks.lock().expect("Task registry mutex poisoned");
+
+        // Don't remove the entry of a newer operation on the same object.
+        if let Some((registered_serial, _)) = tasks.get(&(kind, id)) {
+            if *registered_serial == serial {
+                tasks.remove(&(kind, id));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_running_task() {
+        async_std::task::block_on(async {
+            let registry = Arc::new(TaskRegistry::new());
+            let id = Uuid::new_v4();
+
+            let task = registry.track(TaskKind::RoomAdjust, id, futures::future::pending::<()>());
+            let task = async_std::task::spawn(task);
+
+            assert!(!registry.cancel(TaskKind::EditionCommit, id));
+            assert!(registry.cancel(TaskKind::RoomAdjust, id));
+            assert_eq!(task.await, None);
+            assert!(!registry.cancel(TaskKind::RoomAdjust, id));
+        });
+    }
+
+    #[test]
+    fn finished_task_is_deregistered() {
+        async_std::task::block_on(async {
+            let registry = Arc::new(TaskRegistry::new());
+            let id = Uuid::new_v4();
+
+            let task = registry.track(TaskKind::EditionCommit, id, async { 42 });
+            assert_eq!(task.await, Some(42));
+            assert!(!registry.cancel(TaskKind::EditionCommit, id));
+        });
+    }
+}
@@ -1,11 +1,20 @@
+use std::collections::HashMap;
+
 use chrono::Duration;
 use serde_derive::Deserialize;
 use svc_agent::{mqtt::AgentConfig, AccountId};
 use svc_authn::jose::Algorithm;
 use svc_authz::ConfigMap as Authz;
 use svc_error::extension::sentry::Config as SentryConfig;
+use uuid::Uuid;
 
 const DEFAULT_BAN_DUR_SECS: u64 = 5 * 3600;
+pub(crate) const DEFAULT_MAX_CUT_GAPS: usize = 5000;
+const DEFAULT_MAX_ROOM_TAGS_SIZE: usize = 64 * 1024;
+const DEFAULT_MIN_ROOM_DURATION_MS: i64 = 1000;
+const DEFAULT_MAX_STATE_SIZE: usize = 4 * 1024 * 1024;
+const DEFAULT_MAX_DATA_DEPTH: usize = 32;
+const DEFAULT_MAX_DATA_ARRAY_LENGTH: usize = 10_000;
 
 #[derive(Clone, Debug, Deserialize)]
 pub(crate) struct Config {
@@ -24,12 +33,166 @@ pub(crate) struct Config {
     ban_duration_s: Option<u64>,
     #[serde(default)]
     pub(crate) vacuum: VacuumConfig,
+    #[serde(default)]
+    pub(crate) compact: CompactConfig,
+    #[serde(default)]
+    pub(crate) read_routing: HashMap<String, ReadPool>,
+    max_cut_gaps: Option<usize>,
+    max_room_tags_size: Option<usize>,
+    min_room_duration_ms: Option<i64>,
+    pub(crate) close_grace_period_s: Option<i64>,
+    pub(crate) max_state_size: Option<usize>,
+    pub(crate) max_data_depth: Option<usize>,
+    pub(crate) max_data_array_length: Option<usize>,
+    #[serde(default)]
+    pub(crate) concurrent_state_count: bool,
+    #[serde(default)]
+    pub(crate) edition_commit_audiences: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub(crate) public_read_audiences: Vec<String>,
+    #[serde(default)]
+    pub(crate) default_event_set: Option<String>,
+    #[serde(default)]
+    pub(crate) event_sets_by_kind: HashMap<String, String>,
+    #[serde(default)]
+    pub(crate) allowed_data_keys: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub(crate) cut: CutConfig,
+    pub(crate) event_rate_limit: Option<u32>,
+    #[serde(default)]
+    pub(crate) report_full_lists: bool,
+    #[serde(default)]
+    pub(crate) strict_event_labels: bool,
+    #[serde(default)]
+    pub(crate) strict_utc_datetimes: bool,
+    #[serde(default)]
+    pub(crate) topics: TopicsConfig,
+    #[serde(default)]
+    pub(crate) monotonic_occurred_at: bool,
+    #[serde(default)]
+    pub(crate) features: HashMap<String, FeatureFlags>,
+    #[serde(default)]
+    pub(crate) read_only: bool,
+    pub(crate) auto_adjust_on_commit: Option<AutoAdjustConfig>,
+    pub(crate) s3: Option<S3Config>,
+    pub(crate) max_concurrent_handlers: Option<usize>,
+    pub(crate) max_concurrent_dumps: Option<usize>,
+    pub(crate) max_concurrent_state_reads_per_room: Option<usize>,
+    pub(crate) max_broker_skew_ms: Option<i64>,
 }
 
 impl Config {
     pub fn ban_duration(&self) -> u64 {
         self.ban_duration_s.unwrap_or(DEFAULT_BAN_DUR_SECS)
     }
+
+    /// Maximum number of cut gaps an edition commit or room adjustment may apply.
+    pub(crate) fn max_cut_gaps(&self) -> usize {
+        self.max_cut_gaps.unwrap_or(DEFAULT_MAX_CUT_GAPS)
+    }
+
+    /// Maximum size of room tags serialized to JSON in bytes.
+    pub(crate) fn max_room_tags_size(&self) -> usize {
+        self.max_room_tags_size
+            .unwrap_or(DEFAULT_MAX_ROOM_TAGS_SIZE)
+    }
+
+    /// Maximum size of a serialized `state.read` response in bytes.
+    pub(crate) fn max_state_size(&self) -> usize {
+        self.max_state_size.unwrap_or(DEFAULT_MAX_STATE_SIZE)
+    }
+
+    /// Maximum nesting depth of event `data` JSON.
+    pub(crate) fn max_data_depth(&self) -> usize {
+        self.max_data_depth.unwrap_or(DEFAULT_MAX_DATA_DEPTH)
+    }
+
+    /// Maximum number of elements in any array of event `data` JSON.
+    pub(crate) fn max_data_array_length(&self) -> usize {
+        self.max_data_array_length
+            .unwrap_or(DEFAULT_MAX_DATA_ARRAY_LENGTH)
+    }
+
+    /// Minimum duration of a room with a bounded time.
+    pub(crate) fn min_room_duration(&self) -> Duration {
+        Duration::milliseconds(
+            self.min_room_duration_ms
+                .unwrap_or(DEFAULT_MIN_ROOM_DURATION_MS),
+        )
+    }
+
+    /// How long after a room's closing `event.create` still accepts events.
+    pub(crate) fn close_grace_period(&self) -> Duration {
+        Duration::seconds(self.close_grace_period_s.unwrap_or(0))
+    }
+
+    /// Whether an edition of a room in `source` audience may be committed into `destination`.
+    pub(crate) fn is_commit_audience_allowed(&self, source: &str, destination: &str) -> bool {
+        source == destination
+            || self
+                .edition_commit_audiences
+                .get(source)
+                .map(|audiences| audiences.iter().any(|a| a == destination))
+                .unwrap_or(false)
+    }
+
+    /// Whether rooms of the `audience` may be read without authorization.
+    pub(crate) fn is_public_read_audience(&self, audience: &str) -> bool {
+        self.public_read_audiences.iter().any(|a| a == audience)
+    }
+
+    /// Set to assign to an event of `kind` created without one.
+    /// `None` means falling back to the kind itself.
+    pub(crate) fn default_event_set(&self, kind: &str) -> Option<&str> {
+        self.event_sets_by_kind
+            .get(kind)
+            .or_else(|| self.default_event_set.as_ref())
+            .map(|set| set.as_str())
+    }
+
+    /// Feature flags of the `audience`. Unlisted audiences get all the flags disabled.
+    pub(crate) fn features(&self, audience: &str) -> FeatureFlags {
+        self.features.get(audience).copied().unwrap_or_default()
+    }
+
+    pub(crate) fn read_pool(&self, operation: &str) -> ReadPool {
+        self.read_routing
+            .get(operation)
+            .copied()
+            .unwrap_or(ReadPool::Replica)
+    }
+}
+
+/// Optional behaviors enabled per audience.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub(crate) struct FeatureFlags {
+    /// Same as the global `monotonic_occurred_at` but only for the audience's rooms.
+    #[serde(default)]
+    pub(crate) monotonic_occurred_at: bool,
+}
+
+/// Room adjustment automatically applied to a room right after an edition commit into it.
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct AutoAdjustConfig {
+    #[serde(default)]
+    pub(crate) offset: i64,
+}
+
+/// Which DB pool a read operation goes to.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ReadPool {
+    Primary,
+    Replica,
+}
+
+/// S3-compatible object storage for event dumps.
+/// Credentials are taken from `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY` env vars.
+/// Without this section the endpoint and region fall back to `AWS_ENDPOINT` and `AWS_REGION`.
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct S3Config {
+    pub(crate) endpoint: String,
+    pub(crate) region: String,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -74,6 +237,18 @@ pub(crate) struct VacuumConfig {
     pub(crate) max_history_lifetime: Duration,
     #[serde(with = "crate::serde::duration_seconds")]
     pub(crate) max_deleted_lifetime: Duration,
+    /// Whether to send `room.vacuum` notification to each room vacuum deleted events from.
+    #[serde(default)]
+    pub(crate) notify: bool,
+    /// Overrides of the retention policy by event kind.
+    #[serde(default)]
+    pub(crate) per_kind: HashMap<String, VacuumKindConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct VacuumKindConfig {
+    #[serde(with = "crate::serde::duration_seconds")]
+    pub(crate) max_history_lifetime: Duration,
 }
 
 impl Default for VacuumConfig {
@@ -82,6 +257,128 @@ impl Default for VacuumConfig {
             max_history_size: 10,
             max_history_lifetime: Duration::days(1),
             max_deleted_lifetime: Duration::days(1),
+            notify: false,
+            per_kind: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct CompactConfig {
+    /// Number of the latest versions of each label kept by compaction.
+    pub(crate) max_versions: usize,
+}
+
+impl Default for CompactConfig {
+    fn default() -> Self {
+        Self { max_versions: 10 }
+    }
+}
+
+/// Schema of `stream` events' data marking cut start and stop for edition commit.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub(crate) struct CutConfig {
+    pub(crate) key: String,
+    pub(crate) start: String,
+    pub(crate) stop: String,
+}
+
+impl Default for CutConfig {
+    fn default() -> Self {
+        Self {
+            key: String::from("cut"),
+            start: String::from("start"),
+            stop: String::from("stop"),
+        }
+    }
+}
+
+/// Templates of broadcast topics for notifications.
+/// `{room_id}` and `{audience}` placeholders get substituted on publishing.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub(crate) struct TopicsConfig {
+    /// Prepended to all topics to isolate environments sharing the same broker.
+    pub(crate) topic_prefix: String,
+    pub(crate) room_events: String,
+    pub(crate) room_editions: String,
+    pub(crate) audience_events: String,
+}
+
+impl TopicsConfig {
+    pub(crate) fn room_events(&self, room_id: Uuid) -> String {
+        self.prefixed(self.room_events.replace("{room_id}", &room_id.to_string()))
+    }
+
+    pub(crate) fn room_editions(&self, room_id: Uuid) -> String {
+        self.prefixed(
+            self.room_editions
+                .replace("{room_id}", &room_id.to_string()),
+        )
+    }
+
+    pub(crate) fn audience_events(&self, audience: &str) -> String {
+        self.prefixed(self.audience_events.replace("{audience}", audience))
+    }
+
+    fn prefixed(&self, topic: String) -> String {
+        if self.topic_prefix.is_empty() {
+            topic
+        } else {
+            format!("{}/{}", self.topic_prefix, topic)
         }
     }
+
+    /// Checks that templates reference only placeholders available for them.
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        if self.topic_prefix.ends_with('/') {
+            bail!("Topic prefix must not end with a slash");
+        }
+
+        validate_topic_template("room_events", &self.room_events, &["room_id"])?;
+        validate_topic_template("room_editions", &self.room_editions, &["room_id"])?;
+        validate_topic_template("audience_events", &self.audience_events, &["audience"])?;
+        Ok(())
+    }
+}
+
+impl Default for TopicsConfig {
+    fn default() -> Self {
+        Self {
+            topic_prefix: String::new(),
+            room_events: String::from("rooms/{room_id}/events"),
+            room_editions: String::from("rooms/{room_id}/editions"),
+            audience_events: String::from("audiences/{audience}/events"),
+        }
+    }
+}
+
+fn validate_topic_template(
+    name: &str,
+    template: &str,
+    placeholders: &[&str],
+) -> anyhow::Result<()> {
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let len = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("Unclosed placeholder in '{}' topic template", name))?;
+
+        let placeholder = &rest[(start + 1)..(start + len)];
+
+        if !placeholders.contains(&placeholder) {
+            bail!(
+                "Unknown placeholder '{{{}}}' in '{}' topic template, expected one of: {}",
+                placeholder,
+                name,
+                placeholders.join(", ")
+            );
+        }
+
+        rest = &rest[(start + len + 1)..];
+    }
+
+    Ok(())
 }
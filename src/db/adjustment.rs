@@ -96,6 +96,90 @@ impl Into<Vec<PgRange<i64>>> for Segments {
     }
 }
 
+impl Segments {
+    pub(crate) fn builder() -> SegmentsBuilder {
+        SegmentsBuilder::default()
+    }
+}
+
+/// Builds well-formed segments in milliseconds: non-empty, ordered and non-overlapping.
+#[derive(Debug, Default)]
+pub(crate) struct SegmentsBuilder {
+    segments: Vec<(i64, i64)>,
+    merge_overlapping: bool,
+}
+
+impl SegmentsBuilder {
+    /// Normalize input instead of rejecting it: empty segments get dropped while out-of-order
+    /// and overlapping ones get sorted and merged on `build`.
+    pub(crate) fn merge_overlapping(self) -> Self {
+        Self {
+            merge_overlapping: true,
+            ..self
+        }
+    }
+
+    pub(crate) fn push(mut self, start: i64, stop: i64) -> anyhow::Result<Self> {
+        if self.merge_overlapping {
+            if start < stop {
+                self.segments.push((start, stop));
+            }
+
+            return Ok(self);
+        }
+
+        if start >= stop {
+            bail!(
+                "invalid segment [{}, {}): start must be before stop",
+                start,
+                stop
+            );
+        }
+
+        if let Some((last_start, last_stop)) = self.segments.last() {
+            if start < *last_stop {
+                bail!(
+                    "segment [{}, {}) overlaps or precedes segment [{}, {})",
+                    start,
+                    stop,
+                    last_start,
+                    last_stop
+                );
+            }
+        }
+
+        self.segments.push((start, stop));
+        Ok(self)
+    }
+
+    pub(crate) fn build(mut self) -> Segments {
+        if self.merge_overlapping {
+            self.segments.sort_unstable();
+
+            let mut merged: Vec<(i64, i64)> = Vec::with_capacity(self.segments.len());
+
+            for (start, stop) in self.segments {
+                match merged.last_mut() {
+                    Some((_, last_stop)) if start <= *last_stop => {
+                        *last_stop = std::cmp::max(*last_stop, stop);
+                    }
+                    _ => merged.push((start, stop)),
+                }
+            }
+
+            self.segments = merged;
+        }
+
+        let segments = self
+            .segments
+            .into_iter()
+            .map(|(start, stop)| (Bound::Included(start), Bound::Excluded(stop)))
+            .collect::<BoundedOffsetTuples>();
+
+        Segments::from(segments)
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 pub(crate) mod serde {
@@ -120,3 +204,73 @@ pub(crate) mod serde {
         }
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use super::{BoundedOffsetTuples, Segments};
+
+    #[test]
+    fn build_segments() {
+        let segments: BoundedOffsetTuples = Segments::builder()
+            .push(0, 1000)
+            .and_then(|b| b.push(1000, 2000))
+            .and_then(|b| b.push(3000, 4000))
+            .expect("Failed to push segments")
+            .build()
+            .into();
+
+        assert_eq!(
+            segments,
+            vec![
+                (Bound::Included(0), Bound::Excluded(1000)),
+                (Bound::Included(1000), Bound::Excluded(2000)),
+                (Bound::Included(3000), Bound::Excluded(4000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_malformed_segments() {
+        // Empty and inverted segments.
+        assert!(Segments::builder().push(1000, 1000).is_err());
+        assert!(Segments::builder().push(2000, 1000).is_err());
+
+        // Overlapping segments.
+        assert!(Segments::builder()
+            .push(0, 2000)
+            .and_then(|b| b.push(1000, 3000))
+            .is_err());
+
+        // Out-of-order segments.
+        assert!(Segments::builder()
+            .push(3000, 4000)
+            .and_then(|b| b.push(0, 1000))
+            .is_err());
+    }
+
+    #[test]
+    fn merge_overlapping_segments() {
+        let segments: BoundedOffsetTuples = Segments::builder()
+            .merge_overlapping()
+            .push(5000, 6000)
+            .and_then(|b| b.push(0, 2000))
+            .and_then(|b| b.push(1000, 3000))
+            .and_then(|b| b.push(3000, 4000))
+            .and_then(|b| b.push(4500, 4500))
+            .expect("Failed to push segments")
+            .build()
+            .into();
+
+        assert_eq!(
+            segments,
+            vec![
+                (Bound::Included(0), Bound::Excluded(4000)),
+                (Bound::Included(5000), Bound::Excluded(6000)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::postgres::PgConnection;
+use svc_agent::AgentId;
+use uuid::Uuid;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// An append-only record of a moderation action.
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub(crate) struct Object {
+    id: Uuid,
+    room_id: Uuid,
+    action: String,
+    created_by: AgentId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event_id: Option<Uuid>,
+    details: JsonValue,
+    #[serde(with = "ts_seconds")]
+    created_at: DateTime<Utc>,
+}
+
+impl Object {
+    #[cfg(test)]
+    pub(crate) fn room_id(&self) -> Uuid {
+        self.room_id
+    }
+
+    #[cfg(test)]
+    pub(crate) fn action(&self) -> &str {
+        &self.action
+    }
+
+    #[cfg(test)]
+    pub(crate) fn created_by(&self) -> &AgentId {
+        &self.created_by
+    }
+
+    #[cfg(test)]
+    pub(crate) fn event_id(&self) -> Option<Uuid> {
+        self.event_id
+    }
+
+    #[cfg(test)]
+    pub(crate) fn details(&self) -> &JsonValue {
+        &self.details
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct InsertQuery {
+    room_id: Uuid,
+    action: String,
+    created_by: AgentId,
+    event_id: Option<Uuid>,
+    details: JsonValue,
+}
+
+impl InsertQuery {
+    pub(crate) fn new(room_id: Uuid, action: &str, created_by: AgentId) -> Self {
+        Self {
+            room_id,
+            action: action.to_owned(),
+            created_by,
+            event_id: None,
+            details: JsonValue::Object(Default::default()),
+        }
+    }
+
+    pub(crate) fn event_id(self, event_id: Uuid) -> Self {
+        Self {
+            event_id: Some(event_id),
+            ..self
+        }
+    }
+
+    pub(crate) fn details(self, details: JsonValue) -> Self {
+        Self { details, ..self }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Object> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            INSERT INTO audit_log (room_id, action, created_by, event_id, details)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING
+                id,
+                room_id,
+                action,
+                created_by AS "created_by!: AgentId",
+                event_id,
+                details,
+                created_at
+            "#,
+            self.room_id,
+            self.action,
+            self.created_by as AgentId,
+            self.event_id,
+            self.details,
+        )
+        .fetch_one(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct ListQuery {
+    room_id: Option<Uuid>,
+    created_by: Option<AgentId>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    limit: i64,
+}
+
+impl ListQuery {
+    pub(crate) fn new() -> Self {
+        Self {
+            room_id: None,
+            created_by: None,
+            since: None,
+            until: None,
+            limit: 100,
+        }
+    }
+
+    pub(crate) fn room_id(self, room_id: Uuid) -> Self {
+        Self {
+            room_id: Some(room_id),
+            ..self
+        }
+    }
+
+    pub(crate) fn created_by(self, created_by: AgentId) -> Self {
+        Self {
+            created_by: Some(created_by),
+            ..self
+        }
+    }
+
+    pub(crate) fn since(self, since: DateTime<Utc>) -> Self {
+        Self {
+            since: Some(since),
+            ..self
+        }
+    }
+
+    pub(crate) fn until(self, until: DateTime<Utc>) -> Self {
+        Self {
+            until: Some(until),
+            ..self
+        }
+    }
+
+    pub(crate) fn limit(self, limit: i64) -> Self {
+        Self { limit, ..self }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                room_id,
+                action,
+                created_by AS "created_by!: AgentId",
+                event_id,
+                details,
+                created_at
+            FROM audit_log
+            WHERE ($1::UUID IS NULL OR room_id = $1)
+            AND   ($2::agent_id IS NULL OR created_by = $2)
+            AND   created_at >= COALESCE($3, TO_TIMESTAMP(0))
+            AND   ($4::TIMESTAMPTZ IS NULL OR created_at < $4)
+            ORDER BY created_at DESC
+            LIMIT $5
+            "#,
+            self.room_id,
+            self.created_by as Option<AgentId>,
+            self.since,
+            self.until,
+            self.limit,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
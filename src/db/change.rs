@@ -110,7 +110,10 @@ impl FindWithRoomQuery {
                     r.tags               AS room_tags,
                     r.created_at         AS room_created_at,
                     r.preserve_history   AS room_preserve_history,
-                    r.classroom_id       AS room_classroom_id
+                    r.classroom_id       AS room_classroom_id,
+                    r.closed_at          AS room_closed_at,
+                    r.source_edition_id  AS room_source_edition_id,
+                    r.committed_at       AS room_committed_at
                 FROM change AS c
                 INNER JOIN edition AS e
                 ON e.id = c.edition_id
@@ -149,6 +152,9 @@ impl FindWithRoomQuery {
                     .created_at(row.room_created_at)
                     .preserve_history(row.room_preserve_history)
                     .classroom_id(row.room_classroom_id)
+                    .closed_at(row.room_closed_at)
+                    .source_edition_id(row.room_source_edition_id)
+                    .committed_at(row.room_committed_at)
                     .build()
                     .map_err(|err| sqlx::Error::Decode(err.into()))?;
 
@@ -333,7 +339,11 @@ impl ListQuery {
             q = q.and_where("created_at".greater_than(last_created_at));
         }
 
-        q = q.order_by("created_at".descend()).limit(self.limit);
+        // Order by id as well to keep pages stable for changes created in the same transaction.
+        q = q
+            .order_by("created_at".descend())
+            .order_by("id".descend())
+            .limit(self.limit);
 
         let (sql, bindings) = Postgres::build(q);
         let mut query = sqlx::query_as(&sql);
@@ -354,6 +364,141 @@ impl ListQuery {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Lists the edition's changes of events of the given kind ordered by `event_occurred_at`
+/// and `id`. The last seen pair is the cursor for the next page.
+#[derive(Debug)]
+pub(crate) struct OccurredAtListQuery<'a> {
+    edition_id: Uuid,
+    event_kind: &'a str,
+    last: Option<(i64, Uuid)>,
+    limit: i64,
+}
+
+impl<'a> OccurredAtListQuery<'a> {
+    pub(crate) fn new(edition_id: Uuid, event_kind: &'a str) -> Self {
+        Self {
+            edition_id,
+            event_kind,
+            last: None,
+            limit: 25,
+        }
+    }
+
+    pub(crate) fn last(self, last_occurred_at: i64, last_id: Uuid) -> Self {
+        Self {
+            last: Some((last_occurred_at, last_id)),
+            ..self
+        }
+    }
+
+    pub(crate) fn limit(self, limit: i64) -> Self {
+        Self { limit, ..self }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        let (last_occurred_at, last_id) = match self.last {
+            Some((last_occurred_at, last_id)) => (Some(last_occurred_at), Some(last_id)),
+            None => (None, None),
+        };
+
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                edition_id,
+                kind               AS "kind!: ChangeType",
+                event_id,
+                event_kind,
+                event_set,
+                event_label,
+                event_data,
+                event_occurred_at,
+                event_created_by   AS "event_created_by?: AgentId",
+                created_at
+            FROM change
+            WHERE edition_id = $1
+            AND   event_kind = $2
+            AND   ($3::BIGINT IS NULL OR (event_occurred_at, id) > ($3, $4::UUID))
+            ORDER BY event_occurred_at, id
+            LIMIT $5
+            "#,
+            self.edition_id,
+            self.event_kind,
+            last_occurred_at,
+            last_id,
+            self.limit,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct CountQuery {
+    edition_id: Uuid,
+}
+
+impl CountQuery {
+    pub(crate) fn new(edition_id: Uuid) -> Self {
+        Self { edition_id }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<i64> {
+        sqlx::query!(
+            r#"SELECT COUNT(*) AS "count!" FROM change WHERE edition_id = $1"#,
+            self.edition_id
+        )
+        .fetch_one(conn)
+        .await
+        .map(|r| r.count)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Lists ids of the edition's changes referencing events which are not alive in the room.
+#[derive(Debug)]
+pub(crate) struct DanglingListQuery {
+    edition_id: Uuid,
+    room_id: Uuid,
+}
+
+impl DanglingListQuery {
+    pub(crate) fn new(edition_id: Uuid, room_id: Uuid) -> Self {
+        Self {
+            edition_id,
+            room_id,
+        }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Uuid>> {
+        sqlx::query!(
+            r#"
+            SELECT change.id
+            FROM change
+            LEFT JOIN event
+            ON  event.id = change.event_id
+            AND event.room_id = $2
+            AND event.deleted_at IS NULL
+            WHERE change.edition_id = $1
+            AND   change.event_id IS NOT NULL
+            AND   event.id IS NULL
+            ORDER BY change.created_at
+            "#,
+            self.edition_id,
+            self.room_id,
+        )
+        .fetch_all(conn)
+        .await
+        .map(|rows| rows.into_iter().map(|r| r.id).collect())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug)]
 pub(crate) struct DeleteQuery {
     id: Uuid,
@@ -5,6 +5,7 @@ use sqlx::{postgres::PgConnection, Done};
 use svc_agent::AgentId;
 use uuid::Uuid;
 
+use crate::db::adjustment::Segments;
 use crate::db::room::{Builder as RoomBuilder, Object as Room, Time as RoomTime};
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -16,6 +17,14 @@ pub(crate) struct Object {
     created_by: AgentId,
     #[serde(with = "ts_seconds")]
     created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change_count: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    committed_room_id: Option<Uuid>,
+    #[serde(skip)]
+    committed_segments: Option<Segments>,
+    #[serde(skip)]
+    committed_change_ids: Option<Vec<Uuid>>,
 }
 
 impl Object {
@@ -26,6 +35,35 @@ impl Object {
     pub(crate) fn source_room_id(&self) -> Uuid {
         self.source_room_id
     }
+
+    #[cfg(test)]
+    pub(crate) fn change_count(&self) -> Option<i64> {
+        self.change_count
+    }
+
+    /// Destination room and its segments when the edition has been committed already.
+    pub(crate) fn committed(&self) -> Option<(Uuid, &Segments)> {
+        match (self.committed_room_id, &self.committed_segments) {
+            (Some(room_id), Some(segments)) => Some((room_id, segments)),
+            _ => None,
+        }
+    }
+
+    /// Whether the edition has been committed with the same `change_ids` filter regardless
+    /// of their order. `None` stands for all changes.
+    pub(crate) fn is_committed_with(&self, change_ids: Option<&[Uuid]>) -> bool {
+        self.committed_room_id.is_some()
+            && self.committed_change_ids == normalize_change_ids(change_ids)
+    }
+}
+
+fn normalize_change_ids(change_ids: Option<&[Uuid]>) -> Option<Vec<Uuid>> {
+    change_ids.map(|ids| {
+        let mut ids = ids.to_vec();
+        ids.sort();
+        ids.dedup();
+        ids
+    })
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -51,6 +89,9 @@ impl FindWithRoomQuery {
                 e.source_room_id   AS edition_source_room_id,
                 e.created_by       AS "edition_created_by!: AgentId",
                 e.created_at       AS edition_created_at,
+                e.committed_room_id AS edition_committed_room_id,
+                e.committed_segments AS "edition_committed_segments: Segments",
+                e.committed_change_ids AS edition_committed_change_ids,
                 r.id               AS room_id,
                 r.audience         AS room_audience,
                 r.source_room_id   AS room_source_room_id,
@@ -58,7 +99,10 @@ impl FindWithRoomQuery {
                 r.tags             AS room_tags,
                 r.created_at       AS room_created_at,
                 r.preserve_history AS room_preserve_history,
-                r.classroom_id     AS room_classroom_id
+                r.classroom_id     AS room_classroom_id,
+                r.closed_at        AS room_closed_at,
+                r.source_edition_id AS room_source_edition_id,
+                r.committed_at     AS room_committed_at
             FROM edition AS e
             INNER JOIN room AS r
             ON r.id = e.source_room_id
@@ -77,6 +121,10 @@ impl FindWithRoomQuery {
                     source_room_id: row.edition_source_room_id,
                     created_by: row.edition_created_by,
                     created_at: row.edition_created_at,
+                    change_count: None,
+                    committed_room_id: row.edition_committed_room_id,
+                    committed_segments: row.edition_committed_segments,
+                    committed_change_ids: row.edition_committed_change_ids,
                 };
 
                 let room = RoomBuilder::new()
@@ -88,6 +136,9 @@ impl FindWithRoomQuery {
                     .created_at(row.room_created_at)
                     .preserve_history(row.room_preserve_history)
                     .classroom_id(row.room_classroom_id)
+                    .closed_at(row.room_closed_at)
+                    .source_edition_id(row.room_source_edition_id)
+                    .committed_at(row.room_committed_at)
                     .build()
                     .map_err(|err| sqlx::Error::Decode(err.into()))?;
 
@@ -119,7 +170,15 @@ impl<'a> InsertQuery<'a> {
             r#"
             INSERT INTO edition (source_room_id, created_by)
             VALUES ($1, $2)
-            RETURNING id, source_room_id, created_by AS "created_by!: AgentId", created_at
+            RETURNING
+                id,
+                source_room_id,
+                created_by AS "created_by!: AgentId",
+                created_at,
+                NULL::BIGINT AS change_count,
+                committed_room_id,
+                committed_segments AS "committed_segments: Segments",
+                committed_change_ids
             "#,
             self.source_room_id,
             self.created_by.to_owned() as AgentId,
@@ -136,6 +195,8 @@ pub(crate) struct ListQuery {
     source_room_id: Uuid,
     last_created_at: Option<DateTime<Utc>>,
     limit: i64,
+    with_change_count: bool,
+    include_derived: bool,
 }
 
 impl ListQuery {
@@ -144,6 +205,23 @@ impl ListQuery {
             limit: 25,
             last_created_at: None,
             source_room_id,
+            with_change_count: false,
+            include_derived: false,
+        }
+    }
+
+    /// Also list editions of rooms derived from the source room directly or transitively.
+    pub(crate) fn include_derived(self, include_derived: bool) -> Self {
+        Self {
+            include_derived,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_change_count(self, with_change_count: bool) -> Self {
+        Self {
+            with_change_count,
+            ..self
         }
     }
 
@@ -162,9 +240,29 @@ impl ListQuery {
         sqlx::query_as!(
             Object,
             r#"
-            SELECT id, source_room_id, created_by AS "created_by!: AgentId", created_at
+            WITH RECURSIVE lineage AS (
+                SELECT $1::UUID AS id
+                UNION
+                SELECT room.id
+                FROM room
+                INNER JOIN lineage
+                ON room.source_room_id = lineage.id
+                WHERE $5
+            )
+            SELECT
+                id,
+                source_room_id,
+                created_by AS "created_by!: AgentId",
+                created_at,
+                (CASE WHEN $4
+                    THEN (SELECT COUNT(*) FROM change WHERE change.edition_id = edition.id)
+                    END
+                ) AS change_count,
+                committed_room_id,
+                committed_segments AS "committed_segments: Segments",
+                committed_change_ids
             FROM edition
-            WHERE source_room_id = $1
+            WHERE source_room_id IN (SELECT id FROM lineage)
             AND   created_at > COALESCE($2, TO_TIMESTAMP(0))
             ORDER BY created_at DESC
             LIMIT $3
@@ -172,6 +270,8 @@ impl ListQuery {
             self.source_room_id,
             self.last_created_at,
             self.limit,
+            self.with_change_count,
+            self.include_derived,
         )
         .fetch_all(conn)
         .await
@@ -180,6 +280,52 @@ impl ListQuery {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Marks the edition as committed into the destination room.
+/// Affects no rows when the edition has been committed already.
+#[derive(Debug)]
+pub(crate) struct CommitQuery {
+    id: Uuid,
+    committed_room_id: Uuid,
+    committed_segments: Segments,
+    committed_change_ids: Option<Vec<Uuid>>,
+}
+
+impl CommitQuery {
+    pub(crate) fn new(
+        id: Uuid,
+        committed_room_id: Uuid,
+        committed_segments: Segments,
+        committed_change_ids: Option<&[Uuid]>,
+    ) -> Self {
+        Self {
+            id,
+            committed_room_id,
+            committed_segments,
+            committed_change_ids: normalize_change_ids(committed_change_ids),
+        }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<usize> {
+        sqlx::query!(
+            r#"
+            UPDATE edition
+            SET committed_room_id = $2, committed_segments = $3, committed_change_ids = $4
+            WHERE id = $1
+            AND   committed_room_id IS NULL
+            "#,
+            self.id,
+            self.committed_room_id,
+            self.committed_segments as Segments,
+            self.committed_change_ids.as_deref(),
+        )
+        .execute(conn)
+        .await
+        .map(|r| r.rows_affected() as usize)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug)]
 pub(crate) struct DeleteQuery {
     id: Uuid,
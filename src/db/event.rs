@@ -8,6 +8,25 @@ use uuid::Uuid;
 
 ////////////////////////////////////////////////////////////////////////////////
 
+// `occurred_at` is always stored as the number of nanoseconds since the room opening.
+// Convert with these functions instead of calling `chrono` directly to keep the unit consistent.
+
+/// Converts a duration since the room opening into `occurred_at`, saturating on overflow.
+pub(crate) fn occurred_at_from_duration(duration: Duration) -> i64 {
+    duration.num_nanoseconds().unwrap_or(std::i64::MAX)
+}
+
+/// Converts `occurred_at` back into a duration since the room opening.
+pub(crate) fn occurred_at_to_duration(occurred_at: i64) -> Duration {
+    Duration::nanoseconds(occurred_at)
+}
+
+/// The attribute of a label's latest version marking the label as deleted.
+/// Such labels are omitted from the state and vacuumed after `max_deleted_lifetime`.
+pub(crate) const TOMBSTONE_ATTRIBUTE: &str = "deleted";
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Clone, Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub(crate) struct Object {
     id: Uuid,
@@ -17,7 +36,8 @@ pub(crate) struct Object {
     set: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     label: Option<String>,
-    attribute: Option<String>,
+    #[serde(flatten, with = "crate::serde::attributes_with_legacy_attribute")]
+    attributes: Vec<String>,
     data: JsonValue,
     occurred_at: i64,
     created_by: AgentId,
@@ -31,6 +51,10 @@ pub(crate) struct Object {
     )]
     deleted_at: Option<DateTime<Utc>>,
     original_occurred_at: i64,
+    /// `occurred_at` of the event in the source room it was cloned from by an adjustment
+    /// or an edition commit. Allows mapping the edited timeline back to the source one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_occurred_at: Option<i64>,
     // TODO: remove Option and make the field NOT NULL once migrated production data.
     #[serde(skip_serializing_if = "Option::is_none")]
     original_created_by: Option<AgentId>,
@@ -46,24 +70,21 @@ impl Object {
         self.room_id
     }
 
-    #[cfg(test)]
     pub(crate) fn kind(&self) -> &str {
         &self.kind
     }
 
-    #[cfg(test)]
     pub(crate) fn set(&self) -> &str {
         &self.set
     }
 
-    #[cfg(test)]
     pub(crate) fn label(&self) -> Option<&str> {
         self.label.as_deref()
     }
 
     #[cfg(test)]
-    pub(crate) fn attribute(&self) -> Option<&str> {
-        self.attribute.as_deref()
+    pub(crate) fn attributes(&self) -> &[String] {
+        &self.attributes
     }
 
     pub(crate) fn data(&self) -> &JsonValue {
@@ -78,10 +99,24 @@ impl Object {
         &self.created_by
     }
 
+    pub(crate) fn is_tombstone(&self) -> bool {
+        self.attributes.iter().any(|a| a == TOMBSTONE_ATTRIBUTE)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
     #[cfg(test)]
     pub(crate) fn original_occurred_at(&self) -> i64 {
         self.original_occurred_at
     }
+
+    #[cfg(test)]
+    pub(crate) fn source_occurred_at(&self) -> Option<i64> {
+        self.source_occurred_at
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -95,7 +130,8 @@ pub(crate) struct Builder {
     data: Option<JsonValue>,
     occurred_at: Option<i64>,
     created_by: Option<AgentId>,
-    attribute: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    attributes: Vec<String>,
 }
 
 impl Builder {
@@ -131,9 +167,9 @@ impl Builder {
         }
     }
 
-    pub(crate) fn attribute(self, attribute: &str) -> Self {
+    pub(crate) fn attributes(self, attributes: &[String]) -> Self {
         Self {
-            attribute: Some(attribute.to_owned()),
+            attributes: attributes.to_owned(),
             ..self
         }
     }
@@ -159,6 +195,13 @@ impl Builder {
         }
     }
 
+    pub(crate) fn created_at(self, created_at: DateTime<Utc>) -> Self {
+        Self {
+            created_at: Some(created_at),
+            ..self
+        }
+    }
+
     pub(crate) fn build(self) -> Result<Object, &'static str> {
         let room_id = self.room_id.ok_or("Missing `room_id`")?;
         let kind = self.kind.ok_or("Missing `kind`")?;
@@ -173,13 +216,14 @@ impl Builder {
             kind,
             set,
             label: self.label,
-            attribute: self.attribute,
+            attributes: self.attributes,
             data,
             occurred_at,
             created_by: created_by.clone(),
-            created_at: Utc::now(),
+            created_at: self.created_at.unwrap_or_else(Utc::now),
             deleted_at: None,
             original_occurred_at: occurred_at,
+            source_occurred_at: None,
             original_created_by: Some(created_by),
         })
     }
@@ -200,6 +244,20 @@ impl Default for Direction {
     }
 }
 
+/// Column the events list is sorted and paginated by.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ListOrder {
+    OccurredAt,
+    CreatedAt,
+}
+
+impl Default for ListOrder {
+    fn default() -> Self {
+        Self::OccurredAt
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug)]
@@ -214,9 +272,13 @@ pub(crate) struct ListQuery<'a> {
     kind: Option<KindFilter>,
     set: Option<&'a str>,
     label: Option<&'a str>,
-    attribute: Option<&'a str>,
+    attributes: Option<&'a [String]>,
+    data_filter: Option<&'a JsonValue>,
     last_occurred_at: Option<i64>,
+    last_created_at: Option<DateTime<Utc>>,
+    last_id: Option<Uuid>,
     direction: Direction,
+    order: ListOrder,
     limit: Option<usize>,
 }
 
@@ -260,9 +322,18 @@ impl<'a> ListQuery<'a> {
         }
     }
 
-    pub(crate) fn attribute(self, attribute: &'a str) -> Self {
+    /// Only events having all of the given attributes.
+    pub(crate) fn attributes(self, attributes: &'a [String]) -> Self {
         Self {
-            attribute: Some(attribute),
+            attributes: Some(attributes),
+            ..self
+        }
+    }
+
+    /// Only events whose `data` contains the given JSON object.
+    pub(crate) fn data_filter(self, data_filter: &'a JsonValue) -> Self {
+        Self {
+            data_filter: Some(data_filter),
             ..self
         }
     }
@@ -274,10 +345,30 @@ impl<'a> ListQuery<'a> {
         }
     }
 
+    pub(crate) fn last_created_at(self, last_created_at: DateTime<Utc>) -> Self {
+        Self {
+            last_created_at: Some(last_created_at),
+            ..self
+        }
+    }
+
+    /// Keyset cursor for `ListOrder::CreatedAt`: continue right after the event with this id
+    /// comparing by `(created_at, id)` at the stored precision.
+    pub(crate) fn last_id(self, last_id: Uuid) -> Self {
+        Self {
+            last_id: Some(last_id),
+            ..self
+        }
+    }
+
     pub(crate) fn direction(self, direction: Direction) -> Self {
         Self { direction, ..self }
     }
 
+    pub(crate) fn order(self, order: ListOrder) -> Self {
+        Self { order, ..self }
+    }
+
     pub(crate) fn limit(self, limit: usize) -> Self {
         Self {
             limit: Some(limit),
@@ -286,71 +377,355 @@ impl<'a> ListQuery<'a> {
     }
 
     pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
-        use quaint::ast::{Comparable, Orderable, ParameterizedValue, Select};
-        use quaint::visitor::{Postgres, Visitor};
+        let kinds = match self.kind {
+            Some(KindFilter::Single(kind)) => Some(vec![kind]),
+            Some(KindFilter::Multiple(kinds)) => Some(kinds),
+            None => None,
+        };
 
-        let mut q = Select::from_table("event").so_that("deleted_at".is_null());
+        let attributes = self.attributes.map(|attributes| attributes.to_vec());
+        let data_filter = self.data_filter.cloned();
+        let limit = self.limit.map(|limit| limit as i64);
 
-        if let Some(room_id) = self.room_id {
-            q = q.and_where("room_id".equals(room_id));
+        // The queries differ only in the pagination condition and ordering.
+        match (self.order, self.direction) {
+            (ListOrder::OccurredAt, Direction::Forward) => {
+                sqlx::query_as!(
+                    Object,
+                    r#"
+                    SELECT
+                        id,
+                        room_id,
+                        kind,
+                        set,
+                        label,
+                        attributes,
+                        data,
+                        occurred_at,
+                        created_by AS "created_by!: AgentId",
+                        created_at,
+                        deleted_at,
+                        original_occurred_at,
+                        source_occurred_at,
+                        original_created_by AS "original_created_by: AgentId"
+                    FROM event
+                    WHERE deleted_at IS NULL
+                    AND   ($1::UUID IS NULL OR room_id = $1)
+                    AND   ($2::TEXT[] IS NULL OR kind = ANY($2))
+                    AND   ($3::TEXT IS NULL OR set = $3)
+                    AND   ($4::TEXT IS NULL OR label = $4)
+                    AND   ($5::TEXT[] IS NULL OR attributes @> $5)
+                    AND   ($6::JSONB IS NULL OR data @> $6)
+                    AND   ($7::BIGINT IS NULL OR occurred_at > $7)
+                    ORDER BY occurred_at, created_at
+                    LIMIT $8
+                    "#,
+                    self.room_id,
+                    kinds,
+                    self.set,
+                    self.label,
+                    attributes,
+                    data_filter,
+                    self.last_occurred_at,
+                    limit,
+                )
+                .fetch_all(conn)
+                .await
+            }
+            (ListOrder::OccurredAt, Direction::Backward) => {
+                sqlx::query_as!(
+                    Object,
+                    r#"
+                    SELECT
+                        id,
+                        room_id,
+                        kind,
+                        set,
+                        label,
+                        attributes,
+                        data,
+                        occurred_at,
+                        created_by AS "created_by!: AgentId",
+                        created_at,
+                        deleted_at,
+                        original_occurred_at,
+                        source_occurred_at,
+                        original_created_by AS "original_created_by: AgentId"
+                    FROM event
+                    WHERE deleted_at IS NULL
+                    AND   ($1::UUID IS NULL OR room_id = $1)
+                    AND   ($2::TEXT[] IS NULL OR kind = ANY($2))
+                    AND   ($3::TEXT IS NULL OR set = $3)
+                    AND   ($4::TEXT IS NULL OR label = $4)
+                    AND   ($5::TEXT[] IS NULL OR attributes @> $5)
+                    AND   ($6::JSONB IS NULL OR data @> $6)
+                    AND   ($7::BIGINT IS NULL OR occurred_at < $7)
+                    ORDER BY occurred_at DESC, created_at DESC
+                    LIMIT $8
+                    "#,
+                    self.room_id,
+                    kinds,
+                    self.set,
+                    self.label,
+                    attributes,
+                    data_filter,
+                    self.last_occurred_at,
+                    limit,
+                )
+                .fetch_all(conn)
+                .await
+            }
+            (ListOrder::CreatedAt, Direction::Forward) => {
+                sqlx::query_as!(
+                    Object,
+                    r#"
+                    SELECT
+                        id,
+                        room_id,
+                        kind,
+                        set,
+                        label,
+                        attributes,
+                        data,
+                        occurred_at,
+                        created_by AS "created_by!: AgentId",
+                        created_at,
+                        deleted_at,
+                        original_occurred_at,
+                        source_occurred_at,
+                        original_created_by AS "original_created_by: AgentId"
+                    FROM event
+                    WHERE deleted_at IS NULL
+                    AND   ($1::UUID IS NULL OR room_id = $1)
+                    AND   ($2::TEXT[] IS NULL OR kind = ANY($2))
+                    AND   ($3::TEXT IS NULL OR set = $3)
+                    AND   ($4::TEXT IS NULL OR label = $4)
+                    AND   ($5::TEXT[] IS NULL OR attributes @> $5)
+                    AND   ($6::JSONB IS NULL OR data @> $6)
+                    AND   ($7::TIMESTAMPTZ IS NULL OR created_at > $7)
+                    AND   ($9::UUID IS NULL OR (created_at, id) > (
+                        SELECT created_at, id FROM event WHERE id = $9
+                    ))
+                    ORDER BY created_at, id
+                    LIMIT $8
+                    "#,
+                    self.room_id,
+                    kinds,
+                    self.set,
+                    self.label,
+                    attributes,
+                    data_filter,
+                    self.last_created_at,
+                    limit,
+                    self.last_id,
+                )
+                .fetch_all(conn)
+                .await
+            }
+            (ListOrder::CreatedAt, Direction::Backward) => {
+                sqlx::query_as!(
+                    Object,
+                    r#"
+                    SELECT
+                        id,
+                        room_id,
+                        kind,
+                        set,
+                        label,
+                        attributes,
+                        data,
+                        occurred_at,
+                        created_by AS "created_by!: AgentId",
+                        created_at,
+                        deleted_at,
+                        original_occurred_at,
+                        source_occurred_at,
+                        original_created_by AS "original_created_by: AgentId"
+                    FROM event
+                    WHERE deleted_at IS NULL
+                    AND   ($1::UUID IS NULL OR room_id = $1)
+                    AND   ($2::TEXT[] IS NULL OR kind = ANY($2))
+                    AND   ($3::TEXT IS NULL OR set = $3)
+                    AND   ($4::TEXT IS NULL OR label = $4)
+                    AND   ($5::TEXT[] IS NULL OR attributes @> $5)
+                    AND   ($6::JSONB IS NULL OR data @> $6)
+                    AND   ($7::TIMESTAMPTZ IS NULL OR created_at < $7)
+                    AND   ($9::UUID IS NULL OR (created_at, id) < (
+                        SELECT created_at, id FROM event WHERE id = $9
+                    ))
+                    ORDER BY created_at DESC, id DESC
+                    LIMIT $8
+                    "#,
+                    self.room_id,
+                    kinds,
+                    self.set,
+                    self.label,
+                    attributes,
+                    data_filter,
+                    self.last_created_at,
+                    limit,
+                    self.last_id,
+                )
+                .fetch_all(conn)
+                .await
+            }
         }
+    }
+}
 
-        q = match self.kind {
-            Some(KindFilter::Single(ref kind)) => q.and_where("kind".equals(kind.as_str())),
-            Some(KindFilter::Multiple(ref kinds)) => {
-                let kinds = kinds.iter().map(|k| k.as_str()).collect::<Vec<&str>>();
-                q.and_where("kind".in_selection(kinds))
-            }
-            None => q,
-        };
+///////////////////////////////////////////////////////////////////////////////
+
+/// Counts non-deleted room events matching the same filters as `ListQuery` except pagination.
+#[derive(Debug)]
+pub(crate) struct CountQuery<'a> {
+    room_id: Uuid,
+    kinds: Option<Vec<String>>,
+    set: Option<&'a str>,
+    label: Option<&'a str>,
+    attributes: Option<Vec<String>>,
+    data_filter: Option<JsonValue>,
+}
 
-        if let Some(set) = self.set {
-            q = q.and_where("set".equals(set));
+impl<'a> CountQuery<'a> {
+    pub(crate) fn new(room_id: Uuid) -> Self {
+        Self {
+            room_id,
+            kinds: None,
+            set: None,
+            label: None,
+            attributes: None,
+            data_filter: None,
         }
+    }
 
-        if let Some(label) = self.label {
-            q = q.and_where("label".equals(label));
+    pub(crate) fn kinds(self, kinds: Vec<String>) -> Self {
+        Self {
+            kinds: Some(kinds),
+            ..self
         }
+    }
 
-        if let Some(attribute) = self.attribute {
-            q = q.and_where("attribute".equals(attribute));
+    pub(crate) fn set(self, set: &'a str) -> Self {
+        Self {
+            set: Some(set),
+            ..self
         }
+    }
 
-        if let Some(limit) = self.limit {
-            q = q.limit(limit);
+    pub(crate) fn label(self, label: &'a str) -> Self {
+        Self {
+            label: Some(label),
+            ..self
         }
+    }
 
-        q = match self.direction {
-            Direction::Forward => {
-                if let Some(last_occurred_at) = self.last_occurred_at {
-                    q = q.and_where("occurred_at".greater_than(last_occurred_at));
-                }
+    pub(crate) fn attributes(self, attributes: Vec<String>) -> Self {
+        Self {
+            attributes: Some(attributes),
+            ..self
+        }
+    }
 
-                q.order_by("occurred_at").order_by("created_at")
-            }
-            Direction::Backward => {
-                if let Some(last_occurred_at) = self.last_occurred_at {
-                    q = q.and_where("occurred_at".less_than(last_occurred_at));
-                }
+    pub(crate) fn data_filter(self, data_filter: JsonValue) -> Self {
+        Self {
+            data_filter: Some(data_filter),
+            ..self
+        }
+    }
 
-                q.order_by("occurred_at".descend())
-                    .order_by("created_at".descend())
-            }
-        };
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<i64> {
+        sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "total!"
+            FROM event
+            WHERE room_id = $1
+            AND   deleted_at IS NULL
+            AND   ($2::TEXT[] IS NULL OR kind = ANY($2))
+            AND   ($3::TEXT IS NULL OR set = $3)
+            AND   ($4::TEXT IS NULL OR label = $4)
+            AND   ($5::TEXT[] IS NULL OR attributes @> $5)
+            AND   ($6::JSONB IS NULL OR data @> $6)
+            "#,
+            self.room_id,
+            self.kinds,
+            self.set,
+            self.label,
+            self.attributes,
+            self.data_filter,
+        )
+        .fetch_one(conn)
+        .await
+        .map(|r| r.total)
+    }
+}
 
-        let (sql, bindings) = Postgres::build(q);
-        let mut query = sqlx::query_as(&sql);
+///////////////////////////////////////////////////////////////////////////////
 
-        for binding in bindings {
-            query = match binding {
-                ParameterizedValue::Integer(value) => query.bind(value),
-                ParameterizedValue::Text(value) => query.bind(value.to_string()),
-                ParameterizedValue::Uuid(value) => query.bind(value),
-                _ => query,
-            }
+/// Lists the latest non-deleted events of several rooms at once limiting the number of events
+/// per room. Events are ordered by room and then from the latest to the earliest.
+#[derive(Debug)]
+pub(crate) struct MultiRoomListQuery {
+    room_ids: Vec<Uuid>,
+    kind: Option<String>,
+    limit_per_room: i64,
+}
+
+impl MultiRoomListQuery {
+    pub(crate) fn new(room_ids: Vec<Uuid>, limit_per_room: i64) -> Self {
+        Self {
+            room_ids,
+            kind: None,
+            limit_per_room,
+        }
+    }
+
+    pub(crate) fn kind(self, kind: String) -> Self {
+        Self {
+            kind: Some(kind),
+            ..self
         }
+    }
 
-        query.fetch_all(conn).await
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                room_id,
+                kind,
+                set,
+                label,
+                attributes,
+                data,
+                occurred_at,
+                created_by as "created_by!: AgentId",
+                created_at,
+                deleted_at,
+                original_occurred_at,
+                source_occurred_at,
+                original_created_by as "original_created_by: AgentId"
+            FROM (
+                SELECT
+                    *,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY room_id
+                        ORDER BY occurred_at DESC, created_at DESC
+                    ) AS ordinal
+                FROM event
+                WHERE room_id = ANY($1)
+                AND   deleted_at IS NULL
+                AND   ($2::TEXT IS NULL OR kind = $2)
+            ) AS q
+            WHERE ordinal <= $3
+            ORDER BY room_id, occurred_at DESC, created_at DESC
+            "#,
+            &self.room_ids,
+            self.kind,
+            self.limit_per_room,
+        )
+        .fetch_all(conn)
+        .await
     }
 }
 
@@ -363,10 +738,11 @@ pub(crate) struct InsertQuery {
     set: String,
     label: Option<String>,
     data: JsonValue,
-    attribute: Option<String>,
+    attributes: Vec<String>,
     occurred_at: i64,
     created_by: AgentId,
     created_at: Option<DateTime<Utc>>,
+    monotonic_occurred_at: bool,
 }
 
 impl InsertQuery {
@@ -382,11 +758,12 @@ impl InsertQuery {
             set: kind.clone(),
             kind,
             label: None,
-            attribute: None,
+            attributes: vec![],
             data,
             occurred_at,
             created_by,
             created_at: None,
+            monotonic_occurred_at: false,
         }
     }
 
@@ -401,17 +778,22 @@ impl InsertQuery {
         }
     }
 
-    pub(crate) fn attribute(self, attribute: String) -> Self {
+    pub(crate) fn attributes(self, attributes: Vec<String>) -> Self {
+        Self { attributes, ..self }
+    }
+
+    pub(crate) fn created_at(self, created_at: DateTime<Utc>) -> Self {
         Self {
-            attribute: Some(attribute),
+            created_at: Some(created_at),
             ..self
         }
     }
 
-    #[cfg(test)]
-    pub(crate) fn created_at(self, created_at: DateTime<Utc>) -> Self {
+    /// Take `occurred_at` from the room's clock which never goes backwards:
+    /// it's the given value unless it's not greater than the previous one in the room.
+    pub(crate) fn monotonic_occurred_at(self, monotonic_occurred_at: bool) -> Self {
         Self {
-            created_at: Some(created_at),
+            monotonic_occurred_at,
             ..self
         }
     }
@@ -420,42 +802,65 @@ impl InsertQuery {
         sqlx::query_as!(
             Object,
             r#"
+            WITH clock AS (
+                INSERT INTO room_clock (room_id, last_occurred_at)
+                SELECT $1, $7
+                WHERE $10
+                ON CONFLICT (room_id) DO UPDATE
+                SET last_occurred_at = GREATEST(
+                    room_clock.last_occurred_at + 1,
+                    EXCLUDED.last_occurred_at
+                )
+                RETURNING last_occurred_at
+            )
             INSERT INTO event (
                 room_id,
                 set,
                 kind,
                 label,
-                attribute,
+                attributes,
                 data,
                 occurred_at,
                 created_by,
                 created_at
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            VALUES (
+                $1,
+                $2,
+                $3,
+                $4,
+                $5,
+                $6,
+                COALESCE((SELECT last_occurred_at FROM clock), $7),
+                $8,
+                $9
+            )
             RETURNING
                 id,
                 room_id,
                 kind,
                 set,
                 label,
-                attribute,
+                attributes,
                 data,
                 occurred_at,
                 created_by AS "created_by!: AgentId",
                 created_at,
                 deleted_at,
                 original_occurred_at,
+                source_occurred_at,
                 original_created_by as "original_created_by: AgentId"
             "#,
             self.room_id,
             self.set,
             self.kind,
             self.label,
-            self.attribute,
+            self.attributes,
             self.data,
             self.occurred_at,
             self.created_by as AgentId,
             self.created_at.unwrap_or_else(|| Utc::now()),
+            self.monotonic_occurred_at,
         )
         .fetch_one(conn)
         .await
@@ -494,22 +899,141 @@ impl<'a> DeleteQuery<'a> {
 
 ///////////////////////////////////////////////////////////////////////////////
 
+/// Moves the event to another set and label recalculating its original event fields
+/// like the insert trigger does.
+#[derive(Debug)]
+pub(crate) struct MoveQuery {
+    room_id: Uuid,
+    id: Uuid,
+    set: String,
+    label: Option<String>,
+}
+
+impl MoveQuery {
+    pub(crate) fn new(room_id: Uuid, id: Uuid, set: String, label: Option<String>) -> Self {
+        Self {
+            room_id,
+            id,
+            set,
+            label,
+        }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            WITH original AS (
+                SELECT occurred_at, created_by
+                FROM event
+                WHERE deleted_at IS NULL
+                AND   room_id = $1
+                AND   set = $3
+                AND   label = $4
+                AND   id <> $2
+                ORDER BY occurred_at
+                LIMIT 1
+            )
+            UPDATE event
+            SET set = $3,
+                label = $4,
+                original_occurred_at = LEAST(
+                    event.occurred_at,
+                    COALESCE((SELECT occurred_at FROM original), event.occurred_at)
+                ),
+                original_created_by = CASE
+                    WHEN (SELECT occurred_at FROM original) < event.occurred_at
+                    THEN (SELECT created_by FROM original)
+                    ELSE event.created_by
+                END
+            WHERE deleted_at IS NULL
+            AND   room_id = $1
+            AND   id = $2
+            RETURNING
+                id,
+                room_id,
+                kind,
+                set,
+                label,
+                attributes,
+                data,
+                occurred_at,
+                created_by AS "created_by!: AgentId",
+                created_at,
+                deleted_at,
+                original_occurred_at,
+                source_occurred_at,
+                original_created_by AS "original_created_by: AgentId"
+            "#,
+            self.room_id,
+            self.id,
+            self.set,
+            self.label,
+        )
+        .fetch_optional(conn)
+        .await
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Tells whether the set is a collection of labeled events or a simple set of unlabeled ones.
+/// Returns `None` for an empty set. The event with `except_id` is not taken into account.
+#[derive(Debug)]
+pub(crate) struct SetLabelingQuery<'a> {
+    room_id: Uuid,
+    set: &'a str,
+    except_id: Uuid,
+}
+
+impl<'a> SetLabelingQuery<'a> {
+    pub(crate) fn new(room_id: Uuid, set: &'a str, except_id: Uuid) -> Self {
+        Self {
+            room_id,
+            set,
+            except_id,
+        }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<bool>> {
+        sqlx::query!(
+            r#"
+            SELECT label IS NOT NULL AS "is_labeled!"
+            FROM event
+            WHERE deleted_at IS NULL
+            AND   room_id = $1
+            AND   set = $2
+            AND   id <> $3
+            LIMIT 1
+            "#,
+            self.room_id,
+            self.set,
+            self.except_id,
+        )
+        .fetch_optional(conn)
+        .await
+        .map(|maybe_row| maybe_row.map(|row| row.is_labeled))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
 #[derive(Clone)]
-pub(crate) struct SetStateQuery<'a> {
+pub(crate) struct SetStateQuery {
     room_id: Uuid,
     set: String,
-    attribute: Option<&'a str>,
+    attributes: Option<Vec<String>>,
     occurred_at: Option<i64>,
     original_occurred_at: i64,
     limit: i64,
 }
 
-impl<'a> SetStateQuery<'a> {
+impl SetStateQuery {
     pub(crate) fn new(room_id: Uuid, set: String, original_occurred_at: i64, limit: i64) -> Self {
         Self {
             room_id,
             set,
-            attribute: None,
+            attributes: None,
             occurred_at: None,
             original_occurred_at,
             limit,
@@ -523,15 +1047,19 @@ impl<'a> SetStateQuery<'a> {
         }
     }
 
-    pub(crate) fn attribute(self, attribute: &'a str) -> Self {
+    /// Only labels whose latest version has all of the given attributes.
+    pub(crate) fn attributes(self, attributes: Vec<String>) -> Self {
         Self {
-            attribute: Some(attribute),
+            attributes: Some(attributes),
             ..self
         }
     }
 
+    /// Ties on `occurred_at` are broken by `created_at` and then `id` so that
+    /// the same data always yields the same state.
+    /// Labels whose latest version is a tombstone are omitted.
     pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
-        if let Some(attribute) = self.attribute {
+        if let Some(attributes) = self.attributes {
             sqlx::query_as!(
                 Object,
                 r#"
@@ -541,20 +1069,21 @@ impl<'a> SetStateQuery<'a> {
                     kind,
                     set,
                     label,
-                    attribute,
+                    attributes,
                     data,
                     occurred_at,
                     created_by as "created_by!: AgentId",
                     created_at,
                     deleted_at,
                     original_occurred_at,
+                    source_occurred_at,
                     original_created_by as "original_created_by: AgentId"
                 FROM (
                     SELECT DISTINCT ON(original_occurred_at, label)
                         *,
                         ROW_NUMBER() OVER (
                             PARTITION BY room_id, set, label
-                            ORDER BY occurred_at DESC
+                            ORDER BY occurred_at DESC, created_at DESC, id DESC
                         ) AS reverse_ordinal
                     FROM event
                     WHERE deleted_at IS NULL
@@ -562,15 +1091,17 @@ impl<'a> SetStateQuery<'a> {
                     AND   set = $2
                     AND   original_occurred_at < $4
                     AND   occurred_at < COALESCE($5, 9223372036854775807)
-                    ORDER BY original_occurred_at DESC, label ASC, occurred_at DESC
+                    ORDER BY original_occurred_at DESC, label ASC, occurred_at DESC, created_at DESC, id DESC
                 ) AS q
                 WHERE reverse_ordinal = 1
-                AND   attribute = $3
+                AND   attributes @> $3
+                AND   NOT attributes @> ARRAY['deleted']
+                ORDER BY original_occurred_at DESC, label ASC
                 LIMIT $6
                 "#,
                 self.room_id,
                 self.set,
-                attribute,
+                attributes,
                 self.original_occurred_at,
                 self.occurred_at,
                 self.limit,
@@ -581,27 +1112,33 @@ impl<'a> SetStateQuery<'a> {
             sqlx::query_as!(
                 Object,
                 r#"
-                SELECT DISTINCT ON(original_occurred_at, label)
+                SELECT
                     id,
                     room_id,
                     kind,
                     set,
                     label,
-                    attribute,
+                    attributes,
                     data,
                     occurred_at,
                     created_by as "created_by!: AgentId",
                     created_at,
                     deleted_at,
                     original_occurred_at,
+                    source_occurred_at,
                     original_created_by as "original_created_by: AgentId"
-                FROM event
-                WHERE deleted_at IS NULL
-                AND   room_id = $1
-                AND   set = $2
-                AND   original_occurred_at < $3
-                AND   occurred_at < COALESCE($4, 9223372036854775807)
-                ORDER BY original_occurred_at DESC, label ASC, occurred_at DESC
+                FROM (
+                    SELECT DISTINCT ON(original_occurred_at, label) *
+                    FROM event
+                    WHERE deleted_at IS NULL
+                    AND   room_id = $1
+                    AND   set = $2
+                    AND   original_occurred_at < $3
+                    AND   occurred_at < COALESCE($4, 9223372036854775807)
+                    ORDER BY original_occurred_at DESC, label ASC, occurred_at DESC, created_at DESC, id DESC
+                ) AS q
+                WHERE NOT attributes @> ARRAY['deleted']
+                ORDER BY original_occurred_at DESC, label ASC
                 LIMIT $5
                 "#,
                 self.room_id,
@@ -618,18 +1155,23 @@ impl<'a> SetStateQuery<'a> {
     pub(crate) async fn total_count(&self, conn: &mut PgConnection) -> sqlx::Result<i64> {
         sqlx::query!(
             "
-            SELECT COUNT(DISTINCT label) AS total
-            FROM event
-            WHERE deleted_at IS NULL
-            AND   room_id = $1
-            AND   set = $2
-            AND   ($3::TEXT IS NULL OR attribute = $3::TEXT)
-            AND   original_occurred_at < $4
-            AND   occurred_at < COALESCE($5, 9223372036854775807)
+            SELECT COUNT(*) AS total
+            FROM (
+                SELECT DISTINCT ON(label) attributes
+                FROM event
+                WHERE deleted_at IS NULL
+                AND   room_id = $1
+                AND   set = $2
+                AND   original_occurred_at < $4
+                AND   occurred_at < COALESCE($5, 9223372036854775807)
+                ORDER BY label, occurred_at DESC, created_at DESC, id DESC
+            ) AS q
+            WHERE ($3::TEXT[] IS NULL OR attributes @> $3::TEXT[])
+            AND   NOT attributes @> ARRAY['deleted']
             ",
             self.room_id,
             self.set,
-            self.attribute,
+            self.attributes,
             self.original_occurred_at,
             self.occurred_at,
         )
@@ -641,6 +1183,67 @@ impl<'a> SetStateQuery<'a> {
 
 ///////////////////////////////////////////////////////////////////////////////
 
+/// The latest event for each (set, label) pair across the whole room.
+#[derive(Debug)]
+pub(crate) struct SnapshotQuery {
+    room_id: Uuid,
+    occurred_at: Option<i64>,
+    limit: i64,
+}
+
+impl SnapshotQuery {
+    pub(crate) fn new(room_id: Uuid, limit: i64) -> Self {
+        Self {
+            room_id,
+            occurred_at: None,
+            limit,
+        }
+    }
+
+    pub(crate) fn occurred_at(self, occurred_at: i64) -> Self {
+        Self {
+            occurred_at: Some(occurred_at),
+            ..self
+        }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT DISTINCT ON(set, label)
+                id,
+                room_id,
+                kind,
+                set,
+                label,
+                attributes,
+                data,
+                occurred_at,
+                created_by as "created_by!: AgentId",
+                created_at,
+                deleted_at,
+                original_occurred_at,
+                source_occurred_at,
+                original_created_by as "original_created_by: AgentId"
+            FROM event
+            WHERE deleted_at IS NULL
+            AND   room_id = $1
+            AND   occurred_at < COALESCE($2, 9223372036854775807)
+            ORDER BY set, label, occurred_at DESC, created_at DESC, id DESC
+            LIMIT $3
+            "#,
+            self.room_id,
+            self.occurred_at,
+            self.limit,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug)]
 pub(crate) struct OriginalEventQuery {
     room_id: Uuid,
@@ -667,13 +1270,14 @@ impl OriginalEventQuery {
                 kind,
                 set,
                 label,
-                attribute,
+                attributes,
                 data,
                 occurred_at,
                 created_by as "created_by!: AgentId",
                 created_at,
                 deleted_at,
                 original_occurred_at,
+                source_occurred_at,
                 original_created_by as "original_created_by: AgentId"
             FROM event
             WHERE deleted_at IS NULL
@@ -692,13 +1296,143 @@ impl OriginalEventQuery {
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////
+
+/// Serializes writes of versions of the same label until the end of the transaction.
+#[derive(Debug)]
+pub(crate) struct LabelLockQuery {
+    room_id: Uuid,
+    set: String,
+    label: String,
+}
+
+impl LabelLockQuery {
+    pub(crate) fn new(room_id: Uuid, set: String, label: String) -> Self {
+        Self {
+            room_id,
+            set,
+            label,
+        }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            SELECT 1 AS locked
+            FROM pg_advisory_xact_lock(hashtext($1::TEXT), hashtext($2 || '/' || $3))
+            "#,
+            self.room_id.to_string(),
+            self.set,
+            self.label,
+        )
+        .execute(conn)
+        .await
+        .map(|_| ())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// The id of the latest version of the label. Run it under `LabelLockQuery` for the result
+/// to stay the latest until the transaction ends.
+#[derive(Debug)]
+pub(crate) struct LatestVersionQuery {
+    room_id: Uuid,
+    set: String,
+    label: String,
+}
+
+impl LatestVersionQuery {
+    pub(crate) fn new(room_id: Uuid, set: String, label: String) -> Self {
+        Self {
+            room_id,
+            set,
+            label,
+        }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Uuid>> {
+        sqlx::query!(
+            r#"
+            SELECT id
+            FROM event
+            WHERE deleted_at IS NULL
+            AND   room_id = $1
+            AND   set = $2
+            AND   label = $3
+            ORDER BY occurred_at DESC, created_at DESC, id DESC
+            LIMIT 1
+            "#,
+            self.room_id,
+            self.set,
+            self.label,
+        )
+        .fetch_optional(conn)
+        .await
+        .map(|maybe_row| maybe_row.map(|row| row.id))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct FindQuery {
+    room_id: Uuid,
+    id: Uuid,
+}
+
+impl FindQuery {
+    pub(crate) fn new(room_id: Uuid, id: Uuid) -> Self {
+        Self { room_id, id }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                room_id,
+                kind,
+                set,
+                label,
+                attributes,
+                data,
+                occurred_at,
+                created_by as "created_by!: AgentId",
+                created_at,
+                deleted_at,
+                original_occurred_at,
+                source_occurred_at,
+                original_created_by as "original_created_by: AgentId"
+            FROM event
+            WHERE deleted_at IS NULL
+            AND   room_id = $1
+            AND   id = $2
+            "#,
+            self.room_id,
+            self.id,
+        )
+        .fetch_optional(conn)
+        .await
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Number of events removed from a room by vacuum.
+#[derive(Debug)]
+pub(crate) struct VacuumedRoom {
+    pub(crate) room_id: Uuid,
+    pub(crate) deleted_count: i64,
+}
+
 #[derive(Debug)]
 pub(crate) struct VacuumQuery {
     max_history_size: usize,
     max_history_lifetime: Duration,
     max_deleted_lifetime: Duration,
+    kind_history_lifetimes: Vec<(String, Duration)>,
 }
 
 impl VacuumQuery {
@@ -711,62 +1445,176 @@ impl VacuumQuery {
             max_history_size,
             max_history_lifetime,
             max_deleted_lifetime,
+            kind_history_lifetimes: vec![],
         }
     }
 
-    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
-        sqlx::query!(
+    /// Overrides `max_history_lifetime` for events of particular kinds.
+    pub(crate) fn kind_history_lifetimes(
+        self,
+        kind_history_lifetimes: Vec<(String, Duration)>,
+    ) -> Self {
+        Self {
+            kind_history_lifetimes,
+            ..self
+        }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<VacuumedRoom>> {
+        let (kinds, lifetimes): (Vec<String>, Vec<i64>) = self
+            .kind_history_lifetimes
+            .into_iter()
+            .map(|(kind, lifetime)| (kind, lifetime.num_seconds()))
+            .unzip();
+
+        sqlx::query_as!(
+            VacuumedRoom,
             r#"
-            DELETE FROM event
-            WHERE id IN (
-                -- Exclude preserved rooms and calculate reverse ordinal (history depth).
-                WITH sub AS (
-                    SELECT
-                        e.*,
-                        ROW_NUMBER() OVER (
-                            PARTITION BY e.room_id, e.set, e.label
-                            ORDER BY e.occurred_at DESC
-                        ) AS reverse_ordinal
-                    FROM event AS e
-                    INNER JOIN room AS r
-                    ON r.id = e.room_id
-                    WHERE r.preserve_history = 'f'
+            WITH deleted AS (
+                DELETE FROM event
+                WHERE id IN (
+                    -- Exclude preserved rooms and calculate reverse ordinal (history depth).
+                    WITH sub AS (
+                        SELECT
+                            e.*,
+                            ROW_NUMBER() OVER (
+                                PARTITION BY e.room_id, e.set, e.label
+                                ORDER BY e.occurred_at DESC
+                            ) AS reverse_ordinal
+                        FROM event AS e
+                        INNER JOIN room AS r
+                        ON r.id = e.room_id
+                        WHERE r.preserve_history = 'f'
+                    )
+
+                    -- Too deep history.
+                    SELECT id
+                    FROM sub
+                    WHERE reverse_ordinal > $1
+
+                    UNION ALL
+
+                    -- Too old history, the lifetime may be overridden for the kind.
+                    SELECT id
+                    FROM sub
+                    WHERE reverse_ordinal > 1
+                    AND created_at < NOW() - INTERVAL '1 second' * COALESCE(
+                        (
+                            SELECT kl.lifetime
+                            FROM UNNEST($4::TEXT[], $5::BIGINT[]) AS kl(kind, lifetime)
+                            WHERE kl.kind = sub.kind
+                        ),
+                        $2
+                    )
+
+                    UNION ALL
+
+                    -- Too old deleted labels.
+                    SELECT e.id
+                    FROM sub
+                    INNER JOIN event AS e
+                    ON  e.room_id = sub.room_id
+                    AND e.set = sub.set
+                    AND e.label = sub.label
+                    WHERE e.deleted_at IS NULL
+                    AND   'deleted' = ANY(sub.attributes)
+                    AND   sub.reverse_ordinal = 1
+                    AND   sub.created_at < NOW() - INTERVAL '1 second' * $3
                 )
-
-                -- Too deep history.
-                SELECT id
-                FROM sub
-                WHERE reverse_ordinal > $1
-
-                UNION ALL
-
-                -- Too old history.
-                SELECT id
-                FROM sub
-                WHERE reverse_ordinal > 1
-                AND created_at < NOW() - INTERVAL '1 second' * $2
-
-                UNION ALL
-
-                -- Too old deleted labels.
-                SELECT e.id
-                FROM sub
-                INNER JOIN event AS e
-                ON  e.room_id = sub.room_id
-                AND e.set = sub.set
-                AND e.label = sub.label
-                WHERE e.deleted_at IS NULL
-                AND   sub.attribute = 'deleted'
-                AND   sub.reverse_ordinal = 1
-                AND   sub.created_at < NOW() - INTERVAL '1 second' * $3
+                RETURNING room_id
             )
+            SELECT room_id, COUNT(*) AS "deleted_count!"
+            FROM deleted
+            GROUP BY room_id
             "#,
             self.max_history_size as i64,
             self.max_history_lifetime.num_seconds() as i64,
             self.max_deleted_lifetime.num_seconds() as i64,
+            &kinds,
+            &lifetimes,
         )
-        .execute(conn)
+        .fetch_all(conn)
         .await
-        .map(|_| ())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Number of superseded event versions soft-deleted from a room by compaction.
+#[derive(Debug)]
+pub(crate) struct CompactedRoom {
+    pub(crate) room_id: Uuid,
+    pub(crate) deleted_count: i64,
+}
+
+/// Keeps only `max_versions` latest versions of each label in rooms that don't preserve history.
+#[derive(Debug)]
+pub(crate) struct CompactQuery {
+    max_versions: usize,
+}
+
+impl CompactQuery {
+    pub(crate) fn new(max_versions: usize) -> Self {
+        Self { max_versions }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<CompactedRoom>> {
+        sqlx::query_as!(
+            CompactedRoom,
+            r#"
+            WITH compacted AS (
+                UPDATE event
+                SET deleted_at = NOW()
+                WHERE id IN (
+                    SELECT id
+                    FROM (
+                        SELECT
+                            e.id,
+                            ROW_NUMBER() OVER (
+                                PARTITION BY e.room_id, e.set, e.label
+                                ORDER BY e.occurred_at DESC
+                            ) AS reverse_ordinal
+                        FROM event AS e
+                        INNER JOIN room AS r
+                        ON r.id = e.room_id
+                        WHERE r.preserve_history = 'f'
+                        AND   e.deleted_at IS NULL
+                        AND   e.label IS NOT NULL
+                    ) AS sub
+                    WHERE reverse_ordinal > $1
+                )
+                RETURNING room_id
+            )
+            SELECT room_id, COUNT(*) AS "deleted_count!"
+            FROM compacted
+            GROUP BY room_id
+            "#,
+            self.max_versions as i64,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn occurred_at_is_in_nanoseconds() {
+        assert_eq!(
+            occurred_at_from_duration(Duration::milliseconds(1500)),
+            1_500_000_000
+        );
+
+        assert_eq!(
+            occurred_at_to_duration(1_500_000_000),
+            Duration::milliseconds(1500)
+        );
+
+        assert_eq!(
+            occurred_at_from_duration(Duration::max_value()),
+            std::i64::MAX
+        );
     }
 }
@@ -21,6 +21,7 @@ pub(crate) async fn create_pool(
 
 pub(crate) mod adjustment;
 pub(crate) mod agent;
+pub(crate) mod audit_log;
 pub(crate) mod change;
 pub(crate) mod edition;
 pub(crate) mod event;
@@ -1,7 +1,10 @@
 use std::convert::{TryFrom, TryInto};
 use std::ops::{Bound, RangeBounds};
 
-use chrono::{serde::ts_seconds, DateTime, Utc};
+use chrono::{
+    serde::{ts_seconds, ts_seconds_option},
+    DateTime, Duration, Utc,
+};
 use serde_derive::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use sqlx::postgres::{types::PgRange, PgConnection};
@@ -24,6 +27,20 @@ pub(crate) struct Object {
     preserve_history: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     classroom_id: Option<Uuid>,
+    #[serde(
+        with = "ts_seconds_option",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    closed_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    source_edition_id: Option<Uuid>,
+    #[serde(
+        with = "ts_seconds_option",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    committed_at: Option<DateTime<Utc>>,
 }
 
 impl Object {
@@ -43,6 +60,12 @@ impl Object {
         self.time.clone().try_into()
     }
 
+    /// Time between the room's opening and closing.
+    /// `Ok(None)` for a room without a closing time and `Err` for an invalid room time.
+    pub(crate) fn duration(&self) -> Result<Option<Duration>, String> {
+        self.time().map(|time| time.duration())
+    }
+
     pub(crate) fn tags(&self) -> Option<&JsonValue> {
         self.tags.as_ref()
     }
@@ -56,6 +79,21 @@ impl Object {
         self.classroom_id
     }
 
+    #[cfg(test)]
+    pub(crate) fn closed_at(&self) -> Option<DateTime<Utc>> {
+        self.closed_at
+    }
+
+    #[cfg(test)]
+    pub(crate) fn source_edition_id(&self) -> Option<Uuid> {
+        self.source_edition_id
+    }
+
+    #[cfg(test)]
+    pub(crate) fn committed_at(&self) -> Option<DateTime<Utc>> {
+        self.committed_at
+    }
+
     pub fn authz_object(&self) -> Vec<String> {
         match self.classroom_id {
             Some(cid) => vec!["classrooms".into(), cid.to_string()],
@@ -63,6 +101,9 @@ impl Object {
         }
     }
 
+    /// Room time is the only source of truth for the predicates below. `closed_at` just marks
+    /// that the closing has been processed by `system.close_expired_rooms` and gets cleared
+    /// when the room's end is moved to the future.
     pub(crate) fn is_closed(&self) -> bool {
         match self.time.0.end_bound() {
             Bound::Included(t) => *t < Utc::now(),
@@ -71,6 +112,18 @@ impl Object {
         }
     }
 
+    /// Whether the room has been opened and closed by its time less than `grace_period` ago.
+    pub(crate) fn is_within_close_grace_period(&self, grace_period: Duration) -> bool {
+        let now = Utc::now();
+        let t = (self.time.0.start_bound(), self.time.0.end_bound());
+        match t {
+            (Bound::Included(s), Bound::Excluded(e)) => {
+                *s < now && *e <= now && now < *e + grace_period
+            }
+            _ => false,
+        }
+    }
+
     pub(crate) fn is_open(&self) -> bool {
         let now = Utc::now();
         let t = (self.time.0.start_bound(), self.time.0.end_bound());
@@ -92,6 +145,9 @@ pub(crate) struct Builder {
     created_at: Option<DateTime<Utc>>,
     preserve_history: Option<bool>,
     classroom_id: Option<Uuid>,
+    closed_at: Option<DateTime<Utc>>,
+    source_edition_id: Option<Uuid>,
+    committed_at: Option<DateTime<Utc>>,
 }
 
 impl Builder {
@@ -152,6 +208,24 @@ impl Builder {
         }
     }
 
+    pub(crate) fn closed_at(self, closed_at: Option<DateTime<Utc>>) -> Self {
+        Self { closed_at, ..self }
+    }
+
+    pub(crate) fn source_edition_id(self, source_edition_id: Option<Uuid>) -> Self {
+        Self {
+            source_edition_id,
+            ..self
+        }
+    }
+
+    pub(crate) fn committed_at(self, committed_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            committed_at,
+            ..self
+        }
+    }
+
     pub(crate) fn build(self) -> anyhow::Result<Object> {
         Ok(Object {
             id: self.id.ok_or_else(|| anyhow!("missing id"))?,
@@ -166,6 +240,9 @@ impl Builder {
                 .preserve_history
                 .ok_or_else(|| anyhow!("missing preserve_history"))?,
             classroom_id: self.classroom_id,
+            closed_at: self.closed_at,
+            source_edition_id: self.source_edition_id,
+            committed_at: self.committed_at,
         })
     }
 }
@@ -197,7 +274,10 @@ impl FindQuery {
                 tags,
                 created_at,
                 preserve_history,
-                classroom_id
+                classroom_id,
+                closed_at,
+                source_edition_id,
+                committed_at
             FROM room
             WHERE id = $1
             AND   ($2::TSTZRANGE IS NULL OR time && $2::TSTZRANGE)
@@ -220,6 +300,8 @@ pub(crate) struct InsertQuery {
     tags: Option<JsonValue>,
     preserve_history: bool,
     classroom_id: Option<Uuid>,
+    source_edition_id: Option<Uuid>,
+    committed_at: Option<DateTime<Utc>>,
 }
 
 impl InsertQuery {
@@ -231,6 +313,8 @@ impl InsertQuery {
             tags: None,
             preserve_history: true,
             classroom_id: None,
+            source_edition_id: None,
+            committed_at: None,
         }
     }
 
@@ -262,14 +346,26 @@ impl InsertQuery {
         }
     }
 
+    /// Marks the room as the result of the edition commit at the given moment.
+    pub(crate) fn committed_edition(self, edition_id: Uuid, committed_at: DateTime<Utc>) -> Self {
+        Self {
+            source_edition_id: Some(edition_id),
+            committed_at: Some(committed_at),
+            ..self
+        }
+    }
+
     pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Object> {
         let time: PgRange<DateTime<Utc>> = self.time.into();
 
         sqlx::query_as!(
             Object,
             r#"
-            INSERT INTO room (audience, source_room_id, time, tags, preserve_history, classroom_id)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO room (
+                audience, source_room_id, time, tags, preserve_history, classroom_id,
+                source_edition_id, committed_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             RETURNING
                 id,
                 audience,
@@ -278,7 +374,10 @@ impl InsertQuery {
                 tags,
                 created_at,
                 preserve_history,
-                classroom_id
+                classroom_id,
+                closed_at,
+                source_edition_id,
+                committed_at
             "#,
             self.audience,
             self.source_room_id,
@@ -286,6 +385,8 @@ impl InsertQuery {
             self.tags,
             self.preserve_history,
             self.classroom_id,
+            self.source_edition_id,
+            self.committed_at,
         )
         .fetch_one(conn)
         .await
@@ -336,7 +437,11 @@ impl UpdateQuery {
             UPDATE room
             SET time = COALESCE($2, time),
                 tags = COALESCE($3::JSON, tags),
-                classroom_id = COALESCE($4, classroom_id)
+                classroom_id = COALESCE($4, classroom_id),
+                closed_at = CASE
+                    WHEN UPPER_INF($2) OR UPPER($2) > NOW() THEN NULL
+                    ELSE closed_at
+                END
             WHERE id = $1
             RETURNING
                 id,
@@ -346,7 +451,10 @@ impl UpdateQuery {
                 tags,
                 created_at,
                 preserve_history,
-                classroom_id
+                classroom_id,
+                closed_at,
+                source_edition_id,
+                committed_at
             "#,
             self.id,
             time,
@@ -360,6 +468,191 @@ impl UpdateQuery {
 
 ///////////////////////////////////////////////////////////////////////////////
 
+/// Applies a JSON merge patch (RFC 7396) to tags of multiple rooms at once.
+#[derive(Debug)]
+pub(crate) struct UpdateTagsQuery {
+    ids: Vec<Uuid>,
+    tags_patch: JsonValue,
+}
+
+impl UpdateTagsQuery {
+    pub(crate) fn new(ids: Vec<Uuid>, tags_patch: JsonValue) -> Self {
+        Self { ids, tags_patch }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            UPDATE room
+            SET tags = jsonb_merge_patch(tags::JSONB, $2::JSONB)::JSON
+            WHERE id = ANY($1)
+            RETURNING
+                id,
+                audience,
+                source_room_id,
+                time AS "time!: Time",
+                tags,
+                created_at,
+                preserve_history,
+                classroom_id,
+                closed_at,
+                source_edition_id,
+                committed_at
+            "#,
+            &self.ids,
+            self.tags_patch,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Marks rooms whose right time bound has passed as closed.
+#[derive(Debug)]
+pub(crate) struct CloseExpiredQuery {
+    now: DateTime<Utc>,
+    limit: i64,
+}
+
+impl CloseExpiredQuery {
+    pub(crate) fn new(now: DateTime<Utc>, limit: i64) -> Self {
+        Self { now, limit }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            UPDATE room
+            SET closed_at = $1
+            WHERE id IN (
+                SELECT id
+                FROM room
+                WHERE closed_at IS NULL
+                AND   UPPER(time) <= $1
+                ORDER BY UPPER(time)
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING
+                id,
+                audience,
+                source_room_id,
+                time AS "time!: Time",
+                tags,
+                created_at,
+                preserve_history,
+                classroom_id,
+                closed_at,
+                source_edition_id,
+                committed_at
+            "#,
+            self.now,
+            self.limit,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Lists rooms created by committing editions of the given source room.
+#[derive(Debug)]
+pub(crate) struct DerivedListQuery {
+    source_room_id: Uuid,
+}
+
+impl DerivedListQuery {
+    pub(crate) fn new(source_room_id: Uuid) -> Self {
+        Self { source_room_id }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                audience,
+                source_room_id,
+                time AS "time!: Time",
+                tags,
+                created_at,
+                preserve_history,
+                classroom_id,
+                closed_at,
+                source_edition_id,
+                committed_at
+            FROM room
+            WHERE source_room_id = $1
+            ORDER BY created_at
+            "#,
+            self.source_room_id,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Aggregates over the room's events, online agents and editions.
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub(crate) struct Stats {
+    pub(crate) event_count: i64,
+    pub(crate) agent_count: i64,
+    pub(crate) edition_count: i64,
+    pub(crate) last_event_occurred_at: Option<i64>,
+}
+
+#[derive(Debug)]
+pub(crate) struct StatsQuery {
+    id: Uuid,
+}
+
+impl StatsQuery {
+    pub(crate) fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+
+    pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Stats> {
+        sqlx::query_as!(
+            Stats,
+            r#"
+            SELECT
+                events.count AS "event_count!",
+                events.last_occurred_at AS last_event_occurred_at,
+                (
+                    SELECT COUNT(*)
+                    FROM agent
+                    WHERE room_id = $1
+                    AND   status = 'ready'
+                ) AS "agent_count!",
+                (
+                    SELECT COUNT(*)
+                    FROM edition
+                    WHERE source_room_id = $1
+                ) AS "edition_count!"
+            FROM (
+                SELECT COUNT(*) AS count, MAX(occurred_at) AS last_occurred_at
+                FROM event
+                WHERE room_id = $1
+                AND   deleted_at IS NULL
+            ) AS events
+            "#,
+            self.id,
+        )
+        .fetch_one(conn)
+        .await
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
 use crate::db::room_time::BoundedDateTimeTuple;
 use crate::db::room_time::RoomTime;
 
@@ -1,7 +1,7 @@
 use std::convert::TryFrom;
 use std::ops::Bound;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde_derive::{Deserialize, Serialize};
 
 pub type BoundedDateTimeTuple = (Bound<DateTime<Utc>>, Bound<DateTime<Utc>>);
@@ -95,6 +95,14 @@ impl RoomTime {
     pub fn end(&self) -> &RoomTimeBound {
         &self.end
     }
+
+    /// Time between the room's opening and closing. `None` for a room without a closing time.
+    pub fn duration(&self) -> Option<Duration> {
+        match self.end {
+            RoomTimeBound::Excluded(end) => Some(end.signed_duration_since(self.start)),
+            RoomTimeBound::Unbounded => None,
+        }
+    }
 }
 
 impl From<RoomTime> for BoundedDateTimeTuple {
@@ -215,4 +223,23 @@ mod tests {
         assert_eq!(rt3.0, old_start);
         assert_eq!(rt3.1, Bex(now + Dur::hours(8)));
     }
+
+    #[test]
+    fn test_duration() {
+        let now = Utc::now();
+
+        // closed range
+        let rt = RoomTime::new((Bin(now), Bex(now + Dur::hours(1)))).expect("Cant fail");
+        assert_eq!(rt.duration(), Some(Dur::hours(1)));
+
+        // open ended range
+        let rt = RoomTime::new((Bin(now), Bound::Unbounded)).expect("Cant fail");
+        assert_eq!(rt.duration(), None);
+
+        // ranges not starting with an included bound or empty ones are invalid at all
+        assert_eq!(RoomTime::new((Bex(now), Bex(now + Dur::hours(1)))), None);
+        assert_eq!(RoomTime::new((Bound::Unbounded, Bex(now))), None);
+        assert_eq!(RoomTime::new((Bin(now), Bin(now + Dur::hours(1)))), None);
+        assert_eq!(RoomTime::new((Bin(now), Bex(now))), None);
+    }
 }
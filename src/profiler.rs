@@ -2,12 +2,18 @@ use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::future::Future;
 use std::hash::Hash;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration as StdDuration, Instant};
 
 use anyhow::{Context, Result};
 use chrono::Duration;
 
+const REPORT_RECV_TIMEOUT: StdDuration = StdDuration::from_secs(5);
+// Number of consecutive failed or timed out report requests after which the profiler is unhealthy.
+const UNHEALTHY_FAILURES_THRESHOLD: usize = 10;
+
 #[derive(Default)]
 pub(crate) struct EntryReport {
     pub(crate) p95: usize,
@@ -15,8 +21,14 @@ pub(crate) struct EntryReport {
     pub(crate) max: usize,
 }
 
+/// Reports of both entries and handler timings taken without resetting anything.
+pub(crate) struct Snapshot<K> {
+    pub(crate) entries: Vec<(K, EntryReport)>,
+    pub(crate) handler_timings: Vec<(String, EntryReport)>,
+}
+
 struct Entry {
-    values: Vec<(usize, Instant)>,
+    values: Vec<(usize, Instant, Option<String>)>,
 }
 
 impl Entry {
@@ -24,49 +36,97 @@ impl Entry {
         Self { values: vec![] }
     }
 
-    fn register(&mut self, value: usize) {
-        self.values.push((value, Instant::now()));
+    fn register(&mut self, value: usize, audience: Option<String>) {
+        self.values.push((value, Instant::now(), audience));
     }
 
     fn flush(&mut self, duration: u64) -> EntryReport {
         let now = Instant::now();
         self.values
-            .retain(|(_val, added_at)| now.duration_since(*added_at).as_secs() < duration);
+            .retain(|(_val, added_at, _aud)| now.duration_since(*added_at).as_secs() < duration);
+
+        let values = self.values.iter().map(|(val, _, _)| *val).collect();
+        build_report(values)
+    }
+
+    fn report(&self, duration: u64) -> Option<EntryReport> {
+        let now = Instant::now();
+
+        let values = self
+            .values
+            .iter()
+            .filter(|(_val, added_at, _aud)| now.duration_since(*added_at).as_secs() < duration)
+            .map(|(val, _, _)| *val)
+            .collect::<Vec<_>>();
+
+        if values.is_empty() {
+            None
+        } else {
+            Some(build_report(values))
+        }
+    }
+
+    fn report_for_audience(&self, duration: u64, audience: &str) -> Option<EntryReport> {
+        let now = Instant::now();
 
-        if self.values.is_empty() {
-            EntryReport::default()
+        let values = self
+            .values
+            .iter()
+            .filter(|(_val, added_at, aud)| {
+                aud.as_deref() == Some(audience)
+                    && now.duration_since(*added_at).as_secs() < duration
+            })
+            .map(|(val, _, _)| *val)
+            .collect::<Vec<_>>();
+
+        if values.is_empty() {
+            None
         } else {
-            self.values.sort();
-
-            let count = self.values.len();
-            let p95_idx = (count as f32 * 0.95) as usize;
-            let p99_idx = (count as f32 * 0.99) as usize;
-            let max_idx = count - 1;
-            let max = self.values[max_idx].0;
-
-            let p95 = if p95_idx < max_idx {
-                (self.values[p95_idx].0 + max) / 2
-            } else {
-                max
-            };
-
-            let p99 = if p99_idx < max_idx {
-                (self.values[p99_idx].0 + max) / 2
-            } else {
-                max
-            };
-
-            EntryReport { p95, p99, max }
+            Some(build_report(values))
         }
     }
 }
 
+fn build_report(mut values: Vec<usize>) -> EntryReport {
+    if values.is_empty() {
+        return EntryReport::default();
+    }
+
+    values.sort_unstable();
+
+    EntryReport {
+        p95: percentile(&values, 0.95),
+        p99: percentile(&values, 0.99),
+        max: values[values.len() - 1],
+    }
+}
+
+// Linear interpolation between the closest ranks of sorted non-empty `values`.
+fn percentile(values: &[usize], quantile: f64) -> usize {
+    let rank = quantile * (values.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let weight = rank - lower as f64;
+    let value = values[lower] as f64 + (values[upper] as f64 - values[lower] as f64) * weight;
+    value.round() as usize
+}
+
 enum Message<K> {
     Register {
         key: K,
         value: usize,
+        audience: Option<String>,
     },
     Flush(u64),
+    FlushAudience {
+        duration: u64,
+        audience: String,
+        tx: crossbeam_channel::Sender<Vec<(K, EntryReport)>>,
+    },
+    Snapshot {
+        duration: u64,
+        tx: crossbeam_channel::Sender<Snapshot<K>>,
+    },
     Stop,
     HandlerTiming {
         duration: Duration,
@@ -75,11 +135,120 @@ enum Message<K> {
     GetHandlerTimings {
         tx: crossbeam_channel::Sender<Vec<(String, EntryReport)>>,
     },
+    #[cfg(test)]
+    Poison,
+    #[cfg(test)]
+    Stall(StdDuration),
+}
+
+fn run_worker<K: Eq + Hash + Clone>(
+    rx: &crossbeam_channel::Receiver<Message<K>>,
+    back_tx: &crossbeam_channel::Sender<Vec<(K, EntryReport)>>,
+) {
+    let mut data: HashMap<K, Entry> = HashMap::new();
+    let mut futures_timings: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+
+    for message in rx.iter() {
+        match message {
+            Message::Register {
+                key,
+                value,
+                audience,
+            } => data
+                .entry(key)
+                .or_insert_with(Entry::new)
+                .register(value, audience),
+            Message::Flush(duration) => {
+                let report = data
+                    .iter_mut()
+                    .map(|(k, v)| (k.clone(), v.flush(duration)))
+                    .collect();
+
+                if let Err(err) = back_tx.send(report) {
+                    warn!(crate::LOG, "Failed to send profiler report: {}", err);
+                }
+            }
+            Message::FlushAudience {
+                duration,
+                audience,
+                tx,
+            } => {
+                let report = data
+                    .iter()
+                    .filter_map(|(k, v)| {
+                        v.report_for_audience(duration, &audience)
+                            .map(|report| (k.clone(), report))
+                    })
+                    .collect();
+
+                if let Err(err) = tx.send(report) {
+                    warn!(
+                        crate::LOG,
+                        "Failed to send profiler audience report: {}", err
+                    );
+                }
+            }
+            Message::Snapshot { duration, tx } => {
+                let entries = data
+                    .iter()
+                    .filter_map(|(k, v)| v.report(duration).map(|report| (k.clone(), report)))
+                    .collect();
+
+                let handler_timings = futures_timings
+                    .iter()
+                    .map(|(method, values)| (method.to_owned(), build_report(values.to_owned())))
+                    .collect();
+
+                let snapshot = Snapshot {
+                    entries,
+                    handler_timings,
+                };
+
+                if let Err(err) = tx.send(snapshot) {
+                    warn!(crate::LOG, "Failed to send profiler snapshot: {}", err);
+                }
+            }
+            Message::HandlerTiming { duration, method } => {
+                let vec = futures_timings.entry(method).or_default();
+                let micros =
+                    duration
+                        .num_microseconds()
+                        .map_or(usize::MAX, |micros| match usize::try_from(micros) {
+                            Ok(micros) => micros as usize,
+                            Err(_) => usize::MAX,
+                        });
+
+                vec.push(micros);
+            }
+            Message::GetHandlerTimings { tx } => {
+                let vec = futures_timings
+                    .into_iter()
+                    .map(|(method, values)| (method, build_report(values)))
+                    .collect::<Vec<_>>();
+
+                if let Err(err) = tx.send(vec) {
+                    warn!(
+                        crate::LOG,
+                        "Failed to send dynamic stats collector report: {}", err,
+                    );
+                }
+
+                futures_timings = BTreeMap::new();
+            }
+            #[cfg(test)]
+            Message::Poison => panic!("Profiler poisoned"),
+            #[cfg(test)]
+            Message::Stall(duration) => thread::sleep(duration),
+            Message::Stop => break,
+        }
+    }
 }
 
 pub(crate) struct Profiler<K> {
     tx: crossbeam_channel::Sender<Message<K>>,
     back_rx: crossbeam_channel::Receiver<Vec<(K, EntryReport)>>,
+    failures: AtomicUsize,
+    report_timeout: StdDuration,
 }
 
 impl<K: 'static + Eq + Hash + Send + Clone> Profiler<K> {
@@ -87,135 +256,198 @@ impl<K: 'static + Eq + Hash + Send + Clone> Profiler<K> {
         let (tx, rx) = crossbeam_channel::unbounded();
         let (back_tx, back_rx) = crossbeam_channel::unbounded();
 
-        thread::spawn(move || {
-            let mut data: HashMap<K, Entry> = HashMap::new();
-            let mut futures_timings: BTreeMap<String, Vec<usize>> = BTreeMap::new();
-
-            for message in rx {
-                match message {
-                    Message::Register { key, value } => match data.get_mut(&key) {
-                        Some(entry) => entry.register(value),
-                        None => {
-                            let mut entry = Entry::new();
-                            entry.register(value);
-                            data.insert(key, entry);
-                        }
-                    },
-                    Message::Flush(duration) => {
-                        let report = data
-                            .iter_mut()
-                            .map(|(k, v)| (k.clone(), v.flush(duration)))
-                            .collect();
-
-                        if let Err(err) = back_tx.send(report) {
-                            warn!(crate::LOG, "Failed to send profiler report: {}", err);
-                        }
-                    }
-                    Message::HandlerTiming { duration, method } => {
-                        let vec = futures_timings.entry(method).or_default();
-                        let micros = duration.num_microseconds().map_or(usize::MAX, |micros| {
-                            match usize::try_from(micros) {
-                                Ok(micros) => micros as usize,
-                                Err(_) => usize::MAX,
-                            }
-                        });
-
-                        vec.push(micros);
-                    }
-                    Message::GetHandlerTimings { tx } => {
-                        let vec = futures_timings
-                            .into_iter()
-                            .map(|(method, mut values)| {
-                                values.sort_unstable();
-
-                                let count = values.len();
-                                let p95_idx = (count as f32 * 0.95) as usize;
-                                let p99_idx = (count as f32 * 0.99) as usize;
-                                let max_idx = count - 1;
-                                let max = values[max_idx];
-
-                                let p95 = if p95_idx < max_idx {
-                                    (values[p95_idx] + max) / 2
-                                } else {
-                                    max
-                                };
-
-                                let p99 = if p99_idx < max_idx {
-                                    (values[p99_idx] + max) / 2
-                                } else {
-                                    max
-                                };
-
-                                (method, EntryReport { p95, p99, max })
-                            })
-                            .collect::<Vec<_>>();
-
-                        if let Err(err) = tx.send(vec) {
-                            warn!(
-                                crate::LOG,
-                                "Failed to send dynamic stats collector report: {}", err,
-                            );
-                        }
-
-                        futures_timings = BTreeMap::new();
-                    }
-                    Message::Stop => break,
-                }
+        thread::spawn(move || loop {
+            // Restart the worker with fresh state on panic so the metrics don't die silently.
+            match panic::catch_unwind(AssertUnwindSafe(|| run_worker(&rx, &back_tx))) {
+                Ok(()) => break,
+                Err(_) => error!(crate::LOG, "Profiler worker panicked, restarting"),
             }
         });
 
-        Self { tx, back_rx }
+        Self {
+            tx,
+            back_rx,
+            failures: AtomicUsize::new(0),
+            report_timeout: REPORT_RECV_TIMEOUT,
+        }
+    }
+
+    /// Returns `false` when the worker thread seems to be dead or stuck
+    /// so the metrics are not collected.
+    pub(crate) fn is_healthy(&self) -> bool {
+        self.failures.load(Ordering::Relaxed) < UNHEALTHY_FAILURES_THRESHOLD
     }
 
     pub(crate) async fn measure<F, R>(&self, key: K, func: F) -> R
+    where
+        F: Future<Output = R>,
+    {
+        self.measure_inner(key, None, func).await
+    }
+
+    /// Same as `measure` but also marks the value with the audience so it could be reported
+    /// separately with `flush_for_audience`.
+    pub(crate) async fn measure_for_audience<F, R>(&self, key: K, audience: &str, func: F) -> R
+    where
+        F: Future<Output = R>,
+    {
+        self.measure_inner(key, Some(audience.to_owned()), func)
+            .await
+    }
+
+    async fn measure_inner<F, R>(&self, key: K, audience: Option<String>, func: F) -> R
     where
         F: Future<Output = R>,
     {
         let start_time = Instant::now();
         let result = func.await;
-        let duration = start_time.elapsed();
+        self.register(key, start_time.elapsed(), audience);
+        result
+    }
+
+    /// Registers a duration measured by the caller, e.g. when the key depends on the outcome.
+    pub(crate) fn record(&self, key: K, duration: StdDuration) {
+        self.register(key, duration, None);
+    }
+
+    /// Registers an arbitrary non-duration value such as a query result row count.
+    /// Use a key dedicated to such values so they don't mix up with durations.
+    pub(crate) fn record_value(&self, key: K, audience: &str, value: usize) {
+        self.send_value(key, value, Some(audience.to_owned()));
+    }
 
+    fn register(&self, key: K, duration: StdDuration, audience: Option<String>) {
+        self.send_value(key, duration.as_micros() as usize, audience);
+    }
+
+    fn send_value(&self, key: K, value: usize, audience: Option<String>) {
         let message = Message::Register {
             key,
-            value: duration.as_micros() as usize,
+            value,
+            audience,
         };
 
+        self.send(message, "Failed to register profiler value");
+    }
+
+    // Fire-and-forget sends to the unbounded channel succeed even when the worker is stuck
+    // so they don't affect the health. Only report requests do.
+    fn send(&self, message: Message<K>, error_message: &str) {
         if let Err(err) = self.tx.send(message) {
-            warn!(crate::LOG, "Failed to register profiler value: {}", err);
+            warn!(crate::LOG, "{}: {}", error_message, err);
         }
+    }
 
+    fn track_result<T>(&self, result: &Result<T>) {
+        match result {
+            Ok(_) => self.failures.store(0, Ordering::Relaxed),
+            Err(_) => {
+                self.failures.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn send_and_recv<T>(
+        &self,
+        message: Message<K>,
+        rx: &crossbeam_channel::Receiver<T>,
+        error_message: &'static str,
+    ) -> Result<T> {
+        let result = self
+            .tx
+            .send(message)
+            .map_err(|err| anyhow!(err.to_string()))
+            .context(error_message)
+            .and_then(|()| recv_report(rx, self.report_timeout));
+
+        self.track_result(&result);
         result
     }
 
     pub(crate) fn flush(&self, duration: u64) -> Result<Vec<(K, EntryReport)>> {
-        self.tx
-            .send(Message::Flush(duration))
-            .map_err(|err| anyhow!(err.to_string()))
-            .context("Failed to send flush message to the profiler")?;
+        // Drop a late report left after a previous timeout so it won't be taken for this one.
+        while self.back_rx.try_recv().is_ok() {}
+
+        self.send_and_recv(
+            Message::Flush(duration),
+            &self.back_rx,
+            "Failed to send flush message to the profiler",
+        )
+    }
+
+    /// Unlike `flush` it doesn't drop outdated values so it doesn't affect the global report.
+    pub(crate) fn flush_for_audience(
+        &self,
+        duration: u64,
+        audience: &str,
+    ) -> Result<Vec<(K, EntryReport)>> {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+
+        let message = Message::FlushAudience {
+            duration,
+            audience: audience.to_owned(),
+            tx,
+        };
+
+        self.send_and_recv(
+            message,
+            &rx,
+            "Failed to send FlushAudience message to the profiler",
+        )
+    }
+
+    /// Reports values of the last `duration` seconds and handler timings for on-demand reads.
+    /// Unlike `flush` and `get_handler_timings` it drops or resets nothing
+    /// so the periodic metrics report stays intact.
+    pub(crate) fn snapshot(&self, duration: u64) -> Result<Snapshot<K>> {
+        let (tx, rx) = crossbeam_channel::bounded(1);
 
-        self.back_rx
-            .recv()
-            .context("Failed to receive the profiler report")
+        self.send_and_recv(
+            Message::Snapshot { duration, tx },
+            &rx,
+            "Failed to send Snapshot message to the profiler",
+        )
     }
 
     pub(crate) fn record_future_time(&self, duration: Duration, method: String) {
-        if let Err(err) = self.tx.send(Message::HandlerTiming { duration, method }) {
-            warn!(crate::LOG, "Failed to register profiler value: {}", err);
-        }
+        self.send(
+            Message::HandlerTiming { duration, method },
+            "Failed to register profiler value",
+        );
     }
 
     pub(crate) fn get_handler_timings(&self) -> Result<Vec<(String, EntryReport)>> {
         let (tx, rx) = crossbeam_channel::bounded(1);
 
+        self.send_and_recv(
+            Message::GetHandlerTimings { tx },
+            &rx,
+            "Failed to send GetHandlerTimings message to the profiler",
+        )
+    }
+
+    #[cfg(test)]
+    fn poison(&self) {
         self.tx
-            .send(Message::GetHandlerTimings { tx })
-            .map_err(|err| anyhow!(err.to_string()))
-            .context("Failed to send GetHandlerTimings message to the profiler")?;
+            .send(Message::Poison)
+            .expect("Failed to poison profiler");
+    }
 
-        rx.recv().context("Failed to receive profiler report")
+    #[cfg(test)]
+    fn stall(&self, duration: StdDuration) {
+        self.tx
+            .send(Message::Stall(duration))
+            .expect("Failed to stall profiler");
     }
 }
 
+fn recv_report<T>(rx: &crossbeam_channel::Receiver<T>, timeout: StdDuration) -> Result<T> {
+    rx.recv_timeout(timeout).map_err(|err| {
+        error!(crate::LOG, "Failed to receive the profiler report: {}", err);
+        anyhow!("Failed to receive the profiler report: {}", err)
+    })
+}
+
 impl<K> Drop for Profiler<K> {
     fn drop(&mut self) {
         if let Err(err) = self.tx.send(Message::Stop) {
@@ -240,15 +472,34 @@ mod tests {
         let mut entry = Entry::new();
 
         for i in (1..1000).rev() {
-            entry.register(i);
+            entry.register(i, None);
         }
 
         let report = entry.flush(5);
-        assert_eq!(report.p95, 974);
-        assert_eq!(report.p99, 994);
+        assert_eq!(report.p95, 949);
+        assert_eq!(report.p99, 989);
         assert_eq!(report.max, 999);
     }
 
+    #[test]
+    fn build_report_interpolates_percentiles() {
+        // For 0..=100 the percentiles fall exactly on the ranks.
+        let report = build_report((0..=100).rev().collect());
+        assert_eq!(report.p95, 95);
+        assert_eq!(report.p99, 99);
+        assert_eq!(report.max, 100);
+
+        // Between the ranks: 40 + (50 - 40) * 0.8 and 40 + (50 - 40) * 0.96.
+        let report = build_report(vec![40, 10, 50, 30, 20]);
+        assert_eq!(report.p95, 48);
+        assert_eq!(report.p99, 50);
+        assert_eq!(report.max, 50);
+
+        let report = build_report(vec![7]);
+        assert_eq!(report.p95, 7);
+        assert_eq!(report.p99, 7);
+    }
+
     #[test]
     fn profiler() {
         futures::executor::block_on(async {
@@ -277,4 +528,112 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    fn profiler_audience_report() {
+        futures::executor::block_on(async {
+            let profiler = Profiler::<Key>::start();
+            profiler
+                .measure_for_audience(Key::One, "foo.example.org", async {})
+                .await;
+            profiler
+                .measure_for_audience(Key::Two, "bar.example.org", async {})
+                .await;
+            profiler.measure(Key::Two, async {}).await;
+
+            let reports = profiler
+                .flush_for_audience(5, "foo.example.org")
+                .expect("Failed to flush profiler");
+
+            assert_eq!(reports.len(), 1);
+            assert_eq!(reports[0].0, Key::One);
+
+            let reports = profiler.flush(5).expect("Failed to flush profiler");
+            assert_eq!(reports.len(), 2);
+        });
+    }
+
+    #[test]
+    fn profiler_snapshot() {
+        let profiler = Profiler::<Key>::start();
+        profiler.record(Key::One, Duration::from_micros(5));
+        profiler.record_future_time(chrono::Duration::microseconds(7), String::from("foo"));
+
+        for _ in 0..2 {
+            let snapshot = profiler
+                .snapshot(5)
+                .expect("Failed to take profiler snapshot");
+            assert_eq!(snapshot.entries.len(), 1);
+            assert_eq!(snapshot.entries[0].1.max, 5);
+            assert_eq!(snapshot.handler_timings.len(), 1);
+            assert_eq!(snapshot.handler_timings[0].1.max, 7);
+        }
+
+        // The periodic report still gets everything.
+        let reports = profiler.flush(5).expect("Failed to flush profiler");
+        assert_eq!(reports.len(), 1);
+
+        let timings = profiler
+            .get_handler_timings()
+            .expect("Failed to get handler timings");
+
+        assert_eq!(timings.len(), 1);
+    }
+
+    #[test]
+    fn profiler_record_value() {
+        let profiler = Profiler::<Key>::start();
+        profiler.record_value(Key::One, "foo.example.org", 5);
+        profiler.record_value(Key::One, "foo.example.org", 3);
+
+        let reports = profiler.flush(5).expect("Failed to flush profiler");
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].1.max, 5);
+    }
+
+    #[test]
+    fn profiler_recovers_after_panic() {
+        futures::executor::block_on(async {
+            let profiler = Profiler::<Key>::start();
+            profiler.measure(Key::One, async {}).await;
+            profiler.poison();
+            profiler.measure(Key::Two, async {}).await;
+
+            // The worker restarts with fresh state and keeps serving flushes.
+            let reports = profiler.flush(5).expect("Failed to flush profiler");
+            assert_eq!(reports.len(), 1);
+            assert_eq!(reports[0].0, Key::Two);
+
+            let reports = profiler.flush(5).expect("Failed to flush profiler");
+            assert_eq!(reports.len(), 1);
+            assert!(profiler.is_healthy());
+        });
+    }
+
+    #[test]
+    fn profiler_health() {
+        let mut profiler = Profiler::<Key>::start();
+        profiler.report_timeout = Duration::from_millis(10);
+        profiler.record_value(Key::One, "foo.example.org", 1);
+        assert!(profiler.flush(5).is_ok());
+        assert!(profiler.is_healthy());
+
+        // Block the worker so that report requests time out.
+        profiler.stall(Duration::from_millis(500));
+
+        for _ in 0..UNHEALTHY_FAILURES_THRESHOLD {
+            assert!(profiler.flush(5).is_err());
+
+            // Values are still accepted by the channel but this doesn't mean the worker is fine.
+            profiler.record_value(Key::One, "foo.example.org", 1);
+        }
+
+        assert!(!profiler.is_healthy());
+
+        // The worker gets back and the next report succeeds.
+        std::thread::sleep(Duration::from_millis(500));
+        profiler.report_timeout = REPORT_RECV_TIMEOUT;
+        assert!(profiler.flush(5).is_ok());
+        assert!(profiler.is_healthy());
+    }
 }
@@ -253,6 +253,71 @@ pub(crate) mod duration_seconds {
     }
 }
 
+//////////////////////////////////////////////////////////////////////////////
+
+/// A list of strings which also accepts a single string or null for backward compatibility
+/// with fields that used to be a single optional string.
+pub(crate) mod one_or_many {
+    use serde::de::{self, Deserialize};
+
+    #[derive(serde_derive::Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    pub(crate) fn deserialize<'de, D>(d: D) -> Result<Vec<String>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let value = match Option::<OneOrMany>::deserialize(d)? {
+            Some(OneOrMany::One(value)) => vec![value],
+            Some(OneOrMany::Many(values)) => values,
+            None => vec![],
+        };
+
+        Ok(value)
+    }
+}
+
+/// Flattened `attributes` along with the deprecated single `attribute` holding the first one
+/// for clients that haven't switched to `attributes` yet.
+pub(crate) mod attributes_with_legacy_attribute {
+    use serde::{de, ser, Deserialize, Serialize};
+
+    #[derive(serde_derive::Serialize)]
+    struct Attributes<'a> {
+        attributes: &'a [String],
+        #[serde(skip_serializing_if = "Option::is_none")]
+        attribute: Option<&'a str>,
+    }
+
+    #[derive(serde_derive::Deserialize)]
+    struct AttributesDe {
+        #[serde(default)]
+        attributes: Vec<String>,
+    }
+
+    pub(crate) fn serialize<S>(attributes: &[String], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        Attributes {
+            attributes,
+            attribute: attributes.first().map(|a| a.as_str()),
+        }
+        .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(d: D) -> Result<Vec<String>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        AttributesDe::deserialize(d).map(|value| value.attributes)
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
@@ -345,4 +410,66 @@ mod test {
         let data: TestSecondsDurationData = dbg!(serde_json::from_value(val).unwrap());
         assert_eq!(data.duration, Duration::seconds(123))
     }
+
+    #[derive(Debug, Deserialize)]
+    struct TestOneOrManyData {
+        #[serde(
+            default,
+            alias = "value",
+            deserialize_with = "crate::serde::one_or_many::deserialize"
+        )]
+        values: Vec<String>,
+    }
+
+    #[test]
+    fn one_or_many() {
+        let data: TestOneOrManyData =
+            serde_json::from_value(json!({"values": ["a", "b"]})).unwrap();
+        assert_eq!(data.values, vec!["a", "b"]);
+
+        let data: TestOneOrManyData = serde_json::from_value(json!({"value": "a"})).unwrap();
+        assert_eq!(data.values, vec!["a"]);
+
+        let data: TestOneOrManyData = serde_json::from_value(json!({"value": null})).unwrap();
+        assert!(data.values.is_empty());
+
+        let data: TestOneOrManyData = serde_json::from_value(json!({})).unwrap();
+        assert!(data.values.is_empty());
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct TestAttributesData {
+        id: u32,
+        #[serde(flatten, with = "crate::serde::attributes_with_legacy_attribute")]
+        attributes: Vec<String>,
+    }
+
+    #[test]
+    fn attributes_with_legacy_attribute() {
+        let data = TestAttributesData {
+            id: 1,
+            attributes: vec![String::from("pinned"), String::from("hidden")],
+        };
+
+        let value = serde_json::to_value(&data).unwrap();
+
+        assert_eq!(
+            value,
+            json!({"id": 1, "attributes": ["pinned", "hidden"], "attribute": "pinned"})
+        );
+
+        let data: TestAttributesData = serde_json::from_value(value).unwrap();
+        assert_eq!(data.attributes, vec!["pinned", "hidden"]);
+
+        let data = TestAttributesData {
+            id: 2,
+            attributes: vec![],
+        };
+
+        let value = serde_json::to_value(&data).unwrap();
+        assert_eq!(value, json!({"id": 2, "attributes": []}));
+
+        let data: TestAttributesData = serde_json::from_value(json!({"id": 3})).unwrap();
+        assert!(data.attributes.is_empty());
+    }
 }
@@ -5,14 +5,21 @@ use chrono::{DateTime, Utc};
 use serde_json::json;
 use slog::{Logger, OwnedKV, SendSyncRefUnwindSafeKV};
 use sqlx::postgres::PgPool as Db;
+use svc_agent::mqtt::IntoPublishableMessage;
 use svc_agent::{queue_counter::QueueCounterHandle, AgentId};
 use svc_authz::cache::ConnectionPool as RedisConnectionPool;
 use svc_authz::ClientMap as Authz;
+use tokio::sync::Semaphore;
 
-use crate::app::context::{Context, GlobalContext, MessageContext};
+use crate::app::context::{
+    Clock, Context, GlobalContext, MessageContext, OutgoingMessageSender, SystemClock,
+};
 use crate::app::metrics::Metric;
 use crate::app::metrics::ProfilerKeys;
+use crate::app::metrics::{FullListCounter, OversizedResponseCounter, UnknownLabelCounter};
+use crate::app::rate_limiter::{AgentRequestCounter, RoomConcurrencyLimiter, RoomRateLimiter};
 use crate::app::s3_client::S3Client;
+use crate::app::task_registry::TaskRegistry;
 use crate::config::Config;
 use crate::profiler::Profiler;
 
@@ -20,6 +27,9 @@ use super::authz::{DbBanTestAuthz, TestAuthz};
 use super::db::TestDb;
 use super::SVC_AUDIENCE;
 
+pub(crate) type OutgoingMessageReceiver =
+    futures_channel::mpsc::UnboundedReceiver<Box<dyn IntoPublishableMessage + Send>>;
+
 ///////////////////////////////////////////////////////////////////////////////
 
 fn build_config() -> Config {
@@ -50,11 +60,22 @@ pub(crate) struct TestContext {
     config: Config,
     authz: Authz,
     db: TestDb,
+    ro_db: Option<TestDb>,
     agent_id: AgentId,
     profiler: Arc<Profiler<(ProfilerKeys, Option<String>)>>,
     logger: Logger,
     start_timestamp: DateTime<Utc>,
     s3_client: Option<S3Client>,
+    clock: Arc<dyn Clock>,
+    room_rate_limiter: RoomRateLimiter,
+    state_read_limiter: Arc<RoomConcurrencyLimiter>,
+    full_list_counter: FullListCounter,
+    agent_request_counter: AgentRequestCounter,
+    unknown_label_counter: UnknownLabelCounter,
+    oversized_response_counter: OversizedResponseCounter,
+    task_registry: Arc<TaskRegistry>,
+    dump_gate: Option<Arc<Semaphore>>,
+    outgoing_message_sender: Option<OutgoingMessageSender>,
 }
 
 impl TestContext {
@@ -66,11 +87,22 @@ impl TestContext {
             config,
             authz: authz.into(),
             db,
+            ro_db: None,
             agent_id,
             profiler: Arc::new(Profiler::<(ProfilerKeys, Option<String>)>::start()),
             logger: crate::LOG.new(o!()),
             start_timestamp: Utc::now(),
             s3_client: None,
+            clock: Arc::new(SystemClock),
+            room_rate_limiter: RoomRateLimiter::new(),
+            state_read_limiter: Arc::new(RoomConcurrencyLimiter::new()),
+            full_list_counter: FullListCounter::new(),
+            agent_request_counter: AgentRequestCounter::new(),
+            unknown_label_counter: UnknownLabelCounter::new(),
+            oversized_response_counter: OversizedResponseCounter::new(),
+            task_registry: Arc::new(TaskRegistry::new()),
+            dump_gate: None,
+            outgoing_message_sender: None,
         }
     }
 
@@ -82,17 +114,52 @@ impl TestContext {
             config,
             authz: authz.into(),
             db,
+            ro_db: None,
             agent_id,
             profiler: Arc::new(Profiler::<(ProfilerKeys, Option<String>)>::start()),
             logger: crate::LOG.new(o!()),
             start_timestamp: Utc::now(),
             s3_client: None,
+            clock: Arc::new(SystemClock),
+            room_rate_limiter: RoomRateLimiter::new(),
+            state_read_limiter: Arc::new(RoomConcurrencyLimiter::new()),
+            full_list_counter: FullListCounter::new(),
+            agent_request_counter: AgentRequestCounter::new(),
+            unknown_label_counter: UnknownLabelCounter::new(),
+            oversized_response_counter: OversizedResponseCounter::new(),
+            task_registry: Arc::new(TaskRegistry::new()),
+            dump_gate: None,
+            outgoing_message_sender: None,
         }
     }
 
     pub fn set_s3(&mut self, s3_client: S3Client) {
         self.s3_client = Some(s3_client)
     }
+
+    pub(crate) fn set_dump_gate(&mut self, permits: usize) {
+        self.dump_gate = Some(Arc::new(Semaphore::new(permits)));
+    }
+
+    /// Messages published by background tasks go to the returned receiver.
+    pub(crate) fn outgoing_messages(&mut self) -> OutgoingMessageReceiver {
+        let (tx, rx) = futures_channel::mpsc::unbounded();
+        self.outgoing_message_sender = Some(tx);
+        rx
+    }
+
+    pub(crate) fn set_ro_db(&mut self, ro_db: TestDb) {
+        self.ro_db = Some(ro_db)
+    }
+
+    pub(crate) fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+
+    pub(crate) fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.start_timestamp = clock.now();
+        self.clock = Arc::new(clock);
+    }
 }
 
 impl GlobalContext for TestContext {
@@ -109,7 +176,7 @@ impl GlobalContext for TestContext {
     }
 
     fn ro_db(&self) -> &Db {
-        self.db.connection_pool()
+        self.ro_db.as_ref().unwrap_or(&self.db).connection_pool()
     }
 
     fn agent_id(&self) -> &AgentId {
@@ -132,6 +199,10 @@ impl GlobalContext for TestContext {
         Ok(vec![])
     }
 
+    fn get_audience_metrics(&self, _duration: u64, _audience: &str) -> anyhow::Result<Vec<Metric>> {
+        Ok(vec![])
+    }
+
     fn running_requests(&self) -> Option<Arc<AtomicI64>> {
         None
     }
@@ -139,6 +210,46 @@ impl GlobalContext for TestContext {
     fn s3_client(&self) -> Option<S3Client> {
         self.s3_client.clone()
     }
+
+    fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    fn room_rate_limiter(&self) -> &RoomRateLimiter {
+        &self.room_rate_limiter
+    }
+
+    fn state_read_limiter(&self) -> Arc<RoomConcurrencyLimiter> {
+        self.state_read_limiter.clone()
+    }
+
+    fn full_list_counter(&self) -> &FullListCounter {
+        &self.full_list_counter
+    }
+
+    fn agent_request_counter(&self) -> &AgentRequestCounter {
+        &self.agent_request_counter
+    }
+
+    fn unknown_label_counter(&self) -> &UnknownLabelCounter {
+        &self.unknown_label_counter
+    }
+
+    fn oversized_response_counter(&self) -> &OversizedResponseCounter {
+        &self.oversized_response_counter
+    }
+
+    fn task_registry(&self) -> Arc<TaskRegistry> {
+        self.task_registry.clone()
+    }
+
+    fn dump_gate(&self) -> Option<Arc<Semaphore>> {
+        self.dump_gate.clone()
+    }
+
+    fn outgoing_message_sender(&self) -> Option<OutgoingMessageSender> {
+        self.outgoing_message_sender.clone()
+    }
 }
 
 impl MessageContext for TestContext {
@@ -159,3 +270,15 @@ impl MessageContext for TestContext {
 }
 
 impl Context for TestContext {}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// A clock that always returns the same moment.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FixedClock(pub(crate) DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
@@ -15,11 +15,15 @@ pub(crate) struct TestDb {
 
 impl TestDb {
     pub(crate) async fn new() -> Self {
+        Self::with_pool_size(1).await
+    }
+
+    pub(crate) async fn with_pool_size(size: u32) -> Self {
         let url = var("DATABASE_URL").expect("DATABASE_URL must be specified");
 
         let pool = PgPoolOptions::new()
             .min_connections(1)
-            .max_connections(1)
+            .max_connections(size)
             .connect(&url)
             .await
             .expect("Failed to connect to the DB");
@@ -16,6 +16,7 @@ pub(crate) struct Room {
     time: Option<db::room::Time>,
     tags: Option<JsonValue>,
     preserve_history: Option<bool>,
+    source_room_id: Option<Uuid>,
 }
 
 impl Room {
@@ -51,6 +52,13 @@ impl Room {
         }
     }
 
+    pub(crate) fn source_room_id(self, source_room_id: Uuid) -> Self {
+        Self {
+            source_room_id: Some(source_room_id),
+            ..self
+        }
+    }
+
     pub(crate) async fn insert(self, conn: &mut PgConnection) -> db::room::Object {
         let audience = self.audience.expect("Audience not set");
         let time = self.time.expect("Time not set");
@@ -65,6 +73,10 @@ impl Room {
             query = query.preserve_history(preserve_history)
         }
 
+        if let Some(source_room_id) = self.source_room_id {
+            query = query.source_room_id(source_room_id)
+        }
+
         query.execute(conn).await.expect("Failed to insert room")
     }
 }
@@ -129,7 +141,7 @@ pub(crate) struct Event {
     kind: Option<String>,
     set: Option<String>,
     label: Option<String>,
-    attribute: Option<String>,
+    attributes: Vec<String>,
     data: Option<JsonValue>,
     occurred_at: Option<i64>,
     created_by: Option<AgentId>,
@@ -169,11 +181,10 @@ impl Event {
         }
     }
 
-    pub(crate) fn attribute(self, attribute: &str) -> Self {
-        Self {
-            attribute: Some(attribute.to_owned()),
-            ..self
-        }
+    /// Adds an attribute to the event; may be called several times.
+    pub(crate) fn attribute(mut self, attribute: &str) -> Self {
+        self.attributes.push(attribute.to_owned());
+        self
     }
 
     pub(crate) fn data(self, data: &JsonValue) -> Self {
@@ -221,8 +232,8 @@ impl Event {
             query = query.label(label);
         }
 
-        if let Some(attribute) = self.attribute {
-            query = query.attribute(attribute);
+        if !self.attributes.is_empty() {
+            query = query.attributes(self.attributes);
         }
 
         if let Some(created_at) = self.created_at {
@@ -8,7 +8,7 @@ use svc_agent::{
 };
 use uuid::Uuid;
 
-use crate::app::endpoint::{EventHandler, RequestHandler, ResponseHandler};
+use crate::app::endpoint::{check_read_only, EventHandler, RequestHandler, ResponseHandler};
 use crate::app::error::Error as AppError;
 use crate::app::message_handler::MessageStream;
 use crate::app::API_VERSION;
@@ -31,6 +31,7 @@ pub(crate) async fn handle_request<H: RequestHandler>(
     payload: H::Payload,
 ) -> Result<Vec<OutgoingEnvelope>, AppError> {
     let reqp = build_reqp(agent.agent_id(), "ignore");
+    check_read_only::<H, TestContext>(context)?;
     let messages = H::handle(context, payload, &reqp).await?;
     Ok(parse_messages(messages).await)
 }
@@ -56,7 +57,7 @@ pub(crate) async fn handle_event<H: EventHandler>(
     Ok(parse_messages(messages).await)
 }
 
-async fn parse_messages(mut messages: MessageStream) -> Vec<OutgoingEnvelope> {
+pub(crate) async fn parse_messages(mut messages: MessageStream) -> Vec<OutgoingEnvelope> {
     let mut parsed_messages = vec![];
 
     while let Some(message) = messages.next().await {
@@ -225,7 +226,8 @@ pub(crate) mod prelude {
         context::TestContext,
         db::{test_db_ban_callback, TestDb},
         factory, find_event, find_event_by_predicate, find_request, find_response, handle_event,
-        handle_request, handle_response, shared_helpers, SVC_AUDIENCE, USR_AUDIENCE,
+        handle_request, handle_response, parse_messages, shared_helpers, SVC_AUDIENCE,
+        USR_AUDIENCE,
     };
 }
 
@@ -40,18 +40,24 @@ pub(crate) enum OutgoingEnvelopeProperties {
 #[derive(Debug, Deserialize)]
 pub(crate) struct OutgoingEventProperties {
     label: String,
+    tracking_id: Option<String>,
 }
 
 impl OutgoingEventProperties {
     pub(crate) fn label(&self) -> &str {
         &self.label
     }
+
+    pub(crate) fn tracking_id(&self) -> Option<&str> {
+        self.tracking_id.as_deref()
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct OutgoingResponseProperties {
     status: String,
     correlation_data: String,
+    tracking_id: Option<String>,
 }
 
 impl OutgoingResponseProperties {
@@ -62,6 +68,10 @@ impl OutgoingResponseProperties {
     pub(crate) fn correlation_data(&self) -> &str {
         &self.correlation_data
     }
+
+    pub(crate) fn tracking_id(&self) -> Option<&str> {
+        self.tracking_id.as_deref()
+    }
 }
 
 #[derive(Debug, Deserialize)]